@@ -0,0 +1,365 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Liveness of the drand beacons, reported as Prometheus metrics and by the `idn_beaconHealth`
+//! RPC.
+//!
+//! The latest round of every beacon is read from each new best block and compared to the round
+//! drand should be at by the wall clock. A beacon is healthy while its lag stays under
+//! [`DEGRADED_LAG_SECS`], and stalled once it reaches [`STALLED_LAG_SECS`]. The fetch counts
+//! come from the offchain worker, see [`pallet_beacons::FetchStats`]: every node reports the
+//! liveness of the beacons, but only nodes that run the offchain worker, by default the
+//! collators, count fetches.
+
+use std::{
+	collections::BTreeMap,
+	sync::Arc,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use codec::Decode;
+use futures::StreamExt;
+use ideal_nw_primitives::{
+	api::BeaconsApi,
+	beacon::{BeaconId, BeaconInfo, RoundNumber},
+};
+use ideal_nw_runtime::opaque::{Block, Hash};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+use pallet_beacons::{FetchStats, Url, FETCH_STATS_KEY};
+use parking_lot::Mutex;
+use prometheus_endpoint::{
+	prometheus::{
+		core::{Collector, Desc},
+		proto::MetricFamily,
+	},
+	CounterVec, GaugeVec, Opts, PrometheusError, Registry, U64,
+};
+use sc_client_api::BlockchainEvents;
+use sp_api::ProvideRuntimeApi;
+use sp_core::offchain::OffchainStorage;
+
+/// Lag, in seconds, from which a beacon is degraded.
+pub const DEGRADED_LAG_SECS: u64 = 30;
+
+/// Lag, in seconds, from which a beacon is stalled.
+pub const STALLED_LAG_SECS: u64 = 120;
+
+/// Health of a beacon, or of all of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Health {
+	/// Pulses are ingested on time.
+	Healthy,
+	/// Pulses are late.
+	Degraded,
+	/// Pulses stopped coming in.
+	Stalled,
+}
+
+/// Liveness of one beacon.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BeaconStatus {
+	/// The beacon.
+	pub beacon_id: BeaconId,
+	/// Its health.
+	pub status: Health,
+	/// The latest round stored on chain, if any.
+	pub latest_round: Option<RoundNumber>,
+	/// The round drand is at by the wall clock.
+	pub expected_round: RoundNumber,
+	/// Rounds between the two.
+	pub lag_rounds: u64,
+	/// Seconds since this node saw the latest round stored, if it did.
+	pub seconds_since_last_pulse: Option<u64>,
+}
+
+/// Fetches the offchain worker made from a drand endpoint.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EndpointStatus {
+	/// The endpoint.
+	pub url: String,
+	/// Fetches that returned a pulse.
+	pub successes: u64,
+	/// Fetches that failed.
+	pub failures: u64,
+}
+
+/// Liveness of all beacons, as returned by `idn_beaconHealth`.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthReport {
+	/// The worst health of the beacons, healthy without beacons.
+	pub status: Health,
+	/// Every beacon registered at the best block.
+	pub beacons: Vec<BeaconStatus>,
+	/// Every endpoint the offchain worker fetched from.
+	pub endpoints: Vec<EndpointStatus>,
+}
+
+/// What the node knows of a beacon from the best block.
+struct Observation {
+	info: BeaconInfo,
+	latest_round: Option<RoundNumber>,
+	/// Unix time, in seconds, at which the latest round was first seen at the best block.
+	seen_at: Option<u64>,
+}
+
+/// Metrics refreshed whenever they are scraped.
+struct Metrics {
+	latest_round: GaugeVec<U64>,
+	expected_round: GaugeVec<U64>,
+	lag_rounds: GaugeVec<U64>,
+	seconds_since_last_pulse: GaugeVec<U64>,
+	fetches: CounterVec<U64>,
+}
+
+impl Metrics {
+	fn new() -> Result<Self, PrometheusError> {
+		let gauge =
+			|name: &str, help: &str, labels: &[&str]| GaugeVec::new(Opts::new(name, help), labels);
+		Ok(Self {
+			latest_round: gauge(
+				"idn_beacon_latest_round",
+				"Latest round of the beacon stored at the best block",
+				&["beacon"],
+			)?,
+			expected_round: gauge(
+				"idn_beacon_expected_round",
+				"Round the beacon is at by the wall clock",
+				&["beacon"],
+			)?,
+			lag_rounds: gauge(
+				"idn_beacon_lag_rounds",
+				"Rounds between the expected and the latest stored round of the beacon",
+				&["beacon"],
+			)?,
+			seconds_since_last_pulse: gauge(
+				"idn_beacon_seconds_since_last_pulse",
+				"Seconds since the latest stored round of the beacon was first seen",
+				&["beacon"],
+			)?,
+			fetches: CounterVec::new(
+				Opts::new(
+					"idn_beacon_fetches_total",
+					"Fetches the offchain worker of this node made from each drand endpoint, by \
+					 result",
+				),
+				&["endpoint", "result"],
+			)?,
+		})
+	}
+
+	fn gauges(&self) -> [&GaugeVec<U64>; 4] {
+		[&self.latest_round, &self.expected_round, &self.lag_rounds, &self.seconds_since_last_pulse]
+	}
+
+	fn collectors(&self) -> [&dyn Collector; 5] {
+		[
+			&self.latest_round,
+			&self.expected_round,
+			&self.lag_rounds,
+			&self.seconds_since_last_pulse,
+			&self.fetches,
+		]
+	}
+
+	fn update(&self, report: &HealthReport) {
+		for gauge in self.gauges() {
+			gauge.reset();
+		}
+		for beacon in &report.beacons {
+			let id = beacon.beacon_id.to_string();
+			let labels = &[id.as_str()];
+			if let Some(round) = beacon.latest_round {
+				self.latest_round.with_label_values(labels).set(round);
+			}
+			self.expected_round.with_label_values(labels).set(beacon.expected_round);
+			self.lag_rounds.with_label_values(labels).set(beacon.lag_rounds);
+			if let Some(seconds) = beacon.seconds_since_last_pulse {
+				self.seconds_since_last_pulse.with_label_values(labels).set(seconds);
+			}
+		}
+		// The offchain worker keeps running totals: the counters catch up with them, and don't
+		// go back if they were reset.
+		for endpoint in &report.endpoints {
+			for (result, total) in [("success", endpoint.successes), ("failure", endpoint.failures)]
+			{
+				let counter = self.fetches.with_label_values(&[&endpoint.url, result]);
+				counter.inc_by(total.saturating_sub(counter.get()));
+			}
+		}
+	}
+}
+
+/// Tracks the liveness of the beacons.
+#[derive(Clone)]
+pub struct BeaconHealth {
+	observations: Arc<Mutex<BTreeMap<BeaconId, Observation>>>,
+	fetch_stats: Arc<dyn Fn() -> Option<Vec<u8>> + Send + Sync>,
+	metrics: Arc<Metrics>,
+}
+
+impl BeaconHealth {
+	/// Track the beacons, reading the fetch counts from `offchain_storage`.
+	pub fn new<S: OffchainStorage + 'static>(
+		offchain_storage: Option<S>,
+	) -> Result<Self, PrometheusError> {
+		Ok(Self {
+			observations: Default::default(),
+			fetch_stats: Arc::new(move || {
+				offchain_storage
+					.as_ref()
+					.and_then(|storage| storage.get(sp_offchain::STORAGE_PREFIX, FETCH_STATS_KEY))
+			}),
+			metrics: Arc::new(Metrics::new()?),
+		})
+	}
+
+	/// Report the metrics to `registry`.
+	pub fn register(&self, registry: &Registry) -> Result<(), PrometheusError> {
+		registry.register(Box::new(self.clone()))
+	}
+
+	/// Read the beacons and their latest rounds at block `at`.
+	fn observe<C>(&self, client: &C, at: Hash)
+	where
+		C: ProvideRuntimeApi<Block>,
+		C::Api: BeaconsApi<Block>,
+	{
+		let api = client.runtime_api();
+		let Ok(beacons) = api.beacons(at) else { return };
+		let now = unix_now();
+		let mut observations = self.observations.lock();
+		observations.retain(|id, _| beacons.iter().any(|(beacon_id, _)| beacon_id == id));
+		for (beacon_id, info) in beacons {
+			let latest_round =
+				api.latest_pulse(at, beacon_id).ok().flatten().map(|pulse| pulse.round);
+			match observations.get_mut(&beacon_id) {
+				Some(observation) => {
+					if observation.latest_round != latest_round {
+						observation.seen_at = latest_round.map(|_| now);
+					}
+					observation.info = info;
+					observation.latest_round = latest_round;
+				},
+				// Until the round changes, assume it was stored as soon as drand emitted it.
+				None => {
+					let seen_at = latest_round.map(|round| info.round_time(round).min(now));
+					observations.insert(beacon_id, Observation { info, latest_round, seen_at });
+				},
+			}
+		}
+	}
+
+	/// Liveness of the beacons at unix time `now`, in seconds.
+	pub fn report(&self, now: u64) -> HealthReport {
+		let beacons: Vec<_> = self
+			.observations
+			.lock()
+			.iter()
+			.map(|(&beacon_id, observation)| {
+				let expected_round = observation.info.round_at(now);
+				let lag_rounds =
+					expected_round.saturating_sub(observation.latest_round.unwrap_or_default());
+				let lag_secs = lag_rounds.saturating_mul(observation.info.period.into());
+				let status = match observation.latest_round {
+					None => Health::Stalled,
+					Some(_) if lag_secs >= STALLED_LAG_SECS => Health::Stalled,
+					Some(_) if lag_secs >= DEGRADED_LAG_SECS => Health::Degraded,
+					Some(_) => Health::Healthy,
+				};
+				BeaconStatus {
+					beacon_id,
+					status,
+					latest_round: observation.latest_round,
+					expected_round,
+					lag_rounds,
+					seconds_since_last_pulse: observation
+						.seen_at
+						.map(|seen_at| now.saturating_sub(seen_at)),
+				}
+			})
+			.collect();
+
+		let endpoints = (self.fetch_stats)()
+			.and_then(|stats| Vec::<(Url, FetchStats)>::decode(&mut &stats[..]).ok())
+			.unwrap_or_default()
+			.into_iter()
+			.map(|(url, stats)| EndpointStatus {
+				url: String::from_utf8_lossy(&url).into_owned(),
+				successes: stats.successes,
+				failures: stats.failures,
+			})
+			.collect();
+
+		HealthReport {
+			status: beacons.iter().map(|beacon| beacon.status).max().unwrap_or(Health::Healthy),
+			beacons,
+			endpoints,
+		}
+	}
+
+	/// Follow the best block.
+	pub async fn run<C>(self, client: Arc<C>)
+	where
+		C: ProvideRuntimeApi<Block> + BlockchainEvents<Block>,
+		C::Api: BeaconsApi<Block>,
+	{
+		let mut imports = client.import_notification_stream();
+		while let Some(notification) = imports.next().await {
+			if notification.is_new_best {
+				self.observe(&*client, notification.hash);
+			}
+		}
+	}
+}
+
+impl Collector for BeaconHealth {
+	fn desc(&self) -> Vec<&Desc> {
+		self.metrics.collectors().into_iter().flat_map(|metric| metric.desc()).collect()
+	}
+
+	fn collect(&self) -> Vec<MetricFamily> {
+		self.metrics.update(&self.report(unix_now()));
+		self.metrics
+			.collectors()
+			.into_iter()
+			.flat_map(|metric| metric.collect())
+			.collect()
+	}
+}
+
+fn unix_now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// RPC methods reporting the liveness of the beacons.
+#[rpc(server, namespace = "idn")]
+pub trait BeaconHealthApi {
+	/// Whether the beacons are healthy, degraded or stalled, with the numbers behind it.
+	#[method(name = "beaconHealth")]
+	fn beacon_health(&self) -> RpcResult<HealthReport>;
+}
+
+impl BeaconHealthApiServer for BeaconHealth {
+	fn beacon_health(&self) -> RpcResult<HealthReport> {
+		Ok(self.report(unix_now()))
+	}
+}
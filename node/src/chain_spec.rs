@@ -14,8 +14,11 @@
  * limitations under the License.
  */
 
+use crate::drand::DrandSettings;
 use cumulus_primitives_core::ParaId;
+use ideal_nw_primitives::{beacon::BeaconInfo, dev::DevBeacon};
 use ideal_nw_runtime as runtime;
+use pallet_beacons::GenesisBeacon;
 use runtime::{AccountId, AuraId, Signature, EXISTENTIAL_DEPOSIT};
 use sc_chain_spec::{ChainSpecExtension, ChainSpecGroup};
 use sc_service::ChainType;
@@ -30,6 +33,17 @@ pub type ChainSpec = sc_service::GenericChainSpec<Extensions>;
 /// The default XCM version to set in genesis config.
 const SAFE_XCM_VERSION: u32 = xcm::prelude::XCM_VERSION;
 
+/// Where development chains fetch the pulses of their local beacon, served by `dev-beacon`.
+const DEV_BEACON_ENDPOINTS: [&str; 1] = ["http://127.0.0.1:8085"];
+
+/// The public drand HTTP APIs, in the order the offchain worker tries them.
+const DRAND_ENDPOINTS: [&str; 4] = [
+	"https://drand.cloudflare.com",
+	"https://api.drand.sh",
+	"https://api2.drand.sh",
+	"https://api3.drand.sh",
+];
+
 /// Helper function to generate a crypto pair from seed
 pub fn get_from_seed<TPublic: Public>(seed: &str) -> <TPublic::Pair as Pair>::Public {
 	TPublic::Pair::from_string(&format!("//{}", seed), None)
@@ -45,6 +59,9 @@ pub struct Extensions {
 	pub relay_chain: String,
 	/// The id of the Parachain.
 	pub para_id: u32,
+	/// How nodes fetch drand pulses. The runtime's endpoint list is used when absent.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub drand: Option<DrandSettings>,
 }
 
 impl Extensions {
@@ -79,6 +96,12 @@ pub fn template_session_keys(keys: AuraId) -> runtime::SessionKeys {
 }
 
 pub fn development_config() -> ChainSpec {
+	development_config_with_beacon(DevBeacon::default().info(), &DEV_BEACON_ENDPOINTS)
+}
+
+/// The `dev` chain spec following `beacon`, fetched from `endpoints`, e.g. a beacon served by
+/// `dev-beacon` with a key of its own.
+pub fn development_config_with_beacon(beacon: &BeaconInfo, endpoints: &[&str]) -> ChainSpec {
 	// Give your base currency a unit name and decimal places
 	let mut properties = sc_chain_spec::Properties::new();
 	properties.insert("tokenSymbol".into(), "IDN".into());
@@ -91,6 +114,7 @@ pub fn development_config() -> ChainSpec {
 			relay_chain: "paseo-local".into(),
 			// You MUST set this to the correct network!
 			para_id: 1000,
+			drand: None,
 		},
 	)
 	.with_name("IDN Development")
@@ -118,6 +142,8 @@ pub fn development_config() -> ChainSpec {
 		],
 		get_account_id_from_seed::<sr25519::Public>("Alice"),
 		1000.into(),
+		beacon,
+		endpoints,
 	))
 	.build()
 }
@@ -136,6 +162,7 @@ pub fn local_testnet_config() -> ChainSpec {
 			relay_chain: "paseo-local".into(),
 			// You MUST set this to the correct network!
 			para_id: 2000,
+			drand: None,
 		},
 	)
 	.with_name("IDN Local Testnet")
@@ -166,6 +193,8 @@ pub fn local_testnet_config() -> ChainSpec {
 			.unwrap()
 			.into(),
 		2000.into(),
+		DevBeacon::default().info(),
+		&DEV_BEACON_ENDPOINTS,
 	))
 	.with_protocol_id("template-local")
 	.with_properties(properties)
@@ -182,7 +211,7 @@ pub fn paseo_config() -> ChainSpec {
 	#[allow(deprecated)]
 	ChainSpec::builder(
 		runtime::WASM_BINARY.expect("WASM binary was not built, please build it!"),
-		Extensions { relay_chain: "paseo".into(), para_id: 4502 },
+		Extensions { relay_chain: "paseo".into(), para_id: 4502, drand: None },
 	)
 	.with_name("Ideal Network")
 	.with_id("idn_pso")
@@ -212,6 +241,8 @@ pub fn paseo_config() -> ChainSpec {
 			.unwrap()
 			.into(),
 		4502.into(),
+		&BeaconInfo::quicknet(),
+		&DRAND_ENDPOINTS,
 	))
 	.with_protocol_id("ideal-network-paseo")
 	.with_properties(properties)
@@ -223,6 +254,8 @@ fn testnet_genesis(
 	endowed_accounts: Vec<AccountId>,
 	root: AccountId,
 	id: ParaId,
+	beacon: &BeaconInfo,
+	endpoints: &[&str],
 ) -> serde_json::Value {
 	serde_json::json!({
 		"balances": {
@@ -251,6 +284,13 @@ fn testnet_genesis(
 		"polkadotXcm": {
 			"safeXcmVersion": Some(SAFE_XCM_VERSION),
 		},
+		"beacons": {
+			"beacons": [GenesisBeacon::from(beacon)],
+			"endpoints": endpoints
+				.iter()
+				.map(|url| serde_json::json!({ "url": url, "timeoutMs": 2_000, "backoffMs": 6_000 }))
+				.collect::<Vec<_>>(),
+		},
 		"sudo": { "key": Some(root) }
 	})
 }
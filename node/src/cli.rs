@@ -54,6 +54,15 @@ pub enum Subcommand {
 	/// The pallet benchmarking moved to the `pallet` sub-command.
 	#[command(subcommand)]
 	Benchmark(frame_benchmarking_cli::BenchmarkCmd),
+
+	/// Serve a local drand beacon for development chains.
+	DevBeacon(crate::dev_beacon::DevBeaconCmd),
+
+	/// Verify a drand pulse and print the randomness derived from it.
+	VerifyPulse(crate::verify_pulse::VerifyPulseCmd),
+
+	/// Export the drand pulses stored on chain as JSON Lines or CSV.
+	ExportPulses(crate::export_pulses::ExportPulsesCmd),
 }
 
 const AFTER_HELP_EXAMPLE: &str = color_print::cstr!(
@@ -66,6 +75,10 @@ const AFTER_HELP_EXAMPLE: &str = color_print::cstr!(
            Launch a full node with default parachain <italic>local-testnet</> and relay chain <italic>rococo-local</>.
    <bold>ideal-nw-node --collator</>
            Launch a collator with default parachain <italic>local-testnet</> and relay chain <italic>rococo-local</>.
+   <bold>ideal-nw-node dev-beacon --fail-percent 20</>
+           Serve the beacon of the <italic>dev</> chain on localhost, failing a fifth of the requests.
+   <bold>ideal-nw-node export-pulses --chain paseo --from 1000 --to 2000 --format csv</>
+           Export the pulses included in blocks 1000 to 2000 of the local database.
  "#
 );
 #[derive(Debug, clap::Parser)]
@@ -92,6 +105,16 @@ pub struct Cli {
 	#[arg(long)]
 	pub no_hardware_benchmarks: bool,
 
+	#[command(flatten)]
+	pub drand: crate::drand::DrandParams,
+
+	/// Order the transactions of the blocks this collator proposes by the latest pulse at their
+	/// parent instead of by priority.
+	///
+	/// The runtime flags blocks that don't follow that order, see `pallet_fair_ordering`.
+	#[arg(long)]
+	pub fair_ordering: bool,
+
 	/// Relay chain arguments
 	#[arg(raw = true)]
 	pub relay_chain_args: Vec<String>,
@@ -228,6 +228,37 @@ pub fn run() -> Result<()> {
 				_ => Err("Benchmarking sub-command unsupported".into()),
 			}
 		},
+		Some(Subcommand::DevBeacon(cmd)) => cmd.run(),
+		Some(Subcommand::VerifyPulse(cmd)) => {
+			let verdict = if cmd.needs_database() {
+				let runner = cli.create_runner(cmd)?;
+				let mut verdict = None;
+				runner
+					.sync_run(|config| {
+						let partials = new_partial(&config)?;
+						verdict = Some(cmd.run_with_client(partials.client)?);
+						Ok(())
+					})
+					.map(|()| verdict.expect("set when the run succeeds; qed"))
+			} else {
+				cmd.run()
+			};
+			// Tell an invalid pulse apart from a pulse that could not be checked.
+			match verdict {
+				Ok(verdict) => std::process::exit(verdict.exit_code()),
+				Err(e) => {
+					eprintln!("Error: {e}");
+					std::process::exit(crate::verify_pulse::EXIT_ERROR)
+				},
+			}
+		},
+		Some(Subcommand::ExportPulses(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.sync_run(|config| {
+				let partials = new_partial(&config)?;
+				cmd.run(partials.client)
+			})
+		},
 		None => {
 			let runner = cli.create_runner(&cli.run.normalize())?;
 			let collator_options = cli.run.collator_options();
@@ -243,9 +274,11 @@ pub fn run() -> Result<()> {
 					}))
 					.flatten();
 
-				let para_id = chain_spec::Extensions::try_get(&*config.chain_spec)
+				let extensions = chain_spec::Extensions::try_get(&*config.chain_spec);
+				let para_id = extensions
 					.map(|e| e.para_id)
 					.ok_or("Could not find parachain ID in chain-spec.")?;
+				let drand = cli.drand.resolve(extensions.and_then(|e| e.drand.as_ref()));
 
 				let polkadot_cli = RelayChainCli::new(
 					&config,
@@ -267,6 +300,8 @@ pub fn run() -> Result<()> {
 					collator_options,
 					id,
 					hwbench,
+					drand,
+					cli.fair_ordering,
 				)
 				.await
 				.map(|r| r.0)
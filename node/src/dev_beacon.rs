@@ -0,0 +1,441 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The `dev-beacon` sub-command: a local beacon for development chains.
+//!
+//! Signs rounds like quicknet with a key derived from a seed and serves them over the subset of
+//! the drand HTTP API the offchain worker uses. The defaults match the beacon and endpoint of the
+//! `dev` and `local` chain specs. Faults can be injected to exercise failover, missed rounds,
+//! stalls and an outage the beacon recovers from.
+//!
+//! The seed can also be generated or read from a file. A beacon with another key than the
+//! default one writes a `dev` chain spec following it with `--chain-spec`, to start the node
+//! with.
+
+use crate::chain_spec;
+use ideal_nw_primitives::{
+	beacon::{BeaconInfo, Pulse, RoundNumber},
+	dev::{DevBeacon, DEV_GENESIS_TIME, DEV_PERIOD, DEV_SEED},
+};
+use sp_core::Pair;
+use std::{
+	io::{BufRead, BufReader, Write},
+	net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+	path::PathBuf,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+	thread,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Serve a local drand beacon over HTTP.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct DevBeaconCmd {
+	/// Port to listen on, on localhost.
+	#[arg(long, default_value_t = 8085)]
+	pub port: u16,
+
+	/// Seed of the beacon key. Anyone knowing it can sign rounds.
+	#[arg(
+		long,
+		default_value_t = String::from_utf8_lossy(DEV_SEED).into_owned(),
+		conflicts_with_all = ["key_file", "generate_key"],
+	)]
+	pub seed: String,
+
+	/// Read the seed of the beacon key from this file. With `--generate-key`, write the
+	/// generated seed to it instead.
+	#[arg(long, value_name = "PATH")]
+	pub key_file: Option<PathBuf>,
+
+	/// Generate a random seed for the beacon key.
+	#[arg(long)]
+	pub generate_key: bool,
+
+	/// Write a `dev` chain spec following this beacon, fetched from this server, to the file.
+	#[arg(long, value_name = "PATH")]
+	pub chain_spec: Option<PathBuf>,
+
+	/// Unix time, in seconds, of the first round.
+	#[arg(long, default_value_t = DEV_GENESIS_TIME)]
+	pub genesis_time: u64,
+
+	/// Seconds between two rounds.
+	#[arg(long, default_value_t = DEV_PERIOD, value_parser = clap::value_parser!(u32).range(1..))]
+	pub period: u32,
+
+	/// Percentage of requests answered with `503 Service Unavailable`.
+	#[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=100))]
+	pub fail_percent: u8,
+
+	/// Percentage of pulses served with a corrupted signature.
+	#[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=100))]
+	pub corrupt_percent: u8,
+
+	/// Percentage of rounds never emitted, as if the beacon network missed them. The same
+	/// rounds are missing for every request.
+	#[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..100))]
+	pub skip_percent: u8,
+
+	/// Delay before every response, in milliseconds.
+	#[arg(long, default_value_t = 0)]
+	pub delay_ms: u64,
+
+	/// Stop emitting rounds after this one, as if the beacon network stalled.
+	#[arg(long)]
+	pub stall_after: Option<RoundNumber>,
+
+	/// Answer every request with `503 Service Unavailable` from the time this round is due, as
+	/// if the endpoint went down, for `--down-for` seconds.
+	#[arg(long, value_name = "ROUND", requires = "down_for")]
+	pub down_from: Option<RoundNumber>,
+
+	/// Seconds the endpoint stays down from `--down-from`. It then serves every round again,
+	/// those due during the downtime included.
+	#[arg(long, value_name = "SECONDS", requires = "down_from")]
+	pub down_for: Option<u64>,
+}
+
+impl DevBeaconCmd {
+	/// Serve the beacon until the process is stopped.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		sc_cli::LoggerBuilder::new("").init()?;
+		let seed = self.seed()?;
+		let beacon = DevBeacon::from_seed(seed.as_bytes(), self.genesis_time, self.period);
+		let info = beacon.info();
+		let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, self.port)))?;
+		let url = format!("http://{}", listener.local_addr()?);
+
+		log::info!("Serving dev beacon on {url}");
+		log::info!("  chain hash: {}", hex::encode(info.chain_hash));
+		log::info!("  public key: {}", hex::encode(&info.public_key));
+		log::info!("  period: {}s, genesis time: {}", info.period, info.genesis_time);
+		if let Some(path) = &self.chain_spec {
+			let spec = chain_spec::development_config_with_beacon(info, &[url.as_str()]);
+			std::fs::write(path, spec.as_json(false)?)?;
+			log::info!("  chain spec: {}", path.display());
+		} else if info != DevBeacon::default().info() {
+			log::info!("  not the beacon of the `dev` chain spec, see `--chain-spec`");
+		}
+
+		let server = Arc::new(Server::new(self.clone(), beacon));
+		for stream in listener.incoming() {
+			let Ok(stream) = stream else { continue };
+			let server = server.clone();
+			thread::spawn(move || {
+				if let Err(err) = server.handle(stream) {
+					log::warn!("connection failed: {err}");
+				}
+			});
+		}
+		Ok(())
+	}
+
+	/// The seed of the beacon key: given, read from the key file or generated.
+	fn seed(&self) -> sc_cli::Result<String> {
+		if self.generate_key {
+			let (_, phrase, _) = sp_core::sr25519::Pair::generate_with_phrase(None);
+			match &self.key_file {
+				Some(path) if path.exists() =>
+					return Err(format!("{} exists already", path.display()).into()),
+				Some(path) => {
+					std::fs::write(path, &phrase)?;
+					log::info!("Wrote the generated seed to {}", path.display());
+				},
+				None => log::info!("Generated seed: {phrase}"),
+			}
+			return Ok(phrase);
+		}
+		match &self.key_file {
+			Some(path) => Ok(std::fs::read_to_string(path)?.trim().to_owned()),
+			None => Ok(self.seed.clone()),
+		}
+	}
+}
+
+struct Server {
+	beacon: DevBeacon,
+	cmd: DevBeaconCmd,
+	/// State of the xorshift generator deciding on injected faults.
+	dice: AtomicU64,
+}
+
+impl Server {
+	fn new(cmd: DevBeaconCmd, beacon: DevBeacon) -> Self {
+		let seed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+		Self { beacon, cmd, dice: AtomicU64::new(seed as u64 | 1) }
+	}
+
+	/// Whether an event with a `percent` chance happens.
+	fn chance(&self, percent: u8) -> bool {
+		if percent == 0 {
+			return false;
+		}
+		let mut x = self.dice.load(Ordering::Relaxed);
+		x ^= x << 13;
+		x ^= x >> 7;
+		x ^= x << 17;
+		self.dice.store(x, Ordering::Relaxed);
+		x % 100 < u64::from(percent)
+	}
+
+	fn handle(&self, mut stream: TcpStream) -> std::io::Result<()> {
+		let mut request_line = String::new();
+		let mut reader = BufReader::new(stream.try_clone()?);
+		reader.read_line(&mut request_line)?;
+		// Skip the headers, requests carry no body.
+		let mut header = String::new();
+		while reader.read_line(&mut header)? > 2 {
+			header.clear();
+		}
+
+		let (status, body) = self.answer(&request_line);
+		respond(&mut stream, status, body)
+	}
+
+	/// Answer a request, injecting the configured faults.
+	fn answer(&self, request_line: &str) -> (u16, String) {
+		thread::sleep(Duration::from_millis(self.cmd.delay_ms));
+		let path = match request_line.split_whitespace().collect::<Vec<_>>()[..] {
+			["GET", path, ..] => path,
+			_ => return (405, "method not allowed".into()),
+		};
+		let (status, body) = if self.is_down() {
+			(503, "injected downtime".into())
+		} else if self.chance(self.cmd.fail_percent) {
+			(503, "injected failure".into())
+		} else {
+			self.route(path)
+		};
+		log::info!("GET {path} {status}");
+		(status, body)
+	}
+
+	/// Whether the endpoint is within its configured downtime.
+	fn is_down(&self) -> bool {
+		let (Some(from), Some(duration)) = (self.cmd.down_from, self.cmd.down_for) else {
+			return false;
+		};
+		let start = self.beacon.info().round_time(from);
+		(start..start.saturating_add(duration)).contains(&now())
+	}
+
+	fn route(&self, path: &str) -> (u16, String) {
+		let info = self.beacon.info();
+		let segments: Vec<_> = path.trim_matches('/').split('/').collect();
+		// Requests may name the chain hash first, as the offchain worker does.
+		let segments = match segments[..] {
+			[hash, ref rest @ ..] if hash == hex::encode(info.chain_hash) => rest,
+			_ => &segments[..],
+		};
+
+		match segments {
+			["info"] => (200, chain_info(info)),
+			["public", "latest"] => match self.latest_round() {
+				0 => (404, "no round emitted yet".into()),
+				round => (200, self.pulse(round)),
+			},
+			["public", round] => match round.parse::<RoundNumber>() {
+				Ok(round) if self.skipped(round) => (404, "round missed".into()),
+				Ok(round) if round > 0 && round <= self.latest_round() => (200, self.pulse(round)),
+				Ok(_) => (404, "round not emitted yet".into()),
+				Err(_) => (400, "invalid round".into()),
+			},
+			_ => (404, "not found".into()),
+		}
+	}
+
+	/// The latest emitted round, taking a configured stall and missed rounds into account.
+	fn latest_round(&self) -> RoundNumber {
+		let round = self.beacon.info().round_at(now());
+		let mut round = self.cmd.stall_after.map_or(round, |stall| round.min(stall));
+		while round > 0 && self.skipped(round) {
+			round -= 1;
+		}
+		round
+	}
+
+	/// Whether `round` is one of the missed rounds.
+	fn skipped(&self, round: RoundNumber) -> bool {
+		if self.cmd.skip_percent == 0 {
+			return false;
+		}
+		let hash = sp_core::hashing::sha2_256(&round.to_be_bytes());
+		let dice = u64::from_be_bytes(hash[..8].try_into().expect("8 bytes; qed"));
+		dice % 100 < u64::from(self.cmd.skip_percent)
+	}
+
+	fn pulse(&self, round: RoundNumber) -> String {
+		let mut pulse = self.beacon.sign(round);
+		if self.chance(self.cmd.corrupt_percent) {
+			corrupt(&mut pulse);
+		}
+		serde_json::json!({
+			"round": pulse.round,
+			"randomness": hex::encode(pulse.randomness),
+			"signature": hex::encode(&pulse.signature),
+		})
+		.to_string()
+	}
+}
+
+/// The current unix time, in seconds.
+fn now() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
+/// The `/info` of `beacon`, as drand serves it.
+fn chain_info(beacon: &BeaconInfo) -> String {
+	serde_json::json!({
+		"public_key": hex::encode(&beacon.public_key),
+		"period": beacon.period,
+		"genesis_time": beacon.genesis_time,
+		"hash": hex::encode(beacon.chain_hash),
+		"schemeID": beacon.scheme.scheme_id(),
+		"metadata": { "beaconID": "dev" },
+	})
+	.to_string()
+}
+
+/// Flip a bit of the signature, keeping the randomness consistent with it, so that only the
+/// signature check fails.
+fn corrupt(pulse: &mut Pulse) {
+	let mut signature = pulse.signature.to_vec();
+	if let Some(byte) = signature.last_mut() {
+		*byte ^= 1;
+	}
+	pulse.randomness = sp_core::hashing::sha2_256(&signature);
+	pulse.signature = signature.try_into().expect("the length is unchanged; qed");
+}
+
+fn respond(stream: &mut TcpStream, status: u16, body: String) -> std::io::Result<()> {
+	let reason = match status {
+		200 => "OK",
+		400 => "Bad Request",
+		404 => "Not Found",
+		405 => "Method Not Allowed",
+		_ => "Service Unavailable",
+	};
+	let content_type = if status == 200 { "application/json" } else { "text/plain" };
+	write!(
+		stream,
+		"HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\
+		 Connection: close\r\n\r\n{body}",
+		body.len()
+	)?;
+	stream.flush()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use clap::Parser;
+	use ideal_nw_primitives::bls::verify_pulse;
+	use std::time::Instant;
+
+	/// A beacon with a round every second since 100 seconds ago, with the faults of `args`.
+	fn server(args: &[&str]) -> Server {
+		let genesis_time = (now() - 100).to_string();
+		let base = ["dev-beacon", "--genesis-time", genesis_time.as_str(), "--period", "1"];
+		let cmd = DevBeaconCmd::parse_from(base.iter().chain(args).copied());
+		let beacon = DevBeacon::from_seed(DEV_SEED, cmd.genesis_time, cmd.period);
+		Server::new(cmd, beacon)
+	}
+
+	fn get(server: &Server, path: &str) -> (u16, String) {
+		server.answer(&format!("GET {path} HTTP/1.1\r\n"))
+	}
+
+	/// The pulse served at `path`, and whether it verifies.
+	fn pulse(server: &Server, path: &str) -> (Pulse, bool) {
+		let (status, body) = get(server, path);
+		assert_eq!(status, 200, "{body}");
+		let pulse = pallet_beacons::parse_drand_pulse(body.as_bytes()).expect("a drand pulse");
+		let valid = verify_pulse(server.beacon.info(), &pulse);
+		(pulse, valid)
+	}
+
+	#[test]
+	fn served_pulses_verify() {
+		let server = server(&[]);
+		let (latest, valid) = pulse(&server, "/public/latest");
+		assert!(valid);
+		assert!(latest.round >= 100);
+		let chain_hash = hex::encode(server.beacon.info().chain_hash);
+		let (round, valid) = pulse(&server, &format!("/{chain_hash}/public/42"));
+		assert!(valid);
+		assert_eq!(round.signature, server.beacon.sign(42).signature);
+		assert_eq!(get(&server, &format!("/public/{}", latest.round + 10)).0, 404);
+	}
+
+	#[test]
+	fn failures_are_injected() {
+		let server = server(&["--fail-percent", "100"]);
+		assert_eq!(get(&server, "/public/latest").0, 503);
+	}
+
+	#[test]
+	fn corrupted_pulses_parse_but_fail_verification() {
+		let server = server(&["--corrupt-percent", "100"]);
+		let (pulse, valid) = pulse(&server, "/public/42");
+		assert!(!valid);
+		assert_eq!(pulse.round, 42);
+		assert_eq!(pulse.randomness, sp_core::hashing::sha2_256(&pulse.signature));
+	}
+
+	#[test]
+	fn skipped_rounds_are_missing() {
+		let server = server(&["--skip-percent", "50"]);
+		let (latest, valid) = pulse(&server, "/public/latest");
+		assert!(valid);
+		assert!(!server.skipped(latest.round));
+		let missed = (1..latest.round).find(|round| server.skipped(*round)).expect("half are");
+		assert_eq!(get(&server, &format!("/public/{missed}")).0, 404);
+	}
+
+	#[test]
+	fn delays_are_injected() {
+		let server = server(&["--delay-ms", "50"]);
+		let start = Instant::now();
+		assert!(pulse(&server, "/public/latest").1);
+		assert!(start.elapsed() >= Duration::from_millis(50));
+	}
+
+	#[test]
+	fn stalled_beacon_stops_at_its_round() {
+		let server = server(&["--stall-after", "10"]);
+		let (latest, valid) = pulse(&server, "/public/latest");
+		assert!(valid);
+		assert_eq!(latest.round, 10);
+		assert_eq!(get(&server, "/public/11").0, 404);
+	}
+
+	#[test]
+	fn downtime_ends() {
+		let current = server(&[]).latest_round().to_string();
+		let down = server(&["--down-from", current.as_str(), "--down-for", "3600"]);
+		assert_eq!(get(&down, "/public/latest").0, 503);
+		assert_eq!(get(&down, "/public/42").0, 503);
+
+		let recovered = server(&["--down-from", "10", "--down-for", "20"]);
+		let (latest, valid) = pulse(&recovered, "/public/latest");
+		assert!(valid);
+		assert!(latest.round >= 100);
+		assert!(pulse(&recovered, "/public/20").1);
+	}
+}
@@ -0,0 +1,401 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The collator's part in the committee beacon of `pallet_dkg`.
+//!
+//! On every new best block, for each Aura key in the keystore, the worker:
+//!
+//! - announces an encryption key when the key takes part in the DKG in progress,
+//! - deals once the announce phase is over when the key is a dealer,
+//! - complains about the dealers that sent the key a wrong share once the deal phase is over,
+//! - signs the current round of the committee beacon when the key is a member of the committee, or
+//!   of the next committee waiting to take over,
+//! - and submits the pulse of the round once `threshold` partial signatures are on chain.
+//!
+//! Every secret is derived from the Aura key with a VRF, so it never leaves the keystore in a
+//! form that outlives the node, and a restarted node deals the same polynomial again. Shares are
+//! recomputed from the dealings on chain.
+
+use std::{
+	collections::HashMap,
+	sync::Arc,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+use codec::Encode;
+use futures::StreamExt;
+use ideal_nw_primitives::{
+	api::{BeaconsApi, DkgApi},
+	beacon::{BeaconId, Pulse, RoundNumber},
+	dkg::{
+		self, Announcement, Committee, Complaint, Dealing, DkgRound, Fr, PartialSignature,
+		ParticipantIndex, Phase, Polynomial, SignedDealing,
+	},
+};
+use ideal_nw_runtime::{
+	opaque::{Block, Hash},
+	BlockNumber, RuntimeCall, UncheckedExtrinsic,
+};
+use sc_client_api::BlockchainEvents;
+use sc_transaction_pool_api::{TransactionPool, TransactionSource};
+use sp_api::ProvideRuntimeApi;
+use sp_consensus_aura::sr25519::{AuthorityId as AuraId, AuthoritySignature};
+use sp_core::{
+	crypto::key_types::AURA,
+	hashing::{blake2_256, sha2_256},
+	sr25519::{self, vrf::VrfInput},
+};
+use sp_keystore::KeystorePtr;
+use sp_runtime::OpaqueExtrinsic;
+
+const LOG_TARGET: &str = "dkg";
+
+/// Label of the VRF the worker's secrets are derived from.
+const VRF_LABEL: &[u8] = b"ideal-network/dkg";
+
+/// Runs the DKG and signs rounds with the Aura keys of the node.
+pub struct DkgWorker<C, P> {
+	client: Arc<C>,
+	pool: Arc<P>,
+	keystore: KeystorePtr,
+	/// The share of every key, by committee session, or `None` if a dealer sent a wrong one.
+	shares: HashMap<(u32, sr25519::Public), Option<Fr>>,
+}
+
+impl<C, P> DkgWorker<C, P>
+where
+	C: ProvideRuntimeApi<Block> + BlockchainEvents<Block>,
+	C::Api: DkgApi<Block, AuraId, BlockNumber> + BeaconsApi<Block>,
+	P: TransactionPool<Block = Block>,
+{
+	/// A worker signing with the Aura keys of `keystore`.
+	pub fn new(client: Arc<C>, pool: Arc<P>, keystore: KeystorePtr) -> Self {
+		Self { client, pool, keystore, shares: HashMap::new() }
+	}
+
+	/// Send the node's messages on top of every new best block.
+	pub async fn run(mut self) {
+		let mut blocks = self.client.import_notification_stream();
+		while let Some(block) = blocks.next().await {
+			if !block.is_new_best {
+				continue;
+			}
+			for call in self.calls(block.hash) {
+				let extrinsic = UncheckedExtrinsic::new_unsigned(call).encode();
+				let Ok(extrinsic) = OpaqueExtrinsic::from_bytes(&extrinsic) else { continue };
+				if let Err(err) =
+					self.pool.submit_one(block.hash, TransactionSource::Local, extrinsic).await
+				{
+					log::debug!(target: LOG_TARGET, "Failed to submit a DKG message: {err}");
+				}
+			}
+		}
+	}
+
+	/// The messages due on top of block `at`.
+	fn calls(&mut self, at: Hash) -> Vec<RuntimeCall> {
+		let keys = self.keystore.sr25519_public_keys(AURA);
+		if keys.is_empty() {
+			return Vec::new();
+		}
+		let api = self.client.runtime_api();
+		let round = api.pending_dkg(at).ok().flatten();
+		let committee = api.committee(at).ok().flatten();
+		let next = api.next_committee(at).ok().flatten();
+		drop(api);
+		let mut calls = Vec::new();
+		if let Some(round) = round {
+			calls.extend(match round.phase {
+				Phase::Announce => self.announcements(at, &round, &keys),
+				Phase::Deal => self.dealings(at, &round, &keys),
+				Phase::Complain => self.complaints(at, &round, &keys),
+			});
+		}
+		if let Some(committee) = committee {
+			self.shares.retain(|(session, _), _| *session >= committee.session);
+			calls.extend(self.signatures(at, &committee, &keys));
+		}
+		// Both committees sign until the next one takes over.
+		if let Some(next) = next {
+			calls.extend(self.signatures(at, &next, &keys));
+		}
+		calls
+	}
+
+	/// The seed of the secrets of `key` in the DKG of `session`.
+	fn seed(&self, key: &sr25519::Public, session: u32) -> Option<[u8; 32]> {
+		let input = VrfInput::new(VRF_LABEL, &[(b"session", &session.to_le_bytes())]);
+		let pre_output = self.keystore.sr25519_vrf_pre_output(AURA, key, &input).ok()??;
+		Some(blake2_256(&pre_output.encode()))
+	}
+
+	/// The encryption secret of `key` in the DKG of `session`.
+	fn encryption_secret(&self, key: &sr25519::Public, session: u32) -> Option<Fr> {
+		Some(dkg::scalar_from_seed(&self.seed(key, session)?, b"encryption"))
+	}
+
+	/// `message` signed with `key`.
+	fn sign(&self, key: &sr25519::Public, message: &impl Encode) -> Option<AuthoritySignature> {
+		let signature = self.keystore.sr25519_sign(AURA, key, &message.encode()).ok()??;
+		Some(signature.into())
+	}
+
+	/// Announcements of the keys taking part in `round` that haven't announced yet.
+	fn announcements(
+		&self,
+		at: Hash,
+		round: &DkgRound<AuraId, BlockNumber>,
+		keys: &[sr25519::Public],
+	) -> Vec<RuntimeCall> {
+		let Ok(announced) = self.client.runtime_api().encryption_keys(at) else {
+			return Vec::new();
+		};
+		keys.iter()
+			.filter_map(|key| {
+				let participant = index_of(&round.participants, key)?;
+				if announced.iter().any(|(index, _)| *index == participant) {
+					return None;
+				}
+				let secret = self.encryption_secret(key, round.session)?;
+				let key_bytes = dkg::encryption_key(&secret);
+				let announcement =
+					Announcement { session: round.session, participant, key: key_bytes };
+				let signature = self.sign(key, &announcement)?;
+				Some(RuntimeCall::Dkg(pallet_dkg::Call::announce { announcement, signature }))
+			})
+			.collect()
+	}
+
+	/// Dealings of the keys dealing in `round` that haven't dealt yet.
+	fn dealings(
+		&mut self,
+		at: Hash,
+		round: &DkgRound<AuraId, BlockNumber>,
+		keys: &[sr25519::Public],
+	) -> Vec<RuntimeCall> {
+		let api = self.client.runtime_api();
+		let (Ok(dealt), Ok(encryption_keys), Ok(committee)) =
+			(api.dealings(at, round.session), api.encryption_keys(at), api.committee(at))
+		else {
+			return Vec::new();
+		};
+		drop(api);
+
+		let mut calls = Vec::new();
+		for key in keys {
+			let Some(dealer) = index_of(&round.dealers, key) else { continue };
+			if dealt.iter().any(|(index, _)| *index == dealer) {
+				continue;
+			}
+			// When resharing, the dealer's share is the constant term.
+			let constant = match (round.reshare_threshold, &committee) {
+				(None, _) => None,
+				(Some(_), Some(committee)) => match self.share(at, committee, key) {
+					Some(share) => Some(share),
+					None => continue,
+				},
+				(Some(_), None) => continue,
+			};
+			let Some(seed) = self.seed(key, round.session) else { continue };
+			let polynomial = Polynomial::from_seed(
+				&[&seed[..], b"polynomial"].concat(),
+				round.threshold,
+				constant,
+			);
+			let shares = (0..round.participants.len() as ParticipantIndex)
+				.map(|participant| {
+					let (_, recipient) =
+						encryption_keys.iter().find(|(index, _)| *index == participant)?;
+					let nonce = [&seed[..], b"share", &participant.to_le_bytes()].concat();
+					dkg::encrypt_share(recipient, &polynomial.evaluate(participant), &nonce)
+				})
+				.collect();
+			let dealing = SignedDealing {
+				session: round.session,
+				dealer,
+				dealing: Dealing { commitments: polynomial.commitments(), shares },
+			};
+			let Some(signature) = self.sign(key, &dealing) else { continue };
+			calls.push(RuntimeCall::Dkg(pallet_dkg::Call::deal { dealing, signature }));
+		}
+		calls
+	}
+
+	/// Complaints of the keys taking part in `round` about the dealers that sent them a wrong
+	/// share.
+	fn complaints(
+		&self,
+		at: Hash,
+		round: &DkgRound<AuraId, BlockNumber>,
+		keys: &[sr25519::Public],
+	) -> Vec<RuntimeCall> {
+		let Ok(dealings) = self.client.runtime_api().dealings(at, round.session) else {
+			return Vec::new();
+		};
+		let mut calls = Vec::new();
+		for key in keys {
+			let Some(participant) = index_of(&round.participants, key) else { continue };
+			let (Some(seed), Some(secret)) =
+				(self.seed(key, round.session), self.encryption_secret(key, round.session))
+			else {
+				continue;
+			};
+			for (dealer, dealing) in &dealings {
+				let Some(encrypted) =
+					dealing.shares.get(participant as usize).and_then(Option::as_ref)
+				else {
+					continue;
+				};
+				let right = dkg::decrypt_share(&secret, encrypted).is_some_and(|share| {
+					dkg::verify_share(&dealing.commitments, participant, &share)
+				});
+				if right {
+					continue;
+				}
+				let nonce = [&seed[..], b"complaint", &dealer.to_le_bytes()].concat();
+				let Some((shared_key, proof)) = dkg::reveal_shared_key(&secret, encrypted, &nonce)
+				else {
+					continue;
+				};
+				log::warn!(
+					target: LOG_TARGET,
+					"Dealer {dealer} of session {} sent a wrong share to {key}, complaining",
+					round.session,
+				);
+				let complaint = Complaint {
+					session: round.session,
+					participant,
+					dealer: *dealer,
+					shared_key,
+					proof,
+				};
+				let Some(signature) = self.sign(key, &complaint) else { continue };
+				calls.push(RuntimeCall::Dkg(pallet_dkg::Call::complain { complaint, signature }));
+			}
+		}
+		calls
+	}
+
+	/// The share of `key` in `committee`, recomputed from the dealings on chain.
+	fn share(
+		&mut self,
+		at: Hash,
+		committee: &Committee<AuraId>,
+		key: &sr25519::Public,
+	) -> Option<Fr> {
+		let member = index_of(&committee.members, key)?;
+		if let Some(share) = self.shares.get(&(committee.session, *key)) {
+			return *share;
+		}
+		let secret = self.encryption_secret(key, committee.session)?;
+		let dealings = self.client.runtime_api().dealings(at, committee.session).ok()?;
+
+		let mut shares = Vec::with_capacity(committee.qualified.len());
+		for (dealer, dealing) in dealings {
+			if !committee.qualified.contains(&dealer) {
+				continue;
+			}
+			let share = dealing
+				.shares
+				.get(member as usize)
+				.and_then(Option::as_ref)
+				.and_then(|encrypted| dkg::decrypt_share(&secret, encrypted))
+				.filter(|share| dkg::verify_share(&dealing.commitments, member, share));
+			match share {
+				Some(share) => shares.push((dealer, share)),
+				None => {
+					log::warn!(
+						target: LOG_TARGET,
+						"Dealer {dealer} of session {} sent a wrong share to {key}, not signing",
+						committee.session,
+					);
+					self.shares.insert((committee.session, *key), None);
+					return None;
+				},
+			}
+		}
+		let share = dkg::combine_shares(&shares, committee.reshared);
+		self.shares.insert((committee.session, *key), share);
+		share
+	}
+
+	/// Partial signatures of the current round by the members of `committee` among `keys`, or the
+	/// pulse of the round once enough are on chain.
+	fn signatures(
+		&mut self,
+		at: Hash,
+		committee: &Committee<AuraId>,
+		keys: &[sr25519::Public],
+	) -> Vec<RuntimeCall> {
+		let api = self.client.runtime_api();
+		let Ok(Some(beacon_id)) = api.committee_beacon(at) else { return Vec::new() };
+		let Ok(Some(beacon)) = api.beacon(at, beacon_id) else { return Vec::new() };
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+		let round = beacon.round_at(now);
+		let Ok(partials) = api.partial_signatures(at, committee.session, round) else {
+			return Vec::new();
+		};
+		if partials.len() >= committee.threshold as usize {
+			let latest =
+				api.latest_pulse(at, beacon_id).ok().flatten().map_or(0, |pulse| pulse.round);
+			return (latest < round)
+				.then(|| pulse_call(beacon_id, round, &partials))
+				.flatten()
+				.into_iter()
+				.collect();
+		}
+		drop(api);
+
+		let mut calls = Vec::new();
+		for key in keys {
+			let Some(member) = index_of(&committee.members, key) else { continue };
+			if partials.iter().any(|(index, _)| *index == member) {
+				continue;
+			}
+			let Some(share) = self.share(at, committee, key) else { continue };
+			let Some(signature) = dkg::partial_sign(&share, round) else { continue };
+			let partial = PartialSignature { session: committee.session, member, round, signature };
+			let Some(signature) = self.sign(key, &partial) else { continue };
+			calls.push(RuntimeCall::Dkg(pallet_dkg::Call::submit_partial { partial, signature }));
+		}
+		calls
+	}
+}
+
+/// The index of `key` among `authorities`.
+fn index_of(authorities: &[AuraId], key: &sr25519::Public) -> Option<ParticipantIndex> {
+	let key = AuraId::from(*key);
+	authorities
+		.iter()
+		.position(|authority| *authority == key)
+		.map(|index| index as u32)
+}
+
+/// The call storing `round` of `beacon_id`, signed by aggregating the verified `partials`.
+fn pulse_call(
+	beacon_id: BeaconId,
+	round: RoundNumber,
+	partials: &[(ParticipantIndex, dkg::G1Bytes)],
+) -> Option<RuntimeCall> {
+	let signature = dkg::aggregate_partials(partials)?;
+	let pulse = Pulse {
+		round,
+		randomness: sha2_256(&signature),
+		signature: signature.to_vec().try_into().ok()?,
+		previous_signature: Default::default(),
+	};
+	Some(RuntimeCall::Beacons(pallet_beacons::Call::submit_pulse { beacon_id, pulse }))
+}
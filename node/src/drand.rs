@@ -0,0 +1,262 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Node-side drand fetch settings.
+//!
+//! A chain spec can carry a `drand` extension with the endpoints, timeouts and HTTP proxy its nodes
+//! should use, and every setting can be overridden on the command line. When any endpoint is
+//! configured, the node hands the list to the runtime's offchain worker at startup by writing it
+//! under [`pallet_beacons::ENDPOINTS_OVERRIDE_KEY`] in the persistent offchain storage, where it
+//! takes precedence over the on-chain list. With a proxy, the list handed over is that of a local
+//! relay forwarding to the endpoints through the proxy, see [`crate::drand_proxy`].
+//!
+//! The node remembers the list it wrote under [`NODE_ENDPOINTS_KEY`]. It only replaces or clears
+//! an override it wrote itself, so an override set over RPC survives restarts, and removing the
+//! endpoints from the chain spec and command line hands the offchain worker back to the on-chain
+//! list.
+
+use codec::Encode;
+use pallet_beacons::{Endpoint, ENDPOINTS_OVERRIDE_KEY};
+use sc_client_api::Backend;
+use serde::{Deserialize, Serialize};
+use sp_core::offchain::OffchainStorage;
+
+/// Default timeout of a request, in milliseconds.
+const DEFAULT_TIMEOUT_MS: u32 = 2_000;
+
+/// Default delay before retrying a failed endpoint, in milliseconds.
+const DEFAULT_BACKOFF_MS: u32 = 6_000;
+
+/// Persistent offchain storage key of the endpoint override the node wrote itself, encoded like
+/// the override.
+pub const NODE_ENDPOINTS_KEY: &[u8] = b"idn::drand::node-endpoints";
+
+/// The `drand` chain spec extension.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+pub struct DrandSettings {
+	/// drand HTTP APIs, in order of preference.
+	#[serde(default)]
+	pub endpoints: Vec<String>,
+	/// Timeout of a request, in milliseconds.
+	#[serde(default = "default_timeout_ms")]
+	pub timeout_ms: u32,
+	/// Delay before retrying an endpoint after a first failure, in milliseconds.
+	#[serde(default = "default_backoff_ms")]
+	pub backoff_ms: u32,
+	/// HTTP proxy to fetch from the endpoints through, e.g. `http://10.0.0.1:3128`.
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub proxy: Option<String>,
+}
+
+fn default_timeout_ms() -> u32 {
+	DEFAULT_TIMEOUT_MS
+}
+
+fn default_backoff_ms() -> u32 {
+	DEFAULT_BACKOFF_MS
+}
+
+impl Default for DrandSettings {
+	fn default() -> Self {
+		Self {
+			endpoints: Vec::new(),
+			timeout_ms: DEFAULT_TIMEOUT_MS,
+			backoff_ms: DEFAULT_BACKOFF_MS,
+			proxy: None,
+		}
+	}
+}
+
+/// Command line overrides of the [`DrandSettings`] of the chain spec.
+#[derive(Debug, Clone, Default, clap::Args)]
+pub struct DrandParams {
+	/// drand HTTP API to fetch pulses from, replacing the endpoints of the chain spec and of the
+	/// chain. Repeat the flag to fail over between several endpoints, in order.
+	#[arg(long = "drand-endpoint", value_name = "URL")]
+	pub endpoints: Vec<String>,
+
+	/// Timeout of a drand request, in milliseconds.
+	#[arg(long = "drand-timeout", value_name = "MS")]
+	pub timeout_ms: Option<u32>,
+
+	/// Delay before retrying a failed drand endpoint, in milliseconds. It doubles with every
+	/// further failure.
+	#[arg(long = "drand-backoff", value_name = "MS")]
+	pub backoff_ms: Option<u32>,
+
+	/// HTTP proxy to fetch from the configured drand endpoints through.
+	#[arg(long = "drand-proxy", value_name = "URL")]
+	pub proxy: Option<String>,
+}
+
+impl DrandParams {
+	/// The settings of the chain spec, if any, with the command line overrides applied.
+	pub fn resolve(&self, chain_spec: Option<&DrandSettings>) -> Option<DrandSettings> {
+		let overridden = !self.endpoints.is_empty() ||
+			self.timeout_ms.is_some() ||
+			self.backoff_ms.is_some() ||
+			self.proxy.is_some();
+		if chain_spec.is_none() && !overridden {
+			return None;
+		}
+
+		let mut settings = chain_spec.cloned().unwrap_or_default();
+		if !self.endpoints.is_empty() {
+			settings.endpoints = self.endpoints.clone();
+		}
+		settings.timeout_ms = self.timeout_ms.unwrap_or(settings.timeout_ms);
+		settings.backoff_ms = self.backoff_ms.unwrap_or(settings.backoff_ms);
+		if self.proxy.is_some() {
+			settings.proxy = self.proxy.clone();
+		}
+		Some(settings)
+	}
+}
+
+impl DrandSettings {
+	/// The endpoints in the form the offchain worker reads them.
+	///
+	/// Fails on URLs the runtime would not accept.
+	pub fn offchain_endpoints(&self) -> Result<Vec<Endpoint>, String> {
+		self.endpoints
+			.iter()
+			.map(|url| {
+				let endpoint = Endpoint {
+					url: url
+						.as_bytes()
+						.to_vec()
+						.try_into()
+						.map_err(|_| format!("drand endpoint URL too long: {url}"))?,
+					timeout_ms: self.timeout_ms,
+					backoff_ms: self.backoff_ms,
+				};
+				endpoint
+					.is_valid()
+					.then_some(endpoint)
+					.ok_or_else(|| format!("invalid drand endpoint: {url}"))
+			})
+			.collect()
+	}
+}
+
+/// Make the offchain worker fetch from the endpoints of `settings`.
+///
+/// With a proxy, a relay to the endpoints through it is served for the offchain worker to fetch
+/// from. Without endpoints, an override the node wrote on an earlier start is cleared. An override
+/// written by anyone else, e.g. over RPC, is left in place.
+pub fn apply<B: Backend<ideal_nw_runtime::opaque::Block>>(
+	settings: Option<&DrandSettings>,
+	backend: &B,
+) -> Result<(), String> {
+	let settings = settings.filter(|settings| {
+		if settings.endpoints.is_empty() && settings.proxy.is_some() {
+			log::warn!("No drand endpoints are configured, the drand proxy is ignored");
+		}
+		!settings.endpoints.is_empty()
+	});
+	let endpoints = settings.map(DrandSettings::offchain_endpoints).transpose()?;
+	let Some(mut storage) = backend.offchain_storage() else {
+		return Err("the backend has no offchain storage".into());
+	};
+
+	let prefix = sp_offchain::STORAGE_PREFIX;
+	let current = storage.get(prefix, ENDPOINTS_OVERRIDE_KEY);
+	if current.is_some() && current != storage.get(prefix, NODE_ENDPOINTS_KEY) {
+		log::warn!("Keeping the drand endpoints set over RPC, the configured ones are ignored");
+		return Ok(());
+	}
+	match settings.zip(endpoints) {
+		Some((settings, mut endpoints)) => {
+			if let Some(proxy) = &settings.proxy {
+				let relayed =
+					crate::drand_proxy::spawn(proxy, &settings.endpoints, settings.timeout_ms)?;
+				for (endpoint, url) in endpoints.iter_mut().zip(relayed) {
+					endpoint.url = url
+						.into_bytes()
+						.try_into()
+						.map_err(|_| "drand proxy relay URL too long".to_string())?;
+				}
+				log::info!("Fetching drand pulses through the proxy at {proxy}");
+			}
+			let encoded = endpoints.encode();
+			storage.set(prefix, ENDPOINTS_OVERRIDE_KEY, &encoded);
+			storage.set(prefix, NODE_ENDPOINTS_KEY, &encoded);
+			log::info!("Fetching drand pulses from {}", settings.endpoints.join(", "));
+		},
+		None => {
+			if current.is_some() {
+				log::info!("Fetching drand pulses from the on-chain endpoints");
+			}
+			storage.remove(prefix, ENDPOINTS_OVERRIDE_KEY);
+			storage.remove(prefix, NODE_ENDPOINTS_KEY);
+		},
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn chain_spec_settings_parse() {
+		let settings: DrandSettings = serde_json::from_str(
+			r#"{
+				"endpoints": ["https://api.drand.sh", "https://drand.cloudflare.com"],
+				"timeoutMs": 1500,
+				"proxy": "http://10.0.0.1:3128"
+			}"#,
+		)
+		.unwrap();
+		assert_eq!(
+			settings,
+			DrandSettings {
+				endpoints: vec![
+					"https://api.drand.sh".into(),
+					"https://drand.cloudflare.com".into()
+				],
+				timeout_ms: 1_500,
+				backoff_ms: DEFAULT_BACKOFF_MS,
+				proxy: Some("http://10.0.0.1:3128".into()),
+			}
+		);
+		assert!(serde_json::from_str::<DrandSettings>(r#"{"proxies": []}"#).is_err());
+		assert_eq!(serde_json::from_str::<DrandSettings>("{}").unwrap(), DrandSettings::default());
+	}
+
+	#[test]
+	fn command_line_overrides_the_proxy() {
+		let spec = DrandSettings {
+			endpoints: vec!["https://api.drand.sh".into()],
+			proxy: Some("http://10.0.0.1:3128".into()),
+			..Default::default()
+		};
+		let params =
+			DrandParams { proxy: Some("http://10.0.0.2:8080".into()), ..Default::default() };
+
+		assert_eq!(DrandParams::default().resolve(Some(&spec)), Some(spec.clone()));
+		assert_eq!(
+			params.resolve(Some(&spec)),
+			Some(DrandSettings { proxy: params.proxy.clone(), ..spec })
+		);
+		assert_eq!(
+			params.resolve(None),
+			Some(DrandSettings { proxy: params.proxy.clone(), ..Default::default() })
+		);
+		assert_eq!(DrandParams::default().resolve(None), None);
+	}
+}
@@ -0,0 +1,319 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Relaying the offchain worker's drand requests through an HTTP proxy.
+//!
+//! The offchain worker's HTTP client always connects to endpoints directly. When the `drand`
+//! settings name a proxy, the node serves a relay on localhost and hands the offchain worker the
+//! relay's URLs instead of the endpoints: a request for `http://127.0.0.1:<port>/<index>/<path>`
+//! is forwarded to `<path>` of the endpoint at `<index>` through the proxy. Plain HTTP endpoints
+//! are requested from the proxy in absolute form, HTTPS endpoints over a `CONNECT` tunnel, with
+//! TLS to the endpoint itself.
+
+use std::{
+	io::{self, BufRead, BufReader, Read, Write},
+	net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream},
+	sync::Arc,
+	thread,
+	time::Duration,
+};
+
+/// Longest response head accepted from the proxy to a `CONNECT`.
+const MAX_CONNECT_HEAD: usize = 8 * 1024;
+
+/// Where a request goes.
+#[derive(Debug, Clone, PartialEq)]
+struct Target {
+	tls: bool,
+	host: String,
+	port: u16,
+	path: String,
+}
+
+impl Target {
+	/// Split an `http://` or `https://` URL.
+	fn parse(url: &str) -> Result<Self, String> {
+		let (tls, rest) = if let Some(rest) = url.strip_prefix("https://") {
+			(true, rest)
+		} else if let Some(rest) = url.strip_prefix("http://") {
+			(false, rest)
+		} else {
+			return Err(format!("not an HTTP(S) URL: {url}"));
+		};
+		let (authority, path) = match rest.find('/') {
+			Some(at) => rest.split_at(at),
+			None => (rest, "/"),
+		};
+		let (host, port) = match authority.rsplit_once(':') {
+			Some((host, port)) =>
+				(host, port.parse().map_err(|_| format!("invalid port in {url}"))?),
+			None => (authority, if tls { 443 } else { 80 }),
+		};
+		if host.is_empty() {
+			return Err(format!("no host in {url}"));
+		}
+		Ok(Self { tls, host: host.into(), port, path: path.into() })
+	}
+
+	fn authority(&self) -> String {
+		format!("{}:{}", self.host, self.port)
+	}
+}
+
+/// A relay to some endpoints through a proxy.
+struct Relay {
+	proxy: Target,
+	endpoints: Vec<String>,
+	timeout: Duration,
+	tls: Arc<rustls::ClientConfig>,
+}
+
+/// Serve a relay to `endpoints` through the HTTP proxy at `proxy` on localhost.
+///
+/// Returns the URLs standing in for `endpoints`, in the same order.
+pub fn spawn(proxy: &str, endpoints: &[String], timeout_ms: u32) -> Result<Vec<String>, String> {
+	let proxy = Target::parse(proxy).map_err(|err| format!("invalid drand proxy: {err}"))?;
+	if proxy.tls {
+		return Err("the drand proxy must be an http:// URL".into());
+	}
+	let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0)))
+		.map_err(|err| format!("cannot serve the drand proxy relay: {err}"))?;
+	let port = listener.local_addr().map_err(|err| err.to_string())?.port();
+
+	let relay = Arc::new(Relay {
+		proxy,
+		endpoints: endpoints.to_vec(),
+		timeout: Duration::from_millis(timeout_ms.into()),
+		tls: Arc::new(tls_config()),
+	});
+	thread::Builder::new()
+		.name("drand-proxy-relay".into())
+		.spawn(move || {
+			for stream in listener.incoming().flatten() {
+				let relay = relay.clone();
+				thread::spawn(move || {
+					if let Err(err) = relay.handle(stream) {
+						log::debug!("drand proxy relay: {err}");
+					}
+				});
+			}
+		})
+		.map_err(|err| format!("cannot serve the drand proxy relay: {err}"))?;
+
+	Ok((0..endpoints.len())
+		.map(|index| format!("http://127.0.0.1:{port}/{index}"))
+		.collect())
+}
+
+impl Relay {
+	/// Answer one request of the offchain worker.
+	fn handle(&self, mut client: TcpStream) -> io::Result<()> {
+		client.set_read_timeout(Some(self.timeout))?;
+		let request_line = read_request_head(&client)?;
+		let response = match self.target(&request_line) {
+			Some(target) => self.fetch(&target),
+			None => Err(io::Error::new(io::ErrorKind::InvalidInput, "not a relayed request")),
+		};
+		match response {
+			Ok(response) => client.write_all(&response),
+			Err(err) => {
+				let _ = client.write_all(
+					b"HTTP/1.1 502 Bad Gateway\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+				);
+				Err(err)
+			},
+		}
+	}
+
+	/// The endpoint URL a `GET /<index>/<path>` request line stands for.
+	fn target(&self, request_line: &str) -> Option<Target> {
+		let path = request_line.strip_prefix("GET ")?.split(' ').next()?.strip_prefix('/')?;
+		let (index, rest) = path.split_once('/').unwrap_or((path, ""));
+		let endpoint = self.endpoints.get(index.parse::<usize>().ok()?)?;
+		Target::parse(&format!("{}/{rest}", endpoint.trim_end_matches('/'))).ok()
+	}
+
+	/// Request `target` through the proxy, returning the raw response.
+	fn fetch(&self, target: &Target) -> io::Result<Vec<u8>> {
+		let mut proxy = TcpStream::connect(self.proxy.authority())?;
+		proxy.set_read_timeout(Some(self.timeout))?;
+		proxy.set_write_timeout(Some(self.timeout))?;
+		let mut response = Vec::new();
+
+		if !target.tls {
+			let request = format!(
+				"GET http://{}{} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+				target.authority(),
+				target.path,
+				target.host,
+			);
+			proxy.write_all(request.as_bytes())?;
+			proxy.read_to_end(&mut response)?;
+			return Ok(response);
+		}
+
+		let connect = format!("CONNECT {0} HTTP/1.1\r\nHost: {0}\r\n\r\n", target.authority());
+		proxy.write_all(connect.as_bytes())?;
+		read_tunnel_response(&mut proxy)?;
+		let name = rustls::pki_types::ServerName::try_from(target.host.clone())
+			.map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+		let connection =
+			rustls::ClientConnection::new(self.tls.clone(), name).map_err(io::Error::other)?;
+		let mut stream = rustls::StreamOwned::new(connection, proxy);
+		let request = format!(
+			"GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+			target.path, target.host,
+		);
+		stream.write_all(request.as_bytes())?;
+		match stream.read_to_end(&mut response) {
+			Ok(_) => Ok(response),
+			// Servers commonly close without a TLS close_notify once they have answered.
+			Err(err) if err.kind() == io::ErrorKind::UnexpectedEof && !response.is_empty() =>
+				Ok(response),
+			Err(err) => Err(err),
+		}
+	}
+}
+
+/// Read the head of a request, returning its request line.
+fn read_request_head(stream: &TcpStream) -> io::Result<String> {
+	let mut reader = BufReader::new(stream);
+	let mut request_line = String::new();
+	reader.read_line(&mut request_line)?;
+	let mut header = String::new();
+	while reader.read_line(&mut header)? > 2 {
+		header.clear();
+	}
+	Ok(request_line)
+}
+
+/// Read the proxy's answer to a `CONNECT` up to the end of its head, failing unless it is a 200.
+///
+/// Reads byte by byte so that nothing the endpoint sends through the tunnel is consumed.
+fn read_tunnel_response(proxy: &mut TcpStream) -> io::Result<()> {
+	let mut head = Vec::new();
+	let mut byte = [0; 1];
+	while !head.ends_with(b"\r\n\r\n") {
+		if head.len() >= MAX_CONNECT_HEAD {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "oversized proxy response"));
+		}
+		proxy.read_exact(&mut byte)?;
+		head.push(byte[0]);
+	}
+	if head.split(|&byte| byte == b' ').nth(1) != Some(&b"200"[..]) {
+		let head = String::from_utf8_lossy(&head);
+		let status = head.lines().next().unwrap_or_default();
+		return Err(io::Error::other(format!("the proxy refused the tunnel: {status}")));
+	}
+	Ok(())
+}
+
+/// TLS to endpoints, trusting the Mozilla root certificates.
+fn tls_config() -> rustls::ClientConfig {
+	let roots = rustls::RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+	rustls::ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+		.with_safe_default_protocol_versions()
+		.expect("ring supports the default protocol versions; qed")
+		.with_root_certificates(roots)
+		.with_no_client_auth()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::sync::mpsc;
+
+	/// A proxy answering every connection with `reply`, reporting the request lines it got.
+	fn fake_proxy(reply: &'static [u8]) -> (String, mpsc::Receiver<String>) {
+		let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, 0))).unwrap();
+		let url = format!("http://{}", listener.local_addr().unwrap());
+		let (requests, received) = mpsc::channel();
+		thread::spawn(move || {
+			for mut stream in listener.incoming().flatten() {
+				let request_line = read_request_head(&stream).unwrap();
+				requests.send(request_line.trim_end().to_string()).unwrap();
+				stream.write_all(reply).unwrap();
+			}
+		});
+		(url, received)
+	}
+
+	fn get(url: &str) -> String {
+		let target = Target::parse(url).unwrap();
+		let mut stream = TcpStream::connect(target.authority()).unwrap();
+		let request = format!("GET {} HTTP/1.1\r\nHost: {}\r\n\r\n", target.path, target.host);
+		stream.write_all(request.as_bytes()).unwrap();
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+		response
+	}
+
+	#[test]
+	fn urls_are_split() {
+		assert_eq!(
+			Target::parse("https://api.drand.sh/public/latest"),
+			Ok(Target {
+				tls: true,
+				host: "api.drand.sh".into(),
+				port: 443,
+				path: "/public/latest".into()
+			})
+		);
+		assert_eq!(
+			Target::parse("http://10.0.0.1:3128"),
+			Ok(Target { tls: false, host: "10.0.0.1".into(), port: 3128, path: "/".into() })
+		);
+		assert!(Target::parse("socks5://10.0.0.1:1080").is_err());
+		assert!(Target::parse("http://:80/").is_err());
+	}
+
+	#[test]
+	fn http_endpoints_are_requested_from_the_proxy() {
+		let (proxy, requests) =
+			fake_proxy(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}");
+		let relayed =
+			spawn(&proxy, &["https://a.example".into(), "http://b.example/drand/".into()], 2_000)
+				.unwrap();
+		assert_eq!(relayed.len(), 2);
+
+		let response = get(&format!("{}/52db9b/public/latest", relayed[1]));
+		assert!(response.starts_with("HTTP/1.1 200 OK"));
+		assert!(response.ends_with("{}"));
+		assert_eq!(
+			requests.recv().unwrap(),
+			"GET http://b.example:80/drand/52db9b/public/latest HTTP/1.1"
+		);
+	}
+
+	#[test]
+	fn https_endpoints_are_tunnelled() {
+		let (proxy, requests) = fake_proxy(b"HTTP/1.1 403 Forbidden\r\nContent-Length: 0\r\n\r\n");
+		let relayed = spawn(&proxy, &["https://api.drand.sh".into()], 2_000).unwrap();
+
+		let response = get(&format!("{}/52db9b/public/latest", relayed[0]));
+		assert!(response.starts_with("HTTP/1.1 502 Bad Gateway"));
+		assert_eq!(requests.recv().unwrap(), "CONNECT api.drand.sh:443 HTTP/1.1");
+	}
+
+	#[test]
+	fn unknown_endpoints_are_refused() {
+		let (proxy, _) = fake_proxy(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n");
+		let relayed = spawn(&proxy, &["http://a.example".into()], 2_000).unwrap();
+		let unknown = relayed[0].strip_suffix("/0").unwrap();
+		assert!(get(&format!("{unknown}/1/public/latest")).starts_with("HTTP/1.1 502"));
+		assert!(spawn("https://proxy.example", &[], 2_000).is_err());
+	}
+}
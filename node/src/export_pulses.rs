@@ -0,0 +1,343 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The `export-pulses` sub-command: dump the randomness history from the local database.
+//!
+//! A pulse is exported with the block that stored it and the block timestamp. Pulses are taken
+//! from the `submit_pulse` extrinsics of the block and kept only if the block emitted
+//! `PulseStored` for them, so submissions that failed are left out. Reading the events needs the
+//! state of the block: blocks whose state was pruned are reported and skipped, and a full
+//! export needs an archive node.
+//!
+//! The rounds stored by the former `pallet_drand` are exported first, without a block: its
+//! calls can no longer be decoded, but its `Pulses` map was left in state, see
+//! `ideal_nw_runtime::migrations`. They were all stored up to the block the migration ran in,
+//! and are only exported when that block is in range.
+
+use codec::{Decode, Encode};
+use ideal_nw_primitives::beacon::{BeaconId, Pulse, RoundNumber};
+use ideal_nw_runtime::{
+	opaque::{Block, Hash},
+	BlockNumber, RuntimeCall, RuntimeEvent, UncheckedExtrinsic,
+};
+use sc_cli::{CliConfiguration, Error, SharedParams};
+use sc_client_api::{Backend, BlockBackend, StorageProvider};
+use sp_blockchain::HeaderBackend;
+use sp_core::{hashing::twox_128, storage::StorageKey};
+use std::{
+	collections::BTreeSet,
+	fs::File,
+	io::{self, BufWriter, Write},
+	path::PathBuf,
+	sync::Arc,
+};
+
+/// Output format of [`ExportPulsesCmd`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+	/// One JSON object per line.
+	Jsonl,
+	/// Comma separated values, with a header line.
+	Csv,
+}
+
+/// Export the pulses stored on chain between two blocks or rounds.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct ExportPulsesCmd {
+	/// First block to export from.
+	#[arg(long, value_name = "BLOCK", default_value = "1")]
+	pub from: BlockNumber,
+
+	/// Last block to export from. Defaults to the best block.
+	#[arg(long, value_name = "BLOCK")]
+	pub to: Option<BlockNumber>,
+
+	/// Only export pulses from this round on.
+	#[arg(long, value_name = "ROUND")]
+	pub from_round: Option<RoundNumber>,
+
+	/// Only export pulses up to this round.
+	#[arg(long, value_name = "ROUND")]
+	pub to_round: Option<RoundNumber>,
+
+	/// Only export pulses of this beacon.
+	#[arg(long)]
+	pub beacon_id: Option<BeaconId>,
+
+	/// Leave out the rounds stored by the former `pallet_drand`, which are otherwise exported when
+	/// the blocks in range include the one that migrated them.
+	#[arg(long)]
+	pub without_drand_history: bool,
+
+	/// Output format.
+	#[arg(long, value_enum, default_value_t = Format::Jsonl)]
+	pub format: Format,
+
+	/// File to write to. Defaults to stdout.
+	#[arg(long, short)]
+	pub output: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// A pulse together with the block that stored it, unknown for `pallet_drand` rounds.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+	beacon_id: BeaconId,
+	round: RoundNumber,
+	signature: String,
+	randomness: String,
+	block_number: Option<BlockNumber>,
+	block_hash: Option<String>,
+	timestamp: Option<u64>,
+}
+
+impl Record {
+	const CSV_HEADER: &'static str =
+		"beacon_id,round,signature,randomness,block_number,block_hash,timestamp";
+
+	fn new(beacon_id: BeaconId, pulse: &Pulse, block: Option<(BlockNumber, Hash, u64)>) -> Self {
+		Record {
+			beacon_id,
+			round: pulse.round,
+			signature: hex::encode(&pulse.signature),
+			randomness: hex::encode(pulse.randomness),
+			block_number: block.map(|(number, _, _)| number),
+			block_hash: block.map(|(_, hash, _)| format!("{hash:?}")),
+			timestamp: block.map(|(_, _, timestamp)| timestamp),
+		}
+	}
+
+	fn to_csv(&self) -> String {
+		fn or_empty<T: ToString>(value: &Option<T>) -> String {
+			value.as_ref().map(ToString::to_string).unwrap_or_default()
+		}
+		format!(
+			"{},{},{},{},{},{},{}",
+			self.beacon_id,
+			self.round,
+			self.signature,
+			self.randomness,
+			or_empty(&self.block_number),
+			or_empty(&self.block_hash),
+			or_empty(&self.timestamp)
+		)
+	}
+}
+
+impl ExportPulsesCmd {
+	/// Write out the `pallet_drand` rounds, then walk the blocks in range and write out the
+	/// pulses they stored.
+	pub fn run<C, B>(&self, client: Arc<C>) -> Result<(), Error>
+	where
+		C: HeaderBackend<Block> + BlockBackend<Block> + StorageProvider<Block, B>,
+		B: Backend<Block>,
+	{
+		let to = self.to.unwrap_or(client.info().best_number);
+		let mut output: Box<dyn Write> = match &self.output {
+			Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+			None => Box::new(BufWriter::new(io::stdout().lock())),
+		};
+		if self.format == Format::Csv {
+			writeln!(output, "{}", Record::CSV_HEADER)?;
+		}
+
+		if !self.without_drand_history {
+			let history = drand_history(&*client, client.info().best_hash)?;
+			if let Some((beacon_id, moved_at, pulses)) = history {
+				if (self.from..=to).contains(&moved_at) {
+					for pulse in pulses.iter().filter(|pulse| self.selects(beacon_id, pulse)) {
+						self.write(&mut output, Record::new(beacon_id, pulse, None))?;
+					}
+				}
+			}
+		}
+
+		for number in self.from..=to {
+			let hash = client
+				.hash(number)?
+				.ok_or_else(|| Error::Input(format!("block {number} not found")))?;
+			let body = client
+				.block_body(hash)?
+				.ok_or_else(|| Error::Input(format!("body of block {number} not found")))?;
+			let Some((timestamp, pulses)) = stored_pulses(&*client, hash, &body) else {
+				log::warn!("State of block #{number} is not available, skipping its pulses");
+				continue;
+			};
+
+			for (beacon_id, pulse) in
+				pulses.into_iter().filter(|(id, pulse)| self.selects(*id, pulse))
+			{
+				let block = (number, hash, timestamp);
+				self.write(&mut output, Record::new(beacon_id, &pulse, Some(block)))?;
+			}
+		}
+		output.flush()?;
+		Ok(())
+	}
+
+	fn write(&self, output: &mut impl Write, record: Record) -> Result<(), Error> {
+		match self.format {
+			Format::Jsonl => {
+				let json =
+					serde_json::to_string(&record).map_err(|e| Error::Application(Box::new(e)))?;
+				writeln!(output, "{json}")?
+			},
+			Format::Csv => writeln!(output, "{}", record.to_csv())?,
+		}
+		Ok(())
+	}
+
+	fn selects(&self, beacon_id: BeaconId, pulse: &Pulse) -> bool {
+		self.beacon_id.map_or(true, |id| id == beacon_id) &&
+			self.from_round.map_or(true, |round| pulse.round >= round) &&
+			self.to_round.map_or(true, |round| pulse.round <= round)
+	}
+}
+
+impl CliConfiguration for ExportPulsesCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// The key of a storage value of the runtime.
+fn storage_value_key(pallet: &str, item: &str) -> StorageKey {
+	StorageKey([twox_128(pallet.as_bytes()), twox_128(item.as_bytes())].concat())
+}
+
+/// The `(beacon id, round)` of the pulses block `hash` emitted `PulseStored` for, or `None` if
+/// its state is not available.
+pub(crate) fn stored_rounds<C, B>(
+	client: &C,
+	hash: Hash,
+) -> Option<BTreeSet<(BeaconId, RoundNumber)>>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	type EventRecord = frame_system::EventRecord<RuntimeEvent, Hash>;
+
+	let events = client.storage(hash, &storage_value_key("System", "Events")).ok()?;
+	// Events this runtime can't decode, from before an upgrade that changed their layout, read
+	// as none: the pulses of such blocks are missed.
+	let events = events
+		.and_then(|events| Vec::<EventRecord>::decode(&mut &events.0[..]).ok())
+		.unwrap_or_default();
+	Some(
+		events
+			.into_iter()
+			.filter_map(|record| match record.event {
+				RuntimeEvent::Beacons(pallet_beacons::Event::PulseStored { beacon_id, round }) =>
+					Some((beacon_id, round)),
+				_ => None,
+			})
+			.collect(),
+	)
+}
+
+/// The timestamp of block `hash`, and the pulses it stored, or `None` if its state is not
+/// available.
+///
+/// The pulses are those of its `submit_pulse` extrinsics it emitted `PulseStored` for, see
+/// [`stored_rounds`].
+pub(crate) fn stored_pulses<C, B>(
+	client: &C,
+	hash: Hash,
+	body: &[<Block as sp_runtime::traits::Block>::Extrinsic],
+) -> Option<(u64, Vec<(BeaconId, Pulse)>)>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	let mut stored = stored_rounds(client, hash)?;
+	let (timestamp, mut pulses) = decode_body(body);
+	// A round is stored once, by the first extrinsic that submitted it.
+	pulses.retain(|(beacon_id, pulse)| stored.remove(&(*beacon_id, pulse.round)));
+	Some((timestamp, pulses))
+}
+
+/// The timestamp of a block, and the pulses submitted in it, whether they were stored or not.
+///
+/// Extrinsics this runtime can't decode, e.g. from before a runtime upgrade that changed the
+/// call layout, are skipped.
+pub(crate) fn decode_body(
+	body: &[<Block as sp_runtime::traits::Block>::Extrinsic],
+) -> (u64, Vec<(BeaconId, Pulse)>) {
+	let mut timestamp = 0;
+	let mut pulses = Vec::new();
+	for extrinsic in body {
+		let Ok(extrinsic) = UncheckedExtrinsic::decode(&mut &extrinsic.encode()[..]) else {
+			continue;
+		};
+		match extrinsic.function {
+			RuntimeCall::Timestamp(pallet_timestamp::Call::set { now }) => timestamp = now,
+			RuntimeCall::Beacons(pallet_beacons::Call::submit_pulse { beacon_id, pulse }) =>
+				pulses.push((beacon_id, pulse)),
+			_ => {},
+		}
+	}
+	(timestamp, pulses)
+}
+
+/// A pulse in the encoding of the former `pallet_drand`.
+#[derive(Decode)]
+struct DrandPulse {
+	round: u64,
+	randomness: Vec<u8>,
+	signature: Vec<u8>,
+}
+
+/// The beacon the rounds stored by the former `pallet_drand` were moved to, the block that moved
+/// them and those rounds in order, as of block `hash`.
+fn drand_history<C, B>(
+	client: &C,
+	hash: Hash,
+) -> Result<Option<(BeaconId, BlockNumber, Vec<Pulse>)>, Error>
+where
+	C: StorageProvider<Block, B>,
+	B: Backend<Block>,
+{
+	let value = |item| -> Result<_, Error> {
+		Ok(client.storage(hash, &storage_value_key("Drand", item))?.map(|value| value.0))
+	};
+	let beacon_id = value("MovedTo")?.and_then(|value| BeaconId::decode(&mut &value[..]).ok());
+	let moved_at = value("MovedAt")?.and_then(|value| BlockNumber::decode(&mut &value[..]).ok());
+	let (Some(beacon_id), Some(moved_at)) = (beacon_id, moved_at) else {
+		return Ok(None);
+	};
+	let prefix = storage_value_key("Drand", "Pulses");
+	let mut pulses = Vec::new();
+	for (_, value) in client.storage_pairs(hash, Some(&prefix), None)? {
+		let Ok(pulse) = DrandPulse::decode(&mut &value.0[..]) else { continue };
+		let (Ok(randomness), Ok(signature)) =
+			(pulse.randomness.try_into(), pulse.signature.try_into())
+		else {
+			continue;
+		};
+		pulses.push(Pulse {
+			round: pulse.round,
+			randomness,
+			signature,
+			previous_signature: Default::default(),
+		});
+	}
+	pulses.sort_by_key(|pulse| pulse.round);
+	Ok(Some((beacon_id, moved_at, pulses)))
+}
@@ -0,0 +1,424 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Fair ordering of the transactions of the blocks the node proposes.
+//!
+//! [`FairProposerFactory`] wraps the proposer factory of `sc_basic_authorship` and hands it a
+//! [`FairPool`], which serves the ready transactions in the order of their
+//! [`OrderingKey`]s instead of by priority. The keys are computed by the runtime at the parent of
+//! the block, seeded by its latest pulse, see [`ideal_nw_primitives::ordering`]. Blocks that don't
+//! follow that order are flagged by `pallet_fair_ordering`.
+//!
+//! Fair ordering is opt-in: without it the pool order is kept.
+
+use std::{
+	collections::{HashMap, HashSet},
+	pin::Pin,
+	sync::Arc,
+};
+
+use codec::Encode;
+use futures::Future;
+use ideal_nw_primitives::{api::FairOrderingApi, ordering::OrderingKey};
+use ideal_nw_runtime::{
+	opaque::{Block, Hash},
+	BlockNumber,
+};
+use parking_lot::Mutex;
+use sc_transaction_pool_api::{
+	ImportNotificationStream, InPoolTransaction, PoolFuture, PoolStatus, ReadyTransactions,
+	TransactionFor, TransactionPool, TransactionSource, TransactionStatusStreamFor, TxHash,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::Environment;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor};
+
+const LOG_TARGET: &str = "fair-ordering";
+
+/// A transaction pool serving its ready transactions in fair order.
+pub struct FairPool<P, C> {
+	pool: Arc<P>,
+	client: Arc<C>,
+	enabled: bool,
+	/// The parent of the block being proposed.
+	parent: Mutex<Option<(BlockNumber, Hash)>>,
+}
+
+impl<P, C> FairPool<P, C>
+where
+	P: TransactionPool<Block = Block>,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: FairOrderingApi<Block>,
+{
+	/// Wrap `pool`, ordering its transactions fairly if `enabled`.
+	pub fn new(pool: Arc<P>, client: Arc<C>, enabled: bool) -> Self {
+		Self { pool, client, enabled, parent: Mutex::new(None) }
+	}
+
+	/// The hash of the parent of the block being proposed, at height `number`.
+	fn parent_hash(&self, number: BlockNumber) -> Option<Hash> {
+		match *self.parent.lock() {
+			Some((parent, hash)) if parent == number => Some(hash),
+			_ => self.client.hash(number).ok().flatten(),
+		}
+	}
+}
+
+/// Order `ready` by the keys of the transactions at `parent`.
+///
+/// The pool order is kept if there is no pulse at `parent` yet. Transactions without a key come
+/// last, in pool order.
+fn fair_order<C, T>(client: &C, parent: Hash, ready: impl Iterator<Item = Arc<T>>) -> FairReady<T>
+where
+	C: ProvideRuntimeApi<Block>,
+	C::Api: FairOrderingApi<Block>,
+	T: InPoolTransaction,
+	T::Transaction: Encode,
+{
+	let transactions: Vec<_> = ready.collect();
+	let extrinsics = transactions.iter().map(|tx| tx.data().encode()).collect();
+	let keys = match client.runtime_api().ordering_keys(parent, extrinsics) {
+		Ok(Some(keys)) if keys.len() == transactions.len() => keys,
+		Ok(_) => {
+			log::debug!(target: LOG_TARGET, "No pulse at {parent}, keeping the pool order");
+			return FairReady::new(transactions);
+		},
+		Err(err) => {
+			log::warn!(target: LOG_TARGET, "Failed to order the transactions at {parent}: {err}");
+			return FairReady::new(transactions);
+		},
+	};
+	let mut ordered: Vec<(Option<OrderingKey>, Arc<T>)> =
+		keys.into_iter().zip(transactions).collect();
+	ordered.sort_by_key(|(key, _)| (key.is_none(), *key));
+	FairReady::new(without_unmet_requirements(ordered.into_iter().map(|(_, tx)| tx).collect()))
+}
+
+/// Leave out the transactions that require tags provided by ready transactions ordered after
+/// them.
+///
+/// The transactions of a sender are ordered by nonce, so this only defers transactions depending
+/// on those of another sender to a later block, rather than failing them.
+fn without_unmet_requirements<T: InPoolTransaction>(mut ordered: Vec<Arc<T>>) -> Vec<Arc<T>> {
+	let pending: HashSet<_> = ordered.iter().flat_map(|tx| tx.provides().to_vec()).collect();
+	let mut provided = HashSet::new();
+	let count = ordered.len();
+	ordered.retain(|tx| {
+		let met = tx.requires().iter().all(|tag| !pending.contains(tag) || provided.contains(tag));
+		if met {
+			provided.extend(tx.provides().iter().cloned());
+		}
+		met
+	});
+	if ordered.len() < count {
+		log::debug!(target: LOG_TARGET, "Deferred {} transactions", count - ordered.len());
+	}
+	ordered
+}
+
+/// Ready transactions in a fixed order.
+pub struct FairReady<T> {
+	transactions: std::vec::IntoIter<Arc<T>>,
+	/// Tags provided by transactions reported invalid.
+	invalid: HashSet<Vec<u8>>,
+}
+
+impl<T> FairReady<T> {
+	fn new(transactions: Vec<Arc<T>>) -> Self {
+		Self { transactions: transactions.into_iter(), invalid: HashSet::new() }
+	}
+}
+
+impl<T: InPoolTransaction> Iterator for FairReady<T> {
+	type Item = Arc<T>;
+
+	fn next(&mut self) -> Option<Arc<T>> {
+		loop {
+			let tx = self.transactions.next()?;
+			// Skip the transactions depending on invalid ones, and their own dependents.
+			if tx.requires().iter().any(|tag| self.invalid.contains(tag)) {
+				self.invalid.extend(tx.provides().iter().cloned());
+				continue;
+			}
+			return Some(tx);
+		}
+	}
+}
+
+impl<T: InPoolTransaction> ReadyTransactions for FairReady<T> {
+	fn report_invalid(&mut self, tx: &Self::Item) {
+		self.invalid.extend(tx.provides().iter().cloned());
+	}
+}
+
+type ReadyIterator<P> =
+	Box<dyn ReadyTransactions<Item = Arc<<P as TransactionPool>::InPoolTransaction>> + Send>;
+
+impl<P, C> TransactionPool for FairPool<P, C>
+where
+	P: TransactionPool<Block = Block> + 'static,
+	P::InPoolTransaction: Send + Sync + 'static,
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: FairOrderingApi<Block>,
+{
+	type Block = Block;
+	type Hash = P::Hash;
+	type InPoolTransaction = P::InPoolTransaction;
+	type Error = P::Error;
+
+	fn submit_at(
+		&self,
+		at: Hash,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolFuture<Vec<Result<TxHash<Self>, Self::Error>>, Self::Error> {
+		self.pool.submit_at(at, source, xts)
+	}
+
+	fn submit_one(
+		&self,
+		at: Hash,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TxHash<Self>, Self::Error> {
+		self.pool.submit_one(at, source, xt)
+	}
+
+	fn submit_and_watch(
+		&self,
+		at: Hash,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<Pin<Box<TransactionStatusStreamFor<Self>>>, Self::Error> {
+		self.pool.submit_and_watch(at, source, xt)
+	}
+
+	fn ready_at(
+		&self,
+		at: NumberFor<Block>,
+	) -> Pin<Box<dyn Future<Output = ReadyIterator<P>> + Send>> {
+		let ready = self.pool.ready_at(at);
+		let parent = self.parent_hash(at).filter(|_| self.enabled);
+		let client = self.client.clone();
+		Box::pin(async move {
+			let ready = ready.await;
+			match parent {
+				Some(parent) => Box::new(fair_order(&*client, parent, ready)) as ReadyIterator<P>,
+				None => ready,
+			}
+		})
+	}
+
+	fn ready(&self) -> ReadyIterator<P> {
+		let ready = self.pool.ready();
+		let parent = self.parent.lock().map(|(_, hash)| hash);
+		match parent.filter(|_| self.enabled) {
+			Some(parent) => Box::new(fair_order(&*self.client, parent, ready)),
+			None => ready,
+		}
+	}
+
+	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
+		self.pool.remove_invalid(hashes)
+	}
+
+	fn futures(&self) -> Vec<Self::InPoolTransaction> {
+		self.pool.futures()
+	}
+
+	fn status(&self) -> PoolStatus {
+		self.pool.status()
+	}
+
+	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
+		self.pool.import_notification_stream()
+	}
+
+	fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>) {
+		self.pool.on_broadcasted(propagations)
+	}
+
+	fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self> {
+		self.pool.hash_of(xt)
+	}
+
+	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
+		self.pool.ready_transaction(hash)
+	}
+}
+
+/// A proposer factory whose proposers take their transactions from a [`FairPool`].
+///
+/// It tells the pool the parent of every block it proposes, so that the transactions are ordered
+/// by the latest pulse at that parent rather than at the best block of its height.
+pub struct FairProposerFactory<E, P, C> {
+	inner: E,
+	pool: Arc<FairPool<P, C>>,
+}
+
+impl<E, P, C> FairProposerFactory<E, P, C> {
+	/// Wrap `inner`, a proposer factory taking its transactions from `pool`.
+	pub fn new(inner: E, pool: Arc<FairPool<P, C>>) -> Self {
+		Self { inner, pool }
+	}
+}
+
+impl<E, P, C> Environment<Block> for FairProposerFactory<E, P, C>
+where
+	E: Environment<Block>,
+{
+	type Proposer = E::Proposer;
+	type CreateProposer = E::CreateProposer;
+	type Error = E::Error;
+
+	fn init(&mut self, parent_header: &<Block as BlockT>::Header) -> Self::CreateProposer {
+		*self.pool.parent.lock() = Some((*parent_header.number(), parent_header.hash()));
+		self.inner.init(parent_header)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_api::ApiRef;
+	use sp_runtime::transaction_validity::{
+		TransactionLongevity, TransactionPriority, TransactionTag,
+	};
+
+	/// A client whose runtime returns `keys` for any extrinsics.
+	struct TestClient {
+		keys: Option<Vec<Option<OrderingKey>>>,
+	}
+
+	struct TestApi {
+		keys: Option<Vec<Option<OrderingKey>>>,
+	}
+
+	impl ProvideRuntimeApi<Block> for TestClient {
+		type Api = TestApi;
+
+		fn runtime_api(&self) -> ApiRef<'_, Self::Api> {
+			TestApi { keys: self.keys.clone() }.into()
+		}
+	}
+
+	sp_api::mock_impl_runtime_apis! {
+		impl FairOrderingApi<Block> for TestApi {
+			fn ordering_keys(&self, _: Vec<Vec<u8>>) -> Option<Vec<Option<OrderingKey>>> {
+				self.keys.clone()
+			}
+		}
+	}
+
+	struct TestTransaction {
+		id: u8,
+		requires: Vec<TransactionTag>,
+		provides: Vec<TransactionTag>,
+	}
+
+	impl InPoolTransaction for TestTransaction {
+		type Transaction = u8;
+		type Hash = u8;
+
+		fn data(&self) -> &u8 {
+			&self.id
+		}
+
+		fn hash(&self) -> &u8 {
+			&self.id
+		}
+
+		fn priority(&self) -> &TransactionPriority {
+			&0
+		}
+
+		fn longevity(&self) -> &TransactionLongevity {
+			&TransactionLongevity::MAX
+		}
+
+		fn requires(&self) -> &[TransactionTag] {
+			&self.requires
+		}
+
+		fn provides(&self) -> &[TransactionTag] {
+			&self.provides
+		}
+
+		fn is_propagable(&self) -> bool {
+			true
+		}
+	}
+
+	/// Transaction `id`, requiring the tags `requires` and providing the tags `provides`.
+	fn tx(id: u8, requires: &[&str], provides: &[&str]) -> Arc<TestTransaction> {
+		let tags = |tags: &[&str]| tags.iter().map(|tag| tag.as_bytes().to_vec()).collect();
+		Arc::new(TestTransaction { id, requires: tags(requires), provides: tags(provides) })
+	}
+
+	fn key(rank: u8) -> Option<OrderingKey> {
+		Some(OrderingKey { bucket: 2, rank: [rank; 32], nonce: 0 })
+	}
+
+	fn ordered(
+		keys: Option<Vec<Option<OrderingKey>>>,
+		ready: Vec<Arc<TestTransaction>>,
+	) -> Vec<u8> {
+		fair_order(&TestClient { keys }, Hash::default(), ready.into_iter())
+			.map(|tx| tx.id)
+			.collect()
+	}
+
+	#[test]
+	fn transactions_are_ordered_by_key() {
+		let ready = vec![tx(0, &[], &[]), tx(1, &[], &[]), tx(2, &[], &[]), tx(3, &[], &[])];
+		assert_eq!(ordered(Some(vec![key(3), None, key(1), key(2)]), ready), vec![2, 3, 0, 1]);
+	}
+
+	#[test]
+	fn pool_order_is_kept_without_a_pulse() {
+		let ready = || vec![tx(0, &[], &[]), tx(1, &[], &[]), tx(2, &[], &[])];
+		assert_eq!(ordered(None, ready()), vec![0, 1, 2]);
+		// Nor are keys used that don't match the transactions.
+		assert_eq!(ordered(Some(vec![key(2), key(1)]), ready()), vec![0, 1, 2]);
+	}
+
+	#[test]
+	fn transactions_ordered_before_their_requirements_are_deferred() {
+		// 1 requires a tag of 0, of another sender, and 2 a tag no ready transaction provides.
+		let ready = || vec![tx(0, &[], &["a"]), tx(1, &["a"], &["b"]), tx(2, &["c"], &[])];
+		assert_eq!(ordered(Some(vec![key(1), key(2), key(3)]), ready()), vec![0, 1, 2]);
+		assert_eq!(ordered(Some(vec![key(2), key(1), key(3)]), ready()), vec![0, 2]);
+
+		// Deferring 1 leaves the tags it provides pending for their dependents.
+		let ready = vec![tx(0, &[], &["a"]), tx(1, &["a"], &["b"]), tx(2, &["b"], &[])];
+		assert_eq!(ordered(Some(vec![key(2), key(1), key(3)]), ready), vec![0]);
+	}
+
+	#[test]
+	fn dependents_of_invalid_transactions_are_skipped() {
+		let mut ready = FairReady::new(vec![
+			tx(0, &[], &["a"]),
+			tx(1, &["a"], &["b"]),
+			tx(2, &["b"], &[]),
+			tx(3, &[], &["c"]),
+			tx(4, &["c"], &[]),
+		]);
+		let invalid = ready.next().unwrap();
+		ready.report_invalid(&invalid);
+		assert_eq!(ready.map(|tx| tx.id).collect::<Vec<_>>(), vec![3, 4]);
+	}
+}
@@ -18,11 +18,24 @@
 
 #![warn(missing_docs)]
 
+mod beacon_health;
 mod chain_spec;
 mod cli;
 mod command;
+mod dev_beacon;
+mod dkg_worker;
+mod drand;
+mod drand_proxy;
+mod export_pulses;
+mod fair_ordering;
+mod pulse_archive;
+mod pulse_cache;
+mod pulse_mmr;
+mod pulse_proof;
 mod rpc;
 mod service;
+mod verify_pulse;
+mod vrf_mixing;
 
 fn main() -> sc_cli::Result<()> {
 	command::run()
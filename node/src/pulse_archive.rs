@@ -0,0 +1,433 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Archive of every drand pulse stored on chain.
+//!
+//! The runtime writes each pulse it stores to the offchain database with offchain indexing, see
+//! [`pallet_beacons::archive_key`]. Every imported block does, forks included, so the node
+//! indexes the pulses of finalized blocks, with the hash of the block that stored them, by round
+//! and by block, and serves that index over RPC. Neither depends on the state of old blocks, so
+//! pruned nodes serve the archive too.
+//!
+//! The index lives in the offchain database next to the chain. It is rebuilt from the block
+//! bodies when the node resyncs. A `submit_pulse` extrinsic in a body is only indexed if the
+//! block stored its round: if the runtime archived the same pulse for that block, or else if
+//! the block emitted `PulseStored` for it, which needs the state of the block. The runtime names
+//! the block by its number and parent only, so its entry is not trusted for a block with an
+//! imported sibling, which might have written it. Pulses whose import ran without offchain
+//! indexing are filled in from the events while the state is kept.
+
+use std::sync::Arc;
+
+use codec::{Decode, Encode};
+use futures::StreamExt;
+use ideal_nw_primitives::beacon::{BeaconId, Pulse, RoundNumber};
+use ideal_nw_runtime::{
+	opaque::{Block, Hash},
+	BlockNumber,
+};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::{error::ErrorObject, ErrorObjectOwned},
+};
+use pallet_beacons::{archive_key, ArchivedPulse};
+use parking_lot::Mutex;
+use sc_client_api::{Backend, BlockBackend, BlockchainEvents, StorageProvider};
+use sp_blockchain::{Backend as _, HeaderBackend};
+use sp_core::offchain::OffchainStorage;
+use sp_runtime::traits::Header as _;
+
+use crate::export_pulses::{decode_body, stored_rounds};
+
+/// Key of the [`IndexedPulse`] of a round, followed by the `(beacon id, round)`.
+const PULSE_KEY_PREFIX: &[u8] = b"beacons::index::pulse::";
+
+/// Key of the rounds included by a finalized block, followed by its number.
+const BLOCK_KEY_PREFIX: &[u8] = b"beacons::index::block::";
+
+/// Key of the last indexed block number.
+const HEAD_KEY: &[u8] = b"beacons::index::pulse-head";
+
+/// Maximum number of rounds returned by `idn_archivedPulses`.
+pub const MAX_RANGE: RoundNumber = 1_000;
+
+/// A pulse stored by a finalized block, as indexed by the node.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct IndexedPulse {
+	/// The pulse.
+	pub pulse: Pulse,
+	/// The finalized block that stored it.
+	pub block_number: BlockNumber,
+	/// The hash of that block.
+	pub block_hash: Hash,
+}
+
+fn pulse_key(beacon_id: BeaconId, round: RoundNumber) -> Vec<u8> {
+	[PULSE_KEY_PREFIX, &(beacon_id, round).encode()].concat()
+}
+
+/// Offchain storage, behind a lock so that it can be written through a shared reference.
+trait Store: Send + Sync {
+	fn get(&self, key: &[u8]) -> Option<Vec<u8>>;
+	fn set(&self, key: &[u8], value: &[u8]);
+}
+
+impl<S: OffchainStorage> Store for Mutex<S> {
+	fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.lock().get(sp_offchain::STORAGE_PREFIX, key)
+	}
+
+	fn set(&self, key: &[u8], value: &[u8]) {
+		self.lock().set(sp_offchain::STORAGE_PREFIX, key, value)
+	}
+}
+
+/// The pulse archive, read and written through the node's offchain storage.
+#[derive(Clone)]
+pub struct PulseArchive {
+	store: Arc<dyn Store>,
+}
+
+impl PulseArchive {
+	/// An archive kept in `storage`.
+	pub fn new<S: OffchainStorage + 'static>(storage: S) -> Self {
+		Self { store: Arc::new(Mutex::new(storage)) }
+	}
+
+	fn read<T: Decode>(&self, key: &[u8]) -> Option<T> {
+		self.store.get(key).and_then(|value| T::decode(&mut &value[..]).ok())
+	}
+
+	/// Round `round` of beacon `beacon_id`, with the finalized block that stored it.
+	pub fn pulse(&self, beacon_id: BeaconId, round: RoundNumber) -> Option<IndexedPulse> {
+		self.read(&pulse_key(beacon_id, round))
+	}
+
+	/// The runtime's archive entry of round `round` of beacon `beacon_id`, written by whichever
+	/// block imported last that stored it.
+	fn runtime_entry(
+		&self,
+		beacon_id: BeaconId,
+		round: RoundNumber,
+	) -> Option<ArchivedPulse<BlockNumber, Hash>> {
+		self.read(&archive_key(beacon_id, round))
+	}
+
+	/// The rounds included by finalized block `number`.
+	pub fn block_pulses(&self, number: BlockNumber) -> Vec<(BeaconId, RoundNumber)> {
+		self.read(&[BLOCK_KEY_PREFIX, &number.encode()].concat()).unwrap_or_default()
+	}
+
+	/// The last indexed block.
+	pub fn head(&self) -> Option<BlockNumber> {
+		self.read(HEAD_KEY)
+	}
+
+	/// Index the `pulses` included by finalized block `number`.
+	fn index_block(&self, number: BlockNumber, hash: Hash, pulses: Vec<(BeaconId, Pulse)>) {
+		let mut rounds = Vec::with_capacity(pulses.len());
+		for (beacon_id, pulse) in pulses {
+			let round = pulse.round;
+			let indexed = IndexedPulse { pulse, block_number: number, block_hash: hash };
+			self.store.set(&pulse_key(beacon_id, round), &indexed.encode());
+			rounds.push((beacon_id, round));
+		}
+		if !rounds.is_empty() {
+			self.store.set(&[BLOCK_KEY_PREFIX, &number.encode()].concat(), &rounds.encode());
+		}
+		self.store.set(HEAD_KEY, &number.encode());
+	}
+
+	/// The pulses submitted in the body of block `number` that it stored.
+	///
+	/// A pulse is confirmed by the runtime's own archive entry for this block, see
+	/// [`archived_by`], or else by the events of the block. Pulses that can be confirmed by
+	/// neither, because the block was imported without offchain indexing and its state is
+	/// pruned, are left out.
+	fn stored_pulses<C, B>(
+		&self,
+		client: &C,
+		backend: &B,
+		number: BlockNumber,
+		hash: Hash,
+		body: &[<Block as sp_runtime::traits::Block>::Extrinsic],
+	) -> Vec<(BeaconId, Pulse)>
+	where
+		C: HeaderBackend<Block> + StorageProvider<Block, B>,
+		B: Backend<Block>,
+	{
+		let (_, mut pulses) = decode_body(body);
+		let parent_hash = client.header(hash).ok().flatten().map(|header| *header.parent_hash());
+		let siblings = parent_hash
+			.and_then(|parent_hash| backend.blockchain().children(parent_hash).ok())
+			.unwrap_or_default();
+		let mut events = None;
+		pulses.retain(|(beacon_id, pulse)| {
+			let archived = self.runtime_entry(*beacon_id, pulse.round);
+			if let (Some(archived), Some(parent_hash)) = (archived, parent_hash) {
+				if archived_by(&archived, pulse, number, hash, parent_hash, &siblings) {
+					return true;
+				}
+			}
+			let stored = events.get_or_insert_with(|| stored_rounds(client, hash));
+			match stored {
+				// A round is stored once, by the first extrinsic that submitted it.
+				Some(stored) => stored.remove(&(*beacon_id, pulse.round)),
+				None => {
+					log::debug!(
+						"Cannot confirm round {} of beacon {beacon_id} in block #{number}, not \
+						 archiving it",
+						pulse.round,
+					);
+					false
+				},
+			}
+		});
+		pulses
+	}
+
+	/// Index the finalized blocks not indexed yet.
+	///
+	/// Blocks whose body isn't available, e.g. those before a warp sync target, are skipped.
+	fn catch_up<C, B>(&self, client: &C, backend: &B)
+	where
+		C: HeaderBackend<Block> + BlockBackend<Block> + StorageProvider<Block, B>,
+		B: Backend<Block>,
+	{
+		let finalized = client.info().finalized_number;
+		// The head is past the finalized block after the chain was reverted.
+		let from = self.head().map_or(1, |head| head.min(finalized) + 1);
+		for number in from..=finalized {
+			let Ok(Some(hash)) = client.hash(number) else { return };
+			match client.block_body(hash) {
+				Ok(Some(body)) => {
+					let pulses = self.stored_pulses(client, backend, number, hash, &body);
+					self.index_block(number, hash, pulses)
+				},
+				_ => {
+					log::debug!(
+						"Body of block #{number} is not available, not archiving its pulses"
+					);
+					self.store.set(HEAD_KEY, &number.encode());
+				},
+			}
+		}
+	}
+
+	/// Keep the index up to date with the finalized chain.
+	pub async fn run<C, B>(self, client: Arc<C>, backend: Arc<B>)
+	where
+		C: HeaderBackend<Block>
+			+ BlockBackend<Block>
+			+ BlockchainEvents<Block>
+			+ StorageProvider<Block, B>,
+		B: Backend<Block>,
+	{
+		let mut finality = client.finality_notification_stream();
+		loop {
+			self.catch_up(&*client, &*backend);
+			if finality.next().await.is_none() {
+				return;
+			}
+		}
+	}
+
+	fn info(&self, beacon_id: BeaconId, round: RoundNumber) -> Option<ArchivedPulseInfo> {
+		let IndexedPulse { pulse, block_number, block_hash } = self.pulse(beacon_id, round)?;
+		Some(ArchivedPulseInfo {
+			beacon_id,
+			round,
+			randomness: format!("0x{}", hex::encode(pulse.randomness)),
+			signature: format!("0x{}", hex::encode(&pulse.signature)),
+			previous_signature: format!("0x{}", hex::encode(&pulse.previous_signature)),
+			block_number,
+			block_hash,
+		})
+	}
+}
+
+/// Whether the runtime's archive entry `archived` shows that block `number` with hash `hash`,
+/// child of `parent_hash`, stored `pulse`.
+///
+/// The entry names the block by its number and parent, which only identifies it if it is the
+/// only imported child of `parent_hash`, listed in `siblings`: another child at the same height
+/// may have written the entry.
+fn archived_by(
+	archived: &ArchivedPulse<BlockNumber, Hash>,
+	pulse: &Pulse,
+	number: BlockNumber,
+	hash: Hash,
+	parent_hash: Hash,
+	siblings: &[Hash],
+) -> bool {
+	matches!(siblings, [only] if *only == hash) &&
+		archived.block_number == number &&
+		archived.parent_hash == parent_hash &&
+		archived.pulse == *pulse
+}
+
+/// An archived pulse, as returned over RPC.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedPulseInfo {
+	/// The beacon that signed the pulse.
+	pub beacon_id: BeaconId,
+	/// The round of the pulse.
+	pub round: RoundNumber,
+	/// The randomness of the pulse, hex encoded.
+	pub randomness: String,
+	/// The signature of the pulse, hex encoded.
+	pub signature: String,
+	/// The signature of the previous round for chained beacons, hex encoded.
+	pub previous_signature: String,
+	/// The finalized block that stored the pulse.
+	pub block_number: BlockNumber,
+	/// The hash of that block.
+	pub block_hash: Hash,
+}
+
+/// The block that included a pulse.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Inclusion {
+	/// Number of the block.
+	pub block_number: BlockNumber,
+	/// Hash of the block.
+	pub block_hash: Hash,
+}
+
+/// RPC methods serving the pulse archive.
+#[rpc(server, namespace = "idn")]
+pub trait PulseArchiveApi {
+	/// Round `round` of beacon `beacon_id`.
+	#[method(name = "archivedPulse")]
+	fn archived_pulse(
+		&self,
+		beacon_id: BeaconId,
+		round: RoundNumber,
+	) -> RpcResult<Option<ArchivedPulseInfo>>;
+
+	/// The archived rounds from `from` to `to` of beacon `beacon_id`, at most [`MAX_RANGE`].
+	#[method(name = "archivedPulses")]
+	fn archived_pulses(
+		&self,
+		beacon_id: BeaconId,
+		from: RoundNumber,
+		to: RoundNumber,
+	) -> RpcResult<Vec<ArchivedPulseInfo>>;
+
+	/// The finalized block that included round `round` of beacon `beacon_id`.
+	#[method(name = "pulseInclusion")]
+	fn pulse_inclusion(
+		&self,
+		beacon_id: BeaconId,
+		round: RoundNumber,
+	) -> RpcResult<Option<Inclusion>>;
+
+	/// The pulses included by finalized block `number`.
+	#[method(name = "blockPulses")]
+	fn block_pulses(&self, number: BlockNumber) -> RpcResult<Vec<ArchivedPulseInfo>>;
+}
+
+impl PulseArchiveApiServer for PulseArchive {
+	fn archived_pulse(
+		&self,
+		beacon_id: BeaconId,
+		round: RoundNumber,
+	) -> RpcResult<Option<ArchivedPulseInfo>> {
+		Ok(self.info(beacon_id, round))
+	}
+
+	fn archived_pulses(
+		&self,
+		beacon_id: BeaconId,
+		from: RoundNumber,
+		to: RoundNumber,
+	) -> RpcResult<Vec<ArchivedPulseInfo>> {
+		if to < from || to - from >= MAX_RANGE {
+			return Err(invalid_params(format!("range must hold 1 to {MAX_RANGE} rounds")));
+		}
+		Ok((from..=to).filter_map(|round| self.info(beacon_id, round)).collect())
+	}
+
+	fn pulse_inclusion(
+		&self,
+		beacon_id: BeaconId,
+		round: RoundNumber,
+	) -> RpcResult<Option<Inclusion>> {
+		Ok(self.pulse(beacon_id, round).map(|indexed| Inclusion {
+			block_number: indexed.block_number,
+			block_hash: indexed.block_hash,
+		}))
+	}
+
+	fn block_pulses(&self, number: BlockNumber) -> RpcResult<Vec<ArchivedPulseInfo>> {
+		Ok(PulseArchive::block_pulses(self, number)
+			.into_iter()
+			.filter_map(|(beacon_id, round)| self.info(beacon_id, round))
+			.collect())
+	}
+}
+
+fn invalid_params(message: String) -> ErrorObjectOwned {
+	ErrorObject::owned(jsonrpsee::types::error::INVALID_PARAMS_CODE, message, None::<()>)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{offchain::storage::InMemOffchainStorage, H256};
+
+	fn pulse(round: RoundNumber) -> Pulse {
+		let signature = [round as u8; 48];
+		Pulse {
+			round,
+			randomness: sp_core::hashing::sha2_256(&signature),
+			signature: signature.to_vec().try_into().unwrap(),
+			previous_signature: Default::default(),
+		}
+	}
+
+	#[test]
+	fn forks_at_the_same_height_are_told_apart() {
+		let (parent, a, b) = (H256::repeat_byte(1), H256::repeat_byte(0xa), H256::repeat_byte(0xb));
+		let (other_parent, c) = (H256::repeat_byte(2), H256::repeat_byte(0xc));
+		// Blocks a and b are siblings at height 5, c is at height 5 on another fork. All three
+		// stored round 10, b was imported after a and wrote the runtime's entry last.
+		let entry = ArchivedPulse { pulse: pulse(10), block_number: 5, parent_hash: parent };
+
+		assert!(archived_by(&entry, &pulse(10), 5, a, parent, &[a]));
+		assert!(!archived_by(&entry, &pulse(10), 5, a, parent, &[a, b]));
+		assert!(!archived_by(&entry, &pulse(10), 5, b, parent, &[a, b]));
+		assert!(!archived_by(&entry, &pulse(10), 5, c, other_parent, &[c]));
+		assert!(!archived_by(&entry, &pulse(11), 5, a, parent, &[a]));
+		assert!(!archived_by(&entry, &pulse(10), 6, a, parent, &[a]));
+
+		// The index serves the pulse with the block that was finalized, not the entry's.
+		let archive = PulseArchive::new(InMemOffchainStorage::default());
+		archive.store.set(&archive_key(0, 10), &entry.encode());
+		archive.index_block(5, c, vec![(0, pulse(10))]);
+		assert_eq!(
+			archive.pulse(0, 10),
+			Some(IndexedPulse { pulse: pulse(10), block_number: 5, block_hash: c })
+		);
+		assert_eq!(archive.info(0, 10).map(|info| info.block_hash), Some(c));
+		assert_eq!(archive.block_pulses(5), vec![(0, 10)]);
+		assert_eq!(archive.head(), Some(5));
+		assert_eq!(archive.pulse(0, 11), None);
+	}
+}
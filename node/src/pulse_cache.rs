@@ -0,0 +1,151 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Node-side cache of verified drand pulses.
+//!
+//! The same pulse is typically checked several times before it lands in a block: when the
+//! transaction is received and whenever the pool re-validates it. The cache remembers pulses whose
+//! signature was found valid so those repeated checks can skip the pairing.
+//!
+//! The runtime reads and writes the cache through the offchain local storage host functions, under
+//! [`KEY_PREFIX`], and only while validating transactions. [`PulseCacheExtensions`] registers an
+//! offchain database on every runtime call that serves those keys from the cache, and every other
+//! key from the node's offchain storage.
+
+use std::sync::Arc;
+
+use ideal_nw_primitives::pulse_cache::KEY_PREFIX;
+use ideal_nw_runtime::opaque::{Block, Hash};
+use parking_lot::Mutex;
+use sc_client_api::execution_extensions::ExtensionsFactory;
+use schnellru::{ByLength, LruMap};
+use sp_core::offchain::{
+	storage::OffchainDb, DbExternalities, OffchainDbExt, OffchainStorage, StorageKind,
+};
+use sp_externalities::Extensions;
+use sp_runtime::traits::NumberFor;
+
+/// Number of verified pulses remembered by the cache.
+pub const CACHE_SIZE: u32 = 4096;
+
+/// A bounded, least-recently-used set of verified pulses, by offchain storage key.
+pub struct VerifiedPulseCache {
+	pulses: Mutex<LruMap<Vec<u8>, ()>>,
+}
+
+impl VerifiedPulseCache {
+	/// Create a cache remembering at most `capacity` pulses.
+	pub fn new(capacity: u32) -> Self {
+		Self { pulses: Mutex::new(LruMap::new(ByLength::new(capacity))) }
+	}
+
+	fn contains(&self, key: &[u8]) -> bool {
+		self.pulses.lock().get(key).is_some()
+	}
+
+	fn insert(&self, key: &[u8]) {
+		self.pulses.lock().insert(key.to_vec(), ());
+	}
+
+	fn remove(&self, key: &[u8]) {
+		self.pulses.lock().remove(key);
+	}
+}
+
+impl Default for VerifiedPulseCache {
+	fn default() -> Self {
+		Self::new(CACHE_SIZE)
+	}
+}
+
+/// The offchain database seen by the runtime: verified pulses come from the cache, every other
+/// key from the node's offchain storage, if it has any.
+struct PulseCacheDb<S> {
+	cache: Arc<VerifiedPulseCache>,
+	db: Option<OffchainDb<S>>,
+}
+
+impl<S: OffchainStorage> DbExternalities for PulseCacheDb<S> {
+	fn local_storage_set(&mut self, kind: StorageKind, key: &[u8], value: &[u8]) {
+		if key.starts_with(KEY_PREFIX) {
+			self.cache.insert(key);
+		} else if let Some(db) = &mut self.db {
+			db.local_storage_set(kind, key, value);
+		}
+	}
+
+	fn local_storage_clear(&mut self, kind: StorageKind, key: &[u8]) {
+		if key.starts_with(KEY_PREFIX) {
+			self.cache.remove(key);
+		} else if let Some(db) = &mut self.db {
+			db.local_storage_clear(kind, key);
+		}
+	}
+
+	fn local_storage_compare_and_set(
+		&mut self,
+		kind: StorageKind,
+		key: &[u8],
+		old_value: Option<&[u8]>,
+		new_value: &[u8],
+	) -> bool {
+		if key.starts_with(KEY_PREFIX) {
+			// Cached pulses have no value: the key is either set or not.
+			if self.cache.contains(key) != old_value.is_some() {
+				return false;
+			}
+			self.cache.insert(key);
+			return true;
+		}
+		self.db
+			.as_mut()
+			.is_some_and(|db| db.local_storage_compare_and_set(kind, key, old_value, new_value))
+	}
+
+	fn local_storage_get(&mut self, kind: StorageKind, key: &[u8]) -> Option<Vec<u8>> {
+		if key.starts_with(KEY_PREFIX) {
+			return self.cache.contains(key).then(Vec::new);
+		}
+		self.db.as_mut().and_then(|db| db.local_storage_get(kind, key))
+	}
+}
+
+/// Registers the [`VerifiedPulseCache`] on every runtime call made by the client.
+///
+/// Block execution sees the offchain database too, but the runtime only reads the cache while
+/// validating transactions.
+pub struct PulseCacheExtensions<S> {
+	cache: Arc<VerifiedPulseCache>,
+	offchain_storage: Option<S>,
+}
+
+impl<S> PulseCacheExtensions<S> {
+	/// A cache served alongside `offchain_storage`, the node's offchain storage if it has any.
+	pub fn new(cache: Arc<VerifiedPulseCache>, offchain_storage: Option<S>) -> Self {
+		Self { cache, offchain_storage }
+	}
+}
+
+impl<S: OffchainStorage + 'static> ExtensionsFactory<Block> for PulseCacheExtensions<S> {
+	fn extensions_for(&self, _block_hash: Hash, _block_number: NumberFor<Block>) -> Extensions {
+		let mut extensions = Extensions::new();
+		extensions.register(OffchainDbExt::new(PulseCacheDb {
+			cache: self.cache.clone(),
+			db: self.offchain_storage.clone().map(OffchainDb::new),
+		}));
+		extensions
+	}
+}
@@ -0,0 +1,148 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The `drand_pulseMmrProof` RPC: proofs of old rounds against the MMR of pulses.
+//!
+//! The runtime appends the pulses of every block to an MMR, see [`ideal_nw_primitives::mmr`]. A
+//! round is found in the leaf appended by the block after the one that stored it, which the
+//! [`PulseArchive`] knows. The proof is against the MMR root of the requested block, so it holds
+//! for rounds whose pulses are long pruned from state. It is checked with `mmr_verifyProof`,
+//! `mmr_verifyProofStateless`, or `pallet_mmr::verify_leaves_proof` on another chain.
+
+use std::sync::Arc;
+
+use codec::Encode;
+use ideal_nw_primitives::beacon::{BeaconId, RoundNumber};
+use ideal_nw_runtime::{
+	opaque::{Block, Hash},
+	BlockNumber,
+};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::{error::ErrorObject, ErrorObjectOwned},
+};
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::HeaderBackend;
+use sp_core::{
+	offchain::{storage::OffchainDb, OffchainDbExt, OffchainStorage},
+	Bytes,
+};
+use sp_mmr_primitives::MmrApi;
+
+use crate::pulse_archive::PulseArchive;
+
+/// A proof of a round against the MMR of pulses.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PulseMmrProof {
+	/// The beacon that signed the pulse.
+	pub beacon_id: BeaconId,
+	/// The round of the pulse.
+	pub round: RoundNumber,
+	/// The block whose MMR root the proof is against.
+	pub at: Hash,
+	/// That MMR root, as committed to the header of `at`.
+	pub mmr_root: Hash,
+	/// The block that appended the leaf holding the round.
+	pub leaf_block: BlockNumber,
+	/// The SCALE encoded leaves, as taken by `mmr_verifyProof`.
+	pub leaves: Bytes,
+	/// The SCALE encoded leaf proof.
+	pub proof: Bytes,
+}
+
+/// RPC methods proving rounds against the MMR of pulses.
+#[rpc(server, namespace = "drand")]
+pub trait PulseMmrApi {
+	/// A proof of round `round` of beacon `beacon_id`, 0 by default, against the MMR root of
+	/// block `at`, the best block by default.
+	#[method(name = "pulseMmrProof")]
+	fn pulse_mmr_proof(
+		&self,
+		round: RoundNumber,
+		at: Option<Hash>,
+		beacon_id: Option<BeaconId>,
+	) -> RpcResult<Option<PulseMmrProof>>;
+}
+
+/// Serves [`PulseMmrApiServer`] from the client and the offchain database.
+pub struct PulseMmr<C, S> {
+	client: Arc<C>,
+	archive: PulseArchive,
+	offchain_db: OffchainDb<S>,
+}
+
+impl<C, S> PulseMmr<C, S> {
+	/// Serve proofs of the rounds in `archive` from `client`, whose MMR nodes are in
+	/// `offchain_storage`.
+	pub fn new(client: Arc<C>, archive: PulseArchive, offchain_storage: S) -> Self {
+		Self { client, archive, offchain_db: OffchainDb::new(offchain_storage) }
+	}
+}
+
+impl<C, S> PulseMmrApiServer for PulseMmr<C, S>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+	C::Api: MmrApi<Block, Hash, BlockNumber>,
+	S: OffchainStorage + 'static,
+{
+	fn pulse_mmr_proof(
+		&self,
+		round: RoundNumber,
+		at: Option<Hash>,
+		beacon_id: Option<BeaconId>,
+	) -> RpcResult<Option<PulseMmrProof>> {
+		let beacon_id = beacon_id.unwrap_or_default();
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let Some(archived) = self.archive.pulse(beacon_id, round) else { return Ok(None) };
+		let leaf_block = archived.block_number + 1;
+		let best_known = self
+			.client
+			.number(at)
+			.map_err(internal_error)?
+			.ok_or_else(|| internal_error(format!("block {at} not found")))?;
+		if best_known < leaf_block {
+			return Ok(None);
+		}
+
+		let mut api = self.client.runtime_api();
+		api.register_extension(OffchainDbExt::new(self.offchain_db.clone()));
+		let mmr_root = api.mmr_root(at).map_err(internal_error)?.map_err(internal_error)?;
+		let (leaves, proof) = api
+			.generate_proof(at, vec![leaf_block], Some(best_known))
+			.map_err(internal_error)?
+			.map_err(internal_error)?;
+
+		Ok(Some(PulseMmrProof {
+			beacon_id,
+			round,
+			at,
+			mmr_root,
+			leaf_block,
+			leaves: leaves.encode().into(),
+			proof: proof.encode().into(),
+		}))
+	}
+}
+
+fn internal_error(error: impl std::fmt::Debug) -> ErrorObjectOwned {
+	ErrorObject::owned(
+		jsonrpsee::types::error::INTERNAL_ERROR_CODE,
+		format!("{error:?}"),
+		None::<()>,
+	)
+}
@@ -0,0 +1,137 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The `drand_pulseProof` RPC: stored pulses with a proof against the block's state root.
+//!
+//! Other chains check the proof with [`ideal_nw_primitives::proof`], without trusting the node
+//! that served it.
+
+use std::sync::Arc;
+
+use ideal_nw_primitives::{
+	api::BeaconsApi,
+	beacon::{BeaconId, RoundNumber},
+};
+use ideal_nw_runtime::opaque::{Block, Hash};
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::{error::ErrorObject, ErrorObjectOwned},
+};
+use sc_client_api::ProofProvider;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_core::Bytes;
+use sp_runtime::traits::Header;
+
+/// A stored pulse and the proof of its storage entry.
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PulseProof {
+	/// The beacon that signed the pulse.
+	pub beacon_id: BeaconId,
+	/// The round of the pulse.
+	pub round: RoundNumber,
+	/// The block whose state the proof is against.
+	pub at: Hash,
+	/// The state root of that block.
+	pub state_root: Hash,
+	/// The randomness of the pulse.
+	pub randomness: Bytes,
+	/// The SCALE encoded pulse.
+	pub pulse: Bytes,
+	/// The storage key of the pulse.
+	pub key: Bytes,
+	/// The trie nodes proving the pulse is stored under `key`.
+	pub proof: Vec<Bytes>,
+}
+
+/// RPC methods proving stored pulses.
+#[rpc(server, namespace = "drand")]
+pub trait PulseProofApi {
+	/// Round `round` of beacon `beacon_id`, 0 by default, stored at block `at`, the best block
+	/// by default, with a proof against the block's state root.
+	#[method(name = "pulseProof")]
+	fn pulse_proof(
+		&self,
+		round: RoundNumber,
+		at: Option<Hash>,
+		beacon_id: Option<BeaconId>,
+	) -> RpcResult<Option<PulseProof>>;
+}
+
+/// Serves [`PulseProofApiServer`] from the client.
+pub struct PulseProofs<C> {
+	client: Arc<C>,
+}
+
+impl<C> PulseProofs<C> {
+	/// Serve proofs from `client`.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client }
+	}
+}
+
+impl<C> PulseProofApiServer for PulseProofs<C>
+where
+	C: ProvideRuntimeApi<Block>
+		+ HeaderBackend<Block>
+		+ ProofProvider<Block>
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: BeaconsApi<Block>,
+{
+	fn pulse_proof(
+		&self,
+		round: RoundNumber,
+		at: Option<Hash>,
+		beacon_id: Option<BeaconId>,
+	) -> RpcResult<Option<PulseProof>> {
+		let beacon_id = beacon_id.unwrap_or_default();
+		let at = at.unwrap_or_else(|| self.client.info().best_hash);
+		let api = self.client.runtime_api();
+
+		let Some(pulse) = api.pulse(at, beacon_id, round).map_err(internal_error)? else {
+			return Ok(None);
+		};
+		let key = api.pulse_key(at, beacon_id, round).map_err(internal_error)?;
+		let header = self
+			.client
+			.header(at)
+			.map_err(internal_error)?
+			.ok_or_else(|| internal_error(format!("block {at} not found")))?;
+		let proof = self
+			.client
+			.read_proof(at, &mut std::iter::once(key.as_slice()))
+			.map_err(internal_error)?;
+
+		Ok(Some(PulseProof {
+			beacon_id,
+			round,
+			at,
+			state_root: *header.state_root(),
+			randomness: pulse.randomness.to_vec().into(),
+			pulse: codec::Encode::encode(&pulse).into(),
+			key: key.into(),
+			proof: proof.into_iter_nodes().map(Into::into).collect(),
+		}))
+	}
+}
+
+fn internal_error(error: impl ToString) -> ErrorObjectOwned {
+	ErrorObject::owned(jsonrpsee::types::error::INTERNAL_ERROR_CODE, error.to_string(), None::<()>)
+}
@@ -23,7 +23,10 @@
 
 use std::sync::Arc;
 
-use ideal_nw_runtime::{opaque::Block, AccountId, Balance, Nonce};
+use ideal_nw_runtime::{
+	opaque::{Block, Hash},
+	AccountId, Balance, BlockNumber, Nonce,
+};
 
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
@@ -34,36 +37,65 @@ use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
 pub type RpcExtension = jsonrpsee::RpcModule<()>;
 
 /// Full client dependencies
-pub struct FullDeps<C, P> {
+pub struct FullDeps<C, P, B> {
 	/// The client instance to use.
 	pub client: Arc<C>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
+	/// The backend, whose offchain storage holds the MMR nodes.
+	pub backend: Arc<B>,
+	/// Archive of the pulses stored on chain, if the node has offchain storage.
+	pub pulse_archive: Option<crate::pulse_archive::PulseArchive>,
+	/// Liveness of the beacons.
+	pub beacon_health: crate::beacon_health::BeaconHealth,
 }
 
 /// Instantiate all RPC extensions.
-pub fn create_full<C, P>(
-	deps: FullDeps<C, P>,
+pub fn create_full<C, P, B>(
+	deps: FullDeps<C, P, B>,
 ) -> Result<RpcExtension, Box<dyn std::error::Error + Send + Sync>>
 where
 	C: ProvideRuntimeApi<Block>
 		+ HeaderBackend<Block>
 		+ HeaderMetadata<Block, Error = BlockChainError>
+		+ sc_client_api::ProofProvider<Block>
 		+ Send
 		+ Sync
 		+ 'static,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
 	C::Api: BlockBuilder<Block>,
+	C::Api: ideal_nw_primitives::api::BeaconsApi<Block>,
+	C::Api: sp_mmr_primitives::MmrApi<Block, Hash, BlockNumber>,
 	P: TransactionPool + Sync + Send + 'static,
+	B: sc_client_api::Backend<Block> + Send + Sync + 'static,
 {
+	use crate::{
+		beacon_health::BeaconHealthApiServer,
+		pulse_archive::PulseArchiveApiServer,
+		pulse_mmr::{PulseMmr, PulseMmrApiServer},
+		pulse_proof::{PulseProofApiServer, PulseProofs},
+	};
+	use mmr_rpc::{Mmr, MmrApiServer};
 	use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
+	use sc_client_api::Backend;
 	use substrate_frame_rpc_system::{System, SystemApiServer};
 
 	let mut module = RpcExtension::new(());
-	let FullDeps { client, pool } = deps;
+	let FullDeps { client, pool, backend, pulse_archive, beacon_health } = deps;
 
 	module.merge(System::new(client.clone(), pool).into_rpc())?;
-	module.merge(TransactionPayment::new(client).into_rpc())?;
+	module.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+	module.merge(PulseProofs::new(client.clone()).into_rpc())?;
+	module.merge(beacon_health.into_rpc())?;
+	if let Some(offchain_storage) = backend.offchain_storage() {
+		module.merge(Mmr::new(client.clone(), offchain_storage.clone()).into_rpc())?;
+		if let Some(pulse_archive) = pulse_archive.clone() {
+			module.merge(PulseMmr::new(client, pulse_archive, offchain_storage).into_rpc())?;
+		}
+	}
+	if let Some(pulse_archive) = pulse_archive {
+		module.merge(pulse_archive.into_rpc())?;
+	}
 	Ok(module)
 }
@@ -26,6 +26,16 @@ use ideal_nw_runtime::{
 	opaque::{Block, Hash},
 };
 
+use crate::{
+	beacon_health::BeaconHealth,
+	dkg_worker::DkgWorker,
+	drand::DrandSettings,
+	fair_ordering::{FairPool, FairProposerFactory},
+	pulse_archive::PulseArchive,
+	pulse_cache::{PulseCacheExtensions, VerifiedPulseCache},
+	vrf_mixing::VrfContributions,
+};
+
 // Cumulus Imports
 use cumulus_client_collator::service::CollatorService;
 use cumulus_client_consensus_aura::collators::lookahead::{self as aura, Params as AuraParams};
@@ -45,7 +55,7 @@ use cumulus_relay_chain_interface::{OverseerHandle, RelayChainInterface};
 // Substrate Imports
 use frame_benchmarking_cli::SUBSTRATE_REFERENCE_HARDWARE;
 use prometheus_endpoint::Registry;
-use sc_client_api::Backend;
+use sc_client_api::{Backend, ExecutorProvider};
 use sc_consensus::ImportQueue;
 use sc_executor::{HeapAllocStrategy, WasmExecutor, DEFAULT_HEAP_ALLOC_STRATEGY};
 use sc_network::NetworkBlock;
@@ -112,6 +122,12 @@ pub fn new_partial(config: &Configuration) -> Result<Service, sc_service::Error>
 		)?;
 	let client = Arc::new(client);
 
+	// Lets transaction validation skip re-verifying known pulses.
+	client.execution_extensions().set_extensions_factory(PulseCacheExtensions::new(
+		Arc::new(VerifiedPulseCache::default()),
+		backend.offchain_storage(),
+	));
+
 	let telemetry_worker_handle = telemetry.as_ref().map(|(worker, _)| worker.handle());
 
 	let telemetry = telemetry.map(|(worker, telemetry)| {
@@ -192,16 +208,18 @@ fn start_consensus(
 	collator_key: CollatorPair,
 	overseer_handle: OverseerHandle,
 	announce_block: Arc<dyn Fn(Hash, Option<Vec<u8>>) + Send + Sync>,
+	fair_ordering: bool,
 ) -> Result<(), sc_service::Error> {
+	let transaction_pool = Arc::new(FairPool::new(transaction_pool, client.clone(), fair_ordering));
 	let proposer_factory = sc_basic_authorship::ProposerFactory::with_proof_recording(
 		task_manager.spawn_handle(),
 		client.clone(),
-		transaction_pool,
+		transaction_pool.clone(),
 		prometheus_registry,
 		telemetry.clone(),
 	);
 
-	let proposer = Proposer::new(proposer_factory);
+	let proposer = Proposer::new(FairProposerFactory::new(proposer_factory, transaction_pool));
 
 	let collator_service = CollatorService::new(
 		client.clone(),
@@ -210,8 +228,16 @@ fn start_consensus(
 		client.clone(),
 	);
 
+	let create_inherent_data_providers = {
+		let (client, keystore) = (client.clone(), keystore.clone());
+		move |parent, ()| {
+			let contributions = VrfContributions::new(&*client, &keystore, parent);
+			async move { Ok(contributions) }
+		}
+	};
+
 	let params = AuraParams {
-		create_inherent_data_providers: move |_, ()| async move { Ok(()) },
+		create_inherent_data_providers,
 		block_import,
 		para_client: client.clone(),
 		para_backend: backend,
@@ -246,8 +272,12 @@ pub async fn start_parachain_node(
 	collator_options: CollatorOptions,
 	para_id: ParaId,
 	hwbench: Option<sc_sysinfo::HwBench>,
+	drand: Option<DrandSettings>,
+	fair_ordering: bool,
 ) -> sc_service::error::Result<(TaskManager, Arc<ParachainClient>)> {
-	let parachain_config = prepare_node_config(parachain_config);
+	let mut parachain_config = prepare_node_config(parachain_config);
+	// The runtime archives pulses with offchain indexing, see `crate::pulse_archive`.
+	parachain_config.offchain_worker.indexing_enabled = true;
 
 	let params = new_partial(&parachain_config)?;
 	let (block_import, mut telemetry, telemetry_worker_handle) = params.other;
@@ -298,6 +328,8 @@ pub async fn start_parachain_node(
 	if parachain_config.offchain_worker.enabled {
 		use futures::FutureExt;
 
+		crate::drand::apply(drand.as_ref(), &*backend).map_err(sc_service::Error::Other)?;
+
 		task_manager.spawn_handle().spawn(
 			"offchain-workers-runner",
 			"offchain-work",
@@ -318,13 +350,55 @@ pub async fn start_parachain_node(
 		);
 	}
 
+	let pulse_archive = backend.offchain_storage().map(PulseArchive::new);
+	if let Some(pulse_archive) = pulse_archive.clone() {
+		task_manager.spawn_handle().spawn_blocking(
+			"pulse-archive",
+			None,
+			pulse_archive.run(client.clone(), backend.clone()),
+		);
+	}
+
+	// Moves the MMR nodes of finalized blocks to their canonical offchain keys, for
+	// `crate::pulse_mmr` and `mmr_generateProof` to find them.
+	if backend.offchain_storage().is_some() {
+		task_manager.spawn_essential_handle().spawn_blocking(
+			"mmr-gadget",
+			None,
+			mmr_gadget::MmrGadget::start(
+				client.clone(),
+				backend.clone(),
+				sp_mmr_primitives::INDEXING_PREFIX.to_vec(),
+			),
+		);
+	}
+
+	let beacon_health = BeaconHealth::new(backend.offchain_storage())
+		.map_err(|e| sc_service::Error::Application(Box::new(e)))?;
+	if let Some(registry) = prometheus_registry.as_ref() {
+		beacon_health
+			.register(registry)
+			.map_err(|e| sc_service::Error::Application(Box::new(e)))?;
+	}
+	task_manager.spawn_handle().spawn(
+		"beacon-health",
+		None,
+		beacon_health.clone().run(client.clone()),
+	);
+
 	let rpc_builder = {
 		let client = client.clone();
 		let transaction_pool = transaction_pool.clone();
+		let backend = backend.clone();
 
 		Box::new(move |_| {
-			let deps =
-				crate::rpc::FullDeps { client: client.clone(), pool: transaction_pool.clone() };
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: transaction_pool.clone(),
+				backend: backend.clone(),
+				pulse_archive: pulse_archive.clone(),
+				beacon_health: beacon_health.clone(),
+			};
 
 			crate::rpc::create_full(deps).map_err(Into::into)
 		})
@@ -399,6 +473,16 @@ pub async fn start_parachain_node(
 	})?;
 
 	if validator {
+		task_manager.spawn_handle().spawn(
+			"dkg-worker",
+			None,
+			DkgWorker::new(
+				client.clone(),
+				transaction_pool.clone(),
+				params.keystore_container.keystore(),
+			)
+			.run(),
+		);
 		start_consensus(
 			client.clone(),
 			backend,
@@ -414,6 +498,7 @@ pub async fn start_parachain_node(
 			collator_key.expect("Command line arguments do not allow this. qed"),
 			overseer_handle,
 			announce_block,
+			fair_ordering,
 		)?;
 	}
 
@@ -0,0 +1,289 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The `verify-pulse` sub-command: check a pulse without running a chain.
+//!
+//! The pulse is checked with the verifier the runtime uses, so that disputes over a randomness
+//! value can be settled in a script: the exit status is [`Verdict::exit_code`] when the pulse
+//! could be checked, and [`EXIT_ERROR`] when it could not, because of a bad argument or a
+//! missing pulse or beacon.
+//!
+//! A pulse read from the local database is checked against the beacon as it stood in the block
+//! that stored the round, since a transition applied since may have changed its key.
+
+use codec::{Decode, Encode};
+use ideal_nw_primitives::{
+	api::BeaconsApi,
+	beacon::{BeaconId, BeaconInfo, ChainHash, Pulse, RoundNumber, SignatureScheme},
+	bls,
+	dev::DevBeacon,
+};
+use ideal_nw_runtime::opaque::{Block, Hash};
+use sc_cli::{CliConfiguration, Error, SharedParams};
+use serde::Deserialize;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use std::{str::FromStr, sync::Arc};
+
+/// The exit status when the pulse could not be checked.
+pub const EXIT_ERROR: i32 = 2;
+
+/// The outcome of checking a pulse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+	/// The pulse is valid.
+	Valid,
+	/// The pulse is invalid, for the given reason.
+	Invalid(&'static str),
+}
+
+impl Verdict {
+	/// The exit status to report the verdict with.
+	pub fn exit_code(&self) -> i32 {
+		match self {
+			Verdict::Valid => 0,
+			Verdict::Invalid(_) => 1,
+		}
+	}
+}
+
+/// Verify a drand pulse and print the randomness derived from it.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct VerifyPulseCmd {
+	/// The pulse, as drand serves it in JSON or as hex encoded SCALE. Read from the local
+	/// database with `--beacon-id` when omitted.
+	#[arg(long, value_name = "JSON|HEX")]
+	pub pulse: Option<String>,
+
+	/// The beacon: `quicknet`, `dev`, its drand `/info` JSON, or `@path` of a file holding it.
+	/// Read from the local database with `--beacon-id` when omitted.
+	#[arg(long, value_name = "BEACON")]
+	pub beacon: Option<String>,
+
+	/// Id of the beacon in the runtime, to read the beacon or the pulse from the local database.
+	#[arg(long)]
+	pub beacon_id: Option<BeaconId>,
+
+	/// Round of the pulse read from the local database. Defaults to the latest stored one.
+	#[arg(long, requires = "beacon_id")]
+	pub round: Option<RoundNumber>,
+
+	/// Block, by number or hash, to read from. Defaults to the best block.
+	#[arg(long, value_name = "BLOCK", requires = "beacon_id")]
+	pub at: Option<String>,
+
+	/// Subject to derive randomness for, as the runtime does: UTF-8 text, or hex with a `0x`
+	/// prefix.
+	#[arg(long, default_value = "")]
+	pub subject: String,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+}
+
+/// A beacon as drand describes it at `/info`.
+#[derive(Deserialize)]
+struct DrandInfo {
+	public_key: String,
+	period: u32,
+	genesis_time: u64,
+	hash: String,
+	#[serde(rename = "schemeID")]
+	scheme_id: String,
+}
+
+impl VerifyPulseCmd {
+	/// Whether the pulse or the beacon are read from the local database.
+	pub fn needs_database(&self) -> bool {
+		self.beacon_id.is_some() && (self.pulse.is_none() || self.beacon.is_none())
+	}
+
+	/// Verify a pulse given on the command line against a beacon given on the command line.
+	pub fn run(&self) -> Result<Verdict, Error> {
+		let (Some(pulse), Some(beacon)) = (&self.pulse, &self.beacon) else {
+			return Err(Error::Input(
+				"`--pulse` and `--beacon`, or `--beacon-id`, are needed".into(),
+			));
+		};
+		self.verify(&parse_beacon(beacon)?, &parse_pulse(pulse)?)
+	}
+
+	/// Verify a pulse, reading whatever is not given on the command line from `client`.
+	pub fn run_with_client<C>(&self, client: Arc<C>) -> Result<Verdict, Error>
+	where
+		C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+		C::Api: BeaconsApi<Block>,
+	{
+		let beacon_id =
+			self.beacon_id.ok_or_else(|| Error::Input("`--beacon-id` is needed".into()))?;
+		let at = match &self.at {
+			None => client.info().best_hash,
+			Some(at) => match at.parse::<u32>() {
+				Ok(number) => client
+					.hash(number)?
+					.ok_or_else(|| Error::Input(format!("block {number} not found")))?,
+				Err(_) =>
+					Hash::from_str(at).map_err(|_| Error::Input(format!("invalid block: {at}")))?,
+			},
+		};
+		let api = client.runtime_api();
+		let runtime_error = |e: sp_api::ApiError| Error::Application(Box::new(e));
+
+		let pulse = match (&self.pulse, self.round) {
+			(Some(pulse), _) => parse_pulse(pulse)?,
+			(None, Some(round)) => api.pulse(at, beacon_id, round).map_err(runtime_error)?,
+			(None, None) => api.latest_pulse(at, beacon_id).map_err(runtime_error)?,
+		}
+		.ok_or_else(|| Error::Input(format!("no such pulse of beacon {beacon_id} at {at}")))?;
+		let beacon = match &self.beacon {
+			Some(beacon) => parse_beacon(beacon)?,
+			None => {
+				// The beacon accepted the round as it stood before the block storing it.
+				let before = storing_block(&*client, at, beacon_id, &pulse)?
+					.map(|number| client.hash(number.saturating_sub(1)))
+					.transpose()?
+					.flatten()
+					.unwrap_or(at);
+				api.beacon_at(before, beacon_id, pulse.round)
+					.map_err(runtime_error)?
+					.ok_or_else(|| Error::Input(format!("beacon {beacon_id} not found at {at}")))?
+			},
+		};
+		self.verify(&beacon, &pulse)
+	}
+
+	fn verify(&self, beacon: &BeaconInfo, pulse: &Pulse) -> Result<Verdict, Error> {
+		println!("beacon:     {}", hex::encode(beacon.chain_hash));
+		println!("round:      {}", pulse.round);
+		println!("signature:  {}", hex::encode(&pulse.signature));
+
+		let subject = parse_subject(&self.subject)?;
+		let verdict = if pulse.randomness != sp_core::hashing::sha2_256(&pulse.signature) {
+			Verdict::Invalid("the randomness is not the hash of the signature")
+		} else if !bls::verify_pulse(beacon, pulse) {
+			Verdict::Invalid("the signature does not verify")
+		} else {
+			Verdict::Valid
+		};
+		if let Verdict::Invalid(reason) = verdict {
+			println!("invalid: {reason}");
+			return Ok(verdict);
+		}
+
+		let derived =
+			sp_core::hashing::blake2_256(&(subject.as_slice(), pulse.randomness).encode());
+		println!("randomness: {}", hex::encode(pulse.randomness));
+		println!("derived:    0x{}", hex::encode(derived));
+		println!("valid");
+		Ok(Verdict::Valid)
+	}
+}
+
+/// The number of the canonical block up to `at` that stored `pulse`, if the state needed to find
+/// it is still available.
+///
+/// That is the first block whose latest round of `beacon_id` reaches the round of `pulse`, which
+/// is only meaningful while the beacon's round numbers do not restart, so the block found is
+/// checked to hold `pulse`.
+fn storing_block<C>(
+	client: &C,
+	at: Hash,
+	beacon_id: BeaconId,
+	pulse: &Pulse,
+) -> Result<Option<u32>, Error>
+where
+	C: ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: BeaconsApi<Block>,
+{
+	let Some(mut high) = client.number(at)? else { return Ok(None) };
+	let api = client.runtime_api();
+	let reached = |number: u32| -> Result<Option<bool>, Error> {
+		let Some(hash) = client.hash(number)? else { return Ok(None) };
+		Ok(api
+			.latest_pulse(hash, beacon_id)
+			.ok()
+			.map(|latest| latest.map_or(false, |latest| latest.round >= pulse.round)))
+	};
+	let mut low = 0;
+	while low < high {
+		let middle = low + (high - low) / 2;
+		match reached(middle)? {
+			Some(true) => high = middle,
+			Some(false) => low = middle + 1,
+			// The state of older blocks has been pruned.
+			None => return Ok(None),
+		}
+	}
+	let Some(hash) = client.hash(high)? else { return Ok(None) };
+	let stored = api.pulse(hash, beacon_id, pulse.round).ok().flatten();
+	Ok((stored.as_ref() == Some(pulse)).then_some(high))
+}
+
+impl CliConfiguration for VerifyPulseCmd {
+	fn shared_params(&self) -> &SharedParams {
+		&self.shared_params
+	}
+}
+
+/// Parse a pulse given as drand JSON or hex encoded SCALE.
+fn parse_pulse(pulse: &str) -> Result<Pulse, Error> {
+	let invalid = || Error::Input(format!("invalid pulse: {pulse}"));
+	if pulse.trim_start().starts_with('{') {
+		return pallet_beacons::parse_drand_pulse(pulse.as_bytes()).ok_or_else(invalid);
+	}
+	let bytes = hex::decode(pulse.trim().trim_start_matches("0x")).map_err(|_| invalid())?;
+	Pulse::decode(&mut &bytes[..]).map_err(|_| invalid())
+}
+
+/// Parse a beacon given by name, as drand's `/info` JSON or as a file holding it.
+fn parse_beacon(beacon: &str) -> Result<BeaconInfo, Error> {
+	match beacon {
+		"quicknet" => return Ok(BeaconInfo::quicknet()),
+		"dev" => return Ok(DevBeacon::default().info().clone()),
+		_ => {},
+	}
+	let json = match beacon.strip_prefix('@') {
+		Some(path) => std::fs::read_to_string(path)?,
+		None => beacon.to_owned(),
+	};
+	let invalid = |what: &str| Error::Input(format!("invalid beacon {what}"));
+	let info: DrandInfo = serde_json::from_str(&json).map_err(|e| invalid(&e.to_string()))?;
+	let chain_hash: ChainHash = hex::decode(&info.hash)
+		.ok()
+		.and_then(|hash| hash.try_into().ok())
+		.ok_or_else(|| invalid("hash"))?;
+	Ok(BeaconInfo {
+		chain_hash,
+		public_key: hex::decode(&info.public_key)
+			.ok()
+			.and_then(|key| key.try_into().ok())
+			.ok_or_else(|| invalid("public key"))?,
+		period: info.period,
+		genesis_time: info.genesis_time,
+		scheme: SignatureScheme::from_scheme_id(&info.scheme_id)
+			.ok_or_else(|| invalid("scheme"))?,
+	})
+}
+
+/// Parse a subject given as text or as `0x` prefixed hex.
+fn parse_subject(subject: &str) -> Result<Vec<u8>, Error> {
+	match subject.strip_prefix("0x") {
+		Some(hex) =>
+			hex::decode(hex).map_err(|_| Error::Input(format!("invalid subject: {subject}"))),
+		None => Ok(subject.as_bytes().to_vec()),
+	}
+}
@@ -0,0 +1,78 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The block author's contribution to the mixed randomness.
+//!
+//! The node evaluates the VRF over the latest pulse with every Aura key in its keystore, and the
+//! runtime includes the one of the slot's author, see [`ideal_nw_primitives::mixing`].
+
+use ideal_nw_primitives::{
+	api::MixedRandomnessApi,
+	mixing::{vrf_sign_data, InherentType, VrfContribution, INHERENT_IDENTIFIER},
+};
+use ideal_nw_runtime::{
+	opaque::{Block, Hash},
+	BlockNumber,
+};
+use sp_api::ProvideRuntimeApi;
+use sp_core::crypto::key_types::AURA;
+use sp_inherents::{InherentData, InherentIdentifier};
+use sp_keystore::KeystorePtr;
+
+/// Provides the VRF contributions of the node's Aura keys as inherent data.
+pub struct VrfContributions(InherentType);
+
+impl VrfContributions {
+	/// The contributions of the Aura keys in `keystore` to a block built on `parent`.
+	pub fn new<C>(client: &C, keystore: &KeystorePtr, parent: Hash) -> Self
+	where
+		C: ProvideRuntimeApi<Block>,
+		C::Api: MixedRandomnessApi<Block, BlockNumber>,
+	{
+		let Ok(Some((round, randomness))) = client.runtime_api().vrf_input(parent) else {
+			return Self(Vec::new());
+		};
+		let data = vrf_sign_data(round, &randomness);
+		let contributions = keystore
+			.sr25519_public_keys(AURA)
+			.into_iter()
+			.filter_map(|public| {
+				let signature = keystore.sr25519_vrf_sign(AURA, &public, &data).ok()??;
+				Some(VrfContribution { public, signature })
+			})
+			.collect();
+		Self(contributions)
+	}
+}
+
+#[async_trait::async_trait]
+impl sp_inherents::InherentDataProvider for VrfContributions {
+	async fn provide_inherent_data(
+		&self,
+		inherent_data: &mut InherentData,
+	) -> Result<(), sp_inherents::Error> {
+		inherent_data.put_data(INHERENT_IDENTIFIER, &self.0)
+	}
+
+	async fn try_handle_error(
+		&self,
+		_identifier: &InherentIdentifier,
+		_error: &[u8],
+	) -> Option<Result<(), sp_inherents::Error>> {
+		// The runtime only fails the check when a required contribution is missing, which is fatal.
+		None
+	}
+}
@@ -0,0 +1,53 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Benchmarks for `pallet_beacon_time`.
+
+use super::*;
+use frame_benchmarking::v2::*;
+
+/// The latest verified round.
+const ROUND: RoundNumber = 1_000;
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn report_time(c: Linear<0, { T::MaxCallbackLen::get() }>) {
+		T::BenchmarkHelper::set_latest_round(ROUND);
+		let origin = T::BenchmarkHelper::reporting_origin();
+		let callback = BoundedVec::truncate_from(vec![7; c as usize]);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, callback, Weight::zero());
+	}
+
+	#[benchmark]
+	fn check_drift() {
+		T::BenchmarkHelper::set_latest_round(ROUND);
+
+		// A timestamp at the epoch is behind, and starts drifting.
+		#[block]
+		{
+			Pallet::<T>::check_drift(0);
+		}
+
+		assert!(Pallet::<T>::is_drifting());
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,237 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Beacon Time Pallet
+//!
+//! A time oracle anchored to drand rather than to the collators' clocks.
+//!
+//! A drand round can't be signed before the time it is due, so the latest verified round of the
+//! beacon of [`Config::Source`] is a lower bound of the current time. The oracle reports that
+//! lower bound, see [`ideal_nw_primitives::time`] for why it reports no upper bound:
+//!
+//! - to the runtime and over the runtime API, through [`Pallet::beacon_time`],
+//! - to other chains over XCM: a chain that [`report_time`](Pallet::report_time)s gets the time
+//!   back as a `Transact` of the call it passed, with the SCALE encoded [`BeaconTime`] appended.
+//!
+//! [`CheckDrift`] optionally checks the block timestamps against it, flagging those more than
+//! [`Config::MaxDrift`] behind beacon time. Timestamps ahead of it can't be told apart from
+//! rounds that reached the chain late, and aren't flagged.
+//!
+//! There is no time while the beacon is stalled or has no pulse yet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub use pallet::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+use alloc::vec;
+use core::marker::PhantomData;
+use frame_support::{pallet_prelude::*, traits::OnTimestampSet};
+pub use ideal_nw_primitives::{
+	beacon::{BeaconId, BeaconInfo, RoundNumber},
+	time::BeaconTime,
+};
+use sp_runtime::traits::Saturating;
+use xcm::latest::prelude::*;
+
+pub use weights::WeightInfo;
+
+const LOG_TARGET: &str = "runtime::beacon-time";
+
+/// A beacon, its configuration at its latest verified round and that round.
+pub type VerifiedRound = (BeaconId, BeaconInfo, RoundNumber);
+
+/// The beacon the oracle reads the time from.
+pub trait TimeSource {
+	/// The latest verified round of the beacon, unless it has no pulse or is stalled.
+	fn latest_round() -> Option<VerifiedRound>;
+}
+
+/// Helper used by the benchmarks to set up the beacon and the destination of time reports.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<RuntimeOrigin> {
+	/// Make `round` the latest verified round of [`Config::Source`].
+	fn set_latest_round(round: RoundNumber);
+
+	/// An origin of [`Config::XcmOrigin`] whose location time reports can be sent to.
+	fn reporting_origin() -> RuntimeOrigin;
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The beacon the time is read from.
+		type Source: TimeSource;
+
+		/// Milliseconds the block timestamp may be behind beacon time before [`CheckDrift`]
+		/// flags it.
+		#[pallet::constant]
+		type MaxDrift: Get<u64>;
+
+		/// The origin of time queries over XCM, resolving to the location the time is reported
+		/// to.
+		type XcmOrigin: EnsureOrigin<Self::RuntimeOrigin, Success = Location>;
+
+		/// Sends the time reports.
+		type XcmSender: SendXcm;
+
+		/// Maximum length of the call a time report is sent back as.
+		#[pallet::constant]
+		type MaxCallbackLen: Get<u32>;
+
+		/// Weight information for extrinsics and hooks in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Helper to set up the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<Self::RuntimeOrigin>;
+	}
+
+	/// The block since which block timestamps are behind beacon time by more than
+	/// [`Config::MaxDrift`], while they are.
+	#[pallet::storage]
+	pub type DriftingSince<T: Config> = StorageValue<_, BlockNumberFor<T>, OptionQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The time was reported to `destination` over XCM.
+		TimeReported { destination: Location, time: BeaconTime },
+		/// The block timestamp is `drift` milliseconds behind beacon time.
+		TimestampDrifting { timestamp: u64, time: BeaconTime, drift: u64 },
+		/// The block timestamp is back within [`Config::MaxDrift`] of beacon time.
+		TimestampBackInBounds { drifting_blocks: BlockNumberFor<T> },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The beacon is stalled or has no pulse yet.
+		NoBeaconTime,
+		/// The time report could not be sent.
+		SendFailure,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+			// Reserved for `CheckDrift`, which runs when the timestamp is set.
+			T::WeightInfo::check_drift()
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Report the beacon time to the origin's location, as a `Transact` of `callback` with
+		/// the SCALE encoded [`BeaconTime`] appended, dispatched with at most `weight`.
+		///
+		/// The report is unpaid: the destination must allow unpaid execution from this chain.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::report_time(callback.len() as u32))]
+		pub fn report_time(
+			origin: OriginFor<T>,
+			callback: BoundedVec<u8, T::MaxCallbackLen>,
+			weight: Weight,
+		) -> DispatchResult {
+			let destination = T::XcmOrigin::ensure_origin(origin)?;
+			let time = Self::beacon_time().ok_or(Error::<T>::NoBeaconTime)?;
+			let call = [&callback[..], &time.encode()[..]].concat();
+			let message = Xcm(vec![
+				UnpaidExecution { weight_limit: Unlimited, check_origin: None },
+				Transact {
+					origin_kind: OriginKind::Native,
+					require_weight_at_most: weight,
+					call: call.into(),
+				},
+			]);
+			send_xcm::<T::XcmSender>(destination.clone(), message)
+				.map_err(|_| Error::<T>::SendFailure)?;
+
+			Self::deposit_event(Event::TimeReported { destination, time });
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// A lower bound of the current time according to the latest verified round, unless the
+	/// beacon is stalled or has no pulse yet.
+	pub fn beacon_time() -> Option<BeaconTime> {
+		let (beacon_id, info, round) = T::Source::latest_round()?;
+		Some(BeaconTime { beacon_id, round, time: info.round_time(round).saturating_mul(1_000) })
+	}
+
+	/// Whether block timestamps are behind beacon time by more than [`Config::MaxDrift`].
+	pub fn is_drifting() -> bool {
+		DriftingSince::<T>::exists()
+	}
+
+	/// Flag `timestamp` if it is behind beacon time by more than [`Config::MaxDrift`].
+	fn check_drift(timestamp: u64) {
+		let Some(time) = Self::beacon_time() else { return };
+		let drift = time.lag(timestamp);
+		let now = frame_system::Pallet::<T>::block_number();
+		match (drift > T::MaxDrift::get(), DriftingSince::<T>::get()) {
+			(true, None) => {
+				log::warn!(
+					target: LOG_TARGET,
+					"timestamp {timestamp} is {drift}ms behind round {} of beacon {}",
+					time.round,
+					time.beacon_id,
+				);
+				DriftingSince::<T>::put(now);
+				Self::deposit_event(Event::TimestampDrifting { timestamp, time, drift });
+			},
+			(false, Some(since)) => {
+				DriftingSince::<T>::kill();
+				let drifting_blocks = now.saturating_sub(since);
+				Self::deposit_event(Event::TimestampBackInBounds { drifting_blocks });
+			},
+			_ => {},
+		}
+	}
+}
+
+/// Checks block timestamps against beacon time after passing them on to `Inner`.
+///
+/// Use it as `pallet_timestamp`'s `OnTimestampSet` to flag timestamps more than
+/// [`Config::MaxDrift`] behind beacon time. Flagged timestamps are still accepted.
+pub struct CheckDrift<T, Inner>(PhantomData<(T, Inner)>);
+
+impl<T: Config, Inner: OnTimestampSet<u64>> OnTimestampSet<u64> for CheckDrift<T, Inner> {
+	fn on_timestamp_set(moment: u64) {
+		Inner::on_timestamp_set(moment);
+		Pallet::<T>::check_drift(moment);
+	}
+}
@@ -0,0 +1,131 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::{self as pallet_beacon_time, VerifiedRound};
+use frame_support::{derive_impl, parameter_types, traits::EnsureOrigin};
+use ideal_nw_primitives::beacon::{BeaconInfo, RoundNumber};
+use sp_runtime::BuildStorage;
+use xcm::latest::prelude::*;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		TimeOracle: pallet_beacon_time,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+}
+
+pub const MAX_DRIFT: u64 = 10_000;
+
+parameter_types! {
+	pub const MaxDrift: u64 = MAX_DRIFT;
+	pub const MaxCallbackLen: u32 = 8;
+	pub static Latest: Option<VerifiedRound> = None;
+	pub static Sent: Vec<(Location, Xcm<()>)> = Vec::new();
+}
+
+/// The latest round set with [`store_round`].
+pub struct MockSource;
+
+impl pallet_beacon_time::TimeSource for MockSource {
+	fn latest_round() -> Option<VerifiedRound> {
+		Latest::get()
+	}
+}
+
+/// Signed origins of account `n` stand for sibling parachain `n`.
+pub struct EnsureSibling;
+
+impl EnsureOrigin<RuntimeOrigin> for EnsureSibling {
+	type Success = Location;
+
+	fn try_origin(origin: RuntimeOrigin) -> Result<Location, RuntimeOrigin> {
+		frame_system::ensure_signed(origin.clone())
+			.map(|who| Location::new(1, [Parachain(who as u32)]))
+			.map_err(|_| origin)
+	}
+
+	#[cfg(feature = "runtime-benchmarks")]
+	fn try_successful_origin() -> Result<RuntimeOrigin, ()> {
+		Ok(RuntimeOrigin::signed(2_000))
+	}
+}
+
+/// Records the messages it sends in [`Sent`].
+pub struct MockSender;
+
+impl SendXcm for MockSender {
+	type Ticket = (Location, Xcm<()>);
+
+	fn validate(
+		destination: &mut Option<Location>,
+		message: &mut Option<Xcm<()>>,
+	) -> SendResult<Self::Ticket> {
+		let destination = destination.take().ok_or(SendError::MissingArgument)?;
+		let message = message.take().ok_or(SendError::MissingArgument)?;
+		Ok(((destination, message), Assets::new()))
+	}
+
+	fn deliver(ticket: Self::Ticket) -> Result<XcmHash, SendError> {
+		Sent::mutate(|sent| sent.push(ticket));
+		Ok([0; 32])
+	}
+}
+
+impl pallet_beacon_time::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Source = MockSource;
+	type MaxDrift = MaxDrift;
+	type XcmOrigin = EnsureSibling;
+	type XcmSender = MockSender;
+	type MaxCallbackLen = MaxCallbackLen;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBenchmarkHelper;
+}
+
+/// Sets [`Latest`] and reports to sibling parachain 2000.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct MockBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_beacon_time::BenchmarkHelper<RuntimeOrigin> for MockBenchmarkHelper {
+	fn set_latest_round(round: RoundNumber) {
+		store_round(round);
+	}
+
+	fn reporting_origin() -> RuntimeOrigin {
+		RuntimeOrigin::signed(2_000)
+	}
+}
+
+/// Make `round` of quicknet the latest verified round.
+pub fn store_round(round: RoundNumber) {
+	Latest::set(Some((0, BeaconInfo::quicknet(), round)));
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
@@ -0,0 +1,124 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::{mock::*, *};
+use frame_support::{assert_noop, assert_ok};
+use sp_runtime::DispatchError;
+
+/// Unix time, in milliseconds, of `round` of quicknet.
+fn round_time(round: RoundNumber) -> u64 {
+	BeaconInfo::quicknet().round_time(round) * 1_000
+}
+
+fn report(sibling: u64, callback: &[u8]) -> DispatchResult {
+	TimeOracle::report_time(
+		RuntimeOrigin::signed(sibling),
+		callback.to_vec().try_into().unwrap(),
+		Weight::from_parts(1_000_000, 1_000),
+	)
+}
+
+fn set_timestamp(timestamp: u64) {
+	<CheckDrift<Test, ()> as OnTimestampSet<u64>>::on_timestamp_set(timestamp);
+}
+
+#[test]
+fn time_follows_the_latest_round() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(TimeOracle::beacon_time(), None);
+
+		store_round(1_000);
+		let time = TimeOracle::beacon_time().unwrap();
+		assert_eq!(time, BeaconTime { beacon_id: 0, round: 1_000, time: round_time(1_000) });
+
+		// Blocks built since the round was stored don't move the lower bound.
+		System::set_block_number(3);
+		assert_eq!(TimeOracle::beacon_time(), Some(time));
+
+		store_round(1_002);
+		assert_eq!(TimeOracle::beacon_time().unwrap().time, round_time(1_002));
+	});
+}
+
+#[test]
+fn time_is_reported_over_xcm() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(report(2_000, &[42, 0]), Error::<Test>::NoBeaconTime);
+		store_round(1_000);
+		assert_noop!(
+			TimeOracle::report_time(
+				RuntimeOrigin::root(),
+				Default::default(),
+				Weight::from_parts(1_000_000, 1_000),
+			),
+			DispatchError::BadOrigin
+		);
+
+		assert_ok!(report(2_000, &[42, 0]));
+		let time = TimeOracle::beacon_time().unwrap();
+		let destination = Location::new(1, [Parachain(2_000)]);
+		System::assert_last_event(
+			Event::TimeReported { destination: destination.clone(), time }.into(),
+		);
+
+		let sent = Sent::get();
+		assert_eq!(sent.len(), 1);
+		assert_eq!(sent[0].0, destination);
+		let call = [&[42, 0][..], &time.encode()[..]].concat();
+		assert_eq!(
+			sent[0].1,
+			Xcm(vec![
+				UnpaidExecution { weight_limit: Unlimited, check_origin: None },
+				Transact {
+					origin_kind: OriginKind::Native,
+					require_weight_at_most: Weight::from_parts(1_000_000, 1_000),
+					call: call.into(),
+				},
+			])
+		);
+	});
+}
+
+#[test]
+fn drifting_timestamps_are_flagged() {
+	new_test_ext().execute_with(|| {
+		// Without beacon time, nothing is checked.
+		set_timestamp(0);
+		assert!(!TimeOracle::is_drifting());
+
+		store_round(1_000);
+		let time = TimeOracle::beacon_time().unwrap();
+		set_timestamp(time.time - MAX_DRIFT);
+		// The round may have reached the chain late, so timestamps ahead of it aren't flagged.
+		set_timestamp(time.time + 100 * MAX_DRIFT);
+		assert!(!TimeOracle::is_drifting());
+
+		let timestamp = time.time - MAX_DRIFT - 1;
+		set_timestamp(timestamp);
+		assert!(TimeOracle::is_drifting());
+		System::assert_last_event(
+			Event::TimestampDrifting { timestamp, time, drift: MAX_DRIFT + 1 }.into(),
+		);
+
+		System::set_block_number(4);
+		set_timestamp(time.time - 2 * MAX_DRIFT);
+		assert!(TimeOracle::is_drifting());
+
+		set_timestamp(time.time + 1);
+		assert!(!TimeOracle::is_drifting());
+		System::assert_last_event(Event::TimestampBackInBounds { drifting_blocks: 3 }.into());
+	});
+}
@@ -0,0 +1,82 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Weights for `pallet_beacon_time`.
+//!
+//! The figures below have not been measured yet. Replace this file with the output of the
+//! benchmarks in `benchmarking.rs` on reference hardware:
+//!
+//! ```sh
+//! ideal-nw-node benchmark pallet --chain dev --wasm-execution=compiled \
+//!     --pallet pallet_beacon_time --extrinsic "*" --steps 50 --repeat 20 \
+//!     --output pallets/beacon-time/src/weights.rs
+//! ```
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_beacon_time`.
+pub trait WeightInfo {
+	fn report_time(c: u32, ) -> Weight;
+	fn check_drift() -> Weight;
+}
+
+/// Weights for `pallet_beacon_time` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `Beacons::LatestRound` (r:1 w:0)
+	/// Storage: `Beacons::Stalled` (r:1 w:0)
+	/// Storage: `Beacons::BeaconConfigs` (r:1 w:0)
+	/// Storage: `ParachainSystem::HostConfiguration` (r:1 w:0)
+	/// Storage: `XcmpQueue::OutboundXcmpStatus` (r:1 w:1)
+	/// Storage: `XcmpQueue::OutboundXcmpMessages` (r:0 w:1)
+	/// The range of component `c` is `[0, 1024]`.
+	fn report_time(c: u32, ) -> Weight {
+		Weight::from_parts(40_000_000, 6_000)
+			.saturating_add(Weight::from_parts(2_000, 0).saturating_mul(c.into()))
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: `Beacons::LatestRound` (r:1 w:0)
+	/// Storage: `Beacons::Stalled` (r:1 w:0)
+	/// Storage: `Beacons::BeaconConfigs` (r:1 w:0)
+	/// Storage: `BeaconTime::DriftingSince` (r:1 w:1)
+	fn check_drift() -> Weight {
+		Weight::from_parts(8_000_000, 3_000)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn report_time(c: u32, ) -> Weight {
+		Weight::from_parts(40_000_000, 6_000)
+			.saturating_add(Weight::from_parts(2_000, 0).saturating_mul(c.into()))
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	fn check_drift() -> Weight {
+		Weight::from_parts(8_000_000, 3_000)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+}
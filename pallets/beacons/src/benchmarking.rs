@@ -0,0 +1,245 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Benchmarks for `pallet_beacons`.
+
+use super::*;
+use frame_benchmarking::v2::*;
+use frame_system::RawOrigin;
+
+/// Register `count` beacons with made up chain hashes.
+fn register<T: Config>(count: u32) -> Result<(), BenchmarkError> {
+	let origin =
+		T::BeaconOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+	for i in 0..count {
+		let mut info = T::BenchmarkHelper::beacon(SignatureScheme::BlsUnchainedG1Rfc9380);
+		info.chain_hash = [0xff; 32];
+		info.chain_hash[..4].copy_from_slice(&i.to_le_bytes());
+		Pallet::<T>::register_beacon(origin.clone(), info)?;
+	}
+	Ok(())
+}
+
+/// A transition of beacon 0 to a made up chain hash, from round 1.
+fn transition<T: Config>(origin: T::RuntimeOrigin) -> Result<(), BenchmarkError> {
+	let mut info = T::BenchmarkHelper::beacon(SignatureScheme::BlsUnchainedG1Rfc9380);
+	info.chain_hash = [0xee; 32];
+	Pallet::<T>::schedule_transition(origin, 0, 1, info)?;
+	Ok(())
+}
+
+/// Register beacon 0 with a transition to the beacon of `scheme` starting at the round of the
+/// returned pulse, so that submitting it also applies the transition.
+fn beacon_with_pulse<T: Config>(scheme: SignatureScheme) -> Result<Pulse, BenchmarkError> {
+	let origin =
+		T::BeaconOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+	let mut current = T::BenchmarkHelper::beacon(scheme);
+	current.chain_hash = [0xee; 32];
+	Pallet::<T>::register_beacon(origin.clone(), current)?;
+
+	let pulse = T::BenchmarkHelper::pulse(scheme);
+	Pallet::<T>::schedule_transition(origin, 0, pulse.round, T::BenchmarkHelper::beacon(scheme))?;
+	Ok(pulse)
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn register_beacon() -> Result<(), BenchmarkError> {
+		register::<T>(T::MaxBeacons::get() - 1)?;
+		let origin =
+			T::BeaconOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let info = T::BenchmarkHelper::beacon(SignatureScheme::BlsUnchainedG1Rfc9380);
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, info);
+
+		assert_eq!(BeaconConfigs::<T>::count(), T::MaxBeacons::get());
+		Ok(())
+	}
+
+	#[benchmark]
+	fn remove_beacon() -> Result<(), BenchmarkError> {
+		register::<T>(1)?;
+		let origin =
+			T::BeaconOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		transition::<T>(origin.clone())?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, 0);
+
+		assert_eq!(BeaconConfigs::<T>::count(), 0);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn submit_pulse_unchained() -> Result<(), BenchmarkError> {
+		let pulse = beacon_with_pulse::<T>(SignatureScheme::BlsUnchainedG1Rfc9380)?;
+		let round = pulse.round;
+
+		#[extrinsic_call]
+		submit_pulse(RawOrigin::None, 0, pulse);
+
+		assert_eq!(LatestRound::<T>::get(0), Some(round));
+		assert!(!Transitions::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn submit_pulse_chained() -> Result<(), BenchmarkError> {
+		let pulse = beacon_with_pulse::<T>(SignatureScheme::PedersenBlsChained)?;
+		let round = pulse.round;
+
+		#[extrinsic_call]
+		submit_pulse(RawOrigin::None, 0, pulse);
+
+		assert_eq!(LatestRound::<T>::get(0), Some(round));
+		assert!(!Transitions::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn submit_pulse_bn254() -> Result<(), BenchmarkError> {
+		let pulse = beacon_with_pulse::<T>(SignatureScheme::BlsBn254UnchainedG1)?;
+		let round = pulse.round;
+
+		#[extrinsic_call]
+		submit_pulse(RawOrigin::None, 0, pulse);
+
+		assert_eq!(LatestRound::<T>::get(0), Some(round));
+		assert!(!Transitions::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn schedule_transition() -> Result<(), BenchmarkError> {
+		register::<T>(1)?;
+		let origin =
+			T::BeaconOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		// Replacing a pending transition also releases its chain hash.
+		transition::<T>(origin.clone())?;
+		let info = T::BenchmarkHelper::beacon(SignatureScheme::BlsUnchainedG1Rfc9380);
+		let chain_hash = info.chain_hash;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, 0, 2, info);
+
+		assert_eq!(Transitions::<T>::get(0).map(|t| t.info.chain_hash), Some(chain_hash));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn cancel_transition() -> Result<(), BenchmarkError> {
+		register::<T>(1)?;
+		let origin =
+			T::BeaconOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		transition::<T>(origin.clone())?;
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, 0);
+
+		assert!(!Transitions::<T>::contains_key(0));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_endpoints(n: Linear<0, { T::MaxEndpoints::get() }>) -> Result<(), BenchmarkError> {
+		let origin =
+			T::BeaconOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let endpoint = Endpoint {
+			url: [&b"https://"[..], &[b'a'; MAX_URL_LEN as usize - 8]]
+				.concat()
+				.try_into()
+				.unwrap(),
+			timeout_ms: 1_000,
+			backoff_ms: 1_000,
+		};
+		let endpoints: BoundedVec<_, _> = alloc::vec![endpoint; n as usize].try_into().unwrap();
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, endpoints);
+
+		assert_eq!(Endpoints::<T>::get().len(), n as usize);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn check_stalls(b: Linear<0, { T::MaxBeacons::get() }>) -> Result<(), BenchmarkError> {
+		register::<T>(b)?;
+		let now = frame_system::Pallet::<T>::block_number() + T::StallThreshold::get();
+
+		#[block]
+		{
+			Pallet::<T>::check_stalls(now);
+		}
+
+		assert_eq!(Stalled::<T>::iter().count(), b as usize);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn set_thresholds() -> Result<(), BenchmarkError> {
+		let origin =
+			T::BeaconOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		let thresholds = HealthThresholds { window: 1_024, ..Default::default() };
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, thresholds);
+
+		assert_eq!(Thresholds::<T>::get(), thresholds);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn clear_alarm() -> Result<(), BenchmarkError> {
+		let origin =
+			T::BeaconOrigin::try_successful_origin().map_err(|_| BenchmarkError::Weightless)?;
+		Health::<T>::mutate(0, |health| {
+			health.current.push(&[0; 32]);
+			health.alarm = Some(StatTest::Monobit);
+		});
+
+		#[extrinsic_call]
+		_(origin as T::RuntimeOrigin, 0);
+
+		assert_eq!(Health::<T>::get(0).alarm, None);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn deposit_digests(b: Linear<0, { T::MaxBeacons::get() }>) -> Result<(), BenchmarkError> {
+		register::<T>(b)?;
+		let pulse = T::BenchmarkHelper::pulse(SignatureScheme::BlsUnchainedG1Rfc9380);
+		for beacon_id in 0..b {
+			Pulses::<T>::insert(beacon_id, pulse.round, pulse.clone());
+			LatestRound::<T>::insert(beacon_id, pulse.round);
+		}
+		BlockPulses::<T>::put(BoundedVec::truncate_from((0..b).collect()));
+
+		#[block]
+		{
+			Pallet::<T>::deposit_digests();
+		}
+
+		assert_eq!(frame_system::Pallet::<T>::digest().logs.len(), b as usize);
+		assert_eq!(ParentPulses::<T>::get().len(), b as usize);
+		Ok(())
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
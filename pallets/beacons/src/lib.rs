@@ -0,0 +1,1077 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Beacons Pallet
+//!
+//! Follows several drand beacons side by side, e.g. quicknet and the default League of Entropy
+//! beacon.
+//!
+//! Governance registers every beacon with its chain hash, public key, period, genesis time and
+//! signature scheme, and the beacon is assigned a [`BeaconId`]. Pulses, their lookups and the
+//! randomness handed to consumers are all keyed by that id.
+//!
+//! Pulses carry their own proof, so they are submitted as unsigned transactions: the offchain
+//! worker fetches the latest round of every beacon and submits it, and [`Config::Verifier`]
+//! checks its signature against the beacon both in the transaction pool and on-chain.
+//!
+//! Beacons in [`Config::OptimisticBeacons`] are only registered for their configuration: another
+//! pallet accepts their pulses without verifying them, e.g. `pallet_optimistic_pulses`, and looks
+//! the beacon up to check challenged pulses. Their pulses are neither fetched nor stored here, and
+//! they never stall.
+//!
+//! ## Endpoints
+//!
+//! The drand HTTP APIs to fetch from are an ordered list of mirrors set by governance in
+//! [`Endpoints`]. The offchain worker tries them in order, each with its own timeout, and backs
+//! off from an endpoint that failed for a beacon, doubling the delay on every further failure.
+//!
+//! Operators can override the list on their node by writing a SCALE encoded `Vec<Endpoint>`
+//! under [`ENDPOINTS_OVERRIDE_KEY`] in the persistent offchain local storage, e.g. with the
+//! `offchain_localStorageSet` RPC.
+//!
+//! The offchain worker counts the successful and failed fetches of every endpoint in
+//! [`FetchStats`] under [`FETCH_STATS_KEY`], for the node to report.
+//!
+//! ## Stalls
+//!
+//! A beacon without a stored pulse for [`Config::StallThreshold`] blocks is marked as stalled in
+//! [`Stalled`] and [`Event::BeaconStalled`] is emitted. While it is stalled, [`Pallet::try_random`]
+//! refuses to derive randomness from it rather than serving its last, stale pulse, and
+//! [`Pallet::live_pulse`] and [`BeaconRandomness`] serve nothing. The next stored pulse lifts the
+//! stall with [`Event::BeaconResumed`].
+//!
+//! ## Statistical checks
+//!
+//! The randomness of every stored pulse is folded into rolling frequency, runs and repeat tests,
+//! see [`ideal_nw_primitives::stats`], against the [`Thresholds`] set by governance. A failed
+//! test raises an alarm with [`Event::RandomnessAlarm`], and [`Pallet::try_random`],
+//! [`Pallet::live_pulse`] and [`BeaconRandomness`] refuse to serve the beacon's randomness until
+//! governance clears it with [`Pallet::clear_alarm`].
+//!
+//! ## Header digests
+//!
+//! The latest pulse stored in a block for every beacon is committed to the block header, see
+//! [`ideal_nw_primitives::digest`], so consumers that only follow headers get verifiable
+//! randomness too. Blocks that stored no pulse of a beacon carry no digest for it: light clients
+//! search back through the headers for the latest one.
+//!
+//! ## MMR
+//!
+//! The pallet is the leaf provider of `pallet_mmr`: every block appends the round and randomness
+//! of the pulses its parent stored, kept in [`ParentPulses`], and the new root is committed to
+//! the header, see [`ideal_nw_primitives::mmr`]. Old rounds are proven against a recent header
+//! after their pulses are pruned.
+//!
+//! ## Archive
+//!
+//! Every stored pulse is also written to the offchain database with offchain indexing, under
+//! [`archive_key`], along with the block that stored it. Unlike [`Pulses`] these entries survive
+//! state pruning, so nodes can serve the whole history of a beacon.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub use pallet::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+mod offchain;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+use alloc::{string::String, vec::Vec};
+use core::marker::PhantomData;
+use frame_support::{
+	pallet_prelude::*,
+	traits::{Contains, Randomness},
+};
+use frame_system::{
+	offchain::{SendTransactionTypes, SubmitTransaction},
+	pallet_prelude::BlockNumberFor,
+};
+pub use ideal_nw_primitives::{
+	beacon::{BeaconId, BeaconInfo, ChainHash, Pulse, RoundNumber, SignatureScheme},
+	digest::BeaconDigest,
+	mmr::{LeafPulse, PulseLeaf},
+	stats::{HealthThresholds, RandomnessHealth, StatTest},
+};
+use sp_runtime::{offchain::storage::StorageValueRef, traits::Hash};
+
+pub use weights::WeightInfo;
+
+const LOG_TARGET: &str = "runtime::beacons";
+
+/// Offchain local storage key of the node's own endpoint list, see [`Endpoint`].
+pub const ENDPOINTS_OVERRIDE_KEY: &[u8] = b"beacons::endpoints";
+
+/// Offchain local storage key of the [`FetchStats`] of the endpoints, a SCALE encoded
+/// `Vec<(Url, FetchStats)>`.
+pub const FETCH_STATS_KEY: &[u8] = b"beacons::fetch_stats";
+
+/// Counts of the fetches the offchain worker made from an endpoint.
+#[derive(Encode, Decode, Clone, Copy, Default, PartialEq, Eq, RuntimeDebug)]
+pub struct FetchStats {
+	/// Fetches that returned a pulse.
+	pub successes: u64,
+	/// Fetches that failed or returned something else.
+	pub failures: u64,
+}
+
+/// Prefix of the offchain indexing keys pulses are archived under, see [`archive_key`].
+pub const ARCHIVE_KEY_PREFIX: &[u8] = b"beacons::archive::";
+
+/// Offchain indexing key of the [`ArchivedPulse`] of `round` of beacon `beacon_id`.
+pub fn archive_key(beacon_id: BeaconId, round: RoundNumber) -> Vec<u8> {
+	let mut key = ARCHIVE_KEY_PREFIX.to_vec();
+	(beacon_id, round).encode_to(&mut key);
+	key
+}
+
+/// A pulse as archived with offchain indexing.
+///
+/// Every imported block writes its entries, forks included. The block is named by its number and
+/// parent, as the runtime can't know the hash of the block it runs in.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub struct ArchivedPulse<BlockNumber, Hash> {
+	/// The pulse.
+	pub pulse: Pulse,
+	/// The block that stored it.
+	pub block_number: BlockNumber,
+	/// The parent of that block.
+	pub parent_hash: Hash,
+}
+
+/// Maximum length of an endpoint URL.
+pub const MAX_URL_LEN: u32 = 256;
+
+/// The URL of a drand HTTP API.
+pub type Url = BoundedVec<u8, ConstU32<MAX_URL_LEN>>;
+
+/// A drand HTTP API the offchain worker fetches pulses from.
+#[derive(
+	Encode,
+	Decode,
+	Clone,
+	PartialEq,
+	Eq,
+	RuntimeDebug,
+	TypeInfo,
+	MaxEncodedLen,
+	serde::Serialize,
+	serde::Deserialize,
+)]
+#[serde(rename_all = "camelCase")]
+pub struct Endpoint {
+	/// The base URL, e.g. `https://api.drand.sh`.
+	#[serde(with = "url_as_str")]
+	pub url: Url,
+	/// How long to wait for a response, in milliseconds.
+	pub timeout_ms: u32,
+	/// How long to leave the endpoint alone after a first failure, in milliseconds. The delay
+	/// doubles with every further failure.
+	pub backoff_ms: u32,
+}
+
+impl Endpoint {
+	/// Whether the endpoint is usable: an HTTP(S) URL and a timeout.
+	pub fn is_valid(&self) -> bool {
+		(self.url.starts_with(b"https://") || self.url.starts_with(b"http://")) &&
+			self.timeout_ms > 0
+	}
+}
+
+/// A beacon in the genesis config, shaped like drand's `/info` with hex encoded bytes.
+#[derive(Clone, PartialEq, Eq, RuntimeDebug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenesisBeacon {
+	/// The hex encoded chain hash.
+	pub chain_hash: String,
+	/// The hex encoded group public key.
+	pub public_key: String,
+	/// Seconds between two rounds.
+	pub period: u32,
+	/// Unix time, in seconds, of the first round.
+	pub genesis_time: u64,
+	/// The scheme id, e.g. `bls-unchained-g1-rfc9380`.
+	pub scheme: String,
+}
+
+impl GenesisBeacon {
+	/// The beacon, if every field is well formed.
+	pub fn info(&self) -> Option<BeaconInfo> {
+		Some(BeaconInfo {
+			chain_hash: hex::decode(&self.chain_hash).ok()?.try_into().ok()?,
+			public_key: hex::decode(&self.public_key).ok()?.try_into().ok()?,
+			period: self.period,
+			genesis_time: self.genesis_time,
+			scheme: SignatureScheme::from_scheme_id(&self.scheme)?,
+		})
+	}
+}
+
+impl From<&BeaconInfo> for GenesisBeacon {
+	fn from(info: &BeaconInfo) -> Self {
+		Self {
+			chain_hash: hex::encode(info.chain_hash),
+			public_key: hex::encode(&info.public_key),
+			period: info.period,
+			genesis_time: info.genesis_time,
+			scheme: info.scheme.scheme_id().into(),
+		}
+	}
+}
+
+/// (De)serialize an endpoint URL as a string.
+mod url_as_str {
+	use super::Url;
+	use alloc::string::String;
+	use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+
+	pub fn serialize<S: Serializer>(url: &Url, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.serialize_str(core::str::from_utf8(url).map_err(S::Error::custom)?)
+	}
+
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Url, D::Error> {
+		let url = String::deserialize(deserializer)?;
+		url.into_bytes()
+			.try_into()
+			.map_err(|_| D::Error::custom("endpoint URL too long"))
+	}
+}
+
+/// Checks pulse signatures against the beacon that produced them.
+pub trait VerifyBeaconPulse {
+	/// Whether `pulse` carries a valid signature of `beacon`.
+	///
+	/// Malformed signatures or keys are simply invalid.
+	fn verify(beacon: &BeaconInfo, pulse: &Pulse) -> bool;
+}
+
+/// A scheduled change of the key, chain hash or timing of a beacon.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct BeaconTransition {
+	/// The first round signed by the new beacon.
+	pub from_round: RoundNumber,
+	/// The beacon from `from_round` on.
+	pub info: BeaconInfo,
+}
+
+/// Helper used by the benchmarks to set up verifiable beacons.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper {
+	/// A beacon using `scheme`, accepted by [`Config::Verifier`].
+	fn beacon(scheme: SignatureScheme) -> BeaconInfo;
+
+	/// A valid pulse of the [`BenchmarkHelper::beacon`] using `scheme`.
+	fn pulse(scheme: SignatureScheme) -> Pulse;
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config + SendTransactionTypes<Call<Self>> {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The origin allowed to register and remove beacons.
+		type BeaconOrigin: EnsureOrigin<Self::RuntimeOrigin>;
+
+		/// Checks pulse signatures.
+		type Verifier: VerifyBeaconPulse;
+
+		/// Beacons whose pulses another pallet accepts optimistically, which are not fetched nor
+		/// verified here.
+		type OptimisticBeacons: Contains<BeaconId>;
+
+		/// Maximum number of beacons followed at once.
+		#[pallet::constant]
+		type MaxBeacons: Get<u32>;
+
+		/// Priority of the unsigned transactions submitting pulses.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+
+		/// Maximum number of endpoints in [`Endpoints`].
+		#[pallet::constant]
+		type MaxEndpoints: Get<u32>;
+
+		/// Number of blocks without a stored pulse after which a beacon is stalled.
+		#[pallet::constant]
+		type StallThreshold: Get<BlockNumberFor<Self>>;
+
+		/// Weight information for extrinsics in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Helper to set up the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper;
+	}
+
+	/// The id the next registered beacon gets.
+	#[pallet::storage]
+	pub type NextBeaconId<T: Config> = StorageValue<_, BeaconId, ValueQuery>;
+
+	/// The registered beacons.
+	#[pallet::storage]
+	pub type BeaconConfigs<T: Config> =
+		CountedStorageMap<_, Twox64Concat, BeaconId, BeaconInfo, OptionQuery>;
+
+	/// The id of every registered beacon, by chain hash.
+	///
+	/// The chain hash of a scheduled transition is reserved here as well.
+	#[pallet::storage]
+	pub type BeaconIds<T: Config> = StorageMap<_, Identity, ChainHash, BeaconId, OptionQuery>;
+
+	/// Stored pulses, by beacon and round.
+	#[pallet::storage]
+	pub type Pulses<T: Config> =
+		StorageDoubleMap<_, Twox64Concat, BeaconId, Twox64Concat, RoundNumber, Pulse, OptionQuery>;
+
+	/// The latest stored round of every beacon.
+	#[pallet::storage]
+	pub type LatestRound<T: Config> =
+		StorageMap<_, Twox64Concat, BeaconId, RoundNumber, OptionQuery>;
+
+	/// The drand HTTP APIs the offchain worker fetches pulses from, in order of preference.
+	#[pallet::storage]
+	pub type Endpoints<T: Config> =
+		StorageValue<_, BoundedVec<Endpoint, T::MaxEndpoints>, ValueQuery>;
+
+	/// The pending transition of a beacon, if any.
+	#[pallet::storage]
+	pub type Transitions<T: Config> =
+		StorageMap<_, Twox64Concat, BeaconId, BeaconTransition, OptionQuery>;
+
+	/// The block that stored the latest pulse of every beacon, or registered it if it has none.
+	#[pallet::storage]
+	pub type LastPulseBlock<T: Config> =
+		StorageMap<_, Twox64Concat, BeaconId, BlockNumberFor<T>, OptionQuery>;
+
+	/// The block since which a beacon is stalled, for the stalled beacons.
+	#[pallet::storage]
+	pub type Stalled<T: Config> =
+		StorageMap<_, Twox64Concat, BeaconId, BlockNumberFor<T>, OptionQuery>;
+
+	/// The beacons that stored a pulse in the current block, committed to its header in
+	/// `on_finalize`.
+	#[pallet::storage]
+	pub type BlockPulses<T: Config> =
+		StorageValue<_, BoundedVec<BeaconId, T::MaxBeacons>, ValueQuery>;
+
+	/// The pulses stored in the previous block, the leaf the MMR appends in this one.
+	#[pallet::storage]
+	pub type ParentPulses<T: Config> =
+		StorageValue<_, BoundedVec<LeafPulse, T::MaxBeacons>, ValueQuery>;
+
+	/// When the statistical checks of the randomness fail.
+	#[pallet::storage]
+	pub type Thresholds<T: Config> = StorageValue<_, HealthThresholds, ValueQuery>;
+
+	/// The statistical checks of the randomness of every beacon.
+	#[pallet::storage]
+	pub type Health<T: Config> =
+		StorageMap<_, Twox64Concat, BeaconId, RandomnessHealth, ValueQuery>;
+
+	#[pallet::genesis_config]
+	#[derive(frame_support::DefaultNoBound)]
+	pub struct GenesisConfig<T: Config> {
+		/// Beacons registered at genesis, getting ids from 0 in order.
+		pub beacons: Vec<GenesisBeacon>,
+		/// The initial [`Endpoints`].
+		pub endpoints: Vec<Endpoint>,
+		#[serde(skip)]
+		pub _config: PhantomData<T>,
+	}
+
+	#[pallet::genesis_build]
+	impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+		fn build(&self) {
+			for beacon in &self.beacons {
+				let info = beacon.info().expect("malformed genesis beacon");
+				Pallet::<T>::do_register_beacon(info).expect("invalid genesis beacon");
+			}
+			assert!(self.endpoints.iter().all(Endpoint::is_valid), "invalid genesis endpoint");
+			let endpoints = BoundedVec::try_from(self.endpoints.clone())
+				.expect("more genesis endpoints than `MaxEndpoints`");
+			Endpoints::<T>::put(endpoints);
+		}
+	}
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A beacon was registered.
+		BeaconRegistered { beacon_id: BeaconId, chain_hash: ChainHash },
+		/// A beacon was removed. Its stored pulses remain readable.
+		BeaconRemoved { beacon_id: BeaconId },
+		/// A verified pulse was stored.
+		PulseStored { beacon_id: BeaconId, round: RoundNumber },
+		/// A beacon will be replaced from `from_round` on.
+		TransitionScheduled { beacon_id: BeaconId, from_round: RoundNumber, chain_hash: ChainHash },
+		/// A pending transition was cancelled.
+		TransitionCancelled { beacon_id: BeaconId },
+		/// The first pulse of a transition was stored: rounds from `from_round` on are signed by
+		/// the beacon `chain_hash`.
+		BeaconTransitioned { beacon_id: BeaconId, from_round: RoundNumber, chain_hash: ChainHash },
+		/// The endpoint list was replaced.
+		EndpointsUpdated { count: u32 },
+		/// No pulse of the beacon was stored for [`Config::StallThreshold`] blocks. Its latest
+		/// round is `last_round`.
+		BeaconStalled { beacon_id: BeaconId, last_round: Option<RoundNumber> },
+		/// Pulses of a stalled beacon are stored again, from `round` on. The beacon was stalled
+		/// for `stalled_blocks` blocks.
+		BeaconResumed { beacon_id: BeaconId, round: RoundNumber, stalled_blocks: BlockNumberFor<T> },
+		/// The randomness of a beacon failed `test`, up to `round`. Its randomness is not served
+		/// until the alarm is cleared.
+		RandomnessAlarm { beacon_id: BeaconId, test: StatTest, round: RoundNumber },
+		/// The alarm of a beacon was cleared.
+		AlarmCleared { beacon_id: BeaconId },
+		/// The thresholds of the statistical checks were replaced.
+		ThresholdsUpdated { thresholds: HealthThresholds },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// [`Config::MaxBeacons`] beacons are registered already.
+		TooManyBeacons,
+		/// A beacon with this chain hash is registered already.
+		DuplicateChainHash,
+		/// The beacon period is zero.
+		InvalidPeriod,
+		/// No beacon is registered under this id.
+		UnknownBeacon,
+		/// The pulse is not newer than the latest stored round of its beacon.
+		StaleRound,
+		/// The pulse randomness is not the hash of its signature.
+		RandomnessMismatch,
+		/// The pulse signature does not verify against its beacon.
+		InvalidSignature,
+		/// The transition would start at a round already stored.
+		TransitionInThePast,
+		/// The beacon has no pending transition.
+		NoPendingTransition,
+		/// An endpoint is not an HTTP(S) URL or has no timeout.
+		InvalidEndpoint,
+		/// The beacon has no stored pulse yet.
+		NoPulse,
+		/// The beacon stalled, its latest pulse is too old to derive randomness from.
+		BeaconStalled,
+		/// The randomness of the beacon failed a statistical check.
+		RandomnessAlarm,
+		/// The beacon has no alarm to clear.
+		NoAlarm,
+		/// A statistical window can't be empty.
+		InvalidThresholds,
+		/// The pulses of the beacon are accepted optimistically elsewhere.
+		OptimisticBeacon,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+			Self::check_stalls(n);
+			let beacons = BeaconConfigs::<T>::count();
+			T::WeightInfo::check_stalls(beacons)
+				.saturating_add(T::WeightInfo::deposit_digests(beacons))
+		}
+
+		fn on_finalize(_n: BlockNumberFor<T>) {
+			Self::deposit_digests();
+		}
+
+		fn offchain_worker(_n: BlockNumberFor<T>) {
+			if !sp_io::offchain::is_validator() {
+				return;
+			}
+			for (beacon_id, beacon) in BeaconConfigs::<T>::iter() {
+				if T::OptimisticBeacons::contains(&beacon_id) {
+					continue;
+				}
+				if let Err(err) = Self::fetch_and_submit(beacon_id, &beacon) {
+					log::debug!(target: LOG_TARGET, "beacon {beacon_id}: {err:?}");
+				}
+			}
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Start following a beacon.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::register_beacon())]
+		pub fn register_beacon(origin: OriginFor<T>, info: BeaconInfo) -> DispatchResult {
+			T::BeaconOrigin::ensure_origin(origin)?;
+			Self::do_register_beacon(info)?;
+			Ok(())
+		}
+
+		/// Stop following a beacon.
+		///
+		/// The beacon's pulses stay in storage. Its id is never reused.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::remove_beacon())]
+		pub fn remove_beacon(origin: OriginFor<T>, beacon_id: BeaconId) -> DispatchResult {
+			T::BeaconOrigin::ensure_origin(origin)?;
+			let info = BeaconConfigs::<T>::take(beacon_id).ok_or(Error::<T>::UnknownBeacon)?;
+			BeaconIds::<T>::remove(info.chain_hash);
+			LatestRound::<T>::remove(beacon_id);
+			LastPulseBlock::<T>::remove(beacon_id);
+			Stalled::<T>::remove(beacon_id);
+			Health::<T>::remove(beacon_id);
+			if let Some(transition) = Transitions::<T>::take(beacon_id) {
+				BeaconIds::<T>::remove(transition.info.chain_hash);
+			}
+
+			Self::deposit_event(Event::BeaconRemoved { beacon_id });
+			Ok(())
+		}
+
+		/// Store a pulse of `beacon_id` after verifying it.
+		///
+		/// Submitted as an unsigned transaction, usually by the offchain worker. The weight of the
+		/// most expensive scheme is charged up front and the difference refunded.
+		#[pallet::call_index(2)]
+		#[pallet::weight(Pallet::<T>::max_submit_pulse_weight())]
+		pub fn submit_pulse(
+			origin: OriginFor<T>,
+			beacon_id: BeaconId,
+			pulse: Pulse,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+			let (beacon, weight) = Self::verify_pulse(beacon_id, &pulse)?;
+
+			let round = pulse.round;
+			let block_number = frame_system::Pallet::<T>::block_number();
+			let archived = ArchivedPulse {
+				pulse: pulse.clone(),
+				block_number,
+				parent_hash: frame_system::Pallet::<T>::parent_hash(),
+			};
+			sp_io::offchain_index::set(&archive_key(beacon_id, round), &archived.encode());
+			let randomness = pulse.randomness;
+			Pulses::<T>::insert(beacon_id, round, pulse);
+			LatestRound::<T>::insert(beacon_id, round);
+			LastPulseBlock::<T>::insert(beacon_id, block_number);
+			BlockPulses::<T>::mutate(|beacons| {
+				if !beacons.contains(&beacon_id) {
+					// At most one entry per registered beacon, so it fits.
+					let _ = beacons.try_push(beacon_id);
+				}
+			});
+			Self::check_randomness(beacon_id, round, &randomness);
+
+			Self::deposit_event(Event::PulseStored { beacon_id, round });
+			if let Some(since) = Stalled::<T>::take(beacon_id) {
+				let stalled_blocks = block_number.saturating_sub(since);
+				Self::deposit_event(Event::BeaconResumed { beacon_id, round, stalled_blocks });
+			}
+			Self::apply_transition(beacon_id, round, &beacon);
+			Ok(Some(weight).into())
+		}
+
+		/// Replace beacon `beacon_id` by `info` from `from_round` on, e.g. after drand reshared
+		/// to a new group key or moved to a new chain.
+		///
+		/// Pulses of earlier rounds are still verified against the current beacon. The switch
+		/// happens with the first stored pulse of `from_round` or later signed by `info`.
+		/// Scheduling again replaces the pending transition.
+		///
+		/// A beacon keeping its chain hash keeps its round numbers, so `from_round` must be above
+		/// the latest stored round. A new chain may restart its numbering below it: until the
+		/// switch, a pulse that is new for both chains is then checked against both, and the
+		/// switch resets [`LatestRound`] to the round of the new chain. Stored rounds the new
+		/// chain signs again are overwritten.
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::WeightInfo::schedule_transition())]
+		pub fn schedule_transition(
+			origin: OriginFor<T>,
+			beacon_id: BeaconId,
+			from_round: RoundNumber,
+			info: BeaconInfo,
+		) -> DispatchResult {
+			T::BeaconOrigin::ensure_origin(origin)?;
+			let current = BeaconConfigs::<T>::get(beacon_id).ok_or(Error::<T>::UnknownBeacon)?;
+			ensure!(info.period > 0, Error::<T>::InvalidPeriod);
+			ensure!(
+				info.chain_hash != current.chain_hash ||
+					LatestRound::<T>::get(beacon_id).map_or(true, |latest| from_round > latest),
+				Error::<T>::TransitionInThePast
+			);
+			ensure!(
+				BeaconIds::<T>::get(info.chain_hash).map_or(true, |id| id == beacon_id),
+				Error::<T>::DuplicateChainHash
+			);
+
+			if let Some(pending) = Transitions::<T>::get(beacon_id) {
+				Self::release_chain_hash(&current, &pending.info);
+			}
+			let chain_hash = info.chain_hash;
+			BeaconIds::<T>::insert(chain_hash, beacon_id);
+			Transitions::<T>::insert(beacon_id, BeaconTransition { from_round, info });
+
+			Self::deposit_event(Event::TransitionScheduled { beacon_id, from_round, chain_hash });
+			Ok(())
+		}
+
+		/// Cancel the pending transition of `beacon_id`.
+		#[pallet::call_index(4)]
+		#[pallet::weight(T::WeightInfo::cancel_transition())]
+		pub fn cancel_transition(origin: OriginFor<T>, beacon_id: BeaconId) -> DispatchResult {
+			T::BeaconOrigin::ensure_origin(origin)?;
+			let current = BeaconConfigs::<T>::get(beacon_id).ok_or(Error::<T>::UnknownBeacon)?;
+			let pending =
+				Transitions::<T>::take(beacon_id).ok_or(Error::<T>::NoPendingTransition)?;
+			Self::release_chain_hash(&current, &pending.info);
+
+			Self::deposit_event(Event::TransitionCancelled { beacon_id });
+			Ok(())
+		}
+
+		/// Replace the endpoint list. Earlier endpoints are tried first.
+		#[pallet::call_index(5)]
+		#[pallet::weight(T::WeightInfo::set_endpoints(endpoints.len() as u32))]
+		pub fn set_endpoints(
+			origin: OriginFor<T>,
+			endpoints: BoundedVec<Endpoint, T::MaxEndpoints>,
+		) -> DispatchResult {
+			T::BeaconOrigin::ensure_origin(origin)?;
+			ensure!(endpoints.iter().all(Endpoint::is_valid), Error::<T>::InvalidEndpoint);
+
+			let count = endpoints.len() as u32;
+			Endpoints::<T>::put(endpoints);
+
+			Self::deposit_event(Event::EndpointsUpdated { count });
+			Ok(())
+		}
+
+		/// Replace the thresholds of the statistical checks.
+		///
+		/// Windows in progress are evaluated against the new thresholds.
+		#[pallet::call_index(6)]
+		#[pallet::weight(T::WeightInfo::set_thresholds())]
+		pub fn set_thresholds(
+			origin: OriginFor<T>,
+			thresholds: HealthThresholds,
+		) -> DispatchResult {
+			T::BeaconOrigin::ensure_origin(origin)?;
+			ensure!(thresholds.window > 0, Error::<T>::InvalidThresholds);
+			Thresholds::<T>::put(thresholds);
+			Self::deposit_event(Event::ThresholdsUpdated { thresholds });
+			Ok(())
+		}
+
+		/// Clear the alarm of `beacon_id` and serve its randomness again.
+		///
+		/// The statistical checks start over with a new window.
+		#[pallet::call_index(7)]
+		#[pallet::weight(T::WeightInfo::clear_alarm())]
+		pub fn clear_alarm(origin: OriginFor<T>, beacon_id: BeaconId) -> DispatchResult {
+			T::BeaconOrigin::ensure_origin(origin)?;
+			Health::<T>::try_mutate(beacon_id, |health| {
+				health.alarm.take().ok_or(Error::<T>::NoAlarm)?;
+				health.current = health.current.next();
+				Ok::<_, Error<T>>(())
+			})?;
+			Self::deposit_event(Event::AlarmCleared { beacon_id });
+			Ok(())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let Call::submit_pulse { beacon_id, pulse } = call else {
+				return InvalidTransaction::Call.into();
+			};
+			Self::verify_pulse(*beacon_id, pulse).map_err(invalid_transaction::<T>)?;
+
+			ValidTransaction::with_tag_prefix("BeaconsPulse")
+				.priority(T::UnsignedPriority::get())
+				.and_provides((beacon_id, pulse.round))
+				.longevity(5)
+				.propagate(true)
+				.build()
+		}
+
+		fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+			// The signature is checked when the call is dispatched, no need to check it twice.
+			let Call::submit_pulse { beacon_id, pulse } = call else {
+				return Err(InvalidTransaction::Call.into());
+			};
+			Self::fresh_beacons(*beacon_id, pulse.round)
+				.map(|_| ())
+				.map_err(invalid_transaction::<T>)
+		}
+	}
+}
+
+fn invalid_transaction<T: Config>(err: Error<T>) -> TransactionValidityError {
+	match err {
+		Error::<T>::StaleRound => InvalidTransaction::Stale,
+		Error::<T>::RandomnessMismatch | Error::<T>::InvalidSignature =>
+			InvalidTransaction::BadProof,
+		_ => InvalidTransaction::Call,
+	}
+	.into()
+}
+
+impl<T: Config> Pallet<T> {
+	/// Register the beacon `info` under the next id.
+	pub fn do_register_beacon(info: BeaconInfo) -> Result<BeaconId, Error<T>> {
+		ensure!(BeaconConfigs::<T>::count() < T::MaxBeacons::get(), Error::<T>::TooManyBeacons);
+		ensure!(!BeaconIds::<T>::contains_key(info.chain_hash), Error::<T>::DuplicateChainHash);
+		ensure!(info.period > 0, Error::<T>::InvalidPeriod);
+
+		let beacon_id = NextBeaconId::<T>::mutate(|next| {
+			let id = *next;
+			next.saturating_inc();
+			id
+		});
+		let chain_hash = info.chain_hash;
+		BeaconIds::<T>::insert(chain_hash, beacon_id);
+		BeaconConfigs::<T>::insert(beacon_id, info);
+		LastPulseBlock::<T>::insert(beacon_id, frame_system::Pallet::<T>::block_number());
+
+		Self::deposit_event(Event::BeaconRegistered { beacon_id, chain_hash });
+		Ok(beacon_id)
+	}
+
+	/// The active configuration of `beacon_id`.
+	pub fn beacon(beacon_id: BeaconId) -> Option<BeaconInfo> {
+		BeaconConfigs::<T>::get(beacon_id)
+	}
+
+	/// The latest stored pulse of `beacon_id`.
+	pub fn latest_pulse(beacon_id: BeaconId) -> Option<Pulse> {
+		LatestRound::<T>::get(beacon_id).and_then(|round| Pulses::<T>::get(beacon_id, round))
+	}
+
+	/// The stored pulse of `beacon_id` for `round`.
+	pub fn pulse(beacon_id: BeaconId, round: RoundNumber) -> Option<Pulse> {
+		Pulses::<T>::get(beacon_id, round)
+	}
+
+	/// All registered beacons.
+	pub fn beacons() -> Vec<(BeaconId, BeaconInfo)> {
+		BeaconConfigs::<T>::iter().collect()
+	}
+
+	/// Derive randomness for `subject` from the latest pulse of `beacon_id`.
+	///
+	/// The seed is the default hash as long as the beacon has no pulse. The pulse may be stale,
+	/// see [`Pallet::try_random`] to only derive randomness from a live beacon.
+	pub fn random(beacon_id: BeaconId, subject: &[u8]) -> (T::Hash, BlockNumberFor<T>) {
+		let block_number = frame_system::Pallet::<T>::block_number();
+		let seed = Self::latest_pulse(beacon_id)
+			.map(|pulse| T::Hashing::hash_of(&(subject, pulse.randomness)))
+			.unwrap_or_default();
+		(seed, block_number)
+	}
+
+	/// Derive randomness for `subject` from the latest pulse of `beacon_id`, unless the beacon
+	/// has no pulse, stalled or failed a statistical check.
+	pub fn try_random(
+		beacon_id: BeaconId,
+		subject: &[u8],
+	) -> Result<(T::Hash, BlockNumberFor<T>), Error<T>> {
+		ensure!(BeaconConfigs::<T>::contains_key(beacon_id), Error::<T>::UnknownBeacon);
+		ensure!(!Stalled::<T>::contains_key(beacon_id), Error::<T>::BeaconStalled);
+		ensure!(Health::<T>::get(beacon_id).alarm.is_none(), Error::<T>::RandomnessAlarm);
+		let pulse = Self::latest_pulse(beacon_id).ok_or(Error::<T>::NoPulse)?;
+		let block_number = frame_system::Pallet::<T>::block_number();
+		Ok((T::Hashing::hash_of(&(subject, pulse.randomness)), block_number))
+	}
+
+	/// The latest stored pulse of `beacon_id`, unless the beacon stalled or failed a statistical
+	/// check.
+	pub fn live_pulse(beacon_id: BeaconId) -> Option<Pulse> {
+		if Stalled::<T>::contains_key(beacon_id) || Health::<T>::get(beacon_id).alarm.is_some() {
+			return None;
+		}
+		Self::latest_pulse(beacon_id)
+	}
+
+	/// Whether `beacon_id` is stalled.
+	pub fn is_stalled(beacon_id: BeaconId) -> bool {
+		Stalled::<T>::contains_key(beacon_id)
+	}
+
+	/// The statistical checks of the randomness of `beacon_id`.
+	pub fn randomness_health(beacon_id: BeaconId) -> Option<RandomnessHealth> {
+		BeaconConfigs::<T>::contains_key(beacon_id).then(|| Health::<T>::get(beacon_id))
+	}
+
+	/// Fold the randomness of `round` of `beacon_id` into its statistical checks, raising an
+	/// alarm if one fails.
+	fn check_randomness(beacon_id: BeaconId, round: RoundNumber, randomness: &[u8; 32]) {
+		let thresholds = Thresholds::<T>::get();
+		Health::<T>::mutate(beacon_id, |health| {
+			health.current.push(randomness);
+			let failure = health.current.failure(&thresholds);
+			if let (Some(test), None) = (failure, health.alarm) {
+				health.alarm = Some(test);
+				log::warn!(target: LOG_TARGET, "beacon {beacon_id} failed {test:?} at round {round}");
+				Self::deposit_event(Event::RandomnessAlarm { beacon_id, test, round });
+			}
+			if health.current.is_complete(&thresholds) {
+				let next = health.current.next();
+				health.previous = Some(core::mem::replace(&mut health.current, next));
+			}
+		});
+	}
+
+	/// Commit the latest pulse of every beacon that stored one in this block to its header, and
+	/// keep their randomness for the MMR leaf of the next block.
+	fn deposit_digests() {
+		let mut leaf = BoundedVec::<LeafPulse, T::MaxBeacons>::new();
+		for beacon_id in BlockPulses::<T>::take() {
+			if let Some(pulse) = Self::latest_pulse(beacon_id) {
+				// `BlockPulses` holds at most `MaxBeacons` beacons.
+				let _ = leaf.try_push(LeafPulse {
+					beacon_id,
+					round: pulse.round,
+					randomness: pulse.randomness,
+				});
+				let digest = BeaconDigest { beacon_id, pulse };
+				frame_system::Pallet::<T>::deposit_log(digest.to_digest_item());
+			}
+		}
+		ParentPulses::<T>::put(leaf);
+	}
+
+	/// Mark the beacons without a pulse for [`Config::StallThreshold`] blocks as stalled.
+	fn check_stalls(now: BlockNumberFor<T>) {
+		for beacon_id in BeaconConfigs::<T>::iter_keys() {
+			if Stalled::<T>::contains_key(beacon_id) || T::OptimisticBeacons::contains(&beacon_id) {
+				continue;
+			}
+			let last = LastPulseBlock::<T>::get(beacon_id).unwrap_or_default();
+			if now.saturating_sub(last) >= T::StallThreshold::get() {
+				Stalled::<T>::insert(beacon_id, now);
+				let last_round = LatestRound::<T>::get(beacon_id);
+				log::warn!(target: LOG_TARGET, "beacon {beacon_id} stalled at round {last_round:?}");
+				Self::deposit_event(Event::BeaconStalled { beacon_id, last_round });
+			}
+		}
+	}
+
+	/// The pending transition of `beacon_id`.
+	pub fn pending_transition(beacon_id: BeaconId) -> Option<BeaconTransition> {
+		Transitions::<T>::get(beacon_id)
+	}
+
+	/// The beacon `beacon_id` as it signs `round`, taking its pending transition into account.
+	pub fn beacon_at(beacon_id: BeaconId, round: RoundNumber) -> Option<BeaconInfo> {
+		let current = BeaconConfigs::<T>::get(beacon_id)?;
+		match Transitions::<T>::get(beacon_id) {
+			Some(transition) if round >= transition.from_round => Some(transition.info),
+			_ => Some(current),
+		}
+	}
+
+	/// The beacons that may sign a new `round` of `beacon_id`, in the order to check them.
+	///
+	/// That is the beacon as it signs `round`, provided it has not stored `round` or a later
+	/// round yet. While a transition to a chain that restarts its round numbers is pending, a
+	/// round can be new for both chains, and both are returned.
+	fn fresh_beacons(beacon_id: BeaconId, round: RoundNumber) -> Result<Vec<BeaconInfo>, Error<T>> {
+		let current = BeaconConfigs::<T>::get(beacon_id).ok_or(Error::<T>::UnknownBeacon)?;
+		ensure!(!T::OptimisticBeacons::contains(&beacon_id), Error::<T>::OptimisticBeacon);
+		let latest = LatestRound::<T>::get(beacon_id);
+		let fresh = latest.map_or(true, |latest| round > latest);
+		let beacons = match Transitions::<T>::get(beacon_id) {
+			Some(transition) if round >= transition.from_round => {
+				let restarts = transition.info.chain_hash != current.chain_hash &&
+					latest.map_or(false, |latest| transition.from_round <= latest);
+				match (restarts, fresh) {
+					// Nothing of the new chain is stored before the switch.
+					(true, true) => alloc::vec![transition.info, current],
+					(true, false) => alloc::vec![transition.info],
+					(false, true) => alloc::vec![transition.info],
+					(false, false) => Vec::new(),
+				}
+			},
+			_ if fresh => alloc::vec![current],
+			_ => Vec::new(),
+		};
+		ensure!(!beacons.is_empty(), Error::<T>::StaleRound);
+		Ok(beacons)
+	}
+
+	/// Switch `beacon_id` over to its pending transition once `round` is stored, if the
+	/// transition starts at or before `round` and `signer` signed it.
+	fn apply_transition(beacon_id: BeaconId, round: RoundNumber, signer: &BeaconInfo) {
+		let Some(transition) = Transitions::<T>::get(beacon_id)
+			.filter(|transition| round >= transition.from_round && transition.info == *signer)
+		else {
+			return;
+		};
+		Transitions::<T>::remove(beacon_id);
+		if let Some(previous) = BeaconConfigs::<T>::get(beacon_id) {
+			Self::release_chain_hash(&transition.info, &previous);
+		}
+
+		let BeaconTransition { from_round, info } = transition;
+		let chain_hash = info.chain_hash;
+		BeaconConfigs::<T>::insert(beacon_id, info);
+		Self::deposit_event(Event::BeaconTransitioned { beacon_id, from_round, chain_hash });
+	}
+
+	/// Free the chain hash of `released`, unless `kept` uses it too.
+	fn release_chain_hash(kept: &BeaconInfo, released: &BeaconInfo) {
+		if kept.chain_hash != released.chain_hash {
+			BeaconIds::<T>::remove(released.chain_hash);
+		}
+	}
+
+	/// Check that `pulse` is a new, valid pulse of `beacon_id`.
+	///
+	/// Returns the beacon that signed it and the weight of storing it, which covers every
+	/// signature check made.
+	fn verify_pulse(beacon_id: BeaconId, pulse: &Pulse) -> Result<(BeaconInfo, Weight), Error<T>> {
+		let beacons = Self::fresh_beacons(beacon_id, pulse.round)?;
+		ensure!(
+			pulse.randomness == sp_io::hashing::sha2_256(&pulse.signature),
+			Error::<T>::RandomnessMismatch
+		);
+		let mut weight = Weight::zero();
+		for beacon in beacons {
+			weight.saturating_accrue(Self::submit_pulse_weight(beacon.scheme));
+			if T::Verifier::verify(&beacon, pulse) {
+				return Ok((beacon, weight));
+			}
+		}
+		Err(Error::<T>::InvalidSignature)
+	}
+
+	/// The weight of `submit_pulse` for a beacon using `scheme`.
+	fn submit_pulse_weight(scheme: SignatureScheme) -> Weight {
+		match scheme {
+			SignatureScheme::BlsUnchainedG1Rfc9380 => T::WeightInfo::submit_pulse_unchained(),
+			SignatureScheme::PedersenBlsChained => T::WeightInfo::submit_pulse_chained(),
+			SignatureScheme::BlsBn254UnchainedG1 => T::WeightInfo::submit_pulse_bn254(),
+		}
+	}
+
+	/// The weight of `submit_pulse` checking a pulse against two beacons of the most expensive
+	/// scheme, see [`Pallet::schedule_transition`].
+	fn max_submit_pulse_weight() -> Weight {
+		T::WeightInfo::submit_pulse_unchained()
+			.max(T::WeightInfo::submit_pulse_chained())
+			.max(T::WeightInfo::submit_pulse_bn254())
+			.saturating_mul(2)
+	}
+
+	/// The endpoints the offchain worker uses: the local override if one is set, [`Endpoints`]
+	/// otherwise.
+	fn offchain_endpoints() -> Vec<Endpoint> {
+		let local = StorageValueRef::persistent(ENDPOINTS_OVERRIDE_KEY).get::<Vec<Endpoint>>();
+		match local {
+			Ok(Some(endpoints)) if !endpoints.is_empty() => endpoints,
+			Ok(_) => Endpoints::<T>::get().into_inner(),
+			Err(_) => {
+				log::warn!(target: LOG_TARGET, "ignoring undecodable local endpoint list");
+				Endpoints::<T>::get().into_inner()
+			},
+		}
+	}
+
+	/// Fetch the latest pulse of a beacon and submit it, unless it is stored already.
+	///
+	/// Once the current beacon reaches the round of a pending transition, the pulse is fetched
+	/// from the new beacon instead.
+	fn fetch_and_submit(beacon_id: BeaconId, beacon: &BeaconInfo) -> Result<(), offchain::Error> {
+		let mut latest = LatestRound::<T>::get(beacon_id).unwrap_or_default();
+		let now = sp_io::offchain::timestamp().unix_millis() / 1_000;
+		let beacon = match Transitions::<T>::get(beacon_id) {
+			Some(transition) if transition.info.chain_hash != beacon.chain_hash => {
+				// A new chain counts its own rounds, none of which is stored yet.
+				if transition.info.round_at(now) < transition.from_round {
+					beacon.clone()
+				} else {
+					latest = transition.from_round.saturating_sub(1);
+					transition.info
+				}
+			},
+			Some(transition) if beacon.round_at(now) >= transition.from_round => transition.info,
+			_ => beacon.clone(),
+		};
+		if beacon.round_at(now) <= latest {
+			return Ok(());
+		}
+
+		let pulse = offchain::fetch_latest(&Self::offchain_endpoints(), &beacon.chain_hash)?;
+		if pulse.round <= latest {
+			return Ok(());
+		}
+
+		let call = Call::submit_pulse { beacon_id, pulse };
+		SubmitTransaction::<T, Call<T>>::submit_unsigned_transaction(call.into())
+			.map_err(|()| offchain::Error::Submit)
+	}
+}
+
+/// Feeds the pulses of every block to `pallet_mmr`, one leaf per block.
+impl<T: Config> sp_mmr_primitives::LeafDataProvider for Pallet<T> {
+	type LeafData = PulseLeaf;
+
+	fn leaf_data() -> Self::LeafData {
+		ParentPulses::<T>::get().into_inner()
+	}
+}
+
+/// Commits every new MMR root to the header.
+impl<T: Config> sp_mmr_primitives::OnNewRoot<T::Hash> for Pallet<T> {
+	fn on_new_root(root: &T::Hash) {
+		frame_system::Pallet::<T>::deposit_log(ideal_nw_primitives::mmr::root_digest_item(root));
+	}
+}
+
+/// Parse a pulse from the JSON drand serves for a round, e.g. at `/public/latest`.
+pub fn parse_drand_pulse(body: &[u8]) -> Option<Pulse> {
+	offchain::parse_pulse(body).ok()
+}
+
+/// Randomness from the latest pulse of the beacon `B`, see [`Pallet::live_pulse`].
+///
+/// Since [`Randomness`] can't fail, the seed is the default hash while the beacon is stalled or
+/// its alarm is raised, as before its first pulse. Use [`Pallet::try_random`] to tell these cases
+/// apart.
+pub struct BeaconRandomness<T, B>(PhantomData<(T, B)>);
+
+impl<T: Config, B: Get<BeaconId>> Randomness<T::Hash, BlockNumberFor<T>>
+	for BeaconRandomness<T, B>
+{
+	fn random(subject: &[u8]) -> (T::Hash, BlockNumberFor<T>) {
+		let block_number = frame_system::Pallet::<T>::block_number();
+		let seed = Pallet::<T>::live_pulse(B::get())
+			.map(|pulse| T::Hashing::hash_of(&(subject, pulse.randomness)))
+			.unwrap_or_default();
+		(seed, block_number)
+	}
+}
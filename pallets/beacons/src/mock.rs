@@ -0,0 +1,183 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate as pallet_beacons;
+use crate::{BeaconId, BeaconInfo, Endpoint, Pulse, RoundNumber, VerifyBeaconPulse};
+use frame_support::{derive_impl, parameter_types, traits::Contains};
+use frame_system::EnsureRoot;
+use ideal_nw_primitives::beacon::SignatureScheme;
+use sp_runtime::{testing::TestXt, BuildStorage};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		Beacons: pallet_beacons,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+}
+
+pub type Extrinsic = TestXt<RuntimeCall, ()>;
+
+impl<C> frame_system::offchain::SendTransactionTypes<C> for Test
+where
+	RuntimeCall: From<C>,
+{
+	type OverarchingCall = RuntimeCall;
+	type Extrinsic = Extrinsic;
+}
+
+pub const ALICE: u64 = 1;
+
+pub const ENDPOINT: &str = "https://drand.example";
+pub const MIRROR: &str = "https://mirror.drand.example";
+pub const MAX_BEACONS: u32 = 3;
+pub const MAX_ENDPOINTS: u32 = 2;
+pub const STALL_THRESHOLD: u64 = 10;
+
+/// Signature marker the [`MockVerifier`] accepts.
+pub const VALID: u8 = 1;
+/// Signature marker the [`MockVerifier`] rejects.
+pub const INVALID: u8 = 0;
+
+/// Accepts pulses whose signature starts with [`VALID`]. Signatures made by [`signed_pulse`]
+/// are only accepted for the beacon that signed them.
+pub struct MockVerifier;
+
+impl VerifyBeaconPulse for MockVerifier {
+	fn verify(beacon: &BeaconInfo, pulse: &Pulse) -> bool {
+		pulse.signature.first() == Some(&VALID) &&
+			pulse
+				.signature
+				.get(48)
+				.map_or(true, |key| beacon.public_key.first() == Some(key))
+	}
+}
+
+/// A quicknet-like beacon identified by `chain_hash`.
+pub fn beacon(chain_hash: u8) -> BeaconInfo {
+	BeaconInfo {
+		chain_hash: [chain_hash; 32],
+		public_key: vec![chain_hash; 96].try_into().unwrap(),
+		period: 3,
+		genesis_time: 1_000,
+		scheme: SignatureScheme::BlsUnchainedG1Rfc9380,
+	}
+}
+
+/// A 48 byte signature starting with `marker` and unique to `round`.
+fn signature(round: RoundNumber, marker: u8) -> Vec<u8> {
+	let mut signature = vec![marker; 48];
+	signature[1..9].copy_from_slice(&round.to_le_bytes());
+	signature
+}
+
+/// A pulse for `round` whose signature starts with `marker`.
+pub fn pulse(round: RoundNumber, marker: u8) -> Pulse {
+	let signature = signature(round, marker);
+	Pulse {
+		round,
+		randomness: sp_io::hashing::sha2_256(&signature),
+		signature: signature.try_into().unwrap(),
+		previous_signature: Default::default(),
+	}
+}
+
+/// A valid pulse for `round` bound to the public key of `beacon`.
+pub fn signed_pulse(round: RoundNumber, beacon: &BeaconInfo) -> Pulse {
+	let mut signature = signature(round, VALID);
+	signature.push(beacon.public_key[0]);
+	Pulse {
+		round,
+		randomness: sp_io::hashing::sha2_256(&signature),
+		signature: signature.try_into().unwrap(),
+		previous_signature: Default::default(),
+	}
+}
+
+/// An endpoint at `url` with a 1s timeout and backoff.
+pub fn endpoint(url: &str) -> Endpoint {
+	Endpoint {
+		url: url.as_bytes().to_vec().try_into().unwrap(),
+		timeout_ms: 1_000,
+		backoff_ms: 1_000,
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct MockBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::BenchmarkHelper for MockBenchmarkHelper {
+	fn beacon(scheme: SignatureScheme) -> BeaconInfo {
+		BeaconInfo { scheme, ..beacon(1) }
+	}
+
+	fn pulse(_scheme: SignatureScheme) -> Pulse {
+		pulse(1, VALID)
+	}
+}
+
+/// Treats the beacon in [`Optimistic`] as followed optimistically.
+pub struct MockOptimisticBeacons;
+
+impl Contains<BeaconId> for MockOptimisticBeacons {
+	fn contains(beacon_id: &BeaconId) -> bool {
+		Optimistic::get() == Some(*beacon_id)
+	}
+}
+
+parameter_types! {
+	pub static Optimistic: Option<BeaconId> = None;
+	pub const MaxBeacons: u32 = MAX_BEACONS;
+	pub const UnsignedPriority: u64 = 100;
+	pub const MaxEndpoints: u32 = MAX_ENDPOINTS;
+	pub const StallThreshold: u64 = STALL_THRESHOLD;
+}
+
+impl pallet_beacons::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type BeaconOrigin = EnsureRoot<u64>;
+	type Verifier = MockVerifier;
+	type OptimisticBeacons = MockOptimisticBeacons;
+	type MaxBeacons = MaxBeacons;
+	type UnsignedPriority = UnsignedPriority;
+	type MaxEndpoints = MaxEndpoints;
+	type StallThreshold = StallThreshold;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBenchmarkHelper;
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let storage = RuntimeGenesisConfig {
+		beacons: pallet_beacons::GenesisConfig {
+			endpoints: vec![endpoint(ENDPOINT), endpoint(MIRROR)],
+			..Default::default()
+		},
+		..Default::default()
+	}
+	.build_storage()
+	.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
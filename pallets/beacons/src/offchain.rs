@@ -0,0 +1,164 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Fetching pulses from the drand HTTP API.
+
+use crate::{Endpoint, FetchStats, Url, FETCH_STATS_KEY};
+use alloc::{format, string::String, vec::Vec};
+use codec::{Decode, Encode};
+use ideal_nw_primitives::beacon::{ChainHash, Pulse};
+use serde::Deserialize;
+use sp_runtime::offchain::{http, storage::StorageValueRef, Duration};
+
+/// Upper bound of the delay before an endpoint is retried, in milliseconds.
+const MAX_BACKOFF_MS: u64 = 10 * 60 * 1_000;
+
+/// Why fetching or submitting a pulse failed.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Error {
+	/// The request could not be sent or timed out.
+	Http,
+	/// The endpoint answered with a status other than 200.
+	Status(u16),
+	/// The response is not a pulse.
+	Body,
+	/// The transaction could not be submitted.
+	Submit,
+	/// No endpoint is configured, or all of them are backing off.
+	NoEndpoint,
+}
+
+/// Failures of an endpoint for one beacon, kept in offchain local storage.
+#[derive(Encode, Decode, Default)]
+struct Backoff {
+	/// Consecutive failures.
+	failures: u32,
+	/// Unix time, in milliseconds, before which the endpoint is skipped.
+	retry_at: u64,
+}
+
+/// The offchain local storage key of the backoff of `endpoint` for the beacon `chain_hash`.
+fn backoff_key(endpoint: &Endpoint, chain_hash: &ChainHash) -> Vec<u8> {
+	[&b"beacons::backoff::"[..], chain_hash, &endpoint.url].concat()
+}
+
+/// A pulse as returned by `/{chain_hash}/public/latest`.
+#[derive(Deserialize)]
+struct DrandPulse {
+	round: u64,
+	randomness: String,
+	signature: String,
+	#[serde(default)]
+	previous_signature: String,
+}
+
+/// The URL of the latest pulse of the beacon `chain_hash` at `endpoint`.
+pub(crate) fn latest_url(endpoint: &str, chain_hash: &ChainHash) -> String {
+	format!("{}/{}/public/latest", endpoint.trim_end_matches('/'), hex::encode(chain_hash))
+}
+
+/// Fetch the latest pulse of the beacon `chain_hash` from the first of `endpoints` that answers.
+///
+/// Endpoints backing off are skipped. A failing endpoint backs off for its
+/// [`Endpoint::backoff_ms`], doubled on every consecutive failure up to [`MAX_BACKOFF_MS`].
+pub(crate) fn fetch_latest(endpoints: &[Endpoint], chain_hash: &ChainHash) -> Result<Pulse, Error> {
+	let now = sp_io::offchain::timestamp().unix_millis();
+	let mut last_error = Error::NoEndpoint;
+	for endpoint in endpoints {
+		let Ok(url) = core::str::from_utf8(&endpoint.url) else { continue };
+		let storage = StorageValueRef::persistent(&backoff_key(endpoint, chain_hash));
+		let backoff = storage.get::<Backoff>().ok().flatten().unwrap_or_default();
+		if backoff.retry_at > now {
+			continue;
+		}
+
+		let result = fetch_latest_from(url, chain_hash, endpoint.timeout_ms.into());
+		record_fetch(endpoints, &endpoint.url, result.is_ok());
+		match result {
+			Ok(pulse) => {
+				if backoff.failures > 0 {
+					storage.clear();
+				}
+				return Ok(pulse);
+			},
+			Err(err) => {
+				log::debug!(target: crate::LOG_TARGET, "{url}: {err:?}");
+				let failures = backoff.failures.saturating_add(1);
+				let delay = u64::from(endpoint.backoff_ms)
+					.saturating_mul(1 << (failures - 1).min(16))
+					.min(MAX_BACKOFF_MS);
+				storage.set(&Backoff { failures, retry_at: now.saturating_add(delay) });
+				last_error = err;
+			},
+		}
+	}
+	Err(last_error)
+}
+
+/// Count a fetch from `url` in the [`FetchStats`], dropping the stats of endpoints no longer
+/// in `endpoints`.
+fn record_fetch(endpoints: &[Endpoint], url: &Url, success: bool) {
+	let storage = StorageValueRef::persistent(FETCH_STATS_KEY);
+	let _ = storage.mutate::<Vec<(Url, FetchStats)>, (), _>(|stats| {
+		let mut stats = stats.ok().flatten().unwrap_or_default();
+		stats.retain(|(url, _)| endpoints.iter().any(|endpoint| &endpoint.url == url));
+		let index = match stats.iter().position(|(known, _)| known == url) {
+			Some(index) => index,
+			None => {
+				stats.push((url.clone(), FetchStats::default()));
+				stats.len() - 1
+			},
+		};
+		let counts = &mut stats[index].1;
+		if success {
+			counts.successes = counts.successes.saturating_add(1);
+		} else {
+			counts.failures = counts.failures.saturating_add(1);
+		}
+		Ok(stats)
+	});
+}
+
+/// Fetch the latest pulse of the beacon `chain_hash` from the API at `endpoint`.
+fn fetch_latest_from(
+	endpoint: &str,
+	chain_hash: &ChainHash,
+	timeout_ms: u64,
+) -> Result<Pulse, Error> {
+	let url = latest_url(endpoint, chain_hash);
+	let deadline = sp_io::offchain::timestamp().add(Duration::from_millis(timeout_ms));
+	let pending = http::Request::get(&url).deadline(deadline).send().map_err(|_| Error::Http)?;
+	let response = pending.try_wait(deadline).map_err(|_| Error::Http)?.map_err(|_| Error::Http)?;
+	if response.code != 200 {
+		return Err(Error::Status(response.code));
+	}
+	parse_pulse(&response.body().collect::<Vec<u8>>())
+}
+
+/// Parse a pulse from the JSON body of a drand API response.
+pub(crate) fn parse_pulse(body: &[u8]) -> Result<Pulse, Error> {
+	let pulse: DrandPulse = serde_json::from_slice(body).map_err(|_| Error::Body)?;
+	let decode = |value: &str| hex::decode(value).map_err(|_| Error::Body);
+
+	Ok(Pulse {
+		round: pulse.round,
+		randomness: decode(&pulse.randomness)?.try_into().map_err(|_| Error::Body)?,
+		signature: decode(&pulse.signature)?.try_into().map_err(|_| Error::Body)?,
+		previous_signature: decode(&pulse.previous_signature)?
+			.try_into()
+			.map_err(|_| Error::Body)?,
+	})
+}
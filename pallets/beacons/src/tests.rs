@@ -0,0 +1,783 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::{mock::*, *};
+use codec::{Decode, Encode};
+use frame_support::{
+	assert_noop, assert_ok,
+	dispatch::{DispatchResultWithPostInfo, GetDispatchInfo},
+	traits::{ConstU32, Hooks, Randomness},
+};
+use sp_core::{
+	offchain::{
+		testing::{PendingRequest, TestOffchainExt, TestTransactionPoolExt},
+		OffchainDbExt, OffchainWorkerExt, StorageKind, Timestamp, TransactionPoolExt,
+	},
+	H256,
+};
+use sp_runtime::{
+	traits::ValidateUnsigned,
+	transaction_validity::{InvalidTransaction, TransactionSource},
+	BuildStorage, DispatchError,
+};
+
+fn register(chain_hash: u8) -> BeaconId {
+	let id = NextBeaconId::<Test>::get();
+	assert_ok!(Beacons::register_beacon(RuntimeOrigin::root(), beacon(chain_hash)));
+	id
+}
+
+fn submit(beacon_id: BeaconId, round: RoundNumber, marker: u8) -> DispatchResultWithPostInfo {
+	Beacons::submit_pulse(RuntimeOrigin::none(), beacon_id, pulse(round, marker))
+}
+
+#[test]
+fn register_beacon_assigns_ids() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			Beacons::register_beacon(RuntimeOrigin::signed(ALICE), beacon(1)),
+			DispatchError::BadOrigin
+		);
+
+		assert_eq!(register(1), 0);
+		assert_eq!(register(2), 1);
+		assert_eq!(BeaconIds::<Test>::get([2; 32]), Some(1));
+		System::assert_last_event(
+			Event::BeaconRegistered { beacon_id: 1, chain_hash: [2; 32] }.into(),
+		);
+
+		assert_noop!(
+			Beacons::register_beacon(RuntimeOrigin::root(), beacon(1)),
+			Error::<Test>::DuplicateChainHash
+		);
+		let mut no_period = beacon(3);
+		no_period.period = 0;
+		assert_noop!(
+			Beacons::register_beacon(RuntimeOrigin::root(), no_period),
+			Error::<Test>::InvalidPeriod
+		);
+	});
+}
+
+#[test]
+fn register_beacon_is_bounded() {
+	new_test_ext().execute_with(|| {
+		for chain_hash in 0..MAX_BEACONS as u8 {
+			register(chain_hash);
+		}
+		assert_noop!(
+			Beacons::register_beacon(RuntimeOrigin::root(), beacon(u8::MAX)),
+			Error::<Test>::TooManyBeacons
+		);
+	});
+}
+
+#[test]
+fn remove_beacon_keeps_pulses_and_never_reuses_ids() {
+	new_test_ext().execute_with(|| {
+		let id = register(1);
+		assert_ok!(submit(id, 5, VALID));
+
+		assert_ok!(Beacons::remove_beacon(RuntimeOrigin::root(), id));
+		System::assert_last_event(Event::BeaconRemoved { beacon_id: id }.into());
+		assert!(Beacons::beacons().is_empty());
+		assert_eq!(Beacons::pulse(id, 5), Some(pulse(5, VALID)));
+		assert_noop!(submit(id, 6, VALID), Error::<Test>::UnknownBeacon);
+		assert_noop!(
+			Beacons::remove_beacon(RuntimeOrigin::root(), id),
+			Error::<Test>::UnknownBeacon
+		);
+
+		assert_eq!(register(1), id + 1);
+	});
+}
+
+#[test]
+fn submit_pulse_verifies_and_stores_per_beacon() {
+	new_test_ext().execute_with(|| {
+		let quicknet = register(1);
+		let mainnet = register(2);
+
+		assert_noop!(submit(quicknet, 10, INVALID), Error::<Test>::InvalidSignature);
+		let mut inconsistent = pulse(10, VALID);
+		inconsistent.randomness = [0; 32];
+		assert_noop!(
+			Beacons::submit_pulse(RuntimeOrigin::none(), quicknet, inconsistent),
+			Error::<Test>::RandomnessMismatch
+		);
+
+		assert_ok!(submit(quicknet, 10, VALID));
+		System::assert_last_event(Event::PulseStored { beacon_id: quicknet, round: 10 }.into());
+		assert_noop!(submit(quicknet, 10, VALID), Error::<Test>::StaleRound);
+		assert_noop!(submit(quicknet, 9, VALID), Error::<Test>::StaleRound);
+
+		// Rounds are tracked per beacon.
+		assert_ok!(submit(mainnet, 3, VALID));
+		assert_eq!(LatestRound::<Test>::get(quicknet), Some(10));
+		assert_eq!(LatestRound::<Test>::get(mainnet), Some(3));
+		assert_eq!(Beacons::latest_pulse(mainnet), Some(pulse(3, VALID)));
+		assert_eq!(Beacons::pulse(mainnet, 10), None);
+	});
+}
+
+#[test]
+fn submit_pulse_archives_with_offchain_indexing() {
+	let mut ext = new_test_ext();
+	let beacon_id = ext.execute_with(|| {
+		let beacon_id = register(1);
+		System::set_block_number(7);
+		System::set_parent_hash(H256::repeat_byte(6));
+		assert_ok!(submit(beacon_id, 10, VALID));
+		assert_noop!(submit(beacon_id, 11, INVALID), Error::<Test>::InvalidSignature);
+		beacon_id
+	});
+	ext.persist_offchain_overlay();
+
+	let archived = ext.offchain_db().get(&archive_key(beacon_id, 10)).unwrap();
+	assert_eq!(
+		ArchivedPulse::<u64, H256>::decode(&mut &archived[..]).unwrap(),
+		ArchivedPulse {
+			pulse: pulse(10, VALID),
+			block_number: 7,
+			parent_hash: H256::repeat_byte(6)
+		}
+	);
+	assert_eq!(ext.offchain_db().get(&archive_key(beacon_id, 11)), None);
+}
+
+#[test]
+fn submit_pulse_refunds_down_to_the_beacon_scheme() {
+	new_test_ext().execute_with(|| {
+		let bn254 = BeaconInfo { scheme: SignatureScheme::BlsBn254UnchainedG1, ..beacon(1) };
+		assert_ok!(Beacons::register_beacon(RuntimeOrigin::root(), bn254));
+
+		let call = crate::Call::<Test>::submit_pulse { beacon_id: 0, pulse: pulse(1, VALID) };
+		assert_eq!(call.get_dispatch_info().weight, Beacons::max_submit_pulse_weight());
+		let post_info = submit(0, 1, VALID).unwrap();
+		assert_eq!(post_info.actual_weight, Some(<() as WeightInfo>::submit_pulse_bn254()));
+	});
+}
+
+#[test]
+fn submit_pulse_must_be_unsigned() {
+	new_test_ext().execute_with(|| {
+		let id = register(1);
+		assert_noop!(
+			Beacons::submit_pulse(RuntimeOrigin::signed(ALICE), id, pulse(1, VALID)),
+			DispatchError::BadOrigin
+		);
+	});
+}
+
+#[test]
+fn validate_unsigned_checks_the_pulse() {
+	new_test_ext().execute_with(|| {
+		let id = register(1);
+		let validate = |round, marker| {
+			let call = crate::Call::submit_pulse { beacon_id: id, pulse: pulse(round, marker) };
+			Beacons::validate_unsigned(TransactionSource::External, &call)
+		};
+
+		let valid = validate(2, VALID).unwrap();
+		assert_eq!(valid.priority, UnsignedPriority::get());
+		assert_eq!(valid.provides, vec![("BeaconsPulse", (id, 2u64)).encode()]);
+		assert_eq!(validate(2, INVALID), InvalidTransaction::BadProof.into());
+
+		assert_ok!(submit(id, 2, VALID));
+		assert_eq!(validate(2, VALID), InvalidTransaction::Stale.into());
+
+		let unknown = crate::Call::submit_pulse { beacon_id: 7, pulse: pulse(3, VALID) };
+		assert_eq!(
+			Beacons::validate_unsigned(TransactionSource::External, &unknown),
+			InvalidTransaction::Call.into()
+		);
+	});
+}
+
+#[test]
+fn schedule_transition_checks_the_new_beacon() {
+	new_test_ext().execute_with(|| {
+		let id = register(1);
+		let other = register(2);
+		assert_ok!(submit(id, 10, VALID));
+
+		assert_noop!(
+			Beacons::schedule_transition(RuntimeOrigin::signed(ALICE), id, 20, beacon(3)),
+			DispatchError::BadOrigin
+		);
+		assert_noop!(
+			Beacons::schedule_transition(RuntimeOrigin::root(), 7, 20, beacon(3)),
+			Error::<Test>::UnknownBeacon
+		);
+		assert_noop!(
+			Beacons::schedule_transition(RuntimeOrigin::root(), id, 10, beacon(3)),
+			Error::<Test>::TransitionInThePast
+		);
+		assert_noop!(
+			Beacons::schedule_transition(RuntimeOrigin::root(), id, 20, beacon(2)),
+			Error::<Test>::DuplicateChainHash
+		);
+		assert_noop!(
+			Beacons::schedule_transition(
+				RuntimeOrigin::root(),
+				id,
+				20,
+				BeaconInfo { period: 0, ..beacon(3) }
+			),
+			Error::<Test>::InvalidPeriod
+		);
+
+		// Rotating the key of a beacon keeps its chain hash.
+		let rotated = BeaconInfo { public_key: vec![9; 96].try_into().unwrap(), ..beacon(1) };
+		assert_ok!(Beacons::schedule_transition(RuntimeOrigin::root(), id, 20, rotated));
+
+		// Rescheduling replaces the transition and releases the chain hash it reserved.
+		assert_ok!(Beacons::schedule_transition(RuntimeOrigin::root(), id, 20, beacon(3)));
+		System::assert_last_event(
+			Event::TransitionScheduled { beacon_id: id, from_round: 20, chain_hash: [3; 32] }
+				.into(),
+		);
+		assert_eq!(BeaconIds::<Test>::get([3; 32]), Some(id));
+		assert_noop!(
+			Beacons::schedule_transition(RuntimeOrigin::root(), other, 20, beacon(3)),
+			Error::<Test>::DuplicateChainHash
+		);
+		assert_ok!(Beacons::schedule_transition(RuntimeOrigin::root(), id, 20, beacon(4)));
+		assert_eq!(BeaconIds::<Test>::get([3; 32]), None);
+
+		assert_ok!(Beacons::cancel_transition(RuntimeOrigin::root(), id));
+		System::assert_last_event(Event::TransitionCancelled { beacon_id: id }.into());
+		assert_eq!(BeaconIds::<Test>::get([4; 32]), None);
+		assert_eq!(BeaconIds::<Test>::get([1; 32]), Some(id));
+		assert_noop!(
+			Beacons::cancel_transition(RuntimeOrigin::root(), id),
+			Error::<Test>::NoPendingTransition
+		);
+	});
+}
+
+#[test]
+fn transition_switches_keys_at_the_boundary() {
+	new_test_ext().execute_with(|| {
+		let (old, new) = (beacon(1), BeaconInfo { period: 30, ..beacon(2) });
+		let id = register(1);
+		assert_ok!(Beacons::schedule_transition(RuntimeOrigin::root(), id, 20, new.clone()));
+		assert_eq!(Beacons::beacon_at(id, 19), Some(old.clone()));
+		assert_eq!(Beacons::beacon_at(id, 20), Some(new.clone()));
+
+		// Before the boundary only the old key verifies.
+		assert_noop!(
+			Beacons::submit_pulse(RuntimeOrigin::none(), id, signed_pulse(19, &new)),
+			Error::<Test>::InvalidSignature
+		);
+		assert_ok!(Beacons::submit_pulse(RuntimeOrigin::none(), id, signed_pulse(19, &old)));
+		assert_eq!(Beacons::pending_transition(id).map(|t| t.from_round), Some(20));
+
+		// From the boundary on only the new key does, and the first such pulse applies it.
+		assert_noop!(
+			Beacons::submit_pulse(RuntimeOrigin::none(), id, signed_pulse(21, &old)),
+			Error::<Test>::InvalidSignature
+		);
+		assert_ok!(Beacons::submit_pulse(RuntimeOrigin::none(), id, signed_pulse(21, &new)));
+		System::assert_last_event(
+			Event::BeaconTransitioned { beacon_id: id, from_round: 20, chain_hash: [2; 32] }.into(),
+		);
+		assert_eq!(BeaconConfigs::<Test>::get(id), Some(new));
+		assert_eq!(Beacons::pending_transition(id), None);
+		assert_eq!(BeaconIds::<Test>::get([1; 32]), None);
+		assert_eq!(BeaconIds::<Test>::get([2; 32]), Some(id));
+
+		// The old chain hash is free again.
+		assert_eq!(register(1), id + 1);
+	});
+}
+
+#[test]
+fn transition_to_a_new_chain_restarts_rounds() {
+	new_test_ext().execute_with(|| {
+		let (old, new) = (beacon(1), beacon(2));
+		let id = register(1);
+		assert_ok!(Beacons::submit_pulse(RuntimeOrigin::none(), id, signed_pulse(1_000, &old)));
+
+		// Reusing the chain hash keeps the numbering, a new chain may start over.
+		let rotated = BeaconInfo { public_key: vec![9; 96].try_into().unwrap(), ..old.clone() };
+		assert_noop!(
+			Beacons::schedule_transition(RuntimeOrigin::root(), id, 5, rotated),
+			Error::<Test>::TransitionInThePast
+		);
+		assert_ok!(Beacons::schedule_transition(RuntimeOrigin::root(), id, 5, new.clone()));
+
+		// Until the switch, the old chain goes on and the new one may start.
+		assert_ok!(Beacons::submit_pulse(RuntimeOrigin::none(), id, signed_pulse(1_001, &old)));
+		assert_eq!(Beacons::pending_transition(id).map(|t| t.from_round), Some(5));
+		assert_noop!(
+			Beacons::submit_pulse(RuntimeOrigin::none(), id, signed_pulse(4, &new)),
+			Error::<Test>::StaleRound
+		);
+
+		let post_info =
+			Beacons::submit_pulse(RuntimeOrigin::none(), id, signed_pulse(6, &new)).unwrap();
+		assert_eq!(post_info.actual_weight, Some(<() as WeightInfo>::submit_pulse_unchained()));
+		System::assert_last_event(
+			Event::BeaconTransitioned { beacon_id: id, from_round: 5, chain_hash: [2; 32] }.into(),
+		);
+		assert_eq!(LatestRound::<Test>::get(id), Some(6));
+		assert_eq!(Beacons::latest_pulse(id), Some(signed_pulse(6, &new)));
+
+		// From then on only the new chain counts.
+		assert_noop!(
+			Beacons::submit_pulse(RuntimeOrigin::none(), id, signed_pulse(1_002, &old)),
+			Error::<Test>::InvalidSignature
+		);
+		assert_ok!(Beacons::submit_pulse(RuntimeOrigin::none(), id, signed_pulse(7, &new)));
+	});
+}
+
+#[test]
+fn remove_beacon_drops_its_transition() {
+	new_test_ext().execute_with(|| {
+		let id = register(1);
+		assert_ok!(Beacons::schedule_transition(RuntimeOrigin::root(), id, 20, beacon(2)));
+		assert_ok!(Beacons::remove_beacon(RuntimeOrigin::root(), id));
+		assert_eq!(Beacons::pending_transition(id), None);
+		assert_eq!(BeaconIds::<Test>::get([2; 32]), None);
+	});
+}
+
+#[test]
+fn randomness_depends_on_the_beacon() {
+	new_test_ext().execute_with(|| {
+		let (first, second) = (register(1), register(2));
+		assert_eq!(Beacons::random(first, b"subject").0, Default::default());
+
+		assert_ok!(submit(first, 1, VALID));
+		let signature = [VALID, 2].repeat(24);
+		let other = Pulse {
+			round: 1,
+			randomness: sp_io::hashing::sha2_256(&signature),
+			signature: signature.try_into().unwrap(),
+			previous_signature: Default::default(),
+		};
+		assert_ok!(Beacons::submit_pulse(RuntimeOrigin::none(), second, other));
+
+		let (seed, _) = Beacons::random(first, b"subject");
+		assert_ne!(seed, Default::default());
+		assert_ne!(seed, Beacons::random(second, b"subject").0);
+		assert_ne!(seed, Beacons::random(first, b"other").0);
+	});
+}
+
+#[test]
+fn beacon_stalls_and_resumes() {
+	new_test_ext().execute_with(|| {
+		let beacon_id = register(1);
+		assert_noop!(Beacons::try_random(beacon_id, b"subject"), Error::<Test>::NoPulse);
+		assert_ok!(submit(beacon_id, 10, VALID));
+		let (seed, _) = Beacons::try_random(beacon_id, b"subject").unwrap();
+		assert_eq!(seed, Beacons::random(beacon_id, b"subject").0);
+		assert_eq!(seed, BeaconRandomness::<Test, ConstU32<0>>::random(b"subject").0);
+
+		// Not stalled until the threshold is reached.
+		Beacons::on_initialize(STALL_THRESHOLD);
+		assert!(!Beacons::is_stalled(beacon_id));
+		System::set_block_number(1 + STALL_THRESHOLD);
+		Beacons::on_initialize(1 + STALL_THRESHOLD);
+		assert!(Beacons::is_stalled(beacon_id));
+		System::assert_last_event(Event::BeaconStalled { beacon_id, last_round: Some(10) }.into());
+		assert_noop!(Beacons::try_random(beacon_id, b"subject"), Error::<Test>::BeaconStalled);
+		assert_eq!(Beacons::live_pulse(beacon_id), None);
+		assert_eq!(BeaconRandomness::<Test, ConstU32<0>>::random(b"subject").0, Default::default());
+
+		// Stalls are reported once.
+		System::reset_events();
+		Beacons::on_initialize(2 + STALL_THRESHOLD);
+		assert!(System::events().is_empty());
+
+		System::set_block_number(5 + STALL_THRESHOLD);
+		assert_ok!(submit(beacon_id, 20, VALID));
+		System::assert_last_event(
+			Event::BeaconResumed { beacon_id, round: 20, stalled_blocks: 4 }.into(),
+		);
+		assert!(!Beacons::is_stalled(beacon_id));
+		assert_ok!(Beacons::try_random(beacon_id, b"subject"));
+		assert_eq!(Beacons::live_pulse(beacon_id).map(|pulse| pulse.round), Some(20));
+	});
+}
+
+#[test]
+fn new_beacons_get_a_grace_period() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(100);
+		let beacon_id = register(1);
+		Beacons::on_initialize(100 + STALL_THRESHOLD - 1);
+		assert!(!Beacons::is_stalled(beacon_id));
+		Beacons::on_initialize(100 + STALL_THRESHOLD);
+		System::assert_last_event(Event::BeaconStalled { beacon_id, last_round: None }.into());
+
+		assert_ok!(Beacons::remove_beacon(RuntimeOrigin::root(), beacon_id));
+		assert!(!Beacons::is_stalled(beacon_id));
+	});
+}
+
+#[test]
+fn optimistic_beacons_are_only_registered() {
+	new_test_ext().execute_with(|| {
+		let beacon_id = register(1);
+		Optimistic::set(Some(beacon_id));
+
+		assert_noop!(submit(beacon_id, 2, VALID), Error::<Test>::OptimisticBeacon);
+		let call = crate::Call::submit_pulse { beacon_id, pulse: pulse(2, VALID) };
+		assert_eq!(
+			Beacons::validate_unsigned(TransactionSource::External, &call),
+			InvalidTransaction::Call.into()
+		);
+		assert_eq!(Beacons::beacon_at(beacon_id, 2), Some(beacon(1)));
+
+		Beacons::on_initialize(1 + STALL_THRESHOLD);
+		assert!(!Beacons::is_stalled(beacon_id));
+	});
+}
+
+#[test]
+fn failed_checks_raise_an_alarm_until_cleared() {
+	new_test_ext().execute_with(|| {
+		let beacon_id = register(1);
+		let thresholds = HealthThresholds { window: 4, max_score: 35, max_repeats: 0 };
+		assert_noop!(
+			Beacons::set_thresholds(
+				RuntimeOrigin::root(),
+				HealthThresholds { window: 0, ..thresholds }
+			),
+			Error::<Test>::InvalidThresholds
+		);
+		assert_ok!(Beacons::set_thresholds(RuntimeOrigin::root(), thresholds));
+		assert_noop!(
+			Beacons::clear_alarm(RuntimeOrigin::root(), beacon_id),
+			Error::<Test>::NoAlarm
+		);
+
+		// The threshold is so tight that the first complete window of mock pulses fails.
+		for round in 1..4 {
+			assert_ok!(submit(beacon_id, round, VALID));
+		}
+		assert_ok!(Beacons::try_random(beacon_id, b"subject"));
+		assert_eq!(Beacons::randomness_health(beacon_id).unwrap().current.samples, 3);
+		assert_ok!(submit(beacon_id, 4, VALID));
+		System::assert_has_event(
+			Event::RandomnessAlarm { beacon_id, test: StatTest::Monobit, round: 4 }.into(),
+		);
+		let health = Beacons::randomness_health(beacon_id).unwrap();
+		assert_eq!(health.alarm, Some(StatTest::Monobit));
+		assert_eq!(health.previous.map(|window| window.samples), Some(4));
+		assert_eq!(health.current.samples, 0);
+		assert_noop!(Beacons::try_random(beacon_id, b"subject"), Error::<Test>::RandomnessAlarm);
+		assert_eq!(Beacons::live_pulse(beacon_id), None);
+		assert_eq!(BeaconRandomness::<Test, ConstU32<0>>::random(b"subject").0, Default::default());
+
+		assert_ok!(Beacons::clear_alarm(RuntimeOrigin::root(), beacon_id));
+		System::assert_last_event(Event::AlarmCleared { beacon_id }.into());
+		assert_ok!(Beacons::try_random(beacon_id, b"subject"));
+		assert_eq!(Beacons::live_pulse(beacon_id), Beacons::latest_pulse(beacon_id));
+	});
+}
+
+#[test]
+fn repeated_randomness_raises_an_alarm_at_once() {
+	new_test_ext().execute_with(|| {
+		let beacon_id = register(1);
+		assert_ok!(submit(beacon_id, 1, VALID));
+		let mut repeat = pulse(2, VALID);
+		repeat.randomness = pulse(1, VALID).randomness;
+		repeat.signature = pulse(1, VALID).signature;
+		assert_ok!(Beacons::submit_pulse(RuntimeOrigin::none(), beacon_id, repeat));
+		System::assert_has_event(
+			Event::RandomnessAlarm { beacon_id, test: StatTest::Repeats, round: 2 }.into(),
+		);
+		assert_eq!(Beacons::randomness_health(2), None);
+	});
+}
+
+#[test]
+fn stored_pulses_are_committed_to_the_header() {
+	new_test_ext().execute_with(|| {
+		let (first, second, idle) = (register(1), register(2), register(3));
+		assert_ok!(submit(first, 1, VALID));
+		assert_ok!(submit(first, 2, VALID));
+		assert_ok!(submit(second, 5, VALID));
+		assert_eq!(BlockPulses::<Test>::get().into_inner(), vec![first, second]);
+
+		Beacons::on_finalize(1);
+		let digests: Vec<_> = ideal_nw_primitives::digest::beacon_digests(&System::finalize())
+			.map(|digest| (digest.beacon_id, digest.pulse))
+			.collect();
+		assert_eq!(digests, vec![(first, pulse(2, VALID)), (second, pulse(5, VALID))]);
+		assert!(!digests.iter().any(|(beacon_id, _)| *beacon_id == idle));
+		assert!(BlockPulses::<Test>::get().is_empty());
+	});
+}
+
+#[test]
+fn mmr_leaves_hold_the_pulses_of_the_parent_block() {
+	use ideal_nw_primitives::mmr::{leaf_randomness, mmr_root, LeafPulse};
+	use sp_mmr_primitives::{LeafDataProvider, OnNewRoot};
+
+	new_test_ext().execute_with(|| {
+		let beacon_id = register(1);
+		assert_ok!(submit(beacon_id, 2, VALID));
+		Beacons::on_finalize(1);
+
+		let leaf = Beacons::leaf_data();
+		let randomness = pulse(2, VALID).randomness;
+		assert_eq!(leaf, vec![LeafPulse { beacon_id, round: 2, randomness }]);
+		assert_eq!(leaf_randomness(&leaf, beacon_id, 2), Some(randomness));
+
+		// A block without pulses appends an empty leaf.
+		Beacons::on_finalize(2);
+		assert!(Beacons::leaf_data().is_empty());
+
+		let root = sp_core::H256::repeat_byte(3);
+		Beacons::on_new_root(&root);
+		assert_eq!(mmr_root(&System::finalize()), Some(root));
+	});
+}
+
+#[test]
+fn pulse_keys_match_the_proof_verifier() {
+	use ideal_nw_primitives::proof::{pulse_key, BEACONS_PALLET};
+	assert_eq!(Pulses::<Test>::hashed_key_for(1, 2), pulse_key(BEACONS_PALLET, 1, 2));
+	assert_ne!(Pulses::<Test>::hashed_key_for(2, 1), pulse_key(BEACONS_PALLET, 1, 2));
+}
+
+#[test]
+fn parse_pulse_reads_drand_responses() {
+	let body = br#"{
+		"round": 2,
+		"randomness": "546f5dac4adefbcabbc6aac30a82c4ec786828dd51bc8567c584651508ee9df8",
+		"signature": "0101",
+		"previous_signature": "0202"
+	}"#;
+	let pulse = offchain::parse_pulse(body).unwrap();
+	assert_eq!(pulse.round, 2);
+	assert_eq!(pulse.signature.to_vec(), vec![1, 1]);
+	assert_eq!(pulse.previous_signature.to_vec(), vec![2, 2]);
+
+	let unchained = br#"{
+		"round": 3,
+		"randomness": "546f5dac4adefbcabbc6aac30a82c4ec786828dd51bc8567c584651508ee9df8",
+		"signature": "03"
+	}"#;
+	assert!(offchain::parse_pulse(unchained).unwrap().previous_signature.is_empty());
+
+	let short_randomness = br#"{"round":1,"randomness":"00","signature":"01"}"#;
+	assert_eq!(offchain::parse_pulse(short_randomness), Err(offchain::Error::Body));
+	assert_eq!(offchain::parse_pulse(b"not json"), Err(offchain::Error::Body));
+}
+
+#[test]
+fn offchain_worker_submits_new_pulses() {
+	let (offchain, state) = TestOffchainExt::new();
+	let (pool, pool_state) = TestTransactionPoolExt::new();
+	let mut ext = new_test_ext();
+	ext.register_extension(OffchainWorkerExt::new(offchain.clone()));
+	ext.register_extension(OffchainDbExt::new(offchain));
+	ext.register_extension(TransactionPoolExt::new(pool));
+
+	let expected = pulse(4, VALID);
+	let body = format!(
+		r#"{{"round":4,"randomness":"{}","signature":"{}"}}"#,
+		hex::encode(expected.randomness),
+		hex::encode(&expected.signature),
+	);
+	state.write().expect_request(PendingRequest {
+		method: "GET".into(),
+		uri: offchain::latest_url(ENDPOINT, &[1; 32]),
+		response: Some(body.into_bytes()),
+		sent: true,
+		..Default::default()
+	});
+
+	ext.execute_with(|| {
+		let id = register(1);
+
+		// Nothing is fetched before the beacon is due to emit a new round.
+		state.write().timestamp = Timestamp::from_unix_millis(900_000);
+		Beacons::offchain_worker(1);
+		assert!(pool_state.read().transactions.is_empty());
+
+		// Round 4 is emitted at 1_009s.
+		state.write().timestamp = Timestamp::from_unix_millis(1_010_000);
+		Beacons::offchain_worker(1);
+		let tx = pool_state.write().transactions.pop().unwrap();
+		let tx = Extrinsic::decode(&mut &tx[..]).unwrap();
+		assert_eq!(tx.signature, None);
+		assert_eq!(
+			tx.call,
+			RuntimeCall::Beacons(crate::Call::submit_pulse { beacon_id: id, pulse: expected })
+		);
+	});
+}
+
+#[test]
+fn set_endpoints_replaces_the_list() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(
+			Endpoints::<Test>::get().into_inner(),
+			vec![endpoint(ENDPOINT), endpoint(MIRROR)]
+		);
+
+		let endpoints: BoundedVec<_, _> = vec![endpoint(MIRROR)].try_into().unwrap();
+		assert_noop!(
+			Beacons::set_endpoints(RuntimeOrigin::signed(ALICE), endpoints.clone()),
+			DispatchError::BadOrigin
+		);
+		for invalid in [endpoint("drand.example"), Endpoint { timeout_ms: 0, ..endpoint(MIRROR) }] {
+			assert_noop!(
+				Beacons::set_endpoints(RuntimeOrigin::root(), vec![invalid].try_into().unwrap()),
+				Error::<Test>::InvalidEndpoint
+			);
+		}
+
+		assert_ok!(Beacons::set_endpoints(RuntimeOrigin::root(), endpoints.clone()));
+		System::assert_last_event(Event::EndpointsUpdated { count: 1 }.into());
+		assert_eq!(Endpoints::<Test>::get(), endpoints);
+	});
+}
+
+fn pulse_body(pulse: &Pulse) -> Vec<u8> {
+	format!(
+		r#"{{"round":{},"randomness":"{}","signature":"{}"}}"#,
+		pulse.round,
+		hex::encode(pulse.randomness),
+		hex::encode(&pulse.signature),
+	)
+	.into_bytes()
+}
+
+/// A test externalities with the offchain extensions registered, their states, and a function
+/// expecting a request for the latest pulse of beacon `[1; 32]`.
+macro_rules! offchain_ext {
+	() => {{
+		let (offchain, state) = TestOffchainExt::new();
+		let (pool, pool_state) = TestTransactionPoolExt::new();
+		let mut ext = new_test_ext();
+		ext.register_extension(OffchainWorkerExt::new(offchain.clone()));
+		ext.register_extension(OffchainDbExt::new(offchain));
+		ext.register_extension(TransactionPoolExt::new(pool));
+
+		let expect_latest = {
+			let state = state.clone();
+			move |endpoint: &str, body: &[u8]| {
+				state.write().expect_request(PendingRequest {
+					method: "GET".into(),
+					uri: offchain::latest_url(endpoint, &[1; 32]),
+					response: Some(body.to_vec()),
+					sent: true,
+					..Default::default()
+				})
+			}
+		};
+		(ext, state, pool_state, expect_latest)
+	}};
+}
+
+#[test]
+fn offchain_worker_fails_over_and_backs_off() {
+	let (mut ext, state, pool_state, expect_latest) = offchain_ext!();
+	let expected = pulse(4, VALID);
+
+	ext.execute_with(|| {
+		register(1);
+		state.write().timestamp = Timestamp::from_unix_millis(1_010_000);
+
+		// The first endpoint answers garbage, the mirror is asked next.
+		expect_latest(ENDPOINT, b"<html>");
+		expect_latest(MIRROR, &pulse_body(&expected));
+		Beacons::offchain_worker(1);
+		assert_eq!(pool_state.write().transactions.len(), 1);
+
+		// The first endpoint is left alone for its backoff.
+		pool_state.write().transactions.clear();
+		state.write().timestamp = Timestamp::from_unix_millis(1_010_500);
+		expect_latest(MIRROR, &pulse_body(&expected));
+		Beacons::offchain_worker(1);
+		assert_eq!(pool_state.write().transactions.len(), 1);
+
+		// Then it is tried again, and a failure doubles the backoff.
+		pool_state.write().transactions.clear();
+		state.write().timestamp = Timestamp::from_unix_millis(1_011_000);
+		expect_latest(ENDPOINT, b"<html>");
+		expect_latest(MIRROR, &pulse_body(&expected));
+		Beacons::offchain_worker(1);
+		state.write().timestamp = Timestamp::from_unix_millis(1_012_500);
+		expect_latest(MIRROR, &pulse_body(&expected));
+		Beacons::offchain_worker(1);
+		assert_eq!(pool_state.write().transactions.len(), 2);
+
+		let stats = sp_io::offchain::local_storage_get(StorageKind::PERSISTENT, FETCH_STATS_KEY)
+			.map(|stats| Vec::<(Url, FetchStats)>::decode(&mut &stats[..]).unwrap());
+		assert_eq!(
+			stats,
+			Some(vec![
+				(endpoint(ENDPOINT).url, FetchStats { successes: 0, failures: 2 }),
+				(endpoint(MIRROR).url, FetchStats { successes: 4, failures: 0 }),
+			])
+		);
+	});
+}
+
+#[test]
+fn local_endpoints_override_the_onchain_list() {
+	let (mut ext, state, pool_state, expect_latest) = offchain_ext!();
+	let local = "http://localhost:8080";
+
+	ext.execute_with(|| {
+		register(1);
+		state.write().timestamp = Timestamp::from_unix_millis(1_010_000);
+		sp_io::offchain::local_storage_set(
+			StorageKind::PERSISTENT,
+			ENDPOINTS_OVERRIDE_KEY,
+			&vec![endpoint(local)].encode(),
+		);
+
+		expect_latest(local, &pulse_body(&pulse(4, VALID)));
+		Beacons::offchain_worker(1);
+		assert_eq!(pool_state.write().transactions.len(), 1);
+	});
+}
+
+#[test]
+fn genesis_registers_beacons() {
+	let quicknet = BeaconInfo::quicknet();
+	let genesis = GenesisBeacon::from(&quicknet);
+	assert_eq!(genesis.info(), Some(quicknet.clone()));
+	assert_eq!(GenesisBeacon { scheme: "unknown".into(), ..genesis.clone() }.info(), None);
+
+	let storage = RuntimeGenesisConfig {
+		beacons: crate::GenesisConfig {
+			beacons: vec![genesis, GenesisBeacon::from(&beacon(1))],
+			..Default::default()
+		},
+		..Default::default()
+	}
+	.build_storage()
+	.unwrap();
+	sp_io::TestExternalities::new(storage).execute_with(|| {
+		assert_eq!(Beacons::beacon(0), Some(quicknet.clone()));
+		assert_eq!(Beacons::beacon(1), Some(beacon(1)));
+		assert_eq!(BeaconIds::<Test>::get(quicknet.chain_hash), Some(0));
+		assert_eq!(NextBeaconId::<Test>::get(), 2);
+	});
+}
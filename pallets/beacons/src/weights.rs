@@ -0,0 +1,251 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Weights for `pallet_beacons`.
+//!
+//! The figures below have not been measured yet. Replace this file with the output of the
+//! benchmarks in `benchmarking.rs` on reference hardware:
+//!
+//! ```sh
+//! ideal-nw-node benchmark pallet --chain dev --wasm-execution=compiled --pallet pallet_beacons \
+//!     --extrinsic "*" --steps 50 --repeat 20 --output pallets/beacons/src/weights.rs
+//! ```
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_beacons`.
+pub trait WeightInfo {
+	fn register_beacon() -> Weight;
+	fn remove_beacon() -> Weight;
+	fn submit_pulse_unchained() -> Weight;
+	fn submit_pulse_chained() -> Weight;
+	fn submit_pulse_bn254() -> Weight;
+	fn schedule_transition() -> Weight;
+	fn cancel_transition() -> Weight;
+	fn set_endpoints(n: u32, ) -> Weight;
+	fn check_stalls(b: u32, ) -> Weight;
+	fn set_thresholds() -> Weight;
+	fn clear_alarm() -> Weight;
+	fn deposit_digests(b: u32, ) -> Weight;
+}
+
+/// Weights for `pallet_beacons` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `Beacons::CounterForBeaconConfigs` (r:1 w:1)
+	/// Storage: `Beacons::BeaconIds` (r:1 w:1)
+	/// Storage: `Beacons::NextBeaconId` (r:1 w:1)
+	/// Storage: `Beacons::BeaconConfigs` (r:0 w:1)
+	/// Storage: `Beacons::LastPulseBlock` (r:0 w:1)
+	fn register_beacon() -> Weight {
+		Weight::from_parts(25_000_000, 3_500)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: `Beacons::BeaconConfigs` (r:1 w:1)
+	/// Storage: `Beacons::CounterForBeaconConfigs` (r:1 w:1)
+	/// Storage: `Beacons::Transitions` (r:1 w:1)
+	/// Storage: `Beacons::BeaconIds` (r:0 w:2)
+	/// Storage: `Beacons::LatestRound` (r:0 w:1)
+	/// Storage: `Beacons::LastPulseBlock` (r:0 w:1)
+	/// Storage: `Beacons::Stalled` (r:0 w:1)
+	fn remove_beacon() -> Weight {
+		Weight::from_parts(25_000_000, 3_800)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(8_u64))
+	}
+	/// Storage: `Beacons::BeaconConfigs` (r:1 w:1)
+	/// Storage: `Beacons::Transitions` (r:1 w:1)
+	/// Storage: `Beacons::LatestRound` (r:1 w:1)
+	/// Storage: `Beacons::BeaconIds` (r:0 w:1)
+	/// Storage: `Beacons::Pulses` (r:0 w:1)
+	/// Storage: `Beacons::Stalled` (r:1 w:1)
+	/// Storage: `Beacons::LastPulseBlock` (r:0 w:1)
+	/// Storage: `Beacons::Thresholds` (r:1 w:0)
+	/// Storage: `Beacons::Health` (r:1 w:1)
+	/// Storage: `Beacons::BlockPulses` (r:1 w:1)
+	fn submit_pulse_unchained() -> Weight {
+		// Dominated by the on-chain pairing check.
+		Weight::from_parts(1_200_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+	/// Storage: `Beacons::BeaconConfigs` (r:1 w:1)
+	/// Storage: `Beacons::Transitions` (r:1 w:1)
+	/// Storage: `Beacons::LatestRound` (r:1 w:1)
+	/// Storage: `Beacons::BeaconIds` (r:0 w:1)
+	/// Storage: `Beacons::Pulses` (r:0 w:1)
+	/// Storage: `Beacons::Stalled` (r:1 w:1)
+	/// Storage: `Beacons::LastPulseBlock` (r:0 w:1)
+	/// Storage: `Beacons::Thresholds` (r:1 w:0)
+	/// Storage: `Beacons::Health` (r:1 w:1)
+	/// Storage: `Beacons::BlockPulses` (r:1 w:1)
+	fn submit_pulse_chained() -> Weight {
+		// Hashing to G2 makes this the most expensive scheme.
+		Weight::from_parts(1_350_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+	/// Storage: `Beacons::BeaconConfigs` (r:1 w:1)
+	/// Storage: `Beacons::Transitions` (r:1 w:1)
+	/// Storage: `Beacons::LatestRound` (r:1 w:1)
+	/// Storage: `Beacons::BeaconIds` (r:0 w:1)
+	/// Storage: `Beacons::Pulses` (r:0 w:1)
+	/// Storage: `Beacons::Stalled` (r:1 w:1)
+	/// Storage: `Beacons::LastPulseBlock` (r:0 w:1)
+	/// Storage: `Beacons::Thresholds` (r:1 w:0)
+	/// Storage: `Beacons::Health` (r:1 w:1)
+	/// Storage: `Beacons::BlockPulses` (r:1 w:1)
+	fn submit_pulse_bn254() -> Weight {
+		Weight::from_parts(900_000_000, 4_100)
+			.saturating_add(T::DbWeight::get().reads(7_u64))
+			.saturating_add(T::DbWeight::get().writes(9_u64))
+	}
+	/// Storage: `Beacons::BeaconConfigs` (r:1 w:0)
+	/// Storage: `Beacons::LatestRound` (r:1 w:0)
+	/// Storage: `Beacons::BeaconIds` (r:1 w:2)
+	/// Storage: `Beacons::Transitions` (r:1 w:1)
+	fn schedule_transition() -> Weight {
+		Weight::from_parts(30_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(4_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: `Beacons::BeaconConfigs` (r:1 w:0)
+	/// Storage: `Beacons::Transitions` (r:1 w:1)
+	/// Storage: `Beacons::BeaconIds` (r:0 w:1)
+	fn cancel_transition() -> Weight {
+		Weight::from_parts(25_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: `Beacons::Endpoints` (r:0 w:1)
+	/// The range of component `n` is `[0, 8]`.
+	fn set_endpoints(n: u32, ) -> Weight {
+		Weight::from_parts(12_000_000, 0)
+			.saturating_add(Weight::from_parts(1_500_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Beacons::CounterForBeaconConfigs` (r:1 w:0)
+	/// Storage: `Beacons::BeaconConfigs` (r:9 w:0)
+	/// Storage: `Beacons::Stalled` (r:8 w:8)
+	/// Storage: `Beacons::LastPulseBlock` (r:8 w:0)
+	/// Storage: `Beacons::LatestRound` (r:8 w:0)
+	/// The range of component `b` is `[0, 8]`.
+	fn check_stalls(b: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 1_500)
+			.saturating_add(Weight::from_parts(10_000_000, 2_600).saturating_mul(b.into()))
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().reads((4_u64).saturating_mul(b.into())))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(b.into())))
+	}
+	/// Storage: `Beacons::Thresholds` (r:0 w:1)
+	fn set_thresholds() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Beacons::Health` (r:1 w:1)
+	fn clear_alarm() -> Weight {
+		Weight::from_parts(15_000_000, 3_700)
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Beacons::BlockPulses` (r:1 w:1)
+	/// Storage: `Beacons::LatestRound` (r:8 w:0)
+	/// Storage: `Beacons::Pulses` (r:8 w:0)
+	/// Storage: `System::Digest` (r:1 w:1)
+	/// Storage: `Beacons::ParentPulses` (r:0 w:1)
+	/// The range of component `b` is `[0, 8]`.
+	fn deposit_digests(b: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 1_600)
+			.saturating_add(Weight::from_parts(6_000_000, 4_000).saturating_mul(b.into()))
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(b.into())))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn register_beacon() -> Weight {
+		Weight::from_parts(25_000_000, 3_500)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	fn remove_beacon() -> Weight {
+		Weight::from_parts(25_000_000, 3_800)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(8_u64))
+	}
+	fn submit_pulse_unchained() -> Weight {
+		Weight::from_parts(1_200_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+	fn submit_pulse_chained() -> Weight {
+		Weight::from_parts(1_350_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+	fn submit_pulse_bn254() -> Weight {
+		Weight::from_parts(900_000_000, 4_100)
+			.saturating_add(RocksDbWeight::get().reads(7_u64))
+			.saturating_add(RocksDbWeight::get().writes(9_u64))
+	}
+	fn schedule_transition() -> Weight {
+		Weight::from_parts(30_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(4_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	fn cancel_transition() -> Weight {
+		Weight::from_parts(25_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	fn set_endpoints(n: u32, ) -> Weight {
+		Weight::from_parts(12_000_000, 0)
+			.saturating_add(Weight::from_parts(1_500_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn check_stalls(b: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 1_500)
+			.saturating_add(Weight::from_parts(10_000_000, 2_600).saturating_mul(b.into()))
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().reads((4_u64).saturating_mul(b.into())))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(b.into())))
+	}
+	fn set_thresholds() -> Weight {
+		Weight::from_parts(10_000_000, 0)
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn clear_alarm() -> Weight {
+		Weight::from_parts(15_000_000, 3_700)
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn deposit_digests(b: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 1_600)
+			.saturating_add(Weight::from_parts(6_000_000, 4_000).saturating_mul(b.into()))
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(b.into())))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+}
@@ -0,0 +1,314 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Benchmarks for `pallet_dkg`.
+//!
+//! Every DKG has [`Config::MaxParticipants`] collators and, unless a component says otherwise,
+//! the threshold of that many. Dealings reshare the key of a previous committee, which costs more
+//! than dealing a new one.
+
+use super::*;
+use frame_benchmarking::v2::*;
+use frame_system::RawOrigin;
+use ideal_nw_primitives::dkg::{
+	encrypt_share, encryption_key, partial_sign, reveal_shared_key, scalar_from_seed, Fr,
+	Polynomial,
+};
+
+/// The session of the benchmarked DKG, the committee holding the key being the previous one's.
+const SESSION: u32 = 1;
+
+/// `n` session keys, generated in the keystore.
+fn authorities<T: Config>(n: u32) -> Vec<T::AuthorityId> {
+	(0..n).map(|_| T::AuthorityId::generate_pair(None)).collect()
+}
+
+fn sign<T: Config>(authority: &T::AuthorityId, message: &impl Encode) -> AuthoritySignatureOf<T> {
+	authority.sign(&message.encode()).expect("the key is in the keystore; qed")
+}
+
+/// The encryption secret of `participant`.
+fn secret(participant: ParticipantIndex) -> Fr {
+	scalar_from_seed(&participant.to_le_bytes(), b"encryption")
+}
+
+/// The threshold of the largest committee.
+fn max_threshold<T: Config>() -> u32 {
+	dkg::threshold(T::MaxParticipants::get())
+}
+
+/// The polynomial shared by the committee holding the key.
+fn committee_polynomial<T: Config>() -> Polynomial {
+	Polynomial::from_seed(b"committee", max_threshold::<T>(), None)
+}
+
+/// A dealing of `polynomial` to the first `participants`.
+fn deal_to(polynomial: &Polynomial, participants: u32) -> Dealing {
+	let shares = (0..participants)
+		.map(|participant| {
+			let key = encryption_key(&secret(participant));
+			encrypt_share(&key, &polynomial.evaluate(participant), &participant.to_le_bytes())
+		})
+		.collect();
+	Dealing { commitments: polynomial.commitments(), shares }
+}
+
+/// Publish `dealing` for the first `dealers` of the DKG of `session`.
+fn insert_dealings<T: Config>(session: u32, dealers: u32, dealing: &Dealing) {
+	(0..dealers).for_each(|dealer| Dealings::<T>::insert(session, dealer, dealing.clone()));
+}
+
+/// Make `members` the committee of the session before [`SESSION`], with the dealings that formed
+/// it.
+fn set_committee<T: Config>(members: Vec<T::AuthorityId>) {
+	let count = members.len() as u32;
+	let polynomial = committee_polynomial::<T>();
+	insert_dealings::<T>(SESSION - 1, count, &deal_to(&polynomial, count));
+	CurrentCommittee::<T>::put(Committee {
+		session: SESSION - 1,
+		members,
+		threshold: max_threshold::<T>(),
+		qualified: (0..count).collect(),
+		reshared: false,
+		commitments: polynomial.commitments(),
+	});
+}
+
+/// Start the DKG of `session` in `phase`, resharing the key of the committee if there is one,
+/// every participant having announced its encryption key.
+fn set_pending<T: Config>(
+	session: u32,
+	dealers: Vec<T::AuthorityId>,
+	participants: Vec<T::AuthorityId>,
+	threshold: u32,
+	phase: Phase,
+) -> DkgRoundOf<T> {
+	(0..participants.len() as u32).for_each(|participant| {
+		EncryptionKeys::<T>::insert(participant, encryption_key(&secret(participant)))
+	});
+	let round = DkgRound {
+		session,
+		dealers,
+		participants,
+		threshold,
+		reshare_threshold: CurrentCommittee::<T>::get().map(|committee| committee.threshold),
+		started_at: frame_system::Pallet::<T>::block_number(),
+		phase,
+	};
+	LastSession::<T>::put(T::CurrentSession::get());
+	Pending::<T>::put(&round);
+	round
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn on_initialize() {
+		let members = authorities::<T>(T::MaxParticipants::get());
+		let round = set_pending::<T>(
+			SESSION,
+			members.clone(),
+			members,
+			max_threshold::<T>(),
+			Phase::Announce,
+		);
+		// The first block of the deal phase.
+		let now = round.started_at + T::PhaseLength::get();
+
+		#[block]
+		{
+			Pallet::<T>::on_initialize(now);
+		}
+
+		assert_eq!(Pending::<T>::get().map(|round| round.phase), Some(Phase::Deal));
+	}
+
+	#[benchmark]
+	fn start_dkg(n: Linear<0, { T::MaxParticipants::get() }>) {
+		let max = T::MaxParticipants::get();
+		let members = authorities::<T>(max);
+		let polynomial = committee_polynomial::<T>();
+		let dealing = deal_to(&polynomial, max);
+		set_committee::<T>(members.clone());
+		// A committee waiting to take over is dropped and a DKG that didn't complete abandoned,
+		// with all their dealings.
+		NextCommittee::<T>::put(Committee {
+			session: SESSION,
+			members: members.clone(),
+			threshold: max_threshold::<T>(),
+			qualified: (0..max).collect(),
+			reshared: true,
+			commitments: polynomial.commitments(),
+		});
+		insert_dealings::<T>(SESSION, max, &dealing);
+		let round = set_pending::<T>(
+			SESSION + 1,
+			members.clone(),
+			members,
+			max_threshold::<T>(),
+			Phase::Complain,
+		);
+		insert_dealings::<T>(SESSION + 1, max, &dealing);
+		T::BenchmarkHelper::set_authorities(authorities::<T>(n));
+		LastSession::<T>::kill();
+
+		#[block]
+		{
+			Pallet::<T>::on_initialize(round.started_at);
+		}
+
+		assert_eq!(LastSession::<T>::get(), Some(T::CurrentSession::get()));
+		assert!(NextCommittee::<T>::get().is_none());
+	}
+
+	#[benchmark]
+	fn finish_dkg(
+		d: Linear<{ max_threshold::<T>() }, { T::MaxParticipants::get() }>,
+		t: Linear<1, { max_threshold::<T>() }>,
+	) {
+		let max = T::MaxParticipants::get();
+		let members = authorities::<T>(max);
+		// The committee's beacon failed to register, so the reshared committee takes over and
+		// registers it.
+		set_committee::<T>(members.clone());
+		let round = set_pending::<T>(SESSION, members.clone(), members, t, Phase::Complain);
+		insert_dealings::<T>(SESSION, d, &deal_to(&Polynomial::from_seed(b"dealer", t, None), max));
+		let phase_length = T::PhaseLength::get();
+		let now = round.started_at + phase_length + phase_length + phase_length;
+
+		#[block]
+		{
+			Pallet::<T>::on_initialize(now);
+		}
+
+		assert_eq!(CurrentCommittee::<T>::get().map(|committee| committee.session), Some(SESSION));
+		assert!(CommitteeBeacon::<T>::exists());
+	}
+
+	#[benchmark]
+	fn announce() {
+		let members = authorities::<T>(T::MaxParticipants::get());
+		set_pending::<T>(
+			SESSION,
+			members.clone(),
+			members.clone(),
+			max_threshold::<T>(),
+			Phase::Announce,
+		);
+		EncryptionKeys::<T>::remove(0);
+		let announcement =
+			Announcement { session: SESSION, participant: 0, key: encryption_key(&secret(0)) };
+		let signature = sign::<T>(&members[0], &announcement);
+
+		#[extrinsic_call]
+		_(RawOrigin::None, announcement, signature);
+
+		assert!(EncryptionKeys::<T>::contains_key(0));
+	}
+
+	#[benchmark]
+	fn deal(n: Linear<1, { T::MaxParticipants::get() }>) {
+		let members = authorities::<T>(T::MaxParticipants::get());
+		set_committee::<T>(members.clone());
+		let participants = members[..n as usize].to_vec();
+		set_pending::<T>(SESSION, members.clone(), participants, dkg::threshold(n), Phase::Deal);
+		// Dealer 0 reshares its share of the committee's key.
+		let share = committee_polynomial::<T>().evaluate(0);
+		let polynomial = Polynomial::from_seed(b"dealer", dkg::threshold(n), Some(share));
+		let dealing =
+			SignedDealing { session: SESSION, dealer: 0, dealing: deal_to(&polynomial, n) };
+		let signature = sign::<T>(&members[0], &dealing);
+
+		#[extrinsic_call]
+		_(RawOrigin::None, dealing, signature);
+
+		assert!(Dealings::<T>::contains_key(SESSION, 0));
+	}
+
+	#[benchmark]
+	fn submit_partial(t: Linear<1, { max_threshold::<T>() }>) {
+		let max = T::MaxParticipants::get();
+		let members = authorities::<T>(max);
+		// The previous committee's dealings are dropped when the reshared one takes over.
+		set_committee::<T>(members.clone());
+		let polynomial = Polynomial::from_seed(b"reshared", t, None);
+		NextCommittee::<T>::put(Committee {
+			session: SESSION,
+			members: members.clone(),
+			threshold: t,
+			qualified: (0..max).collect(),
+			reshared: true,
+			commitments: polynomial.commitments(),
+		});
+		let now = T::UnixTime::now().as_secs();
+		let group_key = committee_polynomial::<T>().commitments()[0];
+		let beacon = BeaconInfo {
+			chain_hash: dkg::chain_hash(&group_key),
+			public_key: group_key.to_vec().try_into().expect("G2 points are 96 bytes; qed"),
+			period: T::RoundPeriod::get(),
+			genesis_time: now,
+			scheme: SignatureScheme::BlsUnchainedG1Rfc9380,
+		};
+		let round = beacon.round_at(now) + MAX_ROUNDS_AHEAD;
+		CommitteeBeacon::<T>::put((0, beacon));
+		// One signature short of the threshold, so that the reshared committee takes over.
+		(1..t).for_each(|member| Partials::<T>::insert((round, SESSION, member), [0; 48]));
+		// No room for another round: the oldest one, fully signed, is dropped.
+		let oldest = round - 1;
+		(0..max).for_each(|member| Partials::<T>::insert((oldest, SESSION - 1, member), [0; 48]));
+		let rounds = core::iter::once(oldest).chain(round + 1..);
+		let rounds: Vec<_> = rounds.take(T::MaxPendingRounds::get() as usize).collect();
+		PartialRounds::<T>::put(BoundedVec::truncate_from(rounds));
+		let signature = partial_sign(&polynomial.evaluate(0), round).expect("rounds hash to G1");
+		let partial = PartialSignature { session: SESSION, member: 0, round, signature };
+		let signature = sign::<T>(&members[0], &partial);
+
+		#[extrinsic_call]
+		_(RawOrigin::None, partial, signature);
+
+		assert_eq!(CurrentCommittee::<T>::get().map(|committee| committee.session), Some(SESSION));
+		assert!(!Partials::<T>::contains_key((oldest, SESSION - 1, 0)));
+	}
+
+	#[benchmark]
+	fn complain(t: Linear<1, { max_threshold::<T>() }>) {
+		let max = T::MaxParticipants::get();
+		let members = authorities::<T>(max);
+		set_pending::<T>(SESSION, members.clone(), members.clone(), t, Phase::Complain);
+		let polynomial = Polynomial::from_seed(b"dealer", t, None);
+		let mut dealing = deal_to(&polynomial, max);
+		// Participant 0 got a wrong share.
+		let key = encryption_key(&secret(0));
+		let wrong = polynomial.evaluate(0) + Fr::from(1u64);
+		let share = encrypt_share(&key, &wrong, b"wrong").expect("the key is valid; qed");
+		dealing.shares[0] = Some(share.clone());
+		Dealings::<T>::insert(SESSION, 0, dealing);
+		let (shared_key, proof) =
+			reveal_shared_key(&secret(0), &share, b"complaint").expect("the share is valid; qed");
+		let complaint =
+			Complaint { session: SESSION, participant: 0, dealer: 0, shared_key, proof };
+		let signature = sign::<T>(&members[0], &complaint);
+
+		#[extrinsic_call]
+		_(RawOrigin::None, complaint, signature);
+
+		assert!(!Dealings::<T>::contains_key(SESSION, 0));
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,868 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # DKG Pallet
+//!
+//! A threshold BLS beacon run by the collators, so that the network's randomness doesn't rest on
+//! drand alone.
+//!
+//! At every session change the collators of the new session run a distributed key generation,
+//! coordinated through this pallet, see [`ideal_nw_primitives::dkg`]:
+//!
+//! 1. For [`Config::PhaseLength`] blocks, participants [`announce`](Pallet::announce) the key their
+//!    shares are encrypted to.
+//! 2. For as many blocks, dealers publish their [`deal`](Pallet::deal): commitments to a secret
+//!    polynomial, checked on chain, and a share for every participant that announced a key.
+//! 3. For as many blocks, participants that got a wrong share [`complain`](Pallet::complain) by
+//!    revealing the key it was encrypted with, with a proof. The share is then checked on chain
+//!    and, if wrong, the dealer's dealing is dropped.
+//! 4. The DKG then ends. If enough dealers are left, the participants become the committee holding
+//!    the key, otherwise the previous committee keeps it.
+//!
+//! The first DKG generates a key and registers it with [`Config::Beacons`] as a
+//! `bls-unchained-g1-rfc9380` beacon. Later ones reshare it from the committee to the collators
+//! of the new session, so the key and the beacon stay the same. Members sign every round with
+//! their share and [`submit_partial`](Pallet::submit_partial) the signature, which is checked
+//! against the member's public share. Once `threshold` partial signatures of a round are on
+//! chain, they aggregate into a pulse that is submitted to the beacon like any other.
+//!
+//! A reshared committee waits in [`NextCommittee`] and only takes over once it signed a round,
+//! so that a committee whose members can't sign doesn't stall the beacon. Until then both sign.
+//! If the next DKG starts first, the waiting committee is dropped.
+//!
+//! Messages are unsigned transactions signed with the sender's session key. The node's DKG worker
+//! sends them.
+//!
+//! ## Limitations
+//!
+//! A participant that didn't announce a key in time holds no share for the session, nor does one
+//! that got a wrong share and didn't complain in time: the committee signs as long as `threshold`
+//! of its members hold correct shares. A complaint reveals the key of the share complained about
+//! only, the participant's encryption secret and other shares stay secret.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub use pallet::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+use alloc::vec::Vec;
+use frame_support::{pallet_prelude::*, traits::UnixTime};
+use frame_system::pallet_prelude::BlockNumberFor;
+use ideal_nw_primitives::{beacon::SignatureScheme, dkg};
+pub use ideal_nw_primitives::{
+	beacon::{BeaconId, BeaconInfo, RoundNumber},
+	dkg::{
+		Announcement, Committee, Complaint, Dealing, DkgRound, G1Bytes, G2Bytes, PartialSignature,
+		ParticipantIndex, Phase, SharedKeyProof, SignedDealing,
+	},
+};
+use sp_runtime::{
+	traits::{SaturatedConversion, Saturating},
+	RuntimeAppPublic,
+};
+
+pub use weights::WeightInfo;
+
+const LOG_TARGET: &str = "runtime::dkg";
+
+/// How many rounds past the current one partial signatures are accepted for, to allow for clock
+/// drift between the collators and the block timestamp.
+const MAX_ROUNDS_AHEAD: RoundNumber = 2;
+
+/// Registers the committee's beacon.
+pub trait RegisterBeacon {
+	/// Start following the beacon `info`, returning its id.
+	fn register_beacon(info: BeaconInfo) -> Result<BeaconId, DispatchError>;
+}
+
+/// Helper used by the benchmarks to set up the collators.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AuthorityId> {
+	/// Make `authorities` the collators of the current session.
+	fn set_authorities(authorities: Vec<AuthorityId>);
+}
+
+/// The signature of a session key.
+pub type AuthoritySignatureOf<T> = <<T as Config>::AuthorityId as RuntimeAppPublic>::Signature;
+
+/// A DKG of this runtime.
+pub type DkgRoundOf<T> = DkgRound<<T as Config>::AuthorityId, BlockNumberFor<T>>;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The session key collators sign their DKG messages with.
+		type AuthorityId: Member + Parameter + RuntimeAppPublic;
+
+		/// The collators of the current session, who take part in its DKG.
+		type Authorities: Get<Vec<Self::AuthorityId>>;
+
+		/// The index of the current session.
+		type CurrentSession: Get<u32>;
+
+		/// Registers the committee's beacon after the first DKG.
+		type Beacons: RegisterBeacon;
+
+		/// The time the committee's beacon starts at.
+		type UnixTime: UnixTime;
+
+		/// Seconds between two rounds of the committee's beacon.
+		#[pallet::constant]
+		type RoundPeriod: Get<u32>;
+
+		/// Number of blocks of each phase of a DKG.
+		#[pallet::constant]
+		type PhaseLength: Get<BlockNumberFor<Self>>;
+
+		/// Maximum number of collators taking part in a DKG. Further collators are left out.
+		#[pallet::constant]
+		type MaxParticipants: Get<u32>;
+
+		/// Maximum number of rounds whose partial signatures are kept.
+		#[pallet::constant]
+		type MaxPendingRounds: Get<u32>;
+
+		/// Priority of the unsigned transactions carrying DKG messages.
+		#[pallet::constant]
+		type UnsignedPriority: Get<TransactionPriority>;
+
+		/// Weight information for extrinsics and hooks in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Helper to set up the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<Self::AuthorityId>;
+	}
+
+	/// The session of the latest DKG started.
+	#[pallet::storage]
+	pub type LastSession<T: Config> = StorageValue<_, u32, OptionQuery>;
+
+	/// The DKG in progress.
+	///
+	/// Its lists are bounded by [`Config::MaxParticipants`].
+	#[pallet::storage]
+	#[pallet::unbounded]
+	pub type Pending<T: Config> = StorageValue<_, DkgRoundOf<T>, OptionQuery>;
+
+	/// The encryption keys announced in the DKG in progress, by participant.
+	#[pallet::storage]
+	pub type EncryptionKeys<T: Config> =
+		StorageMap<_, Twox64Concat, ParticipantIndex, G1Bytes, OptionQuery>;
+
+	/// The dealings of the DKG in progress and of the ones that formed the committees, by session
+	/// and dealer. Members recompute their shares from them.
+	///
+	/// Their lists are bounded by [`Config::MaxParticipants`].
+	#[pallet::storage]
+	#[pallet::unbounded]
+	pub type Dealings<T: Config> = StorageDoubleMap<
+		_,
+		Twox64Concat,
+		u32,
+		Twox64Concat,
+		ParticipantIndex,
+		Dealing,
+		OptionQuery,
+	>;
+
+	/// The committee holding the key.
+	///
+	/// Its lists are bounded by [`Config::MaxParticipants`].
+	#[pallet::storage]
+	#[pallet::unbounded]
+	pub type CurrentCommittee<T: Config> = StorageValue<_, Committee<T::AuthorityId>, OptionQuery>;
+
+	/// The committee the key was reshared to, taking over once it signed a round.
+	///
+	/// Its lists are bounded by [`Config::MaxParticipants`].
+	#[pallet::storage]
+	#[pallet::unbounded]
+	pub type NextCommittee<T: Config> = StorageValue<_, Committee<T::AuthorityId>, OptionQuery>;
+
+	/// The beacon the committee signs, registered after the first DKG.
+	#[pallet::storage]
+	pub type CommitteeBeacon<T: Config> = StorageValue<_, (BeaconId, BeaconInfo), OptionQuery>;
+
+	/// The partial signatures of the committees, by round, committee session and member.
+	#[pallet::storage]
+	pub type Partials<T: Config> = StorageNMap<
+		_,
+		(
+			NMapKey<Twox64Concat, RoundNumber>,
+			NMapKey<Twox64Concat, u32>,
+			NMapKey<Twox64Concat, ParticipantIndex>,
+		),
+		G1Bytes,
+		OptionQuery,
+	>;
+
+	/// The rounds in [`Partials`], in ascending order.
+	#[pallet::storage]
+	pub type PartialRounds<T: Config> =
+		StorageValue<_, BoundedVec<RoundNumber, T::MaxPendingRounds>, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The collators of `session` started a DKG.
+		DkgStarted { session: u32, participants: u32, reshare: bool },
+		/// A participant announced its encryption key.
+		KeyAnnounced { session: u32, participant: ParticipantIndex },
+		/// A dealer published its dealing.
+		Dealt { session: u32, dealer: ParticipantIndex },
+		/// A participant's complaint about its share was upheld, dropping the dealer's dealing.
+		DealerDisqualified { session: u32, dealer: ParticipantIndex, participant: ParticipantIndex },
+		/// The participants of the DKG of `session` hold the key. Unless they are the first
+		/// committee, they take over once they signed a round.
+		DkgCompleted { session: u32, members: u32, threshold: u32, dealers: u32 },
+		/// The committee formed by the DKG of `session` took over.
+		CommitteeActivated { session: u32 },
+		/// The committee formed by the DKG of `session` didn't sign a round before the next DKG
+		/// started and was dropped.
+		CommitteeDropped { session: u32 },
+		/// The DKG of `session` failed, the previous committee, if any, keeps the key.
+		DkgFailed { session: u32, dealers: u32 },
+		/// The committee's beacon was registered.
+		BeaconCreated { beacon_id: BeaconId },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// No DKG is in progress.
+		NoDkg,
+		/// The message is for another session.
+		WrongSession,
+		/// The DKG is in another phase.
+		WrongPhase,
+		/// The sender doesn't take part in the DKG or the committee.
+		UnknownSender,
+		/// The participant already announced its key.
+		AlreadyAnnounced,
+		/// The encryption key isn't a valid point.
+		InvalidKey,
+		/// The dealer already dealt.
+		AlreadyDealt,
+		/// The dealing has the wrong shape or its commitments don't check out.
+		InvalidDealing,
+		/// The message isn't signed by the sender's session key.
+		InvalidSignature,
+		/// No committee holds the key.
+		NoCommittee,
+		/// The round is too old to collect signatures for.
+		StaleRound,
+		/// The round hasn't started.
+		FutureRound,
+		/// The member already signed the round.
+		AlreadySigned,
+		/// The round already has `threshold` partial signatures.
+		RoundSigned,
+		/// The partial signature isn't the member's.
+		InvalidPartial,
+		/// The dealer has no dealing to complain about.
+		NoDealing,
+		/// The complaint's proof doesn't check out, or the share is right.
+		InvalidComplaint,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+			let session = T::CurrentSession::get();
+			if LastSession::<T>::get() != Some(session) {
+				return Self::start(session, n);
+			}
+			let Some(mut round) = Pending::<T>::get() else {
+				return T::WeightInfo::on_initialize();
+			};
+			let elapsed = n.saturating_sub(round.started_at);
+			let phase_length = T::PhaseLength::get();
+			let deal_end = phase_length.saturating_add(phase_length);
+			if elapsed >= deal_end.saturating_add(phase_length) {
+				return Self::finish(round);
+			}
+			let phase = if elapsed >= deal_end {
+				Phase::Complain
+			} else if elapsed >= phase_length {
+				Phase::Deal
+			} else {
+				Phase::Announce
+			};
+			if round.phase != phase {
+				round.phase = phase;
+				Pending::<T>::put(round);
+			}
+			T::WeightInfo::on_initialize()
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Announce the key a participant's shares are encrypted to.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::announce())]
+		pub fn announce(
+			origin: OriginFor<T>,
+			announcement: Announcement,
+			signature: AuthoritySignatureOf<T>,
+		) -> DispatchResult {
+			ensure_none(origin)?;
+			Self::verify_announcement(&announcement, &signature)?;
+
+			let Announcement { session, participant, key } = announcement;
+			EncryptionKeys::<T>::insert(participant, key);
+			Self::deposit_event(Event::KeyAnnounced { session, participant });
+			Ok(())
+		}
+
+		/// Publish a dealer's dealing.
+		///
+		/// The weight of the largest DKG is charged up front and the difference refunded.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::deal(T::MaxParticipants::get()))]
+		pub fn deal(
+			origin: OriginFor<T>,
+			dealing: SignedDealing,
+			signature: AuthoritySignatureOf<T>,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+			let participants = Self::verify_dealing(&dealing, &signature)?;
+
+			let SignedDealing { session, dealer, dealing } = dealing;
+			Dealings::<T>::insert(session, dealer, dealing);
+			Self::deposit_event(Event::Dealt { session, dealer });
+			Ok(Some(T::WeightInfo::deal(participants)).into())
+		}
+
+		/// Post a member's signature of a round with its share.
+		///
+		/// The weight of the largest committee is charged up front and the difference refunded.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::submit_partial(T::MaxParticipants::get()))]
+		pub fn submit_partial(
+			origin: OriginFor<T>,
+			partial: PartialSignature,
+			signature: AuthoritySignatureOf<T>,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+			let threshold = Self::verify_partial(&partial, &signature)?;
+
+			let (session, round) = (partial.session, partial.round);
+			Self::note_round(round);
+			Partials::<T>::insert((round, session, partial.member), partial.signature);
+			if let Some(next) = NextCommittee::<T>::get().filter(|next| next.session == session) {
+				let signers = Partials::<T>::iter_key_prefix((round, session)).count();
+				if signers >= next.threshold as usize {
+					NextCommittee::<T>::kill();
+					Self::activate(next);
+				}
+			}
+			Ok(Some(T::WeightInfo::submit_partial(threshold)).into())
+		}
+
+		/// Complain that a dealer sent a participant a wrong share, revealing the key it was
+		/// encrypted with. If the share is wrong, the dealer's dealing is dropped.
+		///
+		/// The weight of the largest threshold is charged up front and the difference refunded.
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::WeightInfo::complain(T::MaxParticipants::get()))]
+		pub fn complain(
+			origin: OriginFor<T>,
+			complaint: Complaint,
+			signature: AuthoritySignatureOf<T>,
+		) -> DispatchResultWithPostInfo {
+			ensure_none(origin)?;
+			let threshold = Self::verify_complaint(&complaint, &signature)?;
+
+			let Complaint { session, participant, dealer, .. } = complaint;
+			Dealings::<T>::remove(session, dealer);
+			Self::deposit_event(Event::DealerDisqualified { session, dealer, participant });
+			Ok(Some(T::WeightInfo::complain(threshold)).into())
+		}
+	}
+
+	#[pallet::validate_unsigned]
+	impl<T: Config> ValidateUnsigned for Pallet<T> {
+		type Call = Call<T>;
+
+		fn validate_unsigned(_source: TransactionSource, call: &Self::Call) -> TransactionValidity {
+			let phase_length = T::PhaseLength::get().saturated_into::<u64>();
+			let (tag, longevity) = match call {
+				Call::announce { announcement, signature } => {
+					Self::verify_announcement(announcement, signature)
+						.map_err(invalid_transaction::<T>)?;
+					((0u8, announcement.session, announcement.participant).encode(), phase_length)
+				},
+				Call::deal { dealing, signature } => {
+					Self::verify_dealing(dealing, signature).map_err(invalid_transaction::<T>)?;
+					((1u8, dealing.session, dealing.dealer).encode(), phase_length)
+				},
+				Call::submit_partial { partial, signature } => {
+					Self::verify_partial(partial, signature).map_err(invalid_transaction::<T>)?;
+					((2u8, partial.round, partial.session, partial.member).encode(), 5)
+				},
+				Call::complain { complaint, signature } => {
+					Self::verify_complaint(complaint, signature)
+						.map_err(invalid_transaction::<T>)?;
+					let Complaint { session, participant, dealer, .. } = complaint;
+					((3u8, session, participant, dealer).encode(), phase_length)
+				},
+				_ => return InvalidTransaction::Call.into(),
+			};
+
+			ValidTransaction::with_tag_prefix("Dkg")
+				.priority(T::UnsignedPriority::get())
+				.and_provides(tag)
+				.longevity(longevity.max(1))
+				.propagate(true)
+				.build()
+		}
+
+		fn pre_dispatch(call: &Self::Call) -> Result<(), TransactionValidityError> {
+			// Signatures and points are checked when the call is dispatched, no need to check
+			// them twice.
+			match call {
+				Call::announce { announcement, .. } =>
+					Self::check_announcement(announcement).map(drop),
+				Call::deal { dealing, .. } => Self::check_dealing(dealing).map(drop),
+				Call::submit_partial { partial, .. } => Self::check_partial(partial).map(drop),
+				Call::complain { complaint, .. } => Self::check_complaint(complaint).map(drop),
+				_ => return Err(InvalidTransaction::Call.into()),
+			}
+			.map_err(invalid_transaction::<T>)
+		}
+	}
+}
+
+fn invalid_transaction<T: Config>(err: Error<T>) -> TransactionValidityError {
+	match err {
+		Error::<T>::WrongSession |
+		Error::<T>::AlreadyAnnounced |
+		Error::<T>::AlreadyDealt |
+		Error::<T>::StaleRound |
+		Error::<T>::AlreadySigned |
+		Error::<T>::RoundSigned |
+		Error::<T>::NoDealing => InvalidTransaction::Stale,
+		Error::<T>::FutureRound => InvalidTransaction::Future,
+		Error::<T>::InvalidKey |
+		Error::<T>::InvalidDealing |
+		Error::<T>::InvalidSignature |
+		Error::<T>::InvalidPartial |
+		Error::<T>::InvalidComplaint => InvalidTransaction::BadProof,
+		_ => InvalidTransaction::Call,
+	}
+	.into()
+}
+
+impl<T: Config> Pallet<T> {
+	/// Start the DKG of `session` among its collators.
+	fn start(session: u32, now: BlockNumberFor<T>) -> Weight {
+		LastSession::<T>::put(session);
+		if let Some(abandoned) = Pending::<T>::take() {
+			Self::abandon(&abandoned);
+		}
+		// The new DKG reshares from the current committee.
+		if let Some(dropped) = NextCommittee::<T>::take() {
+			let _ = Dealings::<T>::clear_prefix(dropped.session, u32::MAX, None);
+			Self::deposit_event(Event::CommitteeDropped { session: dropped.session });
+		}
+
+		let mut participants = T::Authorities::get();
+		participants.truncate(T::MaxParticipants::get() as usize);
+		let count = participants.len() as u32;
+		if participants.is_empty() {
+			return T::WeightInfo::start_dkg(0);
+		}
+		let (dealers, reshare_threshold) = match CurrentCommittee::<T>::get() {
+			Some(committee) => (committee.members, Some(committee.threshold)),
+			None => (participants.clone(), None),
+		};
+		Pending::<T>::put(DkgRound {
+			session,
+			dealers,
+			participants,
+			threshold: dkg::threshold(count),
+			reshare_threshold,
+			started_at: now,
+			phase: Phase::Announce,
+		});
+
+		let reshare = reshare_threshold.is_some();
+		Self::deposit_event(Event::DkgStarted { session, participants: count, reshare });
+		T::WeightInfo::start_dkg(count)
+	}
+
+	/// Drop the messages of a DKG that didn't complete.
+	fn abandon(round: &DkgRoundOf<T>) {
+		let dealers = Dealings::<T>::iter_key_prefix(round.session).count() as u32;
+		let _ = EncryptionKeys::<T>::clear(u32::MAX, None);
+		let _ = Dealings::<T>::clear_prefix(round.session, u32::MAX, None);
+		Self::deposit_event(Event::DkgFailed { session: round.session, dealers });
+	}
+
+	/// End `round` and hand the key to its participants if enough dealers are left.
+	fn finish(round: DkgRoundOf<T>) -> Weight {
+		Pending::<T>::kill();
+		let mut dealings: Vec<_> = Dealings::<T>::iter_prefix(round.session).collect();
+		dealings.sort_unstable_by_key(|(dealer, _)| *dealer);
+		let dealers = dealings.len() as u32;
+		let weight = T::WeightInfo::finish_dkg(dealers, round.threshold);
+
+		// Participants without an encryption key get no share.
+		let holders = EncryptionKeys::<T>::iter_keys().count() as u32;
+		let reshare = round.reshare_threshold.is_some();
+		let commitments = (dealers >= round.reshare_threshold.unwrap_or(round.threshold) &&
+			holders >= round.threshold)
+			.then(|| {
+				let commitments: Vec<_> = dealings
+					.iter()
+					.map(|(dealer, dealing)| (*dealer, &dealing.commitments[..]))
+					.collect();
+				dkg::aggregate_commitments(&commitments, reshare)
+			})
+			.flatten();
+		let Some(commitments) = commitments else {
+			Self::abandon(&round);
+			return weight;
+		};
+
+		let _ = EncryptionKeys::<T>::clear(u32::MAX, None);
+		let committee = Committee {
+			session: round.session,
+			members: round.participants,
+			threshold: round.threshold,
+			qualified: dealings.iter().map(|(dealer, _)| *dealer).collect(),
+			reshared: reshare,
+			commitments,
+		};
+		Self::deposit_event(Event::DkgCompleted {
+			session: round.session,
+			members: committee.members.len() as u32,
+			threshold: round.threshold,
+			dealers,
+		});
+		if CurrentCommittee::<T>::exists() && CommitteeBeacon::<T>::exists() {
+			// Hand over once the new committee signed a round.
+			NextCommittee::<T>::put(committee);
+		} else {
+			// There is no beacon for the previous committee to keep signing.
+			if CommitteeBeacon::<T>::get().is_none() {
+				Self::register_beacon(&committee);
+			}
+			Self::activate(committee);
+		}
+		weight
+	}
+
+	/// Hand the key to `committee`, dropping the dealings of the committee it replaces.
+	fn activate(committee: Committee<T::AuthorityId>) {
+		if let Some(previous) = CurrentCommittee::<T>::get() {
+			let _ = Dealings::<T>::clear_prefix(previous.session, u32::MAX, None);
+		}
+		let session = committee.session;
+		CurrentCommittee::<T>::put(committee);
+		Self::deposit_event(Event::CommitteeActivated { session });
+	}
+
+	/// Register the beacon signed by `committee`, starting now.
+	fn register_beacon(committee: &Committee<T::AuthorityId>) {
+		let Some(group_key) = committee.group_key() else { return };
+		let info = BeaconInfo {
+			chain_hash: dkg::chain_hash(&group_key),
+			public_key: group_key.to_vec().try_into().expect("G2 points are 96 bytes; qed"),
+			period: T::RoundPeriod::get(),
+			genesis_time: T::UnixTime::now().as_secs(),
+			scheme: SignatureScheme::BlsUnchainedG1Rfc9380,
+		};
+		match T::Beacons::register_beacon(info.clone()) {
+			Ok(beacon_id) => {
+				CommitteeBeacon::<T>::put((beacon_id, info));
+				Self::deposit_event(Event::BeaconCreated { beacon_id });
+			},
+			// Retried after the next DKG.
+			Err(err) =>
+				log::error!(target: LOG_TARGET, "failed to register the committee beacon: {err:?}"),
+		}
+	}
+
+	/// The DKG in progress, if it is `session`'s and in `phase`.
+	fn pending(session: u32, phase: Phase) -> Result<DkgRoundOf<T>, Error<T>> {
+		let round = Pending::<T>::get().ok_or(Error::<T>::NoDkg)?;
+		ensure!(round.session == session, Error::<T>::WrongSession);
+		ensure!(round.phase == phase, Error::<T>::WrongPhase);
+		Ok(round)
+	}
+
+	/// Whether `message` is signed by `sender`.
+	fn verify_sender(
+		sender: &T::AuthorityId,
+		message: &impl Encode,
+		signature: &AuthoritySignatureOf<T>,
+	) -> Result<(), Error<T>> {
+		ensure!(
+			message.using_encoded(|message| sender.verify(&message, signature)),
+			Error::<T>::InvalidSignature
+		);
+		Ok(())
+	}
+
+	/// Check `announcement` against the state, returning its sender.
+	fn check_announcement(announcement: &Announcement) -> Result<T::AuthorityId, Error<T>> {
+		let round = Self::pending(announcement.session, Phase::Announce)?;
+		let sender = round
+			.participants
+			.get(announcement.participant as usize)
+			.cloned()
+			.ok_or(Error::<T>::UnknownSender)?;
+		ensure!(
+			!EncryptionKeys::<T>::contains_key(announcement.participant),
+			Error::<T>::AlreadyAnnounced
+		);
+		Ok(sender)
+	}
+
+	/// Fully check `announcement`.
+	fn verify_announcement(
+		announcement: &Announcement,
+		signature: &AuthoritySignatureOf<T>,
+	) -> Result<(), Error<T>> {
+		let sender = Self::check_announcement(announcement)?;
+		Self::verify_sender(&sender, announcement, signature)?;
+		ensure!(dkg::is_valid_encryption_key(&announcement.key), Error::<T>::InvalidKey);
+		Ok(())
+	}
+
+	/// Check `dealing` against the state, returning its sender and DKG.
+	fn check_dealing(dealing: &SignedDealing) -> Result<(T::AuthorityId, DkgRoundOf<T>), Error<T>> {
+		let round = Self::pending(dealing.session, Phase::Deal)?;
+		let sender = round
+			.dealers
+			.get(dealing.dealer as usize)
+			.cloned()
+			.ok_or(Error::<T>::UnknownSender)?;
+		ensure!(
+			!Dealings::<T>::contains_key(dealing.session, dealing.dealer),
+			Error::<T>::AlreadyDealt
+		);
+		Ok((sender, round))
+	}
+
+	/// Fully check `dealing`, returning the number of participants.
+	fn verify_dealing(
+		dealing: &SignedDealing,
+		signature: &AuthoritySignatureOf<T>,
+	) -> Result<u32, Error<T>> {
+		let (sender, round) = Self::check_dealing(dealing)?;
+		Self::verify_sender(&sender, dealing, signature)?;
+
+		// A share for every participant with an encryption key, and for no one else.
+		let shares = &dealing.dealing.shares;
+		ensure!(
+			shares.len() == round.participants.len() &&
+				shares.iter().enumerate().all(|(participant, share)| {
+					share.is_some() == EncryptionKeys::<T>::contains_key(participant as u32)
+				}),
+			Error::<T>::InvalidDealing
+		);
+		let committee = CurrentCommittee::<T>::get();
+		let reshared = match (round.reshare_threshold, &committee) {
+			(Some(_), Some(committee)) => Some((&committee.commitments[..], dealing.dealer)),
+			(Some(_), None) => return Err(Error::<T>::NoCommittee),
+			(None, _) => None,
+		};
+		ensure!(
+			dkg::verify_commitments(&dealing.dealing.commitments, round.threshold, reshared),
+			Error::<T>::InvalidDealing
+		);
+		Ok(round.participants.len() as u32)
+	}
+
+	/// Check `complaint` against the state, returning its sender and the accused dealing.
+	fn check_complaint(complaint: &Complaint) -> Result<(T::AuthorityId, Dealing), Error<T>> {
+		let round = Self::pending(complaint.session, Phase::Complain)?;
+		let sender = round
+			.participants
+			.get(complaint.participant as usize)
+			.cloned()
+			.ok_or(Error::<T>::UnknownSender)?;
+		let dealing =
+			Dealings::<T>::get(complaint.session, complaint.dealer).ok_or(Error::<T>::NoDealing)?;
+		Ok((sender, dealing))
+	}
+
+	/// Fully check `complaint`, returning the dealing's threshold.
+	fn verify_complaint(
+		complaint: &Complaint,
+		signature: &AuthoritySignatureOf<T>,
+	) -> Result<u32, Error<T>> {
+		let (sender, dealing) = Self::check_complaint(complaint)?;
+		Self::verify_sender(&sender, complaint, signature)?;
+		let key =
+			EncryptionKeys::<T>::get(complaint.participant).ok_or(Error::<T>::InvalidComplaint)?;
+		let share = dealing
+			.shares
+			.get(complaint.participant as usize)
+			.and_then(Option::as_ref)
+			.ok_or(Error::<T>::InvalidComplaint)?;
+		ensure!(
+			dkg::is_wrong_share(
+				&dealing.commitments,
+				complaint.participant,
+				&key,
+				share,
+				&complaint.shared_key,
+				&complaint.proof
+			),
+			Error::<T>::InvalidComplaint
+		);
+		Ok(dealing.commitments.len() as u32)
+	}
+
+	/// Check `partial` against the state, returning its sender and the committee, current or
+	/// next, that signs it.
+	fn check_partial(
+		partial: &PartialSignature,
+	) -> Result<(T::AuthorityId, Committee<T::AuthorityId>), Error<T>> {
+		let current = CurrentCommittee::<T>::get().ok_or(Error::<T>::NoCommittee)?;
+		let (_, beacon) = CommitteeBeacon::<T>::get().ok_or(Error::<T>::NoCommittee)?;
+		let committee = match NextCommittee::<T>::get() {
+			Some(next) if next.session == partial.session => next,
+			_ => current,
+		};
+		ensure!(committee.session == partial.session, Error::<T>::WrongSession);
+		let sender = committee
+			.members
+			.get(partial.member as usize)
+			.cloned()
+			.ok_or(Error::<T>::UnknownSender)?;
+
+		let current = beacon.round_at(T::UnixTime::now().as_secs());
+		ensure!(partial.round <= current.saturating_add(MAX_ROUNDS_AHEAD), Error::<T>::FutureRound);
+		let rounds = PartialRounds::<T>::get();
+		ensure!(
+			!rounds.is_full() || rounds.first().map_or(true, |oldest| partial.round >= *oldest),
+			Error::<T>::StaleRound
+		);
+		ensure!(
+			!Partials::<T>::contains_key((partial.round, partial.session, partial.member)),
+			Error::<T>::AlreadySigned
+		);
+		ensure!(
+			Partials::<T>::iter_key_prefix((partial.round, partial.session)).count() <
+				committee.threshold as usize,
+			Error::<T>::RoundSigned
+		);
+		Ok((sender, committee))
+	}
+
+	/// Fully check `partial`, returning the committee's threshold.
+	fn verify_partial(
+		partial: &PartialSignature,
+		signature: &AuthoritySignatureOf<T>,
+	) -> Result<u32, Error<T>> {
+		let (sender, committee) = Self::check_partial(partial)?;
+		Self::verify_sender(&sender, partial, signature)?;
+		ensure!(
+			dkg::verify_partial(
+				&committee.commitments,
+				partial.member,
+				partial.round,
+				&partial.signature
+			),
+			Error::<T>::InvalidPartial
+		);
+		Ok(committee.threshold)
+	}
+
+	/// Keep the partial signatures of `round`, dropping those of the oldest round if needed.
+	fn note_round(round: RoundNumber) {
+		PartialRounds::<T>::mutate(|rounds| {
+			if rounds.contains(&round) {
+				return;
+			}
+			if rounds.is_full() {
+				let oldest = rounds.remove(0);
+				let _ = Partials::<T>::clear_prefix((oldest,), u32::MAX, None);
+			}
+			let position = rounds.partition_point(|kept| *kept < round);
+			// Room was made above.
+			let _ = rounds.try_insert(position, round);
+		});
+	}
+
+	/// The DKG in progress.
+	pub fn pending_dkg() -> Option<DkgRoundOf<T>> {
+		Pending::<T>::get()
+	}
+
+	/// The encryption keys announced in the DKG in progress, by participant.
+	pub fn encryption_keys() -> Vec<(ParticipantIndex, G1Bytes)> {
+		let mut keys: Vec<_> = EncryptionKeys::<T>::iter().collect();
+		keys.sort_unstable_by_key(|(participant, _)| *participant);
+		keys
+	}
+
+	/// The dealings published in the DKG of `session`, by dealer.
+	pub fn dealings(session: u32) -> Vec<(ParticipantIndex, Dealing)> {
+		let mut dealings: Vec<_> = Dealings::<T>::iter_prefix(session).collect();
+		dealings.sort_unstable_by_key(|(dealer, _)| *dealer);
+		dealings
+	}
+
+	/// The committee holding the key.
+	pub fn committee() -> Option<Committee<T::AuthorityId>> {
+		CurrentCommittee::<T>::get()
+	}
+
+	/// The committee taking over once it signed a round.
+	pub fn next_committee() -> Option<Committee<T::AuthorityId>> {
+		NextCommittee::<T>::get()
+	}
+
+	/// The beacon the committee signs.
+	pub fn committee_beacon() -> Option<BeaconId> {
+		CommitteeBeacon::<T>::get().map(|(beacon_id, _)| beacon_id)
+	}
+
+	/// The partial signatures of `round` by the committee of `session`, by member.
+	pub fn partial_signatures(
+		session: u32,
+		round: RoundNumber,
+	) -> Vec<(ParticipantIndex, G1Bytes)> {
+		let mut partials: Vec<_> = Partials::<T>::iter_prefix((round, session)).collect();
+		partials.sort_unstable_by_key(|(member, _)| *member);
+		partials
+	}
+}
@@ -0,0 +1,118 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::{self as pallet_dkg, RegisterBeacon};
+use core::time::Duration;
+use frame_support::{derive_impl, parameter_types, traits::UnixTime};
+use ideal_nw_primitives::beacon::{BeaconId, BeaconInfo};
+use sp_runtime::{testing::UintAuthorityId, BuildStorage, DispatchError};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		Dkg: pallet_dkg,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+}
+
+pub const PHASE_LENGTH: u64 = 3;
+pub const ROUND_PERIOD: u32 = 3;
+pub const GENESIS: u64 = 1_700_000_000;
+
+parameter_types! {
+	pub static Authorities: Vec<UintAuthorityId> = Vec::new();
+	pub static Session: u32 = 0;
+	pub static Registered: Vec<BeaconInfo> = Vec::new();
+	pub static Now: u64 = GENESIS;
+	pub const RoundPeriod: u32 = ROUND_PERIOD;
+	pub const PhaseLength: u64 = PHASE_LENGTH;
+	pub const MaxParticipants: u32 = 8;
+	pub const MaxPendingRounds: u32 = 2;
+	pub const UnsignedPriority: u64 = 1 << 20;
+}
+
+/// Records the registered beacons in [`Registered`].
+pub struct MockBeacons;
+
+impl RegisterBeacon for MockBeacons {
+	fn register_beacon(info: BeaconInfo) -> Result<BeaconId, DispatchError> {
+		Registered::mutate(|registered| registered.push(info));
+		Ok(Registered::get().len() as BeaconId - 1)
+	}
+}
+
+/// The unix time set with [`Now::set`].
+pub struct MockTime;
+
+impl UnixTime for MockTime {
+	fn now() -> Duration {
+		Duration::from_secs(Now::get())
+	}
+}
+
+impl pallet_dkg::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type AuthorityId = UintAuthorityId;
+	type Authorities = Authorities;
+	type CurrentSession = Session;
+	type Beacons = MockBeacons;
+	type UnixTime = MockTime;
+	type RoundPeriod = RoundPeriod;
+	type PhaseLength = PhaseLength;
+	type MaxParticipants = MaxParticipants;
+	type MaxPendingRounds = MaxPendingRounds;
+	type UnsignedPriority = UnsignedPriority;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBenchmarkHelper;
+}
+
+/// Sets the collators in [`Authorities`].
+#[cfg(feature = "runtime-benchmarks")]
+pub struct MockBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_dkg::BenchmarkHelper<UintAuthorityId> for MockBenchmarkHelper {
+	fn set_authorities(authorities: Vec<UintAuthorityId>) {
+		Authorities::set(authorities);
+	}
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Start the next block.
+pub fn next_block() {
+	use frame_support::traits::Hooks;
+
+	System::set_block_number(System::block_number() + 1);
+	Dkg::on_initialize(System::block_number());
+}
+
+/// Start the next `n` blocks.
+pub fn skip_blocks(n: u64) {
+	(0..n).for_each(|_| next_block());
+}
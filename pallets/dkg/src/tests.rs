@@ -0,0 +1,471 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::{mock::*, *};
+use frame_support::{
+	assert_noop, assert_ok,
+	pallet_prelude::{InvalidTransaction, TransactionSource, ValidateUnsigned},
+};
+use ideal_nw_primitives::{
+	bls::verify_unchained_g1,
+	dkg::{
+		aggregate_partials, combine_shares, decrypt_share, encrypt_share, encryption_key,
+		partial_sign, reveal_shared_key, scalar_from_seed, Fr, Polynomial,
+	},
+};
+use sp_runtime::{
+	testing::{TestSignature, UintAuthorityId},
+	DispatchResult,
+};
+
+fn collators(ids: impl IntoIterator<Item = u64>) -> Vec<UintAuthorityId> {
+	ids.into_iter().map(UintAuthorityId).collect()
+}
+
+fn sign(collator: &UintAuthorityId, message: &impl Encode) -> TestSignature {
+	collator.sign(&message.encode()).unwrap()
+}
+
+/// The encryption secret of `collator` in the DKG of `session`.
+fn secret(collator: &UintAuthorityId, session: u32) -> Fr {
+	scalar_from_seed(&(collator, session).encode(), b"encryption")
+}
+
+fn announce(collator: &UintAuthorityId) -> DispatchResult {
+	let round = Dkg::pending_dkg().unwrap();
+	let participant = round.participants.iter().position(|id| id == collator).unwrap() as u32;
+	let key = encryption_key(&secret(collator, round.session));
+	let announcement = Announcement { session: round.session, participant, key };
+	Dkg::announce(RuntimeOrigin::none(), announcement.clone(), sign(collator, &announcement))
+}
+
+/// The share of `collator` in `committee`, from the dealings on chain.
+fn share(committee: &Committee<UintAuthorityId>, collator: &UintAuthorityId) -> Fr {
+	let member = committee.members.iter().position(|id| id == collator).unwrap();
+	let shares: Vec<_> = Dkg::dealings(committee.session)
+		.into_iter()
+		.map(|(dealer, dealing)| {
+			let encrypted = dealing.shares[member].as_ref().unwrap();
+			(dealer, decrypt_share(&secret(collator, committee.session), encrypted).unwrap())
+		})
+		.collect();
+	combine_shares(&shares, committee.reshared).unwrap()
+}
+
+/// The dealing of `collator` in the DKG in progress.
+fn dealing(collator: &UintAuthorityId) -> SignedDealing {
+	let round = Dkg::pending_dkg().unwrap();
+	let dealer = round.dealers.iter().position(|id| id == collator).unwrap() as u32;
+	let seed = (collator, round.session).encode();
+	let constant = round.reshare_threshold.map(|_| share(&Dkg::committee().unwrap(), collator));
+	let polynomial = Polynomial::from_seed(&seed, round.threshold, constant);
+	let keys = Dkg::encryption_keys();
+	let shares = (0..round.participants.len() as u32)
+		.map(|participant| {
+			let (_, key) = keys.iter().find(|(index, _)| *index == participant)?;
+			let seed = [seed.as_slice(), &participant.to_le_bytes()].concat();
+			encrypt_share(key, &polynomial.evaluate(participant), &seed)
+		})
+		.collect();
+	let dealing = Dealing { commitments: polynomial.commitments(), shares };
+	SignedDealing { session: round.session, dealer, dealing }
+}
+
+fn deal(dealing: SignedDealing, collator: &UintAuthorityId) -> DispatchResult {
+	let signature = sign(collator, &dealing);
+	Dkg::deal(RuntimeOrigin::none(), dealing, signature)
+		.map(drop)
+		.map_err(|e| e.error)
+}
+
+/// The complaint of `collator` about the share `dealer` sent it in the DKG in progress.
+fn complaint(collator: &UintAuthorityId, dealer: ParticipantIndex) -> Complaint {
+	let round = Dkg::pending_dkg().unwrap();
+	let participant = round.participants.iter().position(|id| id == collator).unwrap();
+	let (_, dealing) = Dkg::dealings(round.session)
+		.into_iter()
+		.find(|(index, _)| *index == dealer)
+		.unwrap();
+	let encrypted = dealing.shares[participant].as_ref().unwrap();
+	let secret = secret(collator, round.session);
+	let (shared_key, proof) = reveal_shared_key(&secret, encrypted, b"complaint").unwrap();
+	let participant = participant as u32;
+	Complaint { session: round.session, participant, dealer, shared_key, proof }
+}
+
+fn complain(complaint: Complaint, collator: &UintAuthorityId) -> DispatchResult {
+	let signature = sign(collator, &complaint);
+	Dkg::complain(RuntimeOrigin::none(), complaint, signature)
+		.map(drop)
+		.map_err(|e| e.error)
+}
+
+/// Run the DKG in progress, with every participant announcing, every dealer dealing and no one
+/// complaining.
+fn run_dkg() {
+	let round = Dkg::pending_dkg().unwrap();
+	round.participants.iter().for_each(|collator| assert_ok!(announce(collator)));
+	skip_blocks(PHASE_LENGTH);
+	round
+		.dealers
+		.iter()
+		.for_each(|collator| assert_ok!(deal(dealing(collator), collator)));
+	skip_blocks(2 * PHASE_LENGTH);
+	assert!(Dkg::pending_dkg().is_none());
+}
+
+fn partial(
+	committee: &Committee<UintAuthorityId>,
+	collator: &UintAuthorityId,
+	round: RoundNumber,
+) -> PartialSignature {
+	let member = committee.members.iter().position(|id| id == collator).unwrap() as u32;
+	let signature = partial_sign(&share(committee, collator), round).unwrap();
+	PartialSignature { session: committee.session, member, round, signature }
+}
+
+fn submit_partial(partial: PartialSignature, collator: &UintAuthorityId) -> DispatchResult {
+	let signature = sign(collator, &partial);
+	Dkg::submit_partial(RuntimeOrigin::none(), partial, signature)
+		.map(drop)
+		.map_err(|e| e.error)
+}
+
+/// Have `signers` of `committee` sign `round` and aggregate their signatures from the chain.
+fn sign_round(
+	committee: &Committee<UintAuthorityId>,
+	signers: &[UintAuthorityId],
+	round: RoundNumber,
+) -> G1Bytes {
+	for collator in signers {
+		assert_ok!(submit_partial(partial(committee, collator, round), collator));
+	}
+	aggregate_partials(&Dkg::partial_signatures(committee.session, round)).unwrap()
+}
+
+#[test]
+fn dkg_starts_with_every_session() {
+	new_test_ext().execute_with(|| {
+		Authorities::set(collators(1..=4));
+		next_block();
+
+		let round = Dkg::pending_dkg().unwrap();
+		assert_eq!((round.session, round.threshold, round.reshare_threshold), (0, 3, None));
+		assert_eq!((round.phase, round.dealers.len()), (Phase::Announce, 4));
+		System::assert_last_event(
+			Event::DkgStarted { session: 0, participants: 4, reshare: false }.into(),
+		);
+
+		skip_blocks(PHASE_LENGTH);
+		assert_eq!(Dkg::pending_dkg().unwrap().phase, Phase::Deal);
+		skip_blocks(PHASE_LENGTH);
+		assert_eq!(Dkg::pending_dkg().unwrap().phase, Phase::Complain);
+
+		// Nothing was dealt.
+		skip_blocks(PHASE_LENGTH);
+		assert!(Dkg::pending_dkg().is_none());
+		System::assert_last_event(Event::DkgFailed { session: 0, dealers: 0 }.into());
+		assert!(Dkg::committee().is_none());
+
+		Session::set(1);
+		next_block();
+		assert_eq!(Dkg::pending_dkg().unwrap().session, 1);
+	});
+}
+
+#[test]
+fn committee_signs_rounds_of_its_beacon() {
+	new_test_ext().execute_with(|| {
+		let collators = collators(1..=4);
+		Authorities::set(collators.clone());
+		next_block();
+		run_dkg();
+
+		let committee = Dkg::committee().unwrap();
+		assert_eq!((committee.threshold, committee.qualified.len()), (3, 4));
+		System::assert_has_event(Event::BeaconCreated { beacon_id: 0 }.into());
+		let beacon = &Registered::get()[0];
+		assert_eq!(beacon.public_key[..], committee.group_key().unwrap()[..]);
+		assert_eq!((beacon.genesis_time, beacon.period), (GENESIS, ROUND_PERIOD));
+
+		Now::set(GENESIS + 10);
+		let signature = sign_round(&committee, &collators[1..], 4);
+		assert!(verify_unchained_g1(&beacon.public_key, 4, &signature));
+		assert_noop!(
+			submit_partial(partial(&committee, &collators[0], 4), &collators[0]),
+			Error::<Test>::RoundSigned
+		);
+	});
+}
+
+#[test]
+fn resharing_keeps_the_key_and_the_beacon() {
+	new_test_ext().execute_with(|| {
+		Authorities::set(collators(1..=4));
+		next_block();
+		run_dkg();
+		let previous = Dkg::committee().unwrap();
+		let key = previous.group_key();
+
+		let next = collators(3..=8);
+		Authorities::set(next.clone());
+		Session::set(1);
+		next_block();
+		let round = Dkg::pending_dkg().unwrap();
+		assert_eq!((round.threshold, round.reshare_threshold), (5, Some(3)));
+		assert_eq!(round.dealers, collators(1..=4));
+		run_dkg();
+
+		// The previous committee keeps the key until the new one signed a round.
+		let committee = Dkg::next_committee().unwrap();
+		assert_eq!((committee.session, committee.reshared), (1, true));
+		assert_eq!(committee.group_key(), key);
+		assert_eq!(Dkg::committee(), Some(previous.clone()));
+		assert_eq!(Registered::get().len(), 1);
+		assert_eq!(Dkg::dealings(0).len(), 4);
+
+		Now::set(GENESIS + 30);
+		let public_key = &Registered::get()[0].public_key;
+		let signature = sign_round(&previous, &collators(1..=3), 10);
+		assert!(verify_unchained_g1(public_key, 10, &signature));
+		let alice = &collators(1..=1)[0];
+		let late = partial(&previous, alice, 11);
+		let signature = sign_round(&committee, &next[..5], 10);
+		assert!(verify_unchained_g1(public_key, 10, &signature));
+
+		System::assert_last_event(Event::CommitteeActivated { session: 1 }.into());
+		assert_eq!(Dkg::committee(), Some(committee));
+		assert!(Dkg::next_committee().is_none());
+		// The dealings of the previous committee are gone, and so is its say.
+		assert!(Dkg::dealings(0).is_empty());
+		assert_noop!(submit_partial(late, alice), Error::<Test>::WrongSession);
+	});
+}
+
+#[test]
+fn committee_that_never_signed_is_dropped_by_the_next_dkg() {
+	new_test_ext().execute_with(|| {
+		let collators = collators(1..=4);
+		Authorities::set(collators.clone());
+		next_block();
+		run_dkg();
+		let committee = Dkg::committee().unwrap();
+
+		Session::set(1);
+		next_block();
+		run_dkg();
+		assert_eq!(Dkg::next_committee().unwrap().session, 1);
+
+		Session::set(2);
+		next_block();
+		System::assert_has_event(Event::CommitteeDropped { session: 1 }.into());
+		assert!(Dkg::next_committee().is_none());
+		assert!(Dkg::dealings(1).is_empty());
+		assert_eq!(Dkg::committee(), Some(committee));
+		// The next DKG reshares from the committee holding the key.
+		assert_eq!(Dkg::pending_dkg().unwrap().dealers, collators);
+	});
+}
+
+#[test]
+fn complaints_disqualify_dealers_of_wrong_shares() {
+	new_test_ext().execute_with(|| {
+		let collators = collators(1..=4);
+		Authorities::set(collators.clone());
+		next_block();
+		collators.iter().for_each(|collator| assert_ok!(announce(collator)));
+		skip_blocks(PHASE_LENGTH);
+
+		// Alice sends Bob Charlie's share.
+		let (alice, bob, charlie) = (&collators[0], &collators[1], &collators[2]);
+		let mut wrong = dealing(alice);
+		let polynomial = Polynomial::from_seed(&(alice, 0u32).encode(), 3, None);
+		let bob_key = encryption_key(&secret(bob, 0));
+		wrong.dealing.shares[1] = encrypt_share(&bob_key, &polynomial.evaluate(2), b"wrong");
+		assert_ok!(deal(wrong, alice));
+		collators[1..]
+			.iter()
+			.for_each(|collator| assert_ok!(deal(dealing(collator), collator)));
+		assert_noop!(complain(complaint(bob, 0), bob), Error::<Test>::WrongPhase);
+
+		skip_blocks(PHASE_LENGTH);
+		assert_eq!(Dkg::pending_dkg().unwrap().phase, Phase::Complain);
+		// Charlie's share is right.
+		assert_noop!(complain(complaint(charlie, 0), charlie), Error::<Test>::InvalidComplaint);
+		let forged = sign(charlie, &complaint(bob, 0));
+		assert_noop!(
+			Dkg::complain(RuntimeOrigin::none(), complaint(bob, 0), forged),
+			Error::<Test>::InvalidSignature
+		);
+		let mut tampered = complaint(bob, 0);
+		tampered.shared_key = encryption_key(&secret(charlie, 0));
+		assert_eq!(
+			Dkg::validate_unsigned(
+				TransactionSource::External,
+				&Call::complain { complaint: tampered.clone(), signature: sign(bob, &tampered) }
+			),
+			Err(InvalidTransaction::BadProof.into())
+		);
+
+		// Nor can Charlie use Bob's revealed key against Bob.
+		let borrowed = Complaint { participant: 2, ..complaint(bob, 1) };
+		assert_noop!(complain(borrowed, charlie), Error::<Test>::InvalidComplaint);
+
+		let upheld = complaint(bob, 0);
+		assert_ok!(complain(upheld.clone(), bob));
+		System::assert_last_event(
+			Event::DealerDisqualified { session: 0, dealer: 0, participant: 1 }.into(),
+		);
+		assert!(Dkg::dealings(0).iter().all(|(dealer, _)| *dealer != 0));
+		assert_noop!(complain(upheld, bob), Error::<Test>::NoDealing);
+
+		skip_blocks(PHASE_LENGTH);
+		System::assert_has_event(
+			Event::DkgCompleted { session: 0, members: 4, threshold: 3, dealers: 3 }.into(),
+		);
+		let committee = Dkg::committee().unwrap();
+		assert_eq!(committee.qualified, vec![1, 2, 3]);
+
+		// Bob's share no longer depends on Alice's.
+		Now::set(GENESIS + 3);
+		let signature = sign_round(&committee, &collators[1..], 2);
+		assert!(verify_unchained_g1(&Registered::get()[0].public_key, 2, &signature));
+	});
+}
+
+#[test]
+fn failed_reshare_leaves_the_committee_in_place() {
+	new_test_ext().execute_with(|| {
+		let collators = collators(1..=4);
+		Authorities::set(collators.clone());
+		next_block();
+		run_dkg();
+		let committee = Dkg::committee().unwrap();
+
+		Session::set(1);
+		next_block();
+		collators.iter().for_each(|collator| assert_ok!(announce(collator)));
+		skip_blocks(PHASE_LENGTH);
+		// Below the committee's threshold of 3.
+		collators[..2]
+			.iter()
+			.for_each(|collator| assert_ok!(deal(dealing(collator), collator)));
+		skip_blocks(2 * PHASE_LENGTH);
+
+		System::assert_last_event(Event::DkgFailed { session: 1, dealers: 2 }.into());
+		assert_eq!(Dkg::committee(), Some(committee));
+		assert!(Dkg::dealings(1).is_empty());
+		assert_eq!(Dkg::dealings(0).len(), 4);
+
+		Now::set(GENESIS + 3);
+		let signature = sign_round(&committee, &collators[..3], 2);
+		assert!(verify_unchained_g1(&Registered::get()[0].public_key, 2, &signature));
+	});
+}
+
+#[test]
+fn invalid_messages_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let collators = collators(1..=4);
+		Authorities::set(collators.clone());
+		next_block();
+
+		let alice = &collators[0];
+		let key = encryption_key(&secret(alice, 0));
+		let announcement = Announcement { session: 0, participant: 0, key };
+		let forged = sign(&collators[1], &announcement);
+		assert_noop!(
+			Dkg::announce(RuntimeOrigin::none(), announcement.clone(), forged.clone()),
+			Error::<Test>::InvalidSignature
+		);
+		assert_eq!(
+			Dkg::validate_unsigned(
+				TransactionSource::External,
+				&Call::announce { announcement: announcement.clone(), signature: forged }
+			),
+			Err(InvalidTransaction::BadProof.into())
+		);
+		let invalid = Announcement { key: [0; 48], ..announcement.clone() };
+		assert_noop!(
+			Dkg::announce(RuntimeOrigin::none(), invalid.clone(), sign(alice, &invalid)),
+			Error::<Test>::InvalidKey
+		);
+		let stale = Announcement { session: 1, ..announcement.clone() };
+		assert_noop!(
+			Dkg::announce(RuntimeOrigin::none(), stale.clone(), sign(alice, &stale)),
+			Error::<Test>::WrongSession
+		);
+		assert_ok!(Dkg::validate_unsigned(
+			TransactionSource::External,
+			&Call::announce { announcement, signature: sign(alice, &announcement) }
+		));
+		collators.iter().for_each(|collator| assert_ok!(announce(collator)));
+		assert_noop!(announce(alice), Error::<Test>::AlreadyAnnounced);
+		assert_noop!(deal(dealing(alice), alice), Error::<Test>::WrongPhase);
+
+		skip_blocks(PHASE_LENGTH);
+		let mut missing_share = dealing(alice);
+		missing_share.dealing.shares[2] = None;
+		assert_noop!(deal(missing_share, alice), Error::<Test>::InvalidDealing);
+		let mut low_degree = dealing(alice);
+		low_degree.dealing.commitments.pop();
+		assert_noop!(deal(low_degree, alice), Error::<Test>::InvalidDealing);
+		assert_ok!(deal(dealing(alice), alice));
+		assert_noop!(deal(dealing(alice), alice), Error::<Test>::AlreadyDealt);
+
+		collators[1..]
+			.iter()
+			.for_each(|collator| assert_ok!(deal(dealing(collator), collator)));
+		skip_blocks(2 * PHASE_LENGTH);
+		Now::set(GENESIS + 3);
+		let committee = Dkg::committee().unwrap();
+		let mut forged = partial(&committee, alice, 2);
+		forged.signature = partial(&committee, &collators[1], 2).signature;
+		assert_noop!(submit_partial(forged, alice), Error::<Test>::InvalidPartial);
+		assert_noop!(
+			submit_partial(partial(&committee, alice, 5), alice),
+			Error::<Test>::FutureRound
+		);
+		assert_ok!(submit_partial(partial(&committee, alice, 2), alice));
+		assert_noop!(
+			submit_partial(partial(&committee, alice, 2), alice),
+			Error::<Test>::AlreadySigned
+		);
+	});
+}
+
+#[test]
+fn partials_of_old_rounds_are_pruned() {
+	new_test_ext().execute_with(|| {
+		let collators = collators(1..=4);
+		Authorities::set(collators.clone());
+		next_block();
+		run_dkg();
+
+		let alice = &collators[0];
+		let committee = Dkg::committee().unwrap();
+		Now::set(GENESIS + 30);
+		for round in [5, 3, 4] {
+			assert_ok!(submit_partial(partial(&committee, alice, round), alice));
+		}
+		assert_eq!(PartialRounds::<Test>::get().into_inner(), vec![4, 5]);
+		assert!(Dkg::partial_signatures(0, 3).is_empty());
+		assert_noop!(
+			submit_partial(partial(&committee, alice, 3), alice),
+			Error::<Test>::StaleRound
+		);
+	});
+}
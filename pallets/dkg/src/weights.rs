@@ -0,0 +1,187 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Weights for `pallet_dkg`.
+//!
+//! The figures below have not been measured yet. Replace this file with the output of the
+//! benchmarks in `benchmarking.rs` on reference hardware:
+//!
+//! ```sh
+//! ideal-nw-node benchmark pallet --chain dev --wasm-execution=compiled \
+//!     --pallet pallet_dkg --extrinsic "*" --steps 50 --repeat 20 \
+//!     --output pallets/dkg/src/weights.rs
+//! ```
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_dkg`.
+pub trait WeightInfo {
+	fn on_initialize() -> Weight;
+	fn start_dkg(n: u32, ) -> Weight;
+	fn finish_dkg(d: u32, t: u32, ) -> Weight;
+	fn announce() -> Weight;
+	fn deal(n: u32, ) -> Weight;
+	fn submit_partial(t: u32, ) -> Weight;
+	fn complain(t: u32, ) -> Weight;
+}
+
+/// Weights for `pallet_dkg` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `Session::CurrentIndex` (r:1 w:0)
+	/// Storage: `Dkg::LastSession` (r:1 w:0)
+	/// Storage: `Dkg::Pending` (r:1 w:1)
+	fn on_initialize() -> Weight {
+		Weight::from_parts(8_000_000, 2_000)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Session::CurrentIndex` (r:1 w:0)
+	/// Storage: `Dkg::LastSession` (r:1 w:1)
+	/// Storage: `Dkg::Pending` (r:1 w:1)
+	/// Storage: `Dkg::EncryptionKeys` (r:0 w:n)
+	/// Storage: `Dkg::Dealings` (r:n w:n)
+	/// Storage: `Aura::Authorities` (r:1 w:0)
+	/// Storage: `Dkg::CurrentCommittee` (r:1 w:0)
+	/// Storage: `Dkg::NextCommittee` (r:1 w:1)
+	/// The range of component `n` is `[0, 16]`.
+	fn start_dkg(n: u32, ) -> Weight {
+		Weight::from_parts(20_000_000, 8_000)
+			.saturating_add(Weight::from_parts(1_500_000, 8_000).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+			.saturating_add(T::DbWeight::get().writes((3_u64).saturating_mul(n.into())))
+	}
+	/// Storage: `Dkg::Pending` (r:0 w:1)
+	/// Storage: `Dkg::Dealings` (r:16 w:32)
+	/// Storage: `Dkg::EncryptionKeys` (r:16 w:16)
+	/// Storage: `Dkg::CurrentCommittee` (r:1 w:1)
+	/// Storage: `Dkg::NextCommittee` (r:0 w:1)
+	/// Storage: `Dkg::CommitteeBeacon` (r:1 w:1)
+	/// Storage: `Beacons::BeaconConfigs` (r:1 w:1)
+	/// Storage: `Timestamp::Now` (r:1 w:0)
+	/// The range of component `d` is `[11, 16]`.
+	/// The range of component `t` is `[1, 11]`.
+	fn finish_dkg(d: u32, t: u32, ) -> Weight {
+		Weight::from_parts(100_000_000, 400_000)
+			.saturating_add(Weight::from_parts(5_000_000_000, 0).saturating_mul(d.into()))
+			.saturating_add(Weight::from_parts(7_200_000_000, 0).saturating_mul(t.into()))
+			.saturating_add(T::DbWeight::get().reads(36_u64))
+			.saturating_add(T::DbWeight::get().writes(53_u64))
+	}
+	/// Storage: `Dkg::Pending` (r:1 w:0)
+	/// Storage: `Dkg::EncryptionKeys` (r:1 w:1)
+	fn announce() -> Weight {
+		Weight::from_parts(120_000_000, 3_000)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Dkg::Pending` (r:1 w:0)
+	/// Storage: `Dkg::Dealings` (r:1 w:1)
+	/// Storage: `Dkg::EncryptionKeys` (r:n w:0)
+	/// Storage: `Dkg::CurrentCommittee` (r:1 w:0)
+	/// The range of component `n` is `[1, 16]`.
+	fn deal(n: u32, ) -> Weight {
+		Weight::from_parts(150_000_000, 8_000)
+			.saturating_add(Weight::from_parts(500_000_000, 2_000).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `Dkg::CurrentCommittee` (r:1 w:0)
+	/// Storage: `Dkg::CommitteeBeacon` (r:1 w:0)
+	/// Storage: `Timestamp::Now` (r:1 w:0)
+	/// Storage: `Dkg::PartialRounds` (r:1 w:1)
+	/// Storage: `Dkg::Partials` (r:t w:t)
+	/// Storage: `Dkg::NextCommittee` (r:1 w:1)
+	/// Storage: `Dkg::Dealings` (r:0 w:16)
+	/// The range of component `t` is `[1, 11]`.
+	fn submit_partial(t: u32, ) -> Weight {
+		Weight::from_parts(1_300_000_000, 8_000)
+			.saturating_add(Weight::from_parts(450_000_000, 100).saturating_mul(t.into()))
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(t.into())))
+			.saturating_add(T::DbWeight::get().writes(19_u64))
+			.saturating_add(T::DbWeight::get().writes((1_u64).saturating_mul(t.into())))
+	}
+	/// Storage: `Dkg::Pending` (r:1 w:0)
+	/// Storage: `Dkg::Dealings` (r:1 w:1)
+	/// Storage: `Dkg::EncryptionKeys` (r:1 w:0)
+	/// The range of component `t` is `[1, 11]`.
+	fn complain(t: u32, ) -> Weight {
+		Weight::from_parts(200_000_000, 8_000)
+			.saturating_add(Weight::from_parts(450_000_000, 100).saturating_mul(t.into()))
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn on_initialize() -> Weight {
+		Weight::from_parts(8_000_000, 2_000)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn start_dkg(n: u32, ) -> Weight {
+		Weight::from_parts(20_000_000, 8_000)
+			.saturating_add(Weight::from_parts(1_500_000, 8_000).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+			.saturating_add(RocksDbWeight::get().writes((3_u64).saturating_mul(n.into())))
+	}
+	fn finish_dkg(d: u32, t: u32, ) -> Weight {
+		Weight::from_parts(100_000_000, 400_000)
+			.saturating_add(Weight::from_parts(5_000_000_000, 0).saturating_mul(d.into()))
+			.saturating_add(Weight::from_parts(7_200_000_000, 0).saturating_mul(t.into()))
+			.saturating_add(RocksDbWeight::get().reads(36_u64))
+			.saturating_add(RocksDbWeight::get().writes(53_u64))
+	}
+	fn announce() -> Weight {
+		Weight::from_parts(120_000_000, 3_000)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn deal(n: u32, ) -> Weight {
+		Weight::from_parts(150_000_000, 8_000)
+			.saturating_add(Weight::from_parts(500_000_000, 2_000).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn submit_partial(t: u32, ) -> Weight {
+		Weight::from_parts(1_300_000_000, 8_000)
+			.saturating_add(Weight::from_parts(450_000_000, 100).saturating_mul(t.into()))
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(t.into())))
+			.saturating_add(RocksDbWeight::get().writes(19_u64))
+			.saturating_add(RocksDbWeight::get().writes((1_u64).saturating_mul(t.into())))
+	}
+	fn complain(t: u32, ) -> Weight {
+		Weight::from_parts(200_000_000, 8_000)
+			.saturating_add(Weight::from_parts(450_000_000, 100).saturating_mul(t.into()))
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+}
@@ -0,0 +1,71 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Benchmarks for `pallet_fair_ordering`.
+
+use super::*;
+use frame_benchmarking::v2::*;
+
+/// The pulse seeding the ordering.
+fn pulse() -> Pulse {
+	Pulse {
+		round: 1_000,
+		randomness: [7; 32],
+		signature: Default::default(),
+		previous_signature: Default::default(),
+	}
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn set_seed() {
+		T::BenchmarkHelper::set_pulse(pulse());
+		let now = frame_system::Pallet::<T>::block_number();
+
+		#[block]
+		{
+			Pallet::<T>::on_initialize(now);
+		}
+
+		assert!(Seed::<T>::exists());
+	}
+
+	#[benchmark]
+	fn check_order(n: Linear<0, { T::MaxChecked::get() }>) {
+		Seed::<T>::put(ordering::block_seed(&pulse().randomness));
+		FirstTransaction::<T>::put(0);
+		// Every transaction is decoded and keyed.
+		for sender in 0..n {
+			frame_system::Pallet::<T>::note_extrinsic(T::BenchmarkHelper::signed_extrinsic(sender));
+			frame_system::Pallet::<T>::note_applied_extrinsic(&Ok(().into()), Default::default());
+		}
+		frame_system::Pallet::<T>::note_finished_extrinsics();
+		let now = frame_system::Pallet::<T>::block_number();
+
+		#[block]
+		{
+			Pallet::<T>::on_finalize(now);
+		}
+
+		assert_eq!(frame_system::Pallet::<T>::extrinsic_count(), n);
+		assert!(!Seed::<T>::exists());
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
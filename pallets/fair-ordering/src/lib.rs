@@ -0,0 +1,172 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Fair Ordering Pallet
+//!
+//! Checks that blocks order their transactions by the previous pulse, see
+//! [`ideal_nw_primitives::ordering`].
+//!
+//! The seed of a block's ordering is taken from the latest pulse of [`Config::Pulse`] when the
+//! block is initialized, before the block ingests new pulses. When the block is finalized, the
+//! pallet recomputes the [`OrderingKey`]s of the extrinsics applied after the inherents and
+//! emits [`Event::OrderingDeviation`] at the first one that doesn't follow the ordering.
+//!
+//! Deviating blocks are still valid: the ordering is a commitment collators can be held to, not a
+//! consensus rule. Nodes built with fair ordering propose blocks that follow it, see
+//! [`FairOrderingApi`](ideal_nw_primitives::api::FairOrderingApi).
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub use pallet::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+use alloc::vec::Vec;
+use frame_support::{pallet_prelude::*, traits::PostInherents};
+use frame_system::pallet_prelude::BlockNumberFor;
+use ideal_nw_primitives::beacon::Pulse;
+pub use ideal_nw_primitives::ordering::{self, OrderingKey};
+
+pub use weights::WeightInfo;
+
+const LOG_TARGET: &str = "runtime::fair-ordering";
+
+/// The ordering keys of the runtime's extrinsics.
+pub trait ExtrinsicKeys {
+	/// The key of the SCALE encoded `extrinsic` in a block ordered with `seed`, unless it can't
+	/// be decoded.
+	fn ordering_key(extrinsic: &[u8], seed: &[u8; 32]) -> Option<OrderingKey>;
+}
+
+/// Helper used by the benchmarks to set up the pulse and the extrinsics.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper {
+	/// Make `pulse` the latest pulse of [`Config::Pulse`].
+	fn set_pulse(pulse: Pulse);
+
+	/// A SCALE encoded signed extrinsic of the sender `sender`, the costliest kind to key.
+	fn signed_extrinsic(sender: u32) -> Vec<u8>;
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The latest pulse, seeding the ordering of the next block.
+		type Pulse: Get<Option<Pulse>>;
+
+		/// The ordering keys of the extrinsics.
+		type Keys: ExtrinsicKeys;
+
+		/// Maximum number of transactions checked in a block.
+		#[pallet::constant]
+		type MaxChecked: Get<u32>;
+
+		/// Weight information for extrinsics and hooks in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Helper to set up the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper;
+	}
+
+	/// The seed of the ordering of the current block.
+	#[pallet::storage]
+	pub type Seed<T: Config> = StorageValue<_, [u8; 32], OptionQuery>;
+
+	/// The index of the first extrinsic of the current block after the inherents.
+	#[pallet::storage]
+	pub type FirstTransaction<T: Config> = StorageValue<_, u32, OptionQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The extrinsic at `index` is out of order.
+		OrderingDeviation { index: u32 },
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+			match T::Pulse::get() {
+				Some(pulse) => Seed::<T>::put(ordering::block_seed(&pulse.randomness)),
+				None => Seed::<T>::kill(),
+			}
+			// Reserved for the check in `on_finalize`.
+			T::WeightInfo::set_seed()
+				.saturating_add(T::WeightInfo::check_order(T::MaxChecked::get()))
+		}
+
+		fn on_finalize(_n: BlockNumberFor<T>) {
+			let (Some(seed), Some(first)) = (Seed::<T>::take(), FirstTransaction::<T>::take())
+			else {
+				return;
+			};
+			let count = frame_system::Pallet::<T>::extrinsic_count();
+			let last = count.min(first.saturating_add(T::MaxChecked::get()));
+			let keys: Vec<(u32, OrderingKey)> = (first..last)
+				.filter_map(|index| {
+					let extrinsic = frame_system::Pallet::<T>::extrinsic_data(index);
+					T::Keys::ordering_key(&extrinsic, &seed).map(|key| (index, key))
+				})
+				.collect();
+			if let Some(position) = ordering::first_deviation(keys.iter().map(|(_, key)| *key)) {
+				let index = keys[position].0;
+				log::warn!(target: LOG_TARGET, "extrinsic {index} is out of order");
+				Self::deposit_event(Event::OrderingDeviation { index });
+			}
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The keys of `extrinsics` in the next block, unless there is no pulse yet.
+	///
+	/// Called on the parent of the block, whose latest pulse seeds its ordering.
+	pub fn ordering_keys(extrinsics: Vec<Vec<u8>>) -> Option<Vec<Option<OrderingKey>>> {
+		let seed = ordering::block_seed(&T::Pulse::get()?.randomness);
+		Some(
+			extrinsics
+				.iter()
+				.map(|extrinsic| T::Keys::ordering_key(extrinsic, &seed))
+				.collect(),
+		)
+	}
+}
+
+/// Marks where the transactions of a block start, once its inherents are applied.
+impl<T: Config> PostInherents for Pallet<T> {
+	fn post_inherents() {
+		let first = frame_system::Pallet::<T>::extrinsic_index().unwrap_or_default();
+		FirstTransaction::<T>::put(first);
+	}
+}
@@ -0,0 +1,135 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::{self as pallet_fair_ordering, ordering, ExtrinsicKeys, OrderingKey};
+use codec::{Decode, Encode};
+use frame_support::{
+	derive_impl, parameter_types,
+	traits::{Hooks, PostInherents},
+};
+use ideal_nw_primitives::beacon::{Pulse, RoundNumber};
+use sp_runtime::BuildStorage;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		FairOrdering: pallet_fair_ordering,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+}
+
+pub const MAX_CHECKED: u32 = 4;
+
+parameter_types! {
+	pub const MaxChecked: u32 = MAX_CHECKED;
+	pub static LatestPulse: Option<Pulse> = None;
+}
+
+/// A mock extrinsic: its bucket, sender and nonce.
+pub type Extrinsic = (u8, u64, u64);
+
+/// Keys of SCALE encoded [`Extrinsic`]s. Senders of unsigned extrinsics are ignored.
+pub struct MockKeys;
+
+impl ExtrinsicKeys for MockKeys {
+	fn ordering_key(extrinsic: &[u8], seed: &[u8; 32]) -> Option<OrderingKey> {
+		let (bucket, sender, nonce) = Extrinsic::decode(&mut &extrinsic[..]).ok()?;
+		Some(match bucket {
+			ordering::BUCKET_UNSIGNED => ordering::unsigned_key(seed, extrinsic),
+			_ => OrderingKey { bucket, rank: ordering::sender_rank(seed, &sender.encode()), nonce },
+		})
+	}
+}
+
+impl pallet_fair_ordering::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Pulse = LatestPulse;
+	type Keys = MockKeys;
+	type MaxChecked = MaxChecked;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBenchmarkHelper;
+}
+
+/// Sets [`LatestPulse`] and builds normal [`Extrinsic`]s.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct MockBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_fair_ordering::BenchmarkHelper for MockBenchmarkHelper {
+	fn set_pulse(pulse: Pulse) {
+		LatestPulse::set(Some(pulse));
+	}
+
+	fn signed_extrinsic(sender: u32) -> Vec<u8> {
+		(ordering::BUCKET_NORMAL, u64::from(sender), 0u64).encode()
+	}
+}
+
+/// A pulse for `round` with randomness filled with `round`.
+pub fn pulse(round: RoundNumber) -> Pulse {
+	Pulse {
+		round,
+		randomness: [round as u8; 32],
+		signature: Default::default(),
+		previous_signature: Default::default(),
+	}
+}
+
+/// The key of `extrinsic` in the next block.
+pub fn key(extrinsic: Extrinsic) -> OrderingKey {
+	FairOrdering::ordering_keys(vec![extrinsic.encode()]).unwrap()[0].unwrap()
+}
+
+/// `extrinsics` in the order the next block must apply them.
+pub fn fairly_ordered(mut extrinsics: Vec<Extrinsic>) -> Vec<Extrinsic> {
+	extrinsics.sort_by_key(|extrinsic| key(*extrinsic));
+	extrinsics
+}
+
+/// Build the next block out of `inherents` followed by `transactions`, the way the executive
+/// applies them.
+pub fn build_block(inherents: &[Extrinsic], transactions: &[Extrinsic]) {
+	let number = System::block_number() + 1;
+	System::reset_events();
+	System::initialize(&number, &Default::default(), &Default::default());
+	FairOrdering::on_initialize(number);
+
+	let apply = |extrinsic: &Extrinsic| {
+		System::note_extrinsic(extrinsic.encode());
+		System::note_applied_extrinsic(&Ok(().into()), Default::default());
+	};
+	inherents.iter().for_each(apply);
+	FairOrdering::post_inherents();
+	transactions.iter().for_each(apply);
+
+	System::note_finished_extrinsics();
+	FairOrdering::on_finalize(number);
+	System::finalize();
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
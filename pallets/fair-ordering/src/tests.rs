@@ -0,0 +1,108 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::{mock::*, *};
+use ideal_nw_primitives::ordering::{BUCKET_NORMAL, BUCKET_OPERATIONAL, BUCKET_UNSIGNED};
+
+fn deviations() -> Vec<u32> {
+	System::events()
+		.into_iter()
+		.filter_map(|record| match record.event {
+			RuntimeEvent::FairOrdering(Event::OrderingDeviation { index }) => Some(index),
+			_ => None,
+		})
+		.collect()
+}
+
+fn transactions() -> Vec<Extrinsic> {
+	vec![
+		(BUCKET_NORMAL, 1, 0),
+		(BUCKET_NORMAL, 1, 1),
+		(BUCKET_NORMAL, 2, 0),
+		(BUCKET_NORMAL, 3, 0),
+		(BUCKET_OPERATIONAL, 4, 0),
+		(BUCKET_UNSIGNED, 5, 0),
+	]
+}
+
+#[test]
+fn keys_need_a_pulse() {
+	new_test_ext().execute_with(|| {
+		assert_eq!(FairOrdering::ordering_keys(vec![vec![]]), None);
+
+		LatestPulse::set(Some(pulse(1)));
+		assert_eq!(FairOrdering::ordering_keys(vec![vec![]]), Some(vec![None]));
+		let one = key((BUCKET_NORMAL, 1, 0));
+		assert_eq!(key((BUCKET_NORMAL, 1, 1)), OrderingKey { nonce: 1, ..one });
+
+		LatestPulse::set(Some(pulse(2)));
+		assert_ne!(key((BUCKET_NORMAL, 1, 0)), one);
+	});
+}
+
+#[test]
+fn fair_blocks_pass() {
+	new_test_ext().execute_with(|| {
+		LatestPulse::set(Some(pulse(1)));
+		let ordered = fairly_ordered(transactions());
+		assert_eq!(ordered[0].0, BUCKET_UNSIGNED);
+		assert_eq!(ordered[1].0, BUCKET_OPERATIONAL);
+
+		// Inherents precede the transactions in any order.
+		build_block(&[(BUCKET_NORMAL, 9, 0), (BUCKET_UNSIGNED, 0, 0)], &ordered);
+		assert_eq!(deviations(), Vec::<u32>::new());
+		assert!(Seed::<Test>::get().is_none());
+		assert!(FirstTransaction::<Test>::get().is_none());
+
+		// Transactions may be left out.
+		build_block(&[], &[ordered[1], ordered[4]]);
+		assert_eq!(deviations(), Vec::<u32>::new());
+	});
+}
+
+#[test]
+fn deviations_are_reported() {
+	new_test_ext().execute_with(|| {
+		LatestPulse::set(Some(pulse(1)));
+		let mut swapped = fairly_ordered(transactions());
+		swapped.swap(1, 2);
+		build_block(&[(BUCKET_UNSIGNED, 0, 0)], &swapped);
+		assert_eq!(deviations(), vec![3]);
+
+		// A sender's transactions follow their nonces.
+		build_block(&[], &[(BUCKET_NORMAL, 1, 1), (BUCKET_NORMAL, 1, 0)]);
+		assert_eq!(deviations(), vec![1]);
+	});
+}
+
+#[test]
+fn checks_are_bounded() {
+	new_test_ext().execute_with(|| {
+		// Nothing is checked without a pulse.
+		build_block(&[], &[(BUCKET_NORMAL, 1, 1), (BUCKET_NORMAL, 1, 0)]);
+		assert_eq!(deviations(), Vec::<u32>::new());
+
+		// Nor past the first `MaxChecked` transactions.
+		LatestPulse::set(Some(pulse(1)));
+		let mut block = vec![(BUCKET_NORMAL, 1, 0); MAX_CHECKED as usize];
+		for (nonce, extrinsic) in block.iter_mut().enumerate() {
+			extrinsic.2 = nonce as u64;
+		}
+		block.push((BUCKET_NORMAL, 1, 0));
+		build_block(&[], &block);
+		assert_eq!(deviations(), Vec::<u32>::new());
+	});
+}
@@ -0,0 +1,81 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Weights for `pallet_fair_ordering`.
+//!
+//! The figures below have not been measured yet. Replace this file with the output of the
+//! benchmarks in `benchmarking.rs` on reference hardware:
+//!
+//! ```sh
+//! ideal-nw-node benchmark pallet --chain dev --wasm-execution=compiled \
+//!     --pallet pallet_fair_ordering --extrinsic "*" --steps 50 --repeat 20 \
+//!     --output pallets/fair-ordering/src/weights.rs
+//! ```
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_fair_ordering`.
+pub trait WeightInfo {
+	fn set_seed() -> Weight;
+	fn check_order(n: u32, ) -> Weight;
+}
+
+/// Weights for `pallet_fair_ordering` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `Beacons::LatestRound` (r:1 w:0)
+	/// Storage: `Beacons::Pulses` (r:1 w:0)
+	/// Storage: `FairOrdering::Seed` (r:0 w:1)
+	fn set_seed() -> Weight {
+		Weight::from_parts(6_000_000, 3_000)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `FairOrdering::Seed` (r:1 w:1)
+	/// Storage: `FairOrdering::FirstTransaction` (r:1 w:1)
+	/// Storage: `System::ExtrinsicCount` (r:1 w:0)
+	/// Storage: `System::ExtrinsicData` (r:1024 w:0)
+	/// The range of component `n` is `[0, 1024]`.
+	fn check_order(n: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 1_500)
+			.saturating_add(Weight::from_parts(15_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn set_seed() -> Weight {
+		Weight::from_parts(6_000_000, 3_000)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn check_order(n: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 1_500)
+			.saturating_add(Weight::from_parts(15_000_000, 0).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+}
@@ -0,0 +1,86 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Benchmarks for `pallet_mixed_randomness`.
+
+use super::*;
+use frame_benchmarking::v2::*;
+use frame_system::RawOrigin;
+use sp_core::{crypto::VrfSecret, Pair};
+
+/// The session key of the block author.
+fn author() -> sr25519::Pair {
+	sr25519::Pair::from_seed(&[1; 32])
+}
+
+/// Make [`author`] build the current block on a pulse.
+fn prepare<T: Config>() -> (sr25519::Pair, Pulse) {
+	let author = author();
+	let signature = [7; 48];
+	let pulse = Pulse {
+		round: 1_000,
+		randomness: sp_core::hashing::sha2_256(&signature),
+		signature: signature.to_vec().try_into().expect("signature is 48 bytes; qed"),
+		previous_signature: Default::default(),
+	};
+	T::BenchmarkHelper::prepare(author.public(), pulse.clone());
+	(author, pulse)
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn contribute() {
+		let (author, pulse) = prepare::<T>();
+		// One output short of full, so that the most outputs are mixed.
+		let outputs = (1..T::MaxContributions::get()).map(|i| [i as u8; 32]);
+		let outputs = BoundedVec::truncate_from(outputs.collect::<alloc::vec::Vec<_>>());
+		Mixed::<T>::put(MixedSeed {
+			round: pulse.round,
+			randomness: mixing::mix(&pulse.randomness, &outputs),
+			contributions: outputs.len() as u32,
+			block_number: frame_system::Pallet::<T>::block_number(),
+		});
+		Contributions::<T>::put(outputs);
+		let signature = author.vrf_sign(&mixing::vrf_sign_data(pulse.round, &pulse.randomness));
+
+		#[extrinsic_call]
+		_(RawOrigin::None, signature);
+
+		let mixed = Mixed::<T>::get().expect("mixed above; qed");
+		assert_eq!(mixed.contributions, T::MaxContributions::get());
+	}
+
+	#[benchmark]
+	fn on_finalize() {
+		prepare::<T>();
+		let now = frame_system::Pallet::<T>::block_number();
+
+		#[block]
+		{
+			Pallet::<T>::on_initialize(now);
+			// The author's inherent.
+			Contributed::<T>::put(true);
+			Pallet::<T>::on_finalize(now);
+		}
+
+		assert!(!ContributionDue::<T>::exists());
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
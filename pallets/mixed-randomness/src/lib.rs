@@ -0,0 +1,281 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Mixed Randomness Pallet
+//!
+//! An optional randomness feed hedging against a compromised drand group.
+//!
+//! Every block author evaluates an sr25519 VRF over the latest pulse of [`Config::Pulse`] with
+//! its session key, and includes it with the [`Pallet::contribute`] inherent. The runtime
+//! verifies it against the key [`Config::FindAuthor`] finds for the author, and mixes the output
+//! into the randomness of the round, `H(drand_pulse || vrf_outputs)`. Consumers read it through
+//! [`MixedRandomness`].
+//!
+//! The inherent is required whenever there is a pulse and its round takes another output: a
+//! block without it is invalid, so an author can't drop its output and keep its block.
+//!
+//! See [`ideal_nw_primitives::mixing`] for the threat model: the feed stays unpredictable as long
+//! as the drand group and the authors of the round don't collude.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub use pallet::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+use core::marker::PhantomData;
+use frame_support::{
+	pallet_prelude::*,
+	traits::{FindAuthor, Randomness},
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+use ideal_nw_primitives::mixing::{self, InherentType, INHERENT_IDENTIFIER};
+pub use ideal_nw_primitives::{
+	beacon::{Pulse, Randomness as PulseRandomness, RoundNumber},
+	mixing::{MixedSeed, VrfOutput},
+};
+use sp_core::sr25519::{self, vrf::VrfSignature};
+use sp_runtime::traits::Hash;
+
+pub use weights::WeightInfo;
+
+/// Helper used by the benchmarks to set up the block author and the pulse.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper {
+	/// Make the holder of the session key `author` the author of the current block, and `pulse`
+	/// the pulse of [`Config::Pulse`].
+	fn prepare(author: sr25519::Public, pulse: Pulse);
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The session key authors evaluate the VRF with.
+		type AuthorityId: Parameter + Into<sr25519::Public>;
+
+		/// Finds the session key of the block author.
+		type FindAuthor: FindAuthor<Self::AuthorityId>;
+
+		/// The latest drand pulse, which authors evaluate the VRF over.
+		type Pulse: Get<Option<Pulse>>;
+
+		/// Maximum number of VRF outputs mixed into a round.
+		#[pallet::constant]
+		type MaxContributions: Get<u32>;
+
+		/// Weight information for extrinsics and hooks in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Helper to set up the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper;
+	}
+
+	/// The VRF outputs mixed into the round of [`Mixed`].
+	#[pallet::storage]
+	pub type Contributions<T: Config> =
+		StorageValue<_, BoundedVec<VrfOutput, T::MaxContributions>, ValueQuery>;
+
+	/// The latest mixed randomness.
+	#[pallet::storage]
+	pub type Mixed<T: Config> = StorageValue<_, MixedSeed<BlockNumberFor<T>>, OptionQuery>;
+
+	/// Whether the author contributed to the current block.
+	#[pallet::storage]
+	pub type Contributed<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	/// Whether the author of the current block must contribute, see
+	/// [`Pallet::contribution_due`].
+	#[pallet::storage]
+	pub type ContributionDue<T: Config> = StorageValue<_, bool, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// The block author's VRF output was mixed into a round.
+		Contributed { round: RoundNumber, contributions: u32 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The author already contributed to this block.
+		AlreadyContributed,
+		/// The block author's session key is unknown.
+		UnknownAuthor,
+		/// There is no pulse to evaluate the VRF over.
+		NoPulse,
+		/// The VRF signature is not the author's over the latest pulse.
+		InvalidVrf,
+		/// The round already has [`Config::MaxContributions`] outputs.
+		TooManyContributions,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+			// Decided before the inherents, on the pulse the author evaluates its VRF over.
+			if Self::contribution_due() {
+				ContributionDue::<T>::put(true);
+			}
+			T::WeightInfo::on_finalize()
+		}
+
+		fn on_finalize(_n: BlockNumberFor<T>) {
+			let due = ContributionDue::<T>::take();
+			assert!(
+				Contributed::<T>::take() || !due,
+				"the block author must contribute to the mixed randomness"
+			);
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Mix the block author's VRF output over the latest pulse into its round.
+		#[pallet::call_index(0)]
+		#[pallet::weight((T::WeightInfo::contribute(), DispatchClass::Mandatory))]
+		pub fn contribute(origin: OriginFor<T>, signature: VrfSignature) -> DispatchResult {
+			ensure_none(origin)?;
+			ensure!(!Contributed::<T>::get(), Error::<T>::AlreadyContributed);
+			let author = Self::author().ok_or(Error::<T>::UnknownAuthor)?;
+			let pulse = T::Pulse::get().ok_or(Error::<T>::NoPulse)?;
+			let output =
+				mixing::verify_contribution(&author, pulse.round, &pulse.randomness, &signature)
+					.ok_or(Error::<T>::InvalidVrf)?;
+
+			let mut outputs = match Mixed::<T>::get() {
+				Some(mixed) if mixed.round == pulse.round => Contributions::<T>::get(),
+				_ => BoundedVec::new(),
+			};
+			outputs.try_push(output).map_err(|_| Error::<T>::TooManyContributions)?;
+			let contributions = outputs.len() as u32;
+			Mixed::<T>::put(MixedSeed {
+				round: pulse.round,
+				randomness: mixing::mix(&pulse.randomness, &outputs),
+				contributions,
+				block_number: frame_system::Pallet::<T>::block_number(),
+			});
+			Contributions::<T>::put(outputs);
+			Contributed::<T>::put(true);
+
+			Self::deposit_event(Event::Contributed { round: pulse.round, contributions });
+			Ok(())
+		}
+	}
+
+	#[pallet::inherent]
+	impl<T: Config> ProvideInherent for Pallet<T> {
+		type Call = Call<T>;
+		type Error = sp_inherents::MakeFatalError<()>;
+		const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+		fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+			let contributions = data.get_data::<InherentType>(&INHERENT_IDENTIFIER).ok()??;
+			let author = Self::author()?;
+			let pulse = T::Pulse::get()?;
+			if Self::is_full(pulse.round) {
+				return None;
+			}
+			// The node signs with all its session keys, keep the author's if it is valid.
+			contributions
+				.into_iter()
+				.find(|contribution| contribution.public == author)
+				.filter(|contribution| {
+					mixing::verify_contribution(
+						&author,
+						pulse.round,
+						&pulse.randomness,
+						&contribution.signature,
+					)
+					.is_some()
+				})
+				.map(|contribution| Call::contribute { signature: contribution.signature })
+		}
+
+		fn is_inherent_required(_: &InherentData) -> Result<Option<Self::Error>, Self::Error> {
+			Ok(Self::contribution_due().then(|| ().into()))
+		}
+
+		fn is_inherent(call: &Self::Call) -> bool {
+			matches!(call, Call::contribute { .. })
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The session key of the block author.
+	fn author() -> Option<sr25519::Public> {
+		let digest = frame_system::Pallet::<T>::digest();
+		let pre_runtime_digests = digest.logs.iter().filter_map(|item| item.as_pre_runtime());
+		T::FindAuthor::find_author(pre_runtime_digests).map(Into::into)
+	}
+
+	/// Whether the block author must contribute: there is a pulse, the round takes another
+	/// output, and the author is known.
+	fn contribution_due() -> bool {
+		T::Pulse::get().is_some_and(|pulse| !Self::is_full(pulse.round)) && Self::author().is_some()
+	}
+
+	/// Whether `round` can't take another contribution.
+	fn is_full(round: RoundNumber) -> bool {
+		Mixed::<T>::get().is_some_and(|mixed| {
+			mixed.round == round && mixed.contributions >= T::MaxContributions::get()
+		})
+	}
+
+	/// The round and randomness the next author evaluates its VRF over.
+	pub fn vrf_input() -> Option<(RoundNumber, PulseRandomness)> {
+		T::Pulse::get().map(|pulse| (pulse.round, pulse.randomness))
+	}
+
+	/// The latest mixed randomness.
+	pub fn mixed_randomness() -> Option<MixedSeed<BlockNumberFor<T>>> {
+		Mixed::<T>::get()
+	}
+}
+
+/// Randomness mixed from the latest pulse and the authors' VRF outputs.
+///
+/// The seed is the default hash until an author contributed. The block number is the block that
+/// mixed in the last output.
+pub struct MixedRandomness<T>(PhantomData<T>);
+
+impl<T: Config> Randomness<T::Hash, BlockNumberFor<T>> for MixedRandomness<T> {
+	fn random(subject: &[u8]) -> (T::Hash, BlockNumberFor<T>) {
+		match Mixed::<T>::get() {
+			Some(mixed) => (T::Hashing::hash_of(&(subject, mixed.randomness)), mixed.block_number),
+			None => (Default::default(), frame_system::Pallet::<T>::block_number()),
+		}
+	}
+}
@@ -0,0 +1,127 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate as pallet_mixed_randomness;
+use frame_support::{derive_impl, parameter_types, traits::FindAuthor};
+use ideal_nw_primitives::{
+	beacon::{Pulse, RoundNumber},
+	mixing::vrf_sign_data,
+};
+use sp_core::{
+	crypto::VrfSecret,
+	sr25519::{self, vrf::VrfSignature},
+	ConsensusEngineId, Pair,
+};
+use sp_runtime::BuildStorage;
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		Mixing: pallet_mixed_randomness,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+}
+
+pub const MAX_CONTRIBUTIONS: u32 = 3;
+
+parameter_types! {
+	pub const MaxContributions: u32 = MAX_CONTRIBUTIONS;
+	pub static Author: Option<sr25519::Public> = None;
+	pub static LatestPulse: Option<Pulse> = None;
+}
+
+/// Finds the author set with [`Author::set`].
+pub struct MockFindAuthor;
+
+impl FindAuthor<sr25519::Public> for MockFindAuthor {
+	fn find_author<'a, I>(_digests: I) -> Option<sr25519::Public>
+	where
+		I: 'a + IntoIterator<Item = (ConsensusEngineId, &'a [u8])>,
+	{
+		Author::get()
+	}
+}
+
+impl pallet_mixed_randomness::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type AuthorityId = sr25519::Public;
+	type FindAuthor = MockFindAuthor;
+	type Pulse = LatestPulse;
+	type MaxContributions = MaxContributions;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBenchmarkHelper;
+}
+
+/// Sets the statics the mock reads the author and the pulse from.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct MockBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_mixed_randomness::BenchmarkHelper for MockBenchmarkHelper {
+	fn prepare(author: sr25519::Public, pulse: Pulse) {
+		Author::set(Some(author));
+		LatestPulse::set(Some(pulse));
+	}
+}
+
+/// The session key of author `n`.
+pub fn author(n: u8) -> sr25519::Pair {
+	sr25519::Pair::from_seed(&[n; 32])
+}
+
+/// A pulse for `round` with randomness filled with `round`.
+pub fn pulse(round: RoundNumber) -> Pulse {
+	Pulse {
+		round,
+		randomness: [round as u8; 32],
+		signature: Default::default(),
+		previous_signature: Default::default(),
+	}
+}
+
+/// The VRF of `author` over `pulse`.
+pub fn vrf(author: &sr25519::Pair, pulse: &Pulse) -> VrfSignature {
+	author.vrf_sign(&vrf_sign_data(pulse.round, &pulse.randomness))
+}
+
+/// Make `author` build the current block on top of `pulse`.
+pub fn build_on(author: &sr25519::Pair, pulse: &Pulse) {
+	Author::set(Some(author.public()));
+	LatestPulse::set(Some(pulse.clone()));
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Finish the current block and start the next one.
+pub fn next_block() {
+	use frame_support::traits::Hooks;
+
+	Mixing::on_finalize(System::block_number());
+	System::set_block_number(System::block_number() + 1);
+	Mixing::on_initialize(System::block_number());
+}
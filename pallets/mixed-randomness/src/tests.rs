@@ -0,0 +1,174 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::{mock::*, *};
+use frame_support::{
+	assert_noop, assert_ok,
+	inherent::{InherentData, ProvideInherent},
+	traits::Randomness as _,
+};
+use ideal_nw_primitives::mixing::{mix, verify_contribution, VrfContribution, INHERENT_IDENTIFIER};
+use sp_core::{sr25519, Pair};
+use sp_runtime::{
+	traits::{BlakeTwo256, Hash},
+	DispatchResult,
+};
+
+fn contribute(author: &sr25519::Pair, pulse: &Pulse) -> DispatchResult {
+	Mixing::contribute(RuntimeOrigin::none(), vrf(author, pulse))
+}
+
+fn output(author: &sr25519::Pair, pulse: &Pulse) -> VrfOutput {
+	verify_contribution(&author.public(), pulse.round, &pulse.randomness, &vrf(author, pulse))
+		.unwrap()
+}
+
+#[test]
+fn outputs_of_a_round_are_mixed_in() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob, pulse) = (author(1), author(2), pulse(7));
+		build_on(&alice, &pulse);
+		assert_ok!(contribute(&alice, &pulse));
+		System::assert_last_event(Event::Contributed { round: 7, contributions: 1 }.into());
+
+		next_block();
+		build_on(&bob, &pulse);
+		assert_ok!(contribute(&bob, &pulse));
+
+		let mixed = Mixed::<Test>::get().unwrap();
+		let outputs = [output(&alice, &pulse), output(&bob, &pulse)];
+		assert_eq!(mixed.randomness, mix(&pulse.randomness, &outputs));
+		assert_eq!((mixed.round, mixed.contributions, mixed.block_number), (7, 2, 2));
+
+		let (seed, block_number) = MixedRandomness::<Test>::random(b"subject");
+		assert_eq!(seed, BlakeTwo256::hash_of(&(b"subject".as_slice(), mixed.randomness)));
+		assert_eq!(block_number, 2);
+	});
+}
+
+#[test]
+fn a_new_round_starts_a_new_mix() {
+	new_test_ext().execute_with(|| {
+		let alice = author(1);
+		build_on(&alice, &pulse(7));
+		assert_ok!(contribute(&alice, &pulse(7)));
+
+		next_block();
+		build_on(&alice, &pulse(8));
+		assert_ok!(contribute(&alice, &pulse(8)));
+		let mixed = Mixed::<Test>::get().unwrap();
+		assert_eq!((mixed.round, mixed.contributions), (8, 1));
+		assert_eq!(mixed.randomness, mix(&pulse(8).randomness, &[output(&alice, &pulse(8))]));
+	});
+}
+
+#[test]
+fn contributions_are_checked() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob, pulse) = (author(1), author(2), pulse(7));
+		assert_noop!(contribute(&alice, &pulse), Error::<Test>::UnknownAuthor);
+
+		Author::set(Some(alice.public()));
+		assert_noop!(contribute(&alice, &pulse), Error::<Test>::NoPulse);
+
+		build_on(&alice, &pulse);
+		// Another key's VRF, or one over another round, isn't the author's contribution.
+		assert_noop!(contribute(&bob, &pulse), Error::<Test>::InvalidVrf);
+		assert_noop!(contribute(&alice, &super::mock::pulse(6)), Error::<Test>::InvalidVrf);
+
+		assert_ok!(contribute(&alice, &pulse));
+		assert_noop!(contribute(&alice, &pulse), Error::<Test>::AlreadyContributed);
+	});
+}
+
+#[test]
+fn rounds_take_a_bounded_number_of_outputs() {
+	new_test_ext().execute_with(|| {
+		let pulse = pulse(7);
+		for n in 1..=MAX_CONTRIBUTIONS as u8 {
+			build_on(&author(n), &pulse);
+			assert_ok!(contribute(&author(n), &pulse));
+			next_block();
+		}
+		let last = author(MAX_CONTRIBUTIONS as u8 + 1);
+		build_on(&last, &pulse);
+		assert_noop!(contribute(&last, &pulse), Error::<Test>::TooManyContributions);
+	});
+}
+
+#[test]
+fn the_inherent_carries_the_authors_contribution() {
+	new_test_ext().execute_with(|| {
+		let (alice, bob, pulse) = (author(1), author(2), pulse(7));
+		let mut data = InherentData::new();
+		let contributions: Vec<_> = [&bob, &alice]
+			.into_iter()
+			.map(|key| VrfContribution { public: key.public(), signature: vrf(key, &pulse) })
+			.collect();
+		data.put_data(INHERENT_IDENTIFIER, &contributions).unwrap();
+
+		// Nothing to contribute to without a pulse.
+		Author::set(Some(alice.public()));
+		assert_eq!(Mixing::create_inherent(&data), None);
+
+		build_on(&alice, &pulse);
+		let call = Mixing::create_inherent(&data).unwrap();
+		assert_eq!(call, Call::contribute { signature: vrf(&alice, &pulse) });
+		assert!(Mixing::is_inherent(&call));
+
+		// Nodes without the author's key contribute nothing.
+		build_on(&author(3), &pulse);
+		assert_eq!(Mixing::create_inherent(&data), None);
+		assert_eq!(Mixing::create_inherent(&InherentData::new()), None);
+	});
+}
+
+#[test]
+fn the_inherent_is_required_while_the_round_takes_outputs() {
+	new_test_ext().execute_with(|| {
+		let (alice, pulse) = (author(1), pulse(7));
+		let data = InherentData::new();
+		Author::set(Some(alice.public()));
+		assert!(Mixing::is_inherent_required(&data).unwrap().is_none());
+
+		build_on(&alice, &pulse);
+		assert!(Mixing::is_inherent_required(&data).unwrap().is_some());
+		next_block();
+		assert!(ContributionDue::<Test>::get());
+		assert_ok!(contribute(&alice, &pulse));
+		next_block();
+
+		// Not once the round is full.
+		for n in 2..=MAX_CONTRIBUTIONS as u8 {
+			build_on(&author(n), &pulse);
+			assert_ok!(contribute(&author(n), &pulse));
+			next_block();
+		}
+		assert!(!ContributionDue::<Test>::get());
+		assert!(Mixing::is_inherent_required(&data).unwrap().is_none());
+		next_block();
+	});
+}
+
+#[test]
+#[should_panic(expected = "the block author must contribute to the mixed randomness")]
+fn blocks_without_a_due_contribution_are_invalid() {
+	new_test_ext().execute_with(|| {
+		build_on(&author(1), &pulse(7));
+		next_block();
+		next_block();
+	});
+}
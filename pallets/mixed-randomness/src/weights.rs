@@ -0,0 +1,87 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Weights for `pallet_mixed_randomness`.
+//!
+//! The figures below have not been measured yet. Replace this file with the output of the
+//! benchmarks in `benchmarking.rs` on reference hardware:
+//!
+//! ```sh
+//! ideal-nw-node benchmark pallet --chain dev --wasm-execution=compiled \
+//!     --pallet pallet_mixed_randomness --extrinsic "*" --steps 50 --repeat 20 \
+//!     --output pallets/mixed-randomness/src/weights.rs
+//! ```
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_mixed_randomness`.
+pub trait WeightInfo {
+	fn contribute() -> Weight;
+	fn on_finalize() -> Weight;
+}
+
+/// Weights for `pallet_mixed_randomness` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `MixedRandomness::Contributed` (r:1 w:1)
+	/// Storage: `System::Digest` (r:1 w:0)
+	/// Storage: `Aura::Authorities` (r:1 w:0)
+	/// Storage: `Beacons::Stalled` (r:1 w:0)
+	/// Storage: `Beacons::Health` (r:1 w:0)
+	/// Storage: `Beacons::LatestRound` (r:1 w:0)
+	/// Storage: `Beacons::Pulses` (r:1 w:0)
+	/// Storage: `MixedRandomness::Mixed` (r:1 w:1)
+	/// Storage: `MixedRandomness::Contributions` (r:1 w:1)
+	fn contribute() -> Weight {
+		Weight::from_parts(90_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: `System::Digest` (r:1 w:0)
+	/// Storage: `Aura::Authorities` (r:1 w:0)
+	/// Storage: `Beacons::Stalled` (r:1 w:0)
+	/// Storage: `Beacons::Health` (r:1 w:0)
+	/// Storage: `Beacons::LatestRound` (r:1 w:0)
+	/// Storage: `Beacons::Pulses` (r:1 w:0)
+	/// Storage: `MixedRandomness::Mixed` (r:1 w:0)
+	/// Storage: `MixedRandomness::ContributionDue` (r:1 w:2)
+	/// Storage: `MixedRandomness::Contributed` (r:1 w:1)
+	fn on_finalize() -> Weight {
+		Weight::from_parts(6_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn contribute() -> Weight {
+		Weight::from_parts(90_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	fn on_finalize() -> Weight {
+		Weight::from_parts(6_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+}
@@ -0,0 +1,136 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Benchmarks for `pallet_optimistic_pulses`.
+
+use super::*;
+use frame_benchmarking::v2::*;
+use frame_support::traits::fungible::{Inspect, Mutate};
+use frame_system::RawOrigin;
+
+fn funded<T: Config>(name: &'static str, index: u32) -> T::AccountId {
+	let who: T::AccountId = account(name, index, 0);
+	let amount = T::SubmitterBond::get()
+		.saturating_mul(10u32.into())
+		.saturating_add(T::Currency::minimum_balance());
+	T::Currency::set_balance(&who, amount);
+	who
+}
+
+fn bonded<T: Config>(name: &'static str, index: u32) -> Result<T::AccountId, BenchmarkError> {
+	let who = funded::<T>(name, index);
+	Pallet::<T>::bond(RawOrigin::Signed(who.clone()).into())?;
+	Ok(who)
+}
+
+/// Submit the rounds up to `rounds`, from as many submitters as it takes.
+fn submit<T: Config>(rounds: u64) -> Result<(), BenchmarkError> {
+	let per_submitter = u64::from(T::MaxPendingPerSubmitter::get().max(1));
+	let mut who = None;
+	for round in 1..=rounds {
+		if (round - 1) % per_submitter == 0 {
+			who = Some(bonded::<T>("submitter", (round / per_submitter) as u32 + 1)?);
+		}
+		let who = who.clone().expect("bonded in the first round; qed");
+		Pallet::<T>::submit_pulse(
+			RawOrigin::Signed(who).into(),
+			T::BenchmarkHelper::invalid_pulse(round),
+		)?;
+	}
+	Ok(())
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn bond() {
+		let who = funded::<T>("submitter", 0);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(who.clone()));
+
+		assert!(Submitters::<T>::contains_key(&who));
+	}
+
+	#[benchmark]
+	fn unbond() -> Result<(), BenchmarkError> {
+		let who = bonded::<T>("submitter", 0)?;
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(who.clone()));
+
+		assert!(!Submitters::<T>::contains_key(&who));
+		Ok(())
+	}
+
+	#[benchmark]
+	fn submit_pulse() -> Result<(), BenchmarkError> {
+		T::BenchmarkHelper::prepare_verifier();
+		let last = T::MaxPending::get() as u64;
+		submit::<T>(last - 1)?;
+		let who = bonded::<T>("submitter", 0)?;
+		let pulse = T::BenchmarkHelper::invalid_pulse(last);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(who), pulse);
+
+		assert_eq!(OptimisticRound::<T>::get(), last);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn challenge(p: Linear<1, { T::MaxPendingPerSubmitter::get() }>) -> Result<(), BenchmarkError> {
+		T::BenchmarkHelper::prepare_verifier();
+		let who = bonded::<T>("submitter", 0)?;
+		for round in 1..=p as u64 {
+			Pallet::<T>::submit_pulse(
+				RawOrigin::Signed(who.clone()).into(),
+				T::BenchmarkHelper::invalid_pulse(round),
+			)?;
+		}
+		let challenger = funded::<T>("challenger", 0);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(challenger), 1);
+
+		assert!(!Submitters::<T>::contains_key(&who));
+		assert_eq!(OptimisticRound::<T>::get(), 0);
+		Ok(())
+	}
+
+	#[benchmark]
+	fn on_initialize(n: Linear<0, { T::MaxPending::get() }>) -> Result<(), BenchmarkError> {
+		T::BenchmarkHelper::prepare_verifier();
+		submit::<T>(n as u64)?;
+		// Fill the finalized window so that every finalized pulse prunes an older one.
+		let kept = alloc::vec![0; T::MaxFinalized::get() as usize];
+		FinalizedRounds::<T>::put(BoundedVec::truncate_from(kept));
+		let now =
+			frame_system::Pallet::<T>::block_number().saturating_add(T::ChallengePeriod::get());
+
+		#[block]
+		{
+			Pallet::<T>::on_initialize(now);
+		}
+
+		assert_eq!(FinalizedRound::<T>::get(), n as u64);
+		Ok(())
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
@@ -0,0 +1,530 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # Optimistic Pulses Pallet
+//!
+//! Accepts drand pulses without verifying their signature on-chain.
+//!
+//! Submitters lock a bond and post pulses, which are stored straight away and become
+//! *optimistic* randomness. A submitter has at most [`Config::MaxPendingPerSubmitter`] pulses
+//! pending, so that the queue of pending pulses takes a bond per few entries, and pulses may be
+//! at most [`Config::MaxRoundsAhead`] rounds ahead of the beacon. Every pulse then sits in a
+//! challenge window of [`Config::ChallengePeriod`] blocks. During that window anyone can challenge
+//! it, which runs the full signature check on-chain. If the pulse turns out to be invalid, the
+//! challenger receives [`Config::ChallengerReward`] of the submitter's bond, the rest is burned,
+//! and every pulse the submitter still has pending is rolled back. Burning part of the bond keeps
+//! fraud costly even when the submitter controls the challenging account too. Pulses that survive
+//! the window are *finalized*, and only the latest [`Config::MaxFinalized`] of them are kept.
+//!
+//! Consumers pick the guarantee they need through [`OptimisticRandomness`] or
+//! [`FinalizedRandomness`].
+//!
+//! Weight is only saved if the beacon followed here is not also verified on-chain elsewhere: with
+//! `pallet_beacons`, list it in its `OptimisticBeacons`.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub use pallet::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+use alloc::vec::Vec;
+use codec::{Decode, Encode, MaxEncodedLen};
+use core::marker::PhantomData;
+use frame_support::{
+	pallet_prelude::*,
+	traits::{fungible, Randomness},
+};
+use frame_system::pallet_prelude::BlockNumberFor;
+pub use ideal_nw_primitives::beacon::{Pulse, RoundNumber};
+use scale_info::TypeInfo;
+use sp_runtime::{
+	traits::{Hash, Saturating},
+	Perbill,
+};
+
+pub use weights::WeightInfo;
+
+pub type AccountIdOf<T> = <T as frame_system::Config>::AccountId;
+pub type BalanceOf<T> = <<T as Config>::Currency as fungible::Inspect<AccountIdOf<T>>>::Balance;
+
+/// Verifies pulses of the beacon followed by this pallet.
+pub trait VerifyPulse {
+	/// Whether `pulse` carries a valid beacon signature.
+	///
+	/// Errors if the pulse cannot be checked at all, e.g. because the beacon is not configured.
+	/// A malformed pulse is not an error, it is simply invalid.
+	fn verify(pulse: &Pulse) -> Result<bool, DispatchError>;
+
+	/// The latest round the beacon has emitted by now.
+	///
+	/// Errors if the beacon is not configured.
+	fn current_round() -> Result<RoundNumber, DispatchError>;
+}
+
+/// Helper used by the benchmarks to set up a verifiable beacon.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper {
+	/// Prepare whatever [`Config::Verifier`] needs to check pulses, with the beacon at least at
+	/// round [`Config::MaxPending`].
+	fn prepare_verifier();
+
+	/// A pulse for `round` that is well formed but fails verification.
+	fn invalid_pulse(round: RoundNumber) -> Pulse;
+}
+
+/// The finality a consumer requires from the randomness it reads.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum Finality {
+	/// Any accepted pulse, including those still inside their challenge window.
+	Optimistic,
+	/// Only pulses whose challenge window has passed.
+	Finalized,
+}
+
+/// A bonded submitter.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct SubmitterInfo<Balance> {
+	/// The amount held as bond.
+	pub bond: Balance,
+	/// Number of the submitter's pulses that are still challengeable.
+	pub pending: u32,
+}
+
+/// A pulse inside its challenge window.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct PendingPulse<AccountId, BlockNumber> {
+	/// Who submitted the pulse.
+	pub submitter: AccountId,
+	/// The block in which the pulse was submitted.
+	pub submitted_at: BlockNumber,
+}
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_support::traits::{
+		fungible::{Mutate, MutateHold},
+		tokens::{Fortitude, Precision, Restriction},
+	};
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The currency used for submitter bonds.
+		type Currency: Mutate<Self::AccountId>
+			+ MutateHold<Self::AccountId, Reason = Self::RuntimeHoldReason>;
+
+		/// The overarching hold reason.
+		type RuntimeHoldReason: From<HoldReason>;
+
+		/// Checks pulses when they are challenged.
+		type Verifier: VerifyPulse;
+
+		/// The bond a submitter locks before posting pulses.
+		#[pallet::constant]
+		type SubmitterBond: Get<BalanceOf<Self>>;
+
+		/// Number of blocks during which a submitted pulse can be challenged.
+		#[pallet::constant]
+		type ChallengePeriod: Get<BlockNumberFor<Self>>;
+
+		/// Maximum number of pulses that can be inside their challenge window at once.
+		#[pallet::constant]
+		type MaxPending: Get<u32>;
+
+		/// Maximum number of pulses of a single submitter inside their challenge window.
+		#[pallet::constant]
+		type MaxPendingPerSubmitter: Get<u32>;
+
+		/// Number of rounds past the current round of the beacon a pulse can be submitted for,
+		/// allowing for the clocks of the chain and the beacon to differ.
+		#[pallet::constant]
+		type MaxRoundsAhead: Get<RoundNumber>;
+
+		/// Number of finalized pulses kept in storage. Older ones are pruned. Must be at least 1.
+		#[pallet::constant]
+		type MaxFinalized: Get<u32>;
+
+		/// Share of a slashed bond paid to the challenger. The remainder is burned.
+		#[pallet::constant]
+		type ChallengerReward: Get<Perbill>;
+
+		/// Weight information for extrinsics and hooks in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Helper to set up the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper;
+	}
+
+	/// A reason for the pallet placing a hold on funds.
+	#[pallet::composite_enum]
+	pub enum HoldReason {
+		/// Funds bonded by a pulse submitter.
+		#[codec(index = 0)]
+		SubmitterBond,
+	}
+
+	/// Bonded submitters.
+	#[pallet::storage]
+	pub type Submitters<T: Config> =
+		StorageMap<_, Blake2_128Concat, T::AccountId, SubmitterInfo<BalanceOf<T>>, OptionQuery>;
+
+	/// Accepted pulses, both pending and finalized.
+	#[pallet::storage]
+	pub type Pulses<T: Config> = StorageMap<_, Blake2_128Concat, RoundNumber, Pulse, OptionQuery>;
+
+	/// Pulses that are still inside their challenge window.
+	#[pallet::storage]
+	pub type Pending<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		RoundNumber,
+		PendingPulse<T::AccountId, BlockNumberFor<T>>,
+		OptionQuery,
+	>;
+
+	/// Rounds of the pending pulses, in submission (and therefore round) order.
+	#[pallet::storage]
+	pub type PendingRounds<T: Config> =
+		StorageValue<_, BoundedVec<RoundNumber, T::MaxPending>, ValueQuery>;
+
+	/// The latest accepted round, whether pending or finalized.
+	#[pallet::storage]
+	pub type OptimisticRound<T: Config> = StorageValue<_, RoundNumber, ValueQuery>;
+
+	/// The latest round whose challenge window has passed.
+	#[pallet::storage]
+	pub type FinalizedRound<T: Config> = StorageValue<_, RoundNumber, ValueQuery>;
+
+	/// Rounds of the finalized pulses still kept in [`Pulses`], oldest first.
+	#[pallet::storage]
+	pub type FinalizedRounds<T: Config> =
+		StorageValue<_, BoundedVec<RoundNumber, T::MaxFinalized>, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// A submitter locked its bond.
+		SubmitterBonded { who: T::AccountId, bond: BalanceOf<T> },
+		/// A submitter withdrew its bond.
+		SubmitterUnbonded { who: T::AccountId },
+		/// A pulse was accepted optimistically.
+		PulseSubmitted { round: RoundNumber, submitter: T::AccountId },
+		/// A pulse passed its challenge window.
+		PulseFinalized { round: RoundNumber },
+		/// A pulse was proven invalid and its submitter slashed.
+		FraudProven {
+			round: RoundNumber,
+			submitter: T::AccountId,
+			challenger: T::AccountId,
+			rewarded: BalanceOf<T>,
+			burned: BalanceOf<T>,
+		},
+		/// Pending pulses were discarded and the optimistic round moved back.
+		RolledBack { rounds: Vec<RoundNumber>, optimistic_round: RoundNumber },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// The account is already a bonded submitter.
+		AlreadyBonded,
+		/// The account is not a bonded submitter.
+		NotBonded,
+		/// The submitter still has pulses inside their challenge window.
+		PulsesPending,
+		/// The pulse is not newer than the latest accepted round.
+		StaleRound,
+		/// The pulse randomness is not the hash of its signature.
+		RandomnessMismatch,
+		/// Too many pulses are inside their challenge window.
+		TooManyPending,
+		/// The submitter has too many pulses inside their challenge window.
+		TooManyPendingForSubmitter,
+		/// The beacon has not reached the round of the pulse yet.
+		FutureRound,
+		/// There is no challengeable pulse for this round.
+		NotChallengeable,
+		/// The challenged pulse is valid.
+		PulseIsValid,
+		/// Submitters cannot challenge their own pulses.
+		SelfChallenge,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(n: BlockNumberFor<T>) -> Weight {
+			let finalized = Self::finalize_due(n);
+			T::WeightInfo::on_initialize(finalized)
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Lock [`Config::SubmitterBond`] and become a pulse submitter.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::bond())]
+		pub fn bond(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(!Submitters::<T>::contains_key(&who), Error::<T>::AlreadyBonded);
+
+			let bond = T::SubmitterBond::get();
+			T::Currency::hold(&HoldReason::SubmitterBond.into(), &who, bond)?;
+			Submitters::<T>::insert(&who, SubmitterInfo { bond, pending: 0 });
+
+			Self::deposit_event(Event::SubmitterBonded { who, bond });
+			Ok(())
+		}
+
+		/// Release the bond once none of the caller's pulses can be challenged anymore.
+		#[pallet::call_index(1)]
+		#[pallet::weight(T::WeightInfo::unbond())]
+		pub fn unbond(origin: OriginFor<T>) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let info = Submitters::<T>::get(&who).ok_or(Error::<T>::NotBonded)?;
+			ensure!(info.pending == 0, Error::<T>::PulsesPending);
+
+			T::Currency::release(
+				&HoldReason::SubmitterBond.into(),
+				&who,
+				info.bond,
+				Precision::BestEffort,
+			)?;
+			Submitters::<T>::remove(&who);
+
+			Self::deposit_event(Event::SubmitterUnbonded { who });
+			Ok(())
+		}
+
+		/// Post a pulse without verifying its signature.
+		///
+		/// Only the consistency between randomness and signature, and that the beacon is about to
+		/// reach the round, are checked. The signature itself can be challenged for
+		/// [`Config::ChallengePeriod`] blocks.
+		#[pallet::call_index(2)]
+		#[pallet::weight(T::WeightInfo::submit_pulse())]
+		pub fn submit_pulse(origin: OriginFor<T>, pulse: Pulse) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let mut info = Submitters::<T>::get(&who).ok_or(Error::<T>::NotBonded)?;
+			ensure!(pulse.round > OptimisticRound::<T>::get(), Error::<T>::StaleRound);
+			let latest = T::Verifier::current_round()?.saturating_add(T::MaxRoundsAhead::get());
+			ensure!(pulse.round <= latest, Error::<T>::FutureRound);
+			ensure!(
+				info.pending < T::MaxPendingPerSubmitter::get(),
+				Error::<T>::TooManyPendingForSubmitter
+			);
+			ensure!(
+				pulse.randomness == sp_io::hashing::sha2_256(&pulse.signature),
+				Error::<T>::RandomnessMismatch
+			);
+
+			let round = pulse.round;
+			PendingRounds::<T>::try_mutate(|rounds| rounds.try_push(round))
+				.map_err(|_| Error::<T>::TooManyPending)?;
+			Pending::<T>::insert(
+				round,
+				PendingPulse {
+					submitter: who.clone(),
+					submitted_at: frame_system::Pallet::<T>::block_number(),
+				},
+			);
+			Pulses::<T>::insert(round, pulse);
+			OptimisticRound::<T>::put(round);
+			info.pending.saturating_inc();
+			Submitters::<T>::insert(&who, info);
+
+			Self::deposit_event(Event::PulseSubmitted { round, submitter: who });
+			Ok(())
+		}
+
+		/// Challenge a pending pulse by verifying it on-chain.
+		///
+		/// If the pulse is invalid, the caller receives [`Config::ChallengerReward`] of the
+		/// submitter's bond, the rest is burned and all of the submitter's pending pulses are
+		/// discarded. Challenging a valid pulse, or one's own, fails.
+		#[pallet::call_index(3)]
+		#[pallet::weight(T::WeightInfo::challenge(T::MaxPendingPerSubmitter::get()))]
+		pub fn challenge(origin: OriginFor<T>, round: RoundNumber) -> DispatchResultWithPostInfo {
+			let challenger = ensure_signed(origin)?;
+			let pending = Pending::<T>::get(round).ok_or(Error::<T>::NotChallengeable)?;
+			ensure!(pending.submitter != challenger, Error::<T>::SelfChallenge);
+			let pulse = Pulses::<T>::get(round).ok_or(Error::<T>::NotChallengeable)?;
+			ensure!(!T::Verifier::verify(&pulse)?, Error::<T>::PulseIsValid);
+
+			let submitter = pending.submitter;
+			let bond = Submitters::<T>::take(&submitter).map(|info| info.bond).unwrap_or_default();
+			let rewarded = T::Currency::transfer_on_hold(
+				&HoldReason::SubmitterBond.into(),
+				&submitter,
+				&challenger,
+				T::ChallengerReward::get() * bond,
+				Precision::BestEffort,
+				Restriction::Free,
+				Fortitude::Force,
+			)?;
+			let burned = T::Currency::burn_held(
+				&HoldReason::SubmitterBond.into(),
+				&submitter,
+				bond.saturating_sub(rewarded),
+				Precision::BestEffort,
+				Fortitude::Force,
+			)?;
+			Self::deposit_event(Event::FraudProven {
+				round,
+				submitter: submitter.clone(),
+				challenger,
+				rewarded,
+				burned,
+			});
+
+			let rolled_back = Self::roll_back(&submitter);
+			Ok(Some(T::WeightInfo::challenge(rolled_back)).into())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// The latest pulse with the requested finality, if any.
+	pub fn latest_pulse(finality: Finality) -> Option<Pulse> {
+		let round = match finality {
+			Finality::Optimistic => OptimisticRound::<T>::get(),
+			Finality::Finalized => FinalizedRound::<T>::get(),
+		};
+		Pulses::<T>::get(round)
+	}
+
+	/// Derive randomness for `subject` from the latest pulse with the requested finality.
+	pub fn random(finality: Finality, subject: &[u8]) -> (T::Hash, BlockNumberFor<T>) {
+		let block_number = frame_system::Pallet::<T>::block_number();
+		let seed = Self::latest_pulse(finality)
+			.map(|pulse| T::Hashing::hash_of(&(subject, pulse.randomness)))
+			.unwrap_or_default();
+		(seed, block_number)
+	}
+
+	/// Finalize the pulses whose challenge window ended at block `now`.
+	///
+	/// Returns the number of finalized pulses.
+	fn finalize_due(now: BlockNumberFor<T>) -> u32 {
+		let period = T::ChallengePeriod::get();
+		let mut rounds = PendingRounds::<T>::get();
+		let pending_before = rounds.len();
+		let mut finalized = 0u32;
+
+		while let Some(&round) = rounds.first() {
+			let Some(pending) = Pending::<T>::get(round) else {
+				rounds.remove(0);
+				continue;
+			};
+			if pending.submitted_at.saturating_add(period) > now {
+				break;
+			}
+
+			rounds.remove(0);
+			Pending::<T>::remove(round);
+			Submitters::<T>::mutate(&pending.submitter, |info| {
+				if let Some(info) = info {
+					info.pending.saturating_dec();
+				}
+			});
+			FinalizedRound::<T>::put(round);
+			Self::keep_finalized(round);
+			finalized.saturating_inc();
+			Self::deposit_event(Event::PulseFinalized { round });
+		}
+
+		if rounds.len() != pending_before {
+			PendingRounds::<T>::put(rounds);
+		}
+		finalized
+	}
+
+	/// Remember `round` as finalized, pruning the oldest finalized pulse once
+	/// [`Config::MaxFinalized`] are kept.
+	fn keep_finalized(round: RoundNumber) {
+		FinalizedRounds::<T>::mutate(|rounds| {
+			if rounds.is_full() {
+				let oldest = rounds.remove(0);
+				Pulses::<T>::remove(oldest);
+			}
+			// Room was made above, unless `MaxFinalized` is zero.
+			if rounds.try_push(round).is_err() {
+				Pulses::<T>::remove(round);
+			}
+		});
+	}
+
+	/// Discard every pending pulse of `submitter` and move the optimistic round back.
+	///
+	/// Returns the number of discarded pulses.
+	fn roll_back(submitter: &T::AccountId) -> u32 {
+		let mut discarded = Vec::new();
+		PendingRounds::<T>::mutate(|rounds| {
+			rounds.retain(|round| match Pending::<T>::get(round) {
+				Some(pending) if &pending.submitter == submitter => {
+					Pending::<T>::remove(round);
+					Pulses::<T>::remove(round);
+					discarded.push(*round);
+					false
+				},
+				_ => true,
+			});
+		});
+
+		let optimistic_round = PendingRounds::<T>::get()
+			.last()
+			.copied()
+			.unwrap_or_else(FinalizedRound::<T>::get);
+		OptimisticRound::<T>::put(optimistic_round);
+
+		let count = discarded.len() as u32;
+		Self::deposit_event(Event::RolledBack { rounds: discarded, optimistic_round });
+		count
+	}
+}
+
+/// Randomness from the latest accepted pulse, which may still be challenged and rolled back.
+pub struct OptimisticRandomness<T>(PhantomData<T>);
+
+impl<T: Config> Randomness<T::Hash, BlockNumberFor<T>> for OptimisticRandomness<T> {
+	fn random(subject: &[u8]) -> (T::Hash, BlockNumberFor<T>) {
+		Pallet::<T>::random(Finality::Optimistic, subject)
+	}
+}
+
+/// Randomness from the latest pulse whose challenge window has passed.
+pub struct FinalizedRandomness<T>(PhantomData<T>);
+
+impl<T: Config> Randomness<T::Hash, BlockNumberFor<T>> for FinalizedRandomness<T> {
+	fn random(subject: &[u8]) -> (T::Hash, BlockNumberFor<T>) {
+		Pallet::<T>::random(Finality::Finalized, subject)
+	}
+}
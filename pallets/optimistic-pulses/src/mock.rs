@@ -0,0 +1,144 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate as pallet_optimistic_pulses;
+use crate::{Pulse, RoundNumber, VerifyPulse};
+use frame_support::{derive_impl, parameter_types};
+use sp_runtime::{BuildStorage, DispatchError, Perbill};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		Balances: pallet_balances,
+		OptimisticPulses: pallet_optimistic_pulses,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+	type AccountData = pallet_balances::AccountData<u64>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+	type AccountStore = System;
+	type RuntimeHoldReason = RuntimeHoldReason;
+}
+
+pub const ALICE: u64 = 1;
+pub const BOB: u64 = 2;
+pub const CHARLIE: u64 = 3;
+
+pub const BOND: u64 = 100;
+pub const CHALLENGE_PERIOD: u64 = 10;
+
+/// Signature marker the [`MockVerifier`] accepts.
+pub const VALID: u8 = 1;
+/// Signature marker the [`MockVerifier`] rejects.
+pub const INVALID: u8 = 0;
+
+/// Accepts pulses whose signature starts with [`VALID`], from a beacon at [`CurrentRound`].
+pub struct MockVerifier;
+
+impl VerifyPulse for MockVerifier {
+	fn verify(pulse: &Pulse) -> Result<bool, DispatchError> {
+		Ok(pulse.signature.first() == Some(&VALID))
+	}
+
+	fn current_round() -> Result<RoundNumber, DispatchError> {
+		Ok(CurrentRound::get())
+	}
+}
+
+/// A pulse for `round` whose signature is filled with `marker`.
+pub fn pulse(round: RoundNumber, marker: u8) -> Pulse {
+	let signature = vec![marker; 48];
+	Pulse {
+		round,
+		randomness: sp_io::hashing::sha2_256(&signature),
+		signature: signature.try_into().unwrap(),
+		previous_signature: Default::default(),
+	}
+}
+
+#[cfg(feature = "runtime-benchmarks")]
+pub struct MockBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl crate::BenchmarkHelper for MockBenchmarkHelper {
+	fn prepare_verifier() {
+		CurrentRound::set(MaxPending::get().into());
+	}
+
+	fn invalid_pulse(round: RoundNumber) -> Pulse {
+		pulse(round, INVALID)
+	}
+}
+
+parameter_types! {
+	pub static CurrentRound: RoundNumber = 10;
+	pub const SubmitterBond: u64 = BOND;
+	pub const ChallengePeriod: u64 = CHALLENGE_PERIOD;
+	pub const MaxPending: u32 = 8;
+	pub const MaxPendingPerSubmitter: u32 = 4;
+	pub const MaxRoundsAhead: RoundNumber = 2;
+	pub const MaxFinalized: u32 = 2;
+	pub const ChallengerReward: Perbill = Perbill::from_percent(40);
+}
+
+impl pallet_optimistic_pulses::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type Currency = Balances;
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type Verifier = MockVerifier;
+	type SubmitterBond = SubmitterBond;
+	type ChallengePeriod = ChallengePeriod;
+	type MaxPending = MaxPending;
+	type MaxPendingPerSubmitter = MaxPendingPerSubmitter;
+	type MaxRoundsAhead = MaxRoundsAhead;
+	type MaxFinalized = MaxFinalized;
+	type ChallengerReward = ChallengerReward;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBenchmarkHelper;
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(ALICE, 1_000), (BOB, 1_000), (CHARLIE, 1_000)],
+	}
+	.assimilate_storage(&mut storage)
+	.unwrap();
+
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Advance to block `n`, running the pallet's `on_initialize` on the way.
+pub fn run_to_block(n: u64) {
+	use frame_support::traits::Hooks;
+
+	while System::block_number() < n {
+		let next = System::block_number() + 1;
+		System::set_block_number(next);
+		OptimisticPulses::on_initialize(next);
+	}
+}
@@ -0,0 +1,252 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::{mock::*, *};
+use frame_support::{
+	assert_noop, assert_ok,
+	traits::fungible::{Inspect, InspectHold},
+};
+
+fn submit(who: u64, round: RoundNumber, marker: u8) {
+	assert_ok!(OptimisticPulses::submit_pulse(RuntimeOrigin::signed(who), pulse(round, marker)));
+}
+
+#[test]
+fn bond_holds_funds() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(OptimisticPulses::bond(RuntimeOrigin::signed(ALICE)));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SubmitterBond.into(), &ALICE), BOND);
+		assert_noop!(
+			OptimisticPulses::bond(RuntimeOrigin::signed(ALICE)),
+			Error::<Test>::AlreadyBonded
+		);
+	});
+}
+
+#[test]
+fn submit_requires_bond_and_consistent_pulse() {
+	new_test_ext().execute_with(|| {
+		assert_noop!(
+			OptimisticPulses::submit_pulse(RuntimeOrigin::signed(ALICE), pulse(1, VALID)),
+			Error::<Test>::NotBonded
+		);
+
+		assert_ok!(OptimisticPulses::bond(RuntimeOrigin::signed(ALICE)));
+		let mut tampered = pulse(1, VALID);
+		tampered.randomness = [0; 32];
+		assert_noop!(
+			OptimisticPulses::submit_pulse(RuntimeOrigin::signed(ALICE), tampered),
+			Error::<Test>::RandomnessMismatch
+		);
+
+		submit(ALICE, 5, VALID);
+		assert_eq!(OptimisticRound::<Test>::get(), 5);
+		assert_noop!(
+			OptimisticPulses::submit_pulse(RuntimeOrigin::signed(ALICE), pulse(5, VALID)),
+			Error::<Test>::StaleRound
+		);
+	});
+}
+
+#[test]
+fn pending_queue_is_bounded() {
+	new_test_ext().execute_with(|| {
+		for who in [ALICE, BOB, CHARLIE] {
+			assert_ok!(OptimisticPulses::bond(RuntimeOrigin::signed(who)));
+		}
+		let per_submitter = MaxPendingPerSubmitter::get() as u64;
+		for round in 1..=per_submitter {
+			submit(ALICE, round, VALID);
+		}
+		assert_noop!(
+			OptimisticPulses::submit_pulse(RuntimeOrigin::signed(ALICE), pulse(9, VALID)),
+			Error::<Test>::TooManyPendingForSubmitter
+		);
+
+		for round in per_submitter + 1..=MaxPending::get() as u64 {
+			submit(BOB, round, VALID);
+		}
+		assert_noop!(
+			OptimisticPulses::submit_pulse(RuntimeOrigin::signed(CHARLIE), pulse(9, VALID)),
+			Error::<Test>::TooManyPending
+		);
+
+		// Finalized pulses no longer count.
+		run_to_block(1 + CHALLENGE_PERIOD);
+		submit(ALICE, 9, VALID);
+	});
+}
+
+#[test]
+fn rounds_ahead_of_the_beacon_are_refused() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(OptimisticPulses::bond(RuntimeOrigin::signed(ALICE)));
+		let latest = CurrentRound::get() + MaxRoundsAhead::get();
+		assert_noop!(
+			OptimisticPulses::submit_pulse(RuntimeOrigin::signed(ALICE), pulse(latest + 1, VALID)),
+			Error::<Test>::FutureRound
+		);
+		submit(ALICE, latest, VALID);
+
+		CurrentRound::set(latest);
+		submit(ALICE, latest + 1, VALID);
+	});
+}
+
+#[test]
+fn pulses_finalize_after_challenge_period() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(OptimisticPulses::bond(RuntimeOrigin::signed(ALICE)));
+		submit(ALICE, 1, VALID);
+		run_to_block(3);
+		submit(ALICE, 2, VALID);
+
+		run_to_block(CHALLENGE_PERIOD);
+		assert_eq!(FinalizedRound::<Test>::get(), 0);
+		assert_eq!(OptimisticPulses::latest_pulse(Finality::Finalized), None);
+
+		run_to_block(1 + CHALLENGE_PERIOD);
+		assert_eq!(FinalizedRound::<Test>::get(), 1);
+		assert!(Pending::<Test>::get(1).is_none());
+		System::assert_has_event(Event::PulseFinalized { round: 1 }.into());
+
+		run_to_block(3 + CHALLENGE_PERIOD);
+		assert_eq!(FinalizedRound::<Test>::get(), 2);
+		assert!(PendingRounds::<Test>::get().is_empty());
+		assert_eq!(Submitters::<Test>::get(ALICE).unwrap().pending, 0);
+		assert_noop!(
+			OptimisticPulses::challenge(RuntimeOrigin::signed(BOB), 1),
+			Error::<Test>::NotChallengeable
+		);
+	});
+}
+
+#[test]
+fn challenging_a_valid_pulse_fails() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(OptimisticPulses::bond(RuntimeOrigin::signed(ALICE)));
+		submit(ALICE, 1, VALID);
+
+		assert_noop!(
+			OptimisticPulses::challenge(RuntimeOrigin::signed(BOB), 1),
+			Error::<Test>::PulseIsValid
+		);
+	});
+}
+
+#[test]
+fn fraud_proof_slashes_and_rolls_back() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(OptimisticPulses::bond(RuntimeOrigin::signed(ALICE)));
+		assert_ok!(OptimisticPulses::bond(RuntimeOrigin::signed(CHARLIE)));
+		submit(CHARLIE, 1, VALID);
+		run_to_block(1 + CHALLENGE_PERIOD);
+		submit(ALICE, 2, VALID);
+		submit(CHARLIE, 3, VALID);
+		submit(ALICE, 4, INVALID);
+		submit(ALICE, 5, VALID);
+
+		assert_ok!(OptimisticPulses::challenge(RuntimeOrigin::signed(BOB), 4));
+
+		assert!(Submitters::<Test>::get(ALICE).is_none());
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SubmitterBond.into(), &ALICE), 0);
+		assert_eq!(Balances::free_balance(BOB), 1_000 + 40);
+		assert_eq!(Balances::total_issuance(), 3_000 - 60);
+		for round in [2, 4, 5] {
+			assert!(Pulses::<Test>::get(round).is_none());
+			assert!(Pending::<Test>::get(round).is_none());
+		}
+		assert_eq!(PendingRounds::<Test>::get().into_inner(), vec![3]);
+		assert_eq!(OptimisticRound::<Test>::get(), 3);
+		assert_eq!(FinalizedRound::<Test>::get(), 1);
+		System::assert_has_event(
+			Event::FraudProven {
+				round: 4,
+				submitter: ALICE,
+				challenger: BOB,
+				rewarded: 40,
+				burned: 60,
+			}
+			.into(),
+		);
+		System::assert_last_event(
+			Event::RolledBack { rounds: vec![2, 4, 5], optimistic_round: 3 }.into(),
+		);
+	});
+}
+
+#[test]
+fn submitters_cannot_challenge_themselves() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(OptimisticPulses::bond(RuntimeOrigin::signed(ALICE)));
+		submit(ALICE, 1, INVALID);
+
+		assert_noop!(
+			OptimisticPulses::challenge(RuntimeOrigin::signed(ALICE), 1),
+			Error::<Test>::SelfChallenge
+		);
+	});
+}
+
+#[test]
+fn finalized_pulses_are_pruned() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(OptimisticPulses::bond(RuntimeOrigin::signed(ALICE)));
+		for round in 1..=3 {
+			submit(ALICE, round, VALID);
+		}
+
+		run_to_block(1 + CHALLENGE_PERIOD);
+		assert_eq!(FinalizedRound::<Test>::get(), 3);
+		assert_eq!(FinalizedRounds::<Test>::get().into_inner(), vec![2, 3]);
+		assert!(Pulses::<Test>::get(1).is_none());
+		assert_eq!(OptimisticPulses::latest_pulse(Finality::Finalized), Some(pulse(3, VALID)));
+	});
+}
+
+#[test]
+fn unbond_waits_for_pending_pulses() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(OptimisticPulses::bond(RuntimeOrigin::signed(ALICE)));
+		submit(ALICE, 1, VALID);
+		assert_noop!(
+			OptimisticPulses::unbond(RuntimeOrigin::signed(ALICE)),
+			Error::<Test>::PulsesPending
+		);
+
+		run_to_block(1 + CHALLENGE_PERIOD);
+		assert_ok!(OptimisticPulses::unbond(RuntimeOrigin::signed(ALICE)));
+		assert_eq!(Balances::balance_on_hold(&HoldReason::SubmitterBond.into(), &ALICE), 0);
+		assert!(Submitters::<Test>::get(ALICE).is_none());
+	});
+}
+
+#[test]
+fn randomness_respects_finality() {
+	new_test_ext().execute_with(|| {
+		assert_ok!(OptimisticPulses::bond(RuntimeOrigin::signed(ALICE)));
+		submit(ALICE, 1, VALID);
+
+		let (optimistic, _) = OptimisticRandomness::<Test>::random(b"subject");
+		let (finalized, _) = FinalizedRandomness::<Test>::random(b"subject");
+		assert_ne!(optimistic, Default::default());
+		assert_eq!(finalized, Default::default());
+
+		run_to_block(1 + CHALLENGE_PERIOD);
+		assert_eq!(FinalizedRandomness::<Test>::random(b"subject").0, optimistic);
+		assert_ne!(OptimisticRandomness::<Test>::random(b"other").0, optimistic);
+	});
+}
@@ -0,0 +1,137 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Weights for `pallet_optimistic_pulses`.
+//!
+//! These are conservative estimates. Regenerate them with the `benchmark pallet` sub-command
+//! on reference hardware before relying on them.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_optimistic_pulses`.
+pub trait WeightInfo {
+	fn bond() -> Weight;
+	fn unbond() -> Weight;
+	fn submit_pulse() -> Weight;
+	fn challenge(p: u32, ) -> Weight;
+	fn on_initialize(n: u32, ) -> Weight;
+}
+
+/// Weights for `pallet_optimistic_pulses` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `OptimisticPulses::Submitters` (r:1 w:1)
+	/// Storage: `Balances::Holds` (r:1 w:1)
+	fn bond() -> Weight {
+		Weight::from_parts(60_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: `OptimisticPulses::Submitters` (r:1 w:1)
+	/// Storage: `Balances::Holds` (r:1 w:1)
+	fn unbond() -> Weight {
+		Weight::from_parts(55_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+	/// Storage: `OptimisticPulses::Submitters` (r:1 w:1)
+	/// Storage: `OptimisticPulses::OptimisticRound` (r:1 w:1)
+	/// Storage: `OptimisticPulses::PendingRounds` (r:1 w:1)
+	/// Storage: `OptimisticPulses::Pending` (r:0 w:1)
+	/// Storage: `OptimisticPulses::Pulses` (r:0 w:1)
+	/// Storage: `Beacons::BeaconConfigs` (r:1 w:0)
+	/// Storage: `Timestamp::Now` (r:1 w:0)
+	fn submit_pulse() -> Weight {
+		Weight::from_parts(35_000_000, 6_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(5_u64))
+	}
+	/// Storage: `OptimisticPulses::Pending` (r:p w:p)
+	/// Storage: `OptimisticPulses::Pulses` (r:1 w:p)
+	/// Storage: `OptimisticPulses::Submitters` (r:1 w:1)
+	/// Storage: `OptimisticPulses::PendingRounds` (r:2 w:1)
+	/// Storage: `OptimisticPulses::FinalizedRound` (r:1 w:0)
+	/// Storage: `OptimisticPulses::OptimisticRound` (r:0 w:1)
+	/// Storage: `Balances::Holds` (r:2 w:2)
+	/// Storage: `System::Account` (r:1 w:1)
+	/// Storage: `Balances::TotalIssuance` (r:1 w:1)
+	/// The range of component `p` is `[1, 256]`.
+	fn challenge(p: u32, ) -> Weight {
+		// Dominated by the on-chain pairing check.
+		Weight::from_parts(1_200_000_000, 8_000)
+			.saturating_add(Weight::from_parts(4_000_000, 0).saturating_mul(p.into()))
+			.saturating_add(T::DbWeight::get().reads(9_u64))
+			.saturating_add(T::DbWeight::get().reads((1_u64).saturating_mul(p.into())))
+			.saturating_add(T::DbWeight::get().writes(7_u64))
+			.saturating_add(T::DbWeight::get().writes((2_u64).saturating_mul(p.into())))
+	}
+	/// Storage: `OptimisticPulses::PendingRounds` (r:1 w:1)
+	/// Storage: `OptimisticPulses::Pending` (r:n w:n)
+	/// Storage: `OptimisticPulses::Submitters` (r:n w:n)
+	/// Storage: `OptimisticPulses::FinalizedRound` (r:0 w:n)
+	/// Storage: `OptimisticPulses::FinalizedRounds` (r:n w:n)
+	/// Storage: `OptimisticPulses::Pulses` (r:0 w:n)
+	/// The range of component `n` is `[0, 256]`.
+	fn on_initialize(n: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 3_000)
+			.saturating_add(Weight::from_parts(12_000_000, 2_500).saturating_mul(n.into()))
+			.saturating_add(T::DbWeight::get().reads(1_u64))
+			.saturating_add(T::DbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes((5_u64).saturating_mul(n.into())))
+			.saturating_add(T::DbWeight::get().writes((n > 0) as u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn bond() -> Weight {
+		Weight::from_parts(60_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	fn unbond() -> Weight {
+		Weight::from_parts(55_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+	fn submit_pulse() -> Weight {
+		Weight::from_parts(35_000_000, 6_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(5_u64))
+	}
+	fn challenge(p: u32, ) -> Weight {
+		Weight::from_parts(1_200_000_000, 8_000)
+			.saturating_add(Weight::from_parts(4_000_000, 0).saturating_mul(p.into()))
+			.saturating_add(RocksDbWeight::get().reads(9_u64))
+			.saturating_add(RocksDbWeight::get().reads((1_u64).saturating_mul(p.into())))
+			.saturating_add(RocksDbWeight::get().writes(7_u64))
+			.saturating_add(RocksDbWeight::get().writes((2_u64).saturating_mul(p.into())))
+	}
+	fn on_initialize(n: u32, ) -> Weight {
+		Weight::from_parts(5_000_000, 3_000)
+			.saturating_add(Weight::from_parts(12_000_000, 2_500).saturating_mul(n.into()))
+			.saturating_add(RocksDbWeight::get().reads(1_u64))
+			.saturating_add(RocksDbWeight::get().reads((3_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes((5_u64).saturating_mul(n.into())))
+			.saturating_add(RocksDbWeight::get().writes((n > 0) as u64))
+	}
+}
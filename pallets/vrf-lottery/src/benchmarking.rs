@@ -0,0 +1,116 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Benchmarks for `pallet_vrf_lottery`.
+
+use super::*;
+use alloc::vec;
+use frame_benchmarking::v2::*;
+use frame_support::traits::fungible::{Inspect, Unbalanced};
+use frame_system::RawOrigin;
+use sp_core::{crypto::VrfSecret, Pair};
+
+/// The round tickets are drawn on.
+const ROUND: RoundNumber = 1_000;
+
+/// Make `pulse` of `round` the latest one.
+fn store_pulse<T: Config>(round: RoundNumber) -> Pulse {
+	let pulse = Pulse {
+		round,
+		randomness: [7; 32],
+		signature: Default::default(),
+		previous_signature: Default::default(),
+	};
+	T::BenchmarkHelper::store_pulse(pulse.clone());
+	pulse
+}
+
+/// Give `who` enough to hold the deposits of a few tickets.
+fn fund<T: Config>(who: &T::AccountId) {
+	let amount = T::TicketDeposit::get().saturating_mul(10u32.into());
+	let _ = T::Currency::increase_balance(
+		who,
+		T::Currency::minimum_balance().saturating_add(amount),
+		Precision::Exact,
+	);
+}
+
+#[benchmarks]
+mod benchmarks {
+	use super::*;
+
+	#[benchmark]
+	fn draw() {
+		let seed = [1; 32];
+		let who = T::BenchmarkHelper::account(seed);
+		fund::<T>(&who);
+		let pulse = store_pulse::<T>(ROUND);
+		// Every other round holding tickets comes after the drawn one, so that it is inserted
+		// first.
+		let rounds = (ROUND + 1..).map(|round| (round, 1));
+		let rounds = rounds.take(T::MaxRounds::get().saturating_sub(1) as usize).collect();
+		Rounds::<T>::put(BoundedVec::truncate_from(rounds));
+		let context = ContextOf::<T>::truncate_from(vec![7; T::MaxContextLen::get() as usize]);
+		let sign_data = lottery::ticket_sign_data(pulse.round, &pulse.randomness, &context);
+		let signature = sr25519::Pair::from_seed(&seed).vrf_sign(&sign_data);
+
+		#[extrinsic_call]
+		_(RawOrigin::Signed(who.clone()), pulse.round, context.clone(), signature);
+
+		assert!(Tickets::<T>::contains_key((pulse.round, &context, &who)));
+		assert_eq!(Rounds::<T>::get().first(), Some(&(ROUND, 1)));
+	}
+
+	#[benchmark]
+	fn on_initialize() {
+		// The oldest round expired and has no tickets left, the others haven't.
+		let latest = ROUND + T::TicketLifetime::get();
+		store_pulse::<T>(latest);
+		let rounds = core::iter::once(ROUND).chain(latest..).map(|round| (round, 1));
+		let rounds = rounds.take(T::MaxRounds::get() as usize).collect();
+		Rounds::<T>::put(BoundedVec::truncate_from(rounds));
+		let now = frame_system::Pallet::<T>::block_number();
+
+		#[block]
+		{
+			Pallet::<T>::on_initialize(now);
+		}
+
+		assert!(Rounds::<T>::get().first().map_or(true, |(round, _)| *round != ROUND));
+	}
+
+	#[benchmark]
+	fn prune_tickets(t: Linear<0, { T::MaxPrunedTickets::get() }>) -> Result<(), BenchmarkError> {
+		let context = ContextOf::<T>::truncate_from(vec![7; T::MaxContextLen::get() as usize]);
+		let deposit = T::TicketDeposit::get();
+		for i in 0..t {
+			let who: T::AccountId = account("ticket", i, 0);
+			fund::<T>(&who);
+			T::Currency::hold(&HoldReason::TicketDeposit.into(), &who, deposit)?;
+			Tickets::<T>::insert((ROUND, &context, &who), ([i as u8; 32], deposit));
+		}
+
+		#[block]
+		{
+			Pallet::<T>::prune(ROUND, t);
+		}
+
+		assert_eq!(Tickets::<T>::iter_prefix((ROUND,)).count(), 0);
+		Ok(())
+	}
+
+	impl_benchmark_test_suite!(Pallet, crate::mock::new_test_ext(), crate::mock::Test);
+}
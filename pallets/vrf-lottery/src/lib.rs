@@ -0,0 +1,326 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! # VRF Lottery Pallet
+//!
+//! Per-account verifiable draws, e.g. "is this account selected this round?".
+//!
+//! An account [`draw`](Pallet::draw)s a ticket for a round of the beacon of [`Config::Pulses`]
+//! and a context chosen by the application: an sr25519 VRF proof with its own key over the round,
+//! the randomness of that round and the context. The pallet verifies it against the key
+//! [`Config::AccountKey`] finds for the account and records the output, which applications read
+//! through [`Pallet::ticket`] or [`Pallet::is_selected`].
+//!
+//! See [`ideal_nw_primitives::lottery`] for what the draw guarantees, and why applications must
+//! fix the eligible accounts before the round they draw on.
+//!
+//! An account computes its output before it submits the draw, so it can decline to draw when it
+//! doesn't like the output. A missing ticket must therefore count against the account, e.g. as
+//! not selected, never in its favour, and applications that need every eligible account to draw
+//! must enforce it themselves.
+//!
+//! Tickets can be drawn on a round for [`Config::TicketLifetime`] rounds after it, and are
+//! pruned afterwards, at most [`Config::MaxPrunedTickets`] per block. Any account can draw for
+//! any context, so rather than capping the tickets of a round, which would let one account crowd
+//! out the others, every ticket holds [`Config::TicketDeposit`] from the account until pruned.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub use pallet::*;
+
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+pub mod weights;
+
+use frame_support::{
+	pallet_prelude::*,
+	traits::{
+		fungible::{self, MutateHold},
+		tokens::Precision,
+	},
+};
+use ideal_nw_primitives::lottery;
+pub use ideal_nw_primitives::{
+	beacon::{Pulse, RoundNumber},
+	lottery::TicketOutput,
+};
+use sp_core::sr25519::{self, vrf::VrfSignature};
+use sp_runtime::{
+	traits::{Convert, Saturating},
+	Perbill,
+};
+
+pub use weights::WeightInfo;
+
+/// The stored pulses of the beacon tickets draw on.
+pub trait PulseProvider {
+	/// The latest stored pulse.
+	fn latest_pulse() -> Option<Pulse>;
+
+	/// The stored pulse of `round`.
+	fn pulse(round: RoundNumber) -> Option<Pulse>;
+}
+
+/// Helper used by the benchmarks to set up the accounts and the pulses.
+#[cfg(feature = "runtime-benchmarks")]
+pub trait BenchmarkHelper<AccountId> {
+	/// The account whose [`Config::AccountKey`] is the sr25519 key of `seed`.
+	fn account(seed: [u8; 32]) -> AccountId;
+
+	/// Make `pulse` the latest pulse of [`Config::Pulses`].
+	fn store_pulse(pulse: Pulse);
+}
+
+/// The context of a draw.
+pub type ContextOf<T> = BoundedVec<u8, <T as Config>::MaxContextLen>;
+
+pub type BalanceOf<T> =
+	<<T as Config>::Currency as fungible::Inspect<<T as frame_system::Config>::AccountId>>::Balance;
+
+#[frame_support::pallet]
+pub mod pallet {
+	use super::*;
+	use frame_system::pallet_prelude::*;
+
+	#[pallet::pallet]
+	pub struct Pallet<T>(_);
+
+	#[pallet::config]
+	pub trait Config: frame_system::Config {
+		/// The overarching event type.
+		type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
+
+		/// The sr25519 key of an account, which its tickets are verified against.
+		///
+		/// Accounts whose key isn't sr25519 can't produce a valid ticket.
+		type AccountKey: Convert<Self::AccountId, sr25519::Public>;
+
+		/// The pulses tickets draw on.
+		type Pulses: PulseProvider;
+
+		/// The currency ticket deposits are held in.
+		type Currency: MutateHold<Self::AccountId, Reason = Self::RuntimeHoldReason>;
+
+		/// The overarching hold reason.
+		type RuntimeHoldReason: From<HoldReason>;
+
+		/// The deposit held from an account for every ticket it draws, until the ticket is pruned.
+		#[pallet::constant]
+		type TicketDeposit: Get<BalanceOf<Self>>;
+
+		/// Number of rounds after a round during which tickets can be drawn on it. Its tickets
+		/// are pruned afterwards.
+		#[pallet::constant]
+		type TicketLifetime: Get<RoundNumber>;
+
+		/// Maximum length of the context of a draw.
+		#[pallet::constant]
+		type MaxContextLen: Get<u32>;
+
+		/// Maximum number of expired tickets pruned per block.
+		#[pallet::constant]
+		type MaxPrunedTickets: Get<u32>;
+
+		/// Maximum number of rounds holding tickets at once, expired ones waiting to be pruned
+		/// included.
+		#[pallet::constant]
+		type MaxRounds: Get<u32>;
+
+		/// Weight information for extrinsics and hooks in this pallet.
+		type WeightInfo: WeightInfo;
+
+		/// Helper to set up the benchmarks.
+		#[cfg(feature = "runtime-benchmarks")]
+		type BenchmarkHelper: BenchmarkHelper<Self::AccountId>;
+	}
+
+	/// A reason for the pallet placing a hold on funds.
+	#[pallet::composite_enum]
+	pub enum HoldReason {
+		/// Funds held for a ticket until it is pruned.
+		#[codec(index = 0)]
+		TicketDeposit,
+	}
+
+	/// The output of the ticket drawn by an account on a round for a context, and the deposit held
+	/// for it.
+	#[pallet::storage]
+	pub type Tickets<T: Config> = StorageNMap<
+		_,
+		(
+			NMapKey<Twox64Concat, RoundNumber>,
+			NMapKey<Blake2_128Concat, ContextOf<T>>,
+			NMapKey<Blake2_128Concat, T::AccountId>,
+		),
+		(TicketOutput, BalanceOf<T>),
+		OptionQuery,
+	>;
+
+	/// The rounds holding tickets, in ascending order, with their number of tickets.
+	#[pallet::storage]
+	pub type Rounds<T: Config> =
+		StorageValue<_, BoundedVec<(RoundNumber, u32), T::MaxRounds>, ValueQuery>;
+
+	#[pallet::event]
+	#[pallet::generate_deposit(pub(super) fn deposit_event)]
+	pub enum Event<T: Config> {
+		/// `who` drew a ticket on `round` for `context`.
+		TicketDrawn {
+			who: T::AccountId,
+			round: RoundNumber,
+			context: ContextOf<T>,
+			output: TicketOutput,
+		},
+		/// `tickets` expired tickets of `round` were removed and their deposits released.
+		TicketsPruned { round: RoundNumber, tickets: u32 },
+	}
+
+	#[pallet::error]
+	pub enum Error<T> {
+		/// There is no pulse of the round.
+		UnknownRound,
+		/// The round is older than [`Config::TicketLifetime`] rounds.
+		RoundExpired,
+		/// The account already drew a ticket on the round for the context.
+		AlreadyDrawn,
+		/// The VRF signature is not the account's over the round and context.
+		InvalidVrf,
+		/// [`Config::MaxRounds`] rounds already hold tickets.
+		TooManyRounds,
+	}
+
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn on_initialize(_n: BlockNumberFor<T>) -> Weight {
+			let mut weight = T::WeightInfo::on_initialize();
+			let mut rounds = Rounds::<T>::get();
+			if rounds.is_empty() {
+				return weight;
+			}
+			let Some(latest) = T::Pulses::latest_pulse() else { return weight };
+
+			let mut budget = T::MaxPrunedTickets::get();
+			let mut changed = false;
+			while let Some(&(round, tickets)) = rounds.first() {
+				if budget == 0 || !Self::is_expired(round, latest.round) {
+					break;
+				}
+				let pruned = Self::prune(round, budget);
+				weight.saturating_accrue(T::WeightInfo::prune_tickets(pruned));
+				Self::deposit_event(Event::TicketsPruned { round, tickets: pruned });
+				changed = true;
+				// Out of budget, the rest goes in the next blocks.
+				if pruned == budget && pruned < tickets {
+					rounds[0].1 = tickets - pruned;
+					break;
+				}
+				budget -= pruned;
+				rounds.remove(0);
+			}
+			if changed {
+				Rounds::<T>::put(rounds);
+			}
+			weight
+		}
+	}
+
+	#[pallet::call]
+	impl<T: Config> Pallet<T> {
+		/// Draw a ticket on `round` for `context`: `signature` is the caller's VRF over
+		/// [`lottery::ticket_sign_data`] of the round, its randomness and the context.
+		///
+		/// Holds [`Config::TicketDeposit`] until the ticket is pruned.
+		#[pallet::call_index(0)]
+		#[pallet::weight(T::WeightInfo::draw())]
+		pub fn draw(
+			origin: OriginFor<T>,
+			round: RoundNumber,
+			context: ContextOf<T>,
+			signature: VrfSignature,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			let latest = T::Pulses::latest_pulse().ok_or(Error::<T>::UnknownRound)?;
+			ensure!(!Self::is_expired(round, latest.round), Error::<T>::RoundExpired);
+			let pulse = T::Pulses::pulse(round).ok_or(Error::<T>::UnknownRound)?;
+			ensure!(!Tickets::<T>::contains_key((round, &context, &who)), Error::<T>::AlreadyDrawn);
+
+			let public = T::AccountKey::convert(who.clone());
+			let output =
+				lottery::verify_ticket(&public, round, &pulse.randomness, &context, &signature)
+					.ok_or(Error::<T>::InvalidVrf)?;
+
+			Rounds::<T>::try_mutate(|rounds| {
+				match rounds.binary_search_by_key(&round, |(round, _)| *round) {
+					Ok(index) => rounds[index].1.saturating_inc(),
+					Err(index) => {
+						rounds
+							.try_insert(index, (round, 1))
+							.map_err(|_| Error::<T>::TooManyRounds)?;
+					},
+				}
+				Ok::<_, Error<T>>(())
+			})?;
+			let deposit = T::TicketDeposit::get();
+			T::Currency::hold(&HoldReason::TicketDeposit.into(), &who, deposit)?;
+			Tickets::<T>::insert((round, &context, &who), (output, deposit));
+
+			Self::deposit_event(Event::TicketDrawn { who, round, context, output });
+			Ok(())
+		}
+	}
+}
+
+impl<T: Config> Pallet<T> {
+	/// Whether tickets can no longer be drawn on `round` once `latest` is the latest round.
+	fn is_expired(round: RoundNumber, latest: RoundNumber) -> bool {
+		round.saturating_add(T::TicketLifetime::get()) <= latest
+	}
+
+	/// Remove up to `limit` tickets of `round`, releasing their deposits, returning how many.
+	fn prune(round: RoundNumber, limit: u32) -> u32 {
+		let mut pruned = 0;
+		for ((_, who), (_, deposit)) in Tickets::<T>::drain_prefix((round,)).take(limit as usize) {
+			let reason = HoldReason::TicketDeposit.into();
+			let _ = T::Currency::release(&reason, &who, deposit, Precision::BestEffort);
+			pruned += 1;
+		}
+		pruned
+	}
+
+	/// The output of the ticket `who` drew on `round` for `context`.
+	pub fn ticket(who: &T::AccountId, round: RoundNumber, context: &[u8]) -> Option<TicketOutput> {
+		let context = ContextOf::<T>::try_from(context.to_vec()).ok()?;
+		Tickets::<T>::get((round, context, who)).map(|(output, _)| output)
+	}
+
+	/// Whether `who` drew a ticket on `round` for `context` that is selected with probability
+	/// `chance`.
+	pub fn is_selected(
+		who: &T::AccountId,
+		round: RoundNumber,
+		context: &[u8],
+		chance: Perbill,
+	) -> bool {
+		Self::ticket(who, round, context)
+			.is_some_and(|output| lottery::is_selected(&output, chance))
+	}
+}
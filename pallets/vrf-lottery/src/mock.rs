@@ -0,0 +1,159 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate as pallet_vrf_lottery;
+use frame_support::{derive_impl, parameter_types};
+use ideal_nw_primitives::{
+	beacon::{Pulse, RoundNumber},
+	lottery::ticket_sign_data,
+};
+use sp_core::{
+	crypto::VrfSecret,
+	sr25519::{self, vrf::VrfSignature},
+	Pair,
+};
+use sp_runtime::{traits::Convert, BuildStorage};
+
+type Block = frame_system::mocking::MockBlock<Test>;
+
+frame_support::construct_runtime!(
+	pub enum Test {
+		System: frame_system,
+		Balances: pallet_balances,
+		Lottery: pallet_vrf_lottery,
+	}
+);
+
+#[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
+impl frame_system::Config for Test {
+	type Block = Block;
+	type AccountData = pallet_balances::AccountData<u64>;
+}
+
+#[derive_impl(pallet_balances::config_preludes::TestDefaultConfig)]
+impl pallet_balances::Config for Test {
+	type AccountStore = System;
+	type RuntimeHoldReason = RuntimeHoldReason;
+}
+
+pub const TICKET_LIFETIME: RoundNumber = 10;
+pub const DEPOSIT: u64 = 10;
+pub const MAX_PRUNED_TICKETS: u32 = 2;
+pub const MAX_ROUNDS: u32 = 3;
+
+parameter_types! {
+	pub const TicketLifetime: RoundNumber = TICKET_LIFETIME;
+	pub const MaxContextLen: u32 = 16;
+	pub const TicketDeposit: u64 = DEPOSIT;
+	pub const MaxPrunedTickets: u32 = MAX_PRUNED_TICKETS;
+	pub const MaxRounds: u32 = MAX_ROUNDS;
+	pub static StoredPulses: Vec<Pulse> = Vec::new();
+}
+
+/// The key of account `n` is the public key of [`account`]`(n)`.
+pub struct MockAccountKey;
+
+impl Convert<u64, sr25519::Public> for MockAccountKey {
+	fn convert(who: u64) -> sr25519::Public {
+		account(who).public()
+	}
+}
+
+/// Serves the pulses stored with [`store_pulse`].
+pub struct MockPulses;
+
+impl crate::PulseProvider for MockPulses {
+	fn latest_pulse() -> Option<Pulse> {
+		StoredPulses::get().last().cloned()
+	}
+
+	fn pulse(round: RoundNumber) -> Option<Pulse> {
+		StoredPulses::get().into_iter().find(|pulse| pulse.round == round)
+	}
+}
+
+impl pallet_vrf_lottery::Config for Test {
+	type RuntimeEvent = RuntimeEvent;
+	type AccountKey = MockAccountKey;
+	type Pulses = MockPulses;
+	type Currency = Balances;
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type TicketDeposit = TicketDeposit;
+	type TicketLifetime = TicketLifetime;
+	type MaxContextLen = MaxContextLen;
+	type MaxPrunedTickets = MaxPrunedTickets;
+	type MaxRounds = MaxRounds;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = MockBenchmarkHelper;
+}
+
+/// Account `n` has the key seeded with `n`, see [`account`].
+#[cfg(feature = "runtime-benchmarks")]
+pub struct MockBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_vrf_lottery::BenchmarkHelper<u64> for MockBenchmarkHelper {
+	fn account(seed: [u8; 32]) -> u64 {
+		let who = u64::from(seed[0]);
+		assert_eq!(account(who).public(), sr25519::Pair::from_seed(&seed).public());
+		who
+	}
+
+	fn store_pulse(pulse: Pulse) {
+		StoredPulses::mutate(|pulses| pulses.push(pulse));
+	}
+}
+
+/// The key of account `who`.
+pub fn account(who: u64) -> sr25519::Pair {
+	sr25519::Pair::from_seed(&[who as u8; 32])
+}
+
+/// Store a pulse for `round` with randomness filled with `round`, as the latest one.
+pub fn store_pulse(round: RoundNumber) -> Pulse {
+	let pulse = Pulse {
+		round,
+		randomness: [round as u8; 32],
+		signature: Default::default(),
+		previous_signature: Default::default(),
+	};
+	StoredPulses::mutate(|pulses| pulses.push(pulse.clone()));
+	pulse
+}
+
+/// The ticket of account `who` on `pulse` for `context`.
+pub fn ticket(who: u64, pulse: &Pulse, context: &[u8]) -> VrfSignature {
+	account(who).vrf_sign(&ticket_sign_data(pulse.round, &pulse.randomness, context))
+}
+
+pub fn new_test_ext() -> sp_io::TestExternalities {
+	let mut storage = frame_system::GenesisConfig::<Test>::default().build_storage().unwrap();
+	pallet_balances::GenesisConfig::<Test> { balances: (1..=9).map(|who| (who, 100)).collect() }
+		.assimilate_storage(&mut storage)
+		.unwrap();
+	let mut ext = sp_io::TestExternalities::new(storage);
+	ext.execute_with(|| System::set_block_number(1));
+	ext
+}
+
+/// Finish the current block and start the next one.
+pub fn next_block() {
+	use frame_support::traits::Hooks;
+
+	System::set_block_number(System::block_number() + 1);
+	Lottery::on_initialize(System::block_number());
+}
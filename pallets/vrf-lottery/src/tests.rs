@@ -0,0 +1,183 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+use crate::{mock::*, *};
+use frame_support::{assert_noop, assert_ok, traits::fungible::InspectHold};
+use ideal_nw_primitives::lottery;
+use sp_core::Pair;
+use sp_runtime::{DispatchError, DispatchResult, Perbill, TokenError};
+
+fn context(context: &[u8]) -> ContextOf<Test> {
+	context.to_vec().try_into().unwrap()
+}
+
+fn draw(who: u64, pulse: &Pulse, name: &[u8]) -> DispatchResult {
+	Lottery::draw(RuntimeOrigin::signed(who), pulse.round, context(name), ticket(who, pulse, name))
+}
+
+fn output(who: u64, pulse: &Pulse, name: &[u8]) -> TicketOutput {
+	let signature = ticket(who, pulse, name);
+	lottery::verify_ticket(&account(who).public(), pulse.round, &pulse.randomness, name, &signature)
+		.unwrap()
+}
+
+#[test]
+fn tickets_are_verified_and_recorded() {
+	new_test_ext().execute_with(|| {
+		let pulse = store_pulse(7);
+		assert_ok!(draw(1, &pulse, b"raffle"));
+		let output = output(1, &pulse, b"raffle");
+		System::assert_last_event(
+			Event::TicketDrawn { who: 1, round: 7, context: context(b"raffle"), output }.into(),
+		);
+		assert_eq!(Lottery::ticket(&1, 7, b"raffle"), Some(output));
+		assert_eq!(Lottery::ticket(&1, 7, b"other"), None);
+		assert_eq!(Lottery::ticket(&2, 7, b"raffle"), None);
+
+		// Every account and context draws its own ticket.
+		assert_ok!(draw(2, &pulse, b"raffle"));
+		assert_ok!(draw(1, &pulse, b"other"));
+		assert_ne!(Lottery::ticket(&2, 7, b"raffle"), Some(output));
+		assert_ne!(Lottery::ticket(&1, 7, b"other"), Some(output));
+
+		assert!(Lottery::is_selected(&1, 7, b"raffle", Perbill::one()));
+		assert!(!Lottery::is_selected(&1, 7, b"raffle", Perbill::zero()));
+		assert!(!Lottery::is_selected(&3, 7, b"raffle", Perbill::one()));
+	});
+}
+
+#[test]
+fn invalid_tickets_are_rejected() {
+	new_test_ext().execute_with(|| {
+		let (old, pulse) = (store_pulse(6), store_pulse(7));
+		let draw_as = |who: u64, signer: u64, round: RoundNumber, name: &[u8]| {
+			Lottery::draw(
+				RuntimeOrigin::signed(who),
+				round,
+				context(name),
+				ticket(signer, &pulse, name),
+			)
+		};
+		// Another account's ticket.
+		assert_noop!(draw_as(1, 2, 7, b"raffle"), Error::<Test>::InvalidVrf);
+		// A ticket for another round or context.
+		assert_noop!(draw_as(1, 1, old.round, b"raffle"), Error::<Test>::InvalidVrf);
+		assert_noop!(
+			Lottery::draw(
+				RuntimeOrigin::signed(1),
+				7,
+				context(b"other"),
+				ticket(1, &pulse, b"raffle")
+			),
+			Error::<Test>::InvalidVrf
+		);
+		// A round without pulse.
+		assert_noop!(draw_as(1, 1, 8, b"raffle"), Error::<Test>::UnknownRound);
+		assert_noop!(draw_as(1, 1, 5, b"raffle"), Error::<Test>::UnknownRound);
+
+		assert_ok!(draw(1, &pulse, b"raffle"));
+		assert_noop!(draw(1, &pulse, b"raffle"), Error::<Test>::AlreadyDrawn);
+	});
+}
+
+#[test]
+fn draws_are_refused_without_pulse() {
+	new_test_ext().execute_with(|| {
+		let pulse = Pulse {
+			round: 7,
+			randomness: [7; 32],
+			signature: Default::default(),
+			previous_signature: Default::default(),
+		};
+		assert_noop!(draw(1, &pulse, b"raffle"), Error::<Test>::UnknownRound);
+	});
+}
+
+#[test]
+fn tickets_expire_and_are_pruned() {
+	new_test_ext().execute_with(|| {
+		let (first, second) = (store_pulse(1), store_pulse(2));
+		assert_ok!(draw(1, &first, b"raffle"));
+		assert_ok!(draw(2, &first, b"raffle"));
+		assert_ok!(draw(1, &second, b"raffle"));
+
+		// Round 1 expires once round 1 + TICKET_LIFETIME is out.
+		store_pulse(TICKET_LIFETIME);
+		next_block();
+		assert_eq!(Rounds::<Test>::get().into_inner(), vec![(1, 2), (2, 1)]);
+
+		store_pulse(1 + TICKET_LIFETIME);
+		assert_noop!(draw(3, &first, b"raffle"), Error::<Test>::RoundExpired);
+		assert_ok!(draw(3, &second, b"raffle"));
+		next_block();
+		System::assert_last_event(Event::TicketsPruned { round: 1, tickets: 2 }.into());
+		assert_eq!(Rounds::<Test>::get().into_inner(), vec![(2, 2)]);
+		assert_eq!(Lottery::ticket(&1, 1, b"raffle"), None);
+		assert_eq!(Lottery::ticket(&2, 1, b"raffle"), None);
+		assert!(Lottery::ticket(&1, 2, b"raffle").is_some());
+	});
+}
+
+#[test]
+fn tickets_hold_a_deposit_until_pruned() {
+	new_test_ext().execute_with(|| {
+		let (first, second) = (store_pulse(1), store_pulse(2));
+		for who in 1..=3 {
+			assert_ok!(draw(who, &first, b"raffle"));
+		}
+		assert_ok!(draw(1, &second, b"raffle"));
+		assert_eq!(Balances::total_balance_on_hold(&1), 2 * DEPOSIT);
+		assert_eq!(Balances::free_balance(1), 100 - 2 * DEPOSIT);
+		assert_noop!(
+			draw(10, &first, b"raffle"),
+			DispatchError::Token(TokenError::FundsUnavailable)
+		);
+
+		// At most MAX_PRUNED_TICKETS a block.
+		store_pulse(2 + TICKET_LIFETIME);
+		next_block();
+		System::assert_last_event(Event::TicketsPruned { round: 1, tickets: 2 }.into());
+		assert_eq!(Rounds::<Test>::get().into_inner(), vec![(1, 1), (2, 1)]);
+		next_block();
+		System::assert_has_event(Event::TicketsPruned { round: 1, tickets: 1 }.into());
+		System::assert_last_event(Event::TicketsPruned { round: 2, tickets: 1 }.into());
+		assert!(Rounds::<Test>::get().is_empty());
+		for who in 1..=3 {
+			assert_eq!(Balances::total_balance_on_hold(&who), 0);
+			assert_eq!(Balances::free_balance(who), 100);
+		}
+	});
+}
+
+#[test]
+fn rounds_are_bounded_but_not_their_tickets() {
+	new_test_ext().execute_with(|| {
+		let pulses: Vec<_> = (1..=MAX_ROUNDS as RoundNumber + 1).map(store_pulse).collect();
+		// No account can crowd the others out of a round.
+		for who in 1..=9 {
+			assert_ok!(draw(who, &pulses[0], b"raffle"));
+		}
+		assert_eq!(Rounds::<Test>::get().into_inner(), vec![(1, 9)]);
+
+		for pulse in &pulses[1..MAX_ROUNDS as usize] {
+			assert_ok!(draw(1, pulse, b"raffle"));
+		}
+		assert_noop!(
+			draw(1, &pulses[MAX_ROUNDS as usize], b"raffle"),
+			Error::<Test>::TooManyRounds
+		);
+	});
+}
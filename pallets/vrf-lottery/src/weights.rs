@@ -0,0 +1,93 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Weights for `pallet_vrf_lottery`.
+//!
+//! The figures below have not been measured yet. Replace this file with the output of the
+//! benchmarks in `benchmarking.rs` on reference hardware:
+//!
+//! ```sh
+//! ideal-nw-node benchmark pallet --chain dev --wasm-execution=compiled \
+//!     --pallet pallet_vrf_lottery --extrinsic "*" --steps 50 --repeat 20 \
+//!     --output pallets/vrf-lottery/src/weights.rs
+//! ```
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+#![allow(missing_docs)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use core::marker::PhantomData;
+
+/// Weight functions needed for `pallet_vrf_lottery`.
+pub trait WeightInfo {
+	fn draw() -> Weight;
+	fn on_initialize() -> Weight;
+	fn prune_tickets(t: u32, ) -> Weight;
+}
+
+/// Weights for `pallet_vrf_lottery` using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `Beacons::LatestRound` (r:1 w:0)
+	/// Storage: `Beacons::Pulses` (r:2 w:0)
+	/// Storage: `VrfLottery::Tickets` (r:1 w:1)
+	/// Storage: `VrfLottery::Rounds` (r:1 w:1)
+	/// Storage: `Balances::Holds` (r:1 w:1)
+	fn draw() -> Weight {
+		Weight::from_parts(90_000_000, 4_000)
+			.saturating_add(T::DbWeight::get().reads(6_u64))
+			.saturating_add(T::DbWeight::get().writes(3_u64))
+	}
+	/// Storage: `VrfLottery::Rounds` (r:1 w:1)
+	/// Storage: `Beacons::LatestRound` (r:1 w:0)
+	/// Storage: `Beacons::Pulses` (r:1 w:0)
+	fn on_initialize() -> Weight {
+		Weight::from_parts(5_000_000, 2_000)
+			.saturating_add(T::DbWeight::get().reads(3_u64))
+			.saturating_add(T::DbWeight::get().writes(1_u64))
+	}
+	/// Storage: `VrfLottery::Tickets` (r:t w:t)
+	/// Storage: `Balances::Holds` (r:t w:t)
+	/// The range of component `t` is `[0, 512]`.
+	fn prune_tickets(t: u32, ) -> Weight {
+		Weight::from_parts(3_000_000, 0)
+			.saturating_add(Weight::from_parts(20_000_000, 3_600).saturating_mul(t.into()))
+			.saturating_add(T::DbWeight::get().reads((2_u64).saturating_mul(t.into())))
+			.saturating_add(T::DbWeight::get().writes((2_u64).saturating_mul(t.into())))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn draw() -> Weight {
+		Weight::from_parts(90_000_000, 4_000)
+			.saturating_add(RocksDbWeight::get().reads(6_u64))
+			.saturating_add(RocksDbWeight::get().writes(3_u64))
+	}
+	fn on_initialize() -> Weight {
+		Weight::from_parts(5_000_000, 2_000)
+			.saturating_add(RocksDbWeight::get().reads(3_u64))
+			.saturating_add(RocksDbWeight::get().writes(1_u64))
+	}
+	fn prune_tickets(t: u32, ) -> Weight {
+		Weight::from_parts(3_000_000, 0)
+			.saturating_add(Weight::from_parts(20_000_000, 3_600).saturating_mul(t.into()))
+			.saturating_add(RocksDbWeight::get().reads((2_u64).saturating_mul(t.into())))
+			.saturating_add(RocksDbWeight::get().writes((2_u64).saturating_mul(t.into())))
+	}
+}
@@ -0,0 +1,117 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Runtime APIs exposed by the Ideal Network runtime.
+
+use crate::{
+	beacon::{BeaconId, BeaconInfo, Pulse, Randomness, RoundNumber},
+	dkg::{Committee, Dealing, DkgRound, G1Bytes, ParticipantIndex},
+	lottery::TicketOutput,
+	mixing::MixedSeed,
+	ordering::OrderingKey,
+	relay::RelayRandomnessInfo,
+	stats::RandomnessHealth,
+	time::BeaconTime,
+};
+use alloc::vec::Vec;
+use codec::Codec;
+
+sp_api::decl_runtime_apis! {
+	/// Access to the beacons followed by the runtime and their pulses.
+	pub trait BeaconsApi {
+		/// All registered beacons.
+		fn beacons() -> Vec<(BeaconId, BeaconInfo)>;
+
+		/// The configuration `beacon` currently signs with, ignoring pending transitions.
+		fn beacon(beacon: BeaconId) -> Option<BeaconInfo>;
+
+		/// The configuration `beacon` signs `round` with, taking its pending transition into
+		/// account.
+		fn beacon_at(beacon: BeaconId, round: RoundNumber) -> Option<BeaconInfo>;
+
+		/// The latest pulse stored for `beacon`.
+		fn latest_pulse(beacon: BeaconId) -> Option<Pulse>;
+
+		/// The pulse stored for `round` of `beacon`.
+		fn pulse(beacon: BeaconId, round: RoundNumber) -> Option<Pulse>;
+
+		/// The statistical checks of the randomness of `beacon`.
+		fn randomness_health(beacon: BeaconId) -> Option<RandomnessHealth>;
+
+		/// The storage key of `round` of `beacon`, to prove the pulse stored under it.
+		fn pulse_key(beacon: BeaconId, round: RoundNumber) -> Vec<u8>;
+	}
+
+	/// The relay chain's BABE randomness, see [`crate::relay`] for why it is weaker than a
+	/// beacon's.
+	pub trait RelayRandomnessApi {
+		/// The randomness of the relay parent, unless the relay state proof is unavailable.
+		fn relay_randomness() -> Option<RelayRandomnessInfo>;
+	}
+
+	/// Randomness mixed from a beacon and the block authors' VRF outputs, see [`crate::mixing`].
+	pub trait MixedRandomnessApi<BlockNumber: Codec> {
+		/// The round and randomness the author of the next block evaluates its VRF over.
+		fn vrf_input() -> Option<(RoundNumber, Randomness)>;
+
+		/// The latest mixed randomness.
+		fn mixed_randomness() -> Option<MixedSeed<BlockNumber>>;
+	}
+
+	/// The collators' threshold beacon and its DKG, see [`crate::dkg`].
+	pub trait DkgApi<AuthorityId: Codec, BlockNumber: Codec> {
+		/// The DKG in progress, if any.
+		fn pending_dkg() -> Option<DkgRound<AuthorityId, BlockNumber>>;
+
+		/// The encryption keys announced in the DKG in progress, by participant.
+		fn encryption_keys() -> Vec<(ParticipantIndex, G1Bytes)>;
+
+		/// The dealings published in the DKG of `session`, by dealer.
+		fn dealings(session: u32) -> Vec<(ParticipantIndex, Dealing)>;
+
+		/// The committee holding the key.
+		fn committee() -> Option<Committee<AuthorityId>>;
+
+		/// The committee the key was reshared to, taking over once it signed a round.
+		fn next_committee() -> Option<Committee<AuthorityId>>;
+
+		/// The beacon the committee signs, once its first DKG succeeded.
+		fn committee_beacon() -> Option<BeaconId>;
+
+		/// The partial signatures of `round` by the committee of `session`, by member.
+		fn partial_signatures(session: u32, round: RoundNumber) -> Vec<(ParticipantIndex, G1Bytes)>;
+	}
+
+	/// Per-account draws anchored to beacon randomness, see [`crate::lottery`].
+	pub trait LotteryApi<AccountId: Codec> {
+		/// The output of the ticket `who` drew on `round` for `context`.
+		fn ticket(who: AccountId, round: RoundNumber, context: Vec<u8>) -> Option<TicketOutput>;
+	}
+
+	/// Wall-clock time according to a beacon, see [`crate::time`].
+	pub trait BeaconTimeApi {
+		/// The current time according to the latest verified round, unless the beacon is stalled
+		/// or has no pulse yet.
+		fn beacon_time() -> Option<BeaconTime>;
+	}
+
+	/// Fair ordering of the transactions of the next block, see [`crate::ordering`].
+	pub trait FairOrderingApi {
+		/// The ordering keys of `extrinsics` in the next block, unless there is no pulse to seed
+		/// them with yet. Extrinsics that can't be decoded have no key.
+		fn ordering_keys(extrinsics: Vec<Vec<u8>>) -> Option<Vec<Option<OrderingKey>>>;
+	}
+}
@@ -0,0 +1,146 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Drand beacons and their pulses.
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use hex_literal::hex;
+use scale_info::TypeInfo;
+use sp_runtime::{traits::ConstU32, BoundedVec, RuntimeDebug};
+
+/// Identifies a beacon tracked by the network.
+pub type BeaconId = u32;
+
+/// A beacon round.
+pub type RoundNumber = u64;
+
+/// The hash drand uses to identify a beacon chain.
+pub type ChainHash = [u8; 32];
+
+/// Randomness of a single round.
+pub type Randomness = [u8; 32];
+
+/// Maximum length of an encoded beacon public key.
+pub const MAX_PUBLIC_KEY_LEN: u32 = 128;
+
+/// Maximum length of an encoded beacon signature.
+pub const MAX_SIGNATURE_LEN: u32 = 96;
+
+/// An encoded beacon public key.
+pub type PublicKey = BoundedVec<u8, ConstU32<MAX_PUBLIC_KEY_LEN>>;
+
+/// An encoded beacon signature.
+pub type Signature = BoundedVec<u8, ConstU32<MAX_SIGNATURE_LEN>>;
+
+/// The way a beacon signs its rounds.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum SignatureScheme {
+	/// BLS12-381 with signatures on G1, each round signed independently (e.g. quicknet).
+	#[codec(index = 0)]
+	BlsUnchainedG1Rfc9380,
+	/// BLS12-381 with signatures on G2, each round signing the previous signature (e.g. the
+	/// League of Entropy default beacon).
+	#[codec(index = 1)]
+	PedersenBlsChained,
+	/// BN254 with signatures on G1, each round signed independently (e.g. evmnet).
+	#[codec(index = 2)]
+	BlsBn254UnchainedG1,
+}
+
+impl SignatureScheme {
+	/// The identifier drand reports for the scheme in a beacon's `/info`.
+	pub fn scheme_id(&self) -> &'static str {
+		match self {
+			Self::BlsUnchainedG1Rfc9380 => "bls-unchained-g1-rfc9380",
+			Self::PedersenBlsChained => "pedersen-bls-chained",
+			Self::BlsBn254UnchainedG1 => "bls-bn254-unchained-on-g1",
+		}
+	}
+
+	/// The scheme drand reports as `id` in a beacon's `/info`.
+	pub fn from_scheme_id(id: &str) -> Option<Self> {
+		[Self::BlsUnchainedG1Rfc9380, Self::PedersenBlsChained, Self::BlsBn254UnchainedG1]
+			.into_iter()
+			.find(|scheme| scheme.scheme_id() == id)
+	}
+
+	/// Whether the signature of a round covers the signature of the previous round.
+	pub fn is_chained(&self) -> bool {
+		matches!(self, Self::PedersenBlsChained)
+	}
+}
+
+/// Everything needed to follow and verify a beacon.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct BeaconInfo {
+	/// The drand chain hash.
+	pub chain_hash: ChainHash,
+	/// The group public key.
+	pub public_key: PublicKey,
+	/// Seconds between two rounds.
+	pub period: u32,
+	/// Unix time, in seconds, of the first round.
+	pub genesis_time: u64,
+	/// How rounds are signed.
+	pub scheme: SignatureScheme,
+}
+
+impl BeaconInfo {
+	/// drand's quicknet beacon.
+	pub fn quicknet() -> Self {
+		Self {
+			chain_hash: hex!("52db9ba70e0cc0f6eaf7803dd07447a1f5477735fd3f661792ba94600c84e971"),
+			public_key: hex!(
+				"83cf0f2896adee7eb8b5f01fcad3912212c437e0073e911fb90022d3e760183c8c4b450b6a0a6c3a"
+				"c6a5776a2d1064510d1fec758c921cc22b0e17e63aaf4bcb5ed66304de9cf809bd274ca73bab4af5"
+				"a6e9c76a4bc09e76eae8991ef5ece45a"
+			)
+			.to_vec()
+			.try_into()
+			.expect("96 bytes fit; qed"),
+			period: 3,
+			genesis_time: 1_692_803_367,
+			scheme: SignatureScheme::BlsUnchainedG1Rfc9380,
+		}
+	}
+
+	/// The latest round emitted at unix time `now`, in seconds, or 0 before genesis.
+	pub fn round_at(&self, now: u64) -> RoundNumber {
+		match now.checked_sub(self.genesis_time) {
+			Some(elapsed) => elapsed / u64::from(self.period.max(1)) + 1,
+			None => 0,
+		}
+	}
+
+	/// Unix time, in seconds, at which `round` is emitted.
+	pub fn round_time(&self, round: RoundNumber) -> u64 {
+		self.genesis_time
+			.saturating_add(round.saturating_sub(1).saturating_mul(u64::from(self.period)))
+	}
+}
+
+/// A signed round of a beacon.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Pulse {
+	/// The round.
+	pub round: RoundNumber,
+	/// The randomness of the round, i.e. the SHA-256 hash of its signature.
+	pub randomness: Randomness,
+	/// The beacon signature of the round.
+	pub signature: Signature,
+	/// The signature of the previous round. Only set for chained schemes.
+	pub previous_signature: Signature,
+}
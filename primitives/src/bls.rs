@@ -0,0 +1,190 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! BLS signature checks for the drand schemes.
+//!
+//! The BLS12-381 schemes are implemented here, the BN254 one in [`crate::bn254`]. The checks only
+//! use `arkworks` arithmetic and need no host functions, so the same code runs in the runtime, on
+//! relay chain validators and on the node.
+
+use crate::{
+	beacon::{BeaconInfo, Pulse, SignatureScheme},
+	bn254,
+};
+use ark_bls12_381::{g1, g2, Bls12_381, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{
+	hashing::{curve_maps::wb::WBMap, map_to_curve_hasher::MapToCurveBasedHasher, HashToCurve},
+	pairing::Pairing,
+	AffineRepr,
+};
+use ark_ff::{field_hashers::DefaultFieldHasher, One};
+use ark_serialize::CanonicalDeserialize;
+use sha2::{Digest, Sha256};
+
+/// Domain separation tag of `bls-unchained-g1-rfc9380` signatures.
+pub const DST_G1: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// Domain separation tag of `pedersen-bls-chained` signatures.
+pub const DST_G2: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+/// The message an unchained beacon signs for `round`.
+pub fn unchained_message(round: u64) -> [u8; 32] {
+	Sha256::digest(round.to_be_bytes()).into()
+}
+
+/// The message a chained beacon signs for `round`.
+pub fn chained_message(previous_signature: &[u8], round: u64) -> [u8; 32] {
+	let mut hasher = Sha256::new();
+	hasher.update(previous_signature);
+	hasher.update(round.to_be_bytes());
+	hasher.finalize().into()
+}
+
+/// Hash `message` to G1 with the `bls-unchained-g1-rfc9380` domain separation tag.
+pub fn hash_to_g1(message: &[u8]) -> Option<G1Affine> {
+	MapToCurveBasedHasher::<G1Projective, DefaultFieldHasher<Sha256, 128>, WBMap<g1::Config>>::new(
+		DST_G1,
+	)
+	.and_then(|hasher| hasher.hash(message))
+	.ok()
+}
+
+/// Verify a `bls-unchained-g1-rfc9380` signature: signature on G1, public key on G2.
+pub fn verify_unchained_g1(public_key: &[u8], round: u64, signature: &[u8]) -> bool {
+	let (Ok(public_key), Ok(signature)) =
+		(G2Affine::deserialize_compressed(public_key), G1Affine::deserialize_compressed(signature))
+	else {
+		return false;
+	};
+	if public_key.is_zero() || signature.is_zero() {
+		return false;
+	}
+	let Some(message) = hash_to_g1(&unchained_message(round)) else { return false };
+
+	Bls12_381::multi_pairing([signature, -message], [G2Affine::generator(), public_key])
+		.0
+		.is_one()
+}
+
+/// Verify a `pedersen-bls-chained` signature: signature on G2, public key on G1.
+pub fn verify_chained_g2(
+	public_key: &[u8],
+	previous_signature: &[u8],
+	round: u64,
+	signature: &[u8],
+) -> bool {
+	let (Ok(public_key), Ok(signature)) =
+		(G1Affine::deserialize_compressed(public_key), G2Affine::deserialize_compressed(signature))
+	else {
+		return false;
+	};
+	if public_key.is_zero() || signature.is_zero() {
+		return false;
+	}
+	let Ok(hasher) = MapToCurveBasedHasher::<
+		G2Projective,
+		DefaultFieldHasher<Sha256, 128>,
+		WBMap<g2::Config>,
+	>::new(DST_G2) else {
+		return false;
+	};
+	let Ok(message) = hasher.hash(&chained_message(previous_signature, round)) else {
+		return false;
+	};
+
+	Bls12_381::multi_pairing([public_key, -G1Affine::generator()], [message, signature])
+		.0
+		.is_one()
+}
+
+/// Verify the signature of `pulse` against `beacon`, using the beacon's scheme.
+///
+/// Only the signature is checked, not whether the randomness matches it.
+pub fn verify_pulse(beacon: &BeaconInfo, pulse: &Pulse) -> bool {
+	match beacon.scheme {
+		SignatureScheme::BlsUnchainedG1Rfc9380 =>
+			verify_unchained_g1(&beacon.public_key, pulse.round, &pulse.signature),
+		SignatureScheme::PedersenBlsChained => verify_chained_g2(
+			&beacon.public_key,
+			&pulse.previous_signature,
+			pulse.round,
+			&pulse.signature,
+		),
+		SignatureScheme::BlsBn254UnchainedG1 =>
+			bn254::verify_unchained_g1(&beacon.public_key, pulse.round, &pulse.signature),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::fixtures::*;
+
+	#[test]
+	fn unchained_pulse_verifies() {
+		let (beacon, pulse) = (unchained_beacon(), unchained_pulse());
+		assert!(verify_pulse(&beacon, &pulse));
+		assert_eq!(Sha256::digest(&pulse.signature)[..], pulse.randomness[..]);
+	}
+
+	#[test]
+	fn unchained_pulse_is_bound_to_its_round() {
+		let mut pulse = unchained_pulse();
+		pulse.round += 1;
+		assert!(!verify_pulse(&unchained_beacon(), &pulse));
+	}
+
+	#[test]
+	fn chained_pulse_verifies() {
+		let (beacon, pulse) = (chained_beacon(), chained_pulse());
+		assert!(verify_pulse(&beacon, &pulse));
+		assert_eq!(Sha256::digest(&pulse.signature)[..], pulse.randomness[..]);
+	}
+
+	#[test]
+	fn chained_pulse_is_bound_to_the_previous_signature() {
+		let mut pulse = chained_pulse();
+		pulse.previous_signature = Default::default();
+		assert!(!verify_pulse(&chained_beacon(), &pulse));
+	}
+
+	#[test]
+	fn evmnet_pulse_verifies() {
+		let (beacon, pulse) = (evmnet_beacon(), evmnet_pulse());
+		assert!(verify_pulse(&beacon, &pulse));
+		assert_eq!(Sha256::digest(&pulse.signature)[..], pulse.randomness[..]);
+	}
+
+	#[test]
+	fn scheme_mismatch_is_rejected() {
+		let mut beacon = chained_beacon();
+		beacon.scheme = SignatureScheme::BlsUnchainedG1Rfc9380;
+		assert!(!verify_pulse(&beacon, &chained_pulse()));
+		assert!(!verify_pulse(&beacon, &unchained_pulse()));
+		beacon.scheme = SignatureScheme::BlsBn254UnchainedG1;
+		assert!(!verify_pulse(&beacon, &evmnet_pulse()));
+	}
+
+	#[test]
+	fn malformed_points_are_rejected() {
+		let beacon = unchained_beacon();
+		let mut pulse = unchained_pulse();
+		pulse.signature = pulse.signature[..47].to_vec().try_into().unwrap();
+		assert!(!verify_pulse(&beacon, &pulse));
+		pulse.signature = [0xff; 48].to_vec().try_into().unwrap();
+		assert!(!verify_pulse(&beacon, &pulse));
+	}
+}
@@ -0,0 +1,133 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! A beacon with a well-known key, for development chains.
+//!
+//! The secret key is derived from a public seed, so anyone can sign its rounds. Never follow it
+//! outside of a development chain.
+
+use crate::{
+	beacon::{BeaconInfo, ChainHash, Pulse, RoundNumber, SignatureScheme},
+	bls::{unchained_message, DST_G1},
+};
+use alloc::vec::Vec;
+use ark_bls12_381::{g1, Fr, G1Projective, G2Projective};
+use ark_ec::{
+	hashing::{curve_maps::wb::WBMap, map_to_curve_hasher::MapToCurveBasedHasher, HashToCurve},
+	CurveGroup, Group,
+};
+use ark_ff::{field_hashers::DefaultFieldHasher, PrimeField, Zero};
+use ark_serialize::CanonicalSerialize;
+use sha2::{Digest, Sha256};
+
+/// Seed of the key of the [`DevBeacon::default`] beacon.
+pub const DEV_SEED: &[u8] = b"//IdealNetworkDevBeacon";
+
+/// Seconds between two rounds of the default development beacon.
+pub const DEV_PERIOD: u32 = 3;
+
+/// Unix time of the first round of the default development beacon, 2024-10-01 00:00:00 UTC.
+pub const DEV_GENESIS_TIME: u64 = 1_727_740_800;
+
+/// A beacon signing its rounds like quicknet, with a key derived from a seed.
+pub struct DevBeacon {
+	secret: Fr,
+	info: BeaconInfo,
+}
+
+impl DevBeacon {
+	/// The beacon whose secret key is derived from `seed`.
+	pub fn from_seed(seed: &[u8], genesis_time: u64, period: u32) -> Self {
+		let mut secret = Fr::from_be_bytes_mod_order(&Sha256::digest(seed));
+		if secret.is_zero() {
+			secret = Fr::from(1u64);
+		}
+		let mut public_key = Vec::new();
+		(G2Projective::generator() * secret)
+			.into_affine()
+			.serialize_compressed(&mut public_key)
+			.expect("writing to a vector cannot fail; qed");
+
+		let scheme = SignatureScheme::BlsUnchainedG1Rfc9380;
+		let chain_hash: ChainHash = Sha256::new()
+			.chain_update(&public_key)
+			.chain_update(period.to_be_bytes())
+			.chain_update(genesis_time.to_be_bytes())
+			.chain_update(scheme.scheme_id())
+			.finalize()
+			.into();
+		let info = BeaconInfo {
+			chain_hash,
+			public_key: public_key.try_into().expect("G2 points are 96 bytes; qed"),
+			period,
+			genesis_time,
+			scheme,
+		};
+		Self { secret, info }
+	}
+
+	/// The beacon, as followed by the runtime.
+	pub fn info(&self) -> &BeaconInfo {
+		&self.info
+	}
+
+	/// The signed pulse of `round`.
+	pub fn sign(&self, round: RoundNumber) -> Pulse {
+		let message = MapToCurveBasedHasher::<
+			G1Projective,
+			DefaultFieldHasher<Sha256, 128>,
+			WBMap<g1::Config>,
+		>::new(DST_G1)
+		.and_then(|hasher| hasher.hash(&unchained_message(round)))
+		.expect("the DST is valid and hashing to G1 cannot fail; qed");
+
+		let mut signature = Vec::new();
+		(message * self.secret)
+			.into_affine()
+			.serialize_compressed(&mut signature)
+			.expect("writing to a vector cannot fail; qed");
+		Pulse {
+			round,
+			randomness: Sha256::digest(&signature).into(),
+			signature: signature.try_into().expect("G1 points are 48 bytes; qed"),
+			previous_signature: Default::default(),
+		}
+	}
+}
+
+impl Default for DevBeacon {
+	fn default() -> Self {
+		Self::from_seed(DEV_SEED, DEV_GENESIS_TIME, DEV_PERIOD)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bls::verify_pulse;
+
+	#[test]
+	fn dev_pulses_verify() {
+		let beacon = DevBeacon::default();
+		for round in [1, 2, 1_000_000] {
+			assert!(verify_pulse(beacon.info(), &beacon.sign(round)));
+		}
+
+		let other = DevBeacon::from_seed(b"//Other", DEV_GENESIS_TIME, DEV_PERIOD);
+		assert_ne!(other.info().chain_hash, beacon.info().chain_hash);
+		assert!(!verify_pulse(beacon.info(), &other.sign(1)));
+	}
+}
@@ -0,0 +1,174 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Pulses committed to block headers.
+//!
+//! For every beacon that stored a pulse in a block, the runtime deposits a
+//! [`DigestItem::Consensus`] under [`IDN_ENGINE_ID`] holding a [`BeaconDigest`]. The pulse carries
+//! its signature, so consumers that only follow headers, such as light clients and bridges, can
+//! check it against the beacon's public key without a state proof.
+//!
+//! Blocks that stored no pulse of a beacon carry no digest for it, so a header alone need not
+//! hold the latest pulse. To find it, consumers search back from the latest header to the
+//! closest one that commits to the beacon, see [`latest_verified_randomness`]. How far that goes
+//! is unbounded while the beacon is stalled: the search should be capped, and a pulse too old
+//! for the consumer refused.
+
+use crate::{
+	beacon::{BeaconId, BeaconInfo, Pulse, Randomness, RoundNumber},
+	bls,
+};
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sha2::{Digest, Sha256};
+use sp_runtime::{
+	traits::{Hash, Header},
+	ConsensusEngineId, DigestItem, RuntimeDebug,
+};
+
+/// Engine id of the digest items holding pulses.
+pub const IDN_ENGINE_ID: ConsensusEngineId = *b"IDNB";
+
+/// A pulse stored in a block, as committed to its header.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct BeaconDigest {
+	/// The beacon that signed the pulse.
+	pub beacon_id: BeaconId,
+	/// The pulse.
+	pub pulse: Pulse,
+}
+
+impl BeaconDigest {
+	/// The digest item to deposit.
+	pub fn to_digest_item(&self) -> DigestItem {
+		DigestItem::Consensus(IDN_ENGINE_ID, self.encode())
+	}
+
+	/// The digest held by `item`, if it is one.
+	pub fn from_digest_item(item: &DigestItem) -> Option<Self> {
+		item.consensus_try_to(&IDN_ENGINE_ID)
+	}
+
+	/// Whether the pulse is signed by `beacon` and its randomness is the hash of the signature.
+	pub fn verify(&self, beacon: &BeaconInfo) -> bool {
+		Sha256::digest(&self.pulse.signature)[..] == self.pulse.randomness[..] &&
+			bls::verify_pulse(beacon, &self.pulse)
+	}
+}
+
+/// The pulses committed to `header`.
+pub fn beacon_digests<H: Header>(header: &H) -> impl Iterator<Item = BeaconDigest> + '_ {
+	header.digest().logs().iter().filter_map(BeaconDigest::from_digest_item)
+}
+
+/// The round and randomness `header` commits to for `beacon_id`, provided the pulse verifies
+/// against `beacon`.
+pub fn verified_randomness<H: Header>(
+	header: &H,
+	beacon_id: BeaconId,
+	beacon: &BeaconInfo,
+) -> Option<(RoundNumber, Randomness)> {
+	beacon_digests(header)
+		.find(|digest| digest.beacon_id == beacon_id)
+		.filter(|digest| digest.verify(beacon))
+		.map(|digest| (digest.pulse.round, digest.pulse.randomness))
+}
+
+/// The latest round and randomness committed for `beacon_id` by `headers`, given from the newest
+/// to the oldest, provided the pulse verifies against `beacon`.
+///
+/// Headers are searched until one commits to the beacon, at most `max_headers` of them.
+pub fn latest_verified_randomness<'a, H: Header + 'a>(
+	headers: impl IntoIterator<Item = &'a H>,
+	max_headers: usize,
+	beacon_id: BeaconId,
+	beacon: &BeaconInfo,
+) -> Option<(RoundNumber, Randomness)> {
+	headers
+		.into_iter()
+		.take(max_headers)
+		.find_map(|header| beacon_digests(header).find(|digest| digest.beacon_id == beacon_id))
+		.filter(|digest| digest.verify(beacon))
+		.map(|digest| (digest.pulse.round, digest.pulse.randomness))
+}
+
+/// Randomness for `subject` derived from `randomness` with the hasher `H`, as the runtime
+/// derives it with its own hasher.
+pub fn derive_randomness<H: Hash>(subject: &[u8], randomness: &Randomness) -> H::Output {
+	H::hash_of(&(subject, randomness))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::fixtures::*;
+	use sp_runtime::{generic, traits::BlakeTwo256, Digest as HeaderDigest};
+
+	type TestHeader = generic::Header<u32, BlakeTwo256>;
+
+	fn header(logs: Vec<DigestItem>) -> TestHeader {
+		TestHeader::new(
+			1,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			HeaderDigest { logs },
+		)
+	}
+
+	#[test]
+	fn randomness_is_extracted_and_verified() {
+		let (beacon, pulse) = (unchained_beacon(), unchained_pulse());
+		let digest = BeaconDigest { beacon_id: 1, pulse: pulse.clone() };
+		let header = header(vec![
+			DigestItem::Other(vec![1, 2, 3]),
+			BeaconDigest { beacon_id: 0, pulse: chained_pulse() }.to_digest_item(),
+			digest.to_digest_item(),
+		]);
+
+		assert_eq!(beacon_digests(&header).count(), 2);
+		assert_eq!(verified_randomness(&header, 1, &beacon), Some((pulse.round, pulse.randomness)));
+		// Another beacon's key doesn't verify it, and a missing beacon yields nothing.
+		assert_eq!(verified_randomness(&header, 1, &chained_beacon()), None);
+		assert_eq!(verified_randomness(&header, 2, &beacon), None);
+	}
+
+	#[test]
+	fn tampered_randomness_is_rejected() {
+		let mut pulse = unchained_pulse();
+		pulse.randomness[0] ^= 1;
+		let header = header(vec![BeaconDigest { beacon_id: 1, pulse }.to_digest_item()]);
+		assert_eq!(verified_randomness(&header, 1, &unchained_beacon()), None);
+	}
+
+	#[test]
+	fn latest_randomness_is_searched_back() {
+		let (beacon, pulse) = (unchained_beacon(), unchained_pulse());
+		let headers = [
+			header(vec![]),
+			header(vec![BeaconDigest { beacon_id: 0, pulse: chained_pulse() }.to_digest_item()]),
+			header(vec![BeaconDigest { beacon_id: 1, pulse: pulse.clone() }.to_digest_item()]),
+			header(vec![]),
+		];
+
+		let found = Some((pulse.round, pulse.randomness));
+		assert_eq!(latest_verified_randomness(&headers, 4, 1, &beacon), found);
+		assert_eq!(latest_verified_randomness(&headers, 3, 1, &beacon), found);
+		// Too few headers searched, or none committing to the beacon.
+		assert_eq!(latest_verified_randomness(&headers, 2, 1, &beacon), None);
+		assert_eq!(latest_verified_randomness(&headers, 4, 2, &beacon), None);
+	}
+}
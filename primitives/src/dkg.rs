@@ -0,0 +1,756 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Distributed key generation of the collator beacon.
+//!
+//! The collators of a session jointly generate a BLS12-381 key with a Joint-Feldman DKG, so that
+//! any `threshold` of them can sign a round but fewer learn nothing about the key. Signatures are
+//! on G1 and the group key on G2, as with `bls-unchained-g1-rfc9380`: rounds signed by the
+//! committee verify like quicknet's.
+//!
+//! Every dealer picks a secret polynomial of degree `threshold - 1`, publishes commitments to its
+//! coefficients on G2 and sends each participant its evaluation, encrypted to the participant's
+//! encryption key. A participant's share is the sum of the evaluations it received, and the group
+//! key the sum of the dealers' constant terms. When the key is reshared to the next session's
+//! collators, the dealers are the old committee, the constant term of each dealer is its own
+//! share, and shares and commitments are combined with Lagrange coefficients instead of summed:
+//! the group key stays the same.
+//!
+//! Participant `i` is evaluated at `i + 1`, so that no one is given the constant term.
+//!
+//! A participant that received a wrong share complains by revealing the key its share was
+//! encrypted with, the dealer's ephemeral key times the participant's encryption secret, with a
+//! Chaum-Pedersen proof that it is. Anyone can then decrypt that one share and check it against
+//! the dealer's commitments, see [`is_wrong_share`], while the participant's other shares stay
+//! secret.
+
+use crate::{
+	beacon::RoundNumber,
+	bls::{hash_to_g1, unchained_message},
+};
+use alloc::vec::Vec;
+pub use ark_bls12_381::Fr;
+use ark_bls12_381::{Bls12_381, G1Affine, G1Projective, G2Affine, G2Projective};
+use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, Group};
+use ark_ff::{Field, One, PrimeField, Zero};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sha2::{Digest, Sha256};
+use sp_runtime::RuntimeDebug;
+
+/// Domain separation of the values derived from a seed.
+const SEED_DOMAIN: &[u8] = b"ideal-network/dkg/seed";
+
+/// Domain separation of the chain hash of the committee beacon.
+const CHAIN_HASH_DOMAIN: &[u8] = b"ideal-network/dkg/chain";
+
+/// Domain separation of the keys encrypting shares.
+const SHARE_DOMAIN: &[u8] = b"ideal-network/dkg/share";
+
+/// Domain separation of the challenges of shared key proofs.
+const PROOF_DOMAIN: &[u8] = b"ideal-network/dkg/proof";
+
+/// A compressed G1 point: encryption keys and signatures.
+pub type G1Bytes = [u8; 48];
+
+/// A compressed G2 point: commitments and public keys.
+pub type G2Bytes = [u8; 96];
+
+/// A serialized scalar.
+pub type ScalarBytes = [u8; 32];
+
+/// Position of a participant in its DKG, or of a member in its committee.
+pub type ParticipantIndex = u32;
+
+/// A secret share, encrypted to its recipient's encryption key.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct EncryptedShare {
+	/// The ephemeral key of the encryption.
+	pub ephemeral: G1Bytes,
+	/// The share, masked with the shared key.
+	pub ciphertext: ScalarBytes,
+}
+
+/// What a dealer publishes: commitments to its polynomial and the evaluation for every
+/// participant.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct Dealing {
+	/// Commitments to the coefficients, constant term first.
+	pub commitments: Vec<G2Bytes>,
+	/// The share of every participant, by index, or `None` for those without encryption key.
+	pub shares: Vec<Option<EncryptedShare>>,
+}
+
+/// Phase of a DKG.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub enum Phase {
+	/// Participants announce their encryption keys.
+	Announce,
+	/// Dealers publish their dealings.
+	Deal,
+	/// Participants complain about wrong shares.
+	Complain,
+}
+
+/// A DKG in progress.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct DkgRound<AuthorityId, BlockNumber> {
+	/// The session whose collators take part.
+	pub session: u32,
+	/// The dealers: the members of the committee when resharing, the participants otherwise.
+	pub dealers: Vec<AuthorityId>,
+	/// The participants, who receive shares.
+	pub participants: Vec<AuthorityId>,
+	/// The threshold of the new committee.
+	pub threshold: u32,
+	/// The threshold of the committee resharing its key, `None` for a new key.
+	pub reshare_threshold: Option<u32>,
+	/// The block that started the DKG.
+	pub started_at: BlockNumber,
+	/// The current phase.
+	pub phase: Phase,
+}
+
+/// The committee holding the key.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct Committee<AuthorityId> {
+	/// The session of the DKG that formed the committee.
+	pub session: u32,
+	/// The members, by index.
+	pub members: Vec<AuthorityId>,
+	/// How many members sign a round.
+	pub threshold: u32,
+	/// The dealers whose dealings make up the shares.
+	pub qualified: Vec<ParticipantIndex>,
+	/// Whether the dealings reshared a previous committee's key.
+	pub reshared: bool,
+	/// Commitments to the shared polynomial, the group key first.
+	pub commitments: Vec<G2Bytes>,
+}
+
+impl<AuthorityId> Committee<AuthorityId> {
+	/// The group key.
+	pub fn group_key(&self) -> Option<G2Bytes> {
+		self.commitments.first().copied()
+	}
+}
+
+/// A participant's encryption key, signed with its session key.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct Announcement {
+	/// The session of the DKG.
+	pub session: u32,
+	/// The participant's index in the DKG.
+	pub participant: ParticipantIndex,
+	/// The key shares are encrypted to.
+	pub key: G1Bytes,
+}
+
+/// A dealer's dealing, signed with its session key.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct SignedDealing {
+	/// The session of the DKG.
+	pub session: u32,
+	/// The dealer's index in the DKG.
+	pub dealer: ParticipantIndex,
+	/// The dealing.
+	pub dealing: Dealing,
+}
+
+/// A proof that a revealed key is the key a share was encrypted with: a Chaum-Pedersen proof that
+/// the encryption key and the revealed key share their discrete logarithm, to the generator and
+/// to the ephemeral key.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct SharedKeyProof {
+	/// The challenge.
+	pub challenge: ScalarBytes,
+	/// The response.
+	pub response: ScalarBytes,
+}
+
+/// A participant's complaint that a dealer sent it a wrong share, signed with its session key.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct Complaint {
+	/// The session of the DKG.
+	pub session: u32,
+	/// The participant's index in the DKG.
+	pub participant: ParticipantIndex,
+	/// The accused dealer's index in the DKG.
+	pub dealer: ParticipantIndex,
+	/// The key the participant's share was encrypted with.
+	pub shared_key: G1Bytes,
+	/// The proof that it is.
+	pub proof: SharedKeyProof,
+}
+
+/// A committee member's signature of a round with its share, signed with its session key.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct PartialSignature {
+	/// The session of the committee.
+	pub session: u32,
+	/// The member's index in the committee.
+	pub member: ParticipantIndex,
+	/// The signed round.
+	pub round: RoundNumber,
+	/// The signature with the member's share.
+	pub signature: G1Bytes,
+}
+
+/// The threshold of a committee of `members`: more than two thirds.
+pub fn threshold(members: u32) -> u32 {
+	members * 2 / 3 + 1
+}
+
+/// The chain hash identifying the beacon of `group_key`.
+pub fn chain_hash(group_key: &G2Bytes) -> [u8; 32] {
+	Sha256::new()
+		.chain_update(CHAIN_HASH_DOMAIN)
+		.chain_update(group_key)
+		.finalize()
+		.into()
+}
+
+/// A scalar derived from `seed` and `nonce`.
+pub fn scalar_from_seed(seed: &[u8], nonce: &[u8]) -> Fr {
+	let mut wide = [0u8; 64];
+	for (half, chunk) in wide.chunks_mut(32).enumerate() {
+		let mut hasher = Sha256::new();
+		hasher.update(SEED_DOMAIN);
+		hasher.update((seed.len() as u32).to_le_bytes());
+		hasher.update(seed);
+		hasher.update(nonce);
+		hasher.update([half as u8]);
+		chunk.copy_from_slice(&hasher.finalize());
+	}
+	Fr::from_le_bytes_mod_order(&wide)
+}
+
+/// Serialize `scalar`.
+pub fn scalar_to_bytes(scalar: &Fr) -> ScalarBytes {
+	let mut bytes = [0u8; 32];
+	scalar.serialize_compressed(&mut bytes[..]).expect("scalars are 32 bytes; qed");
+	bytes
+}
+
+/// Deserialize a scalar, if canonical.
+pub fn scalar_from_bytes(bytes: &ScalarBytes) -> Option<Fr> {
+	Fr::deserialize_compressed(&bytes[..]).ok()
+}
+
+fn g1_to_bytes(point: G1Projective) -> G1Bytes {
+	let mut bytes = [0u8; 48];
+	point
+		.into_affine()
+		.serialize_compressed(&mut bytes[..])
+		.expect("G1 points are 48 bytes; qed");
+	bytes
+}
+
+fn g2_to_bytes(point: G2Projective) -> G2Bytes {
+	let mut bytes = [0u8; 96];
+	point
+		.into_affine()
+		.serialize_compressed(&mut bytes[..])
+		.expect("G2 points are 96 bytes; qed");
+	bytes
+}
+
+/// The x coordinate of participant `index`.
+fn x(index: ParticipantIndex) -> Fr {
+	Fr::from(index as u64 + 1)
+}
+
+/// A secret polynomial of a dealer.
+pub struct Polynomial(Vec<Fr>);
+
+impl Polynomial {
+	/// A polynomial of degree `threshold - 1` derived from `seed`, with constant term `constant`
+	/// if given.
+	pub fn from_seed(seed: &[u8], threshold: u32, constant: Option<Fr>) -> Self {
+		Self(
+			(0..threshold.max(1))
+				.map(|k| match (k, constant) {
+					(0, Some(constant)) => constant,
+					_ => scalar_from_seed(
+						seed,
+						&[b"coefficient".as_slice(), &k.to_le_bytes()].concat(),
+					),
+				})
+				.collect(),
+		)
+	}
+
+	/// The evaluation for participant `index`.
+	pub fn evaluate(&self, index: ParticipantIndex) -> Fr {
+		let x = x(index);
+		self.0.iter().rev().fold(Fr::zero(), |acc, coefficient| acc * x + coefficient)
+	}
+
+	/// Commitments to the coefficients.
+	pub fn commitments(&self) -> Vec<G2Bytes> {
+		self.0
+			.iter()
+			.map(|coefficient| g2_to_bytes(G2Projective::generator() * coefficient))
+			.collect()
+	}
+}
+
+/// Decode commitments, rejecting any that isn't a valid point.
+fn decode_commitments(commitments: &[G2Bytes]) -> Option<Vec<G2Affine>> {
+	commitments
+		.iter()
+		.map(|bytes| G2Affine::deserialize_compressed(&bytes[..]).ok())
+		.collect()
+}
+
+/// The public key of participant `index`'s share, from the commitments to a polynomial.
+fn public_share(commitments: &[G2Affine], index: ParticipantIndex) -> G2Projective {
+	let x = x(index);
+	commitments
+		.iter()
+		.rev()
+		.fold(G2Projective::zero(), |acc, commitment| acc * x + commitment)
+}
+
+/// Whether `commitments` commit to a polynomial of degree `threshold - 1` and, when resharing,
+/// whether its constant term is the share of `dealer` under the `previous` commitments.
+pub fn verify_commitments(
+	commitments: &[G2Bytes],
+	threshold: u32,
+	reshared: Option<(&[G2Bytes], ParticipantIndex)>,
+) -> bool {
+	let Some(commitments) = decode_commitments(commitments) else { return false };
+	if commitments.len() != threshold as usize || commitments[0].is_zero() {
+		return false;
+	}
+	match reshared {
+		Some((previous, dealer)) => decode_commitments(previous)
+			.is_some_and(|previous| public_share(&previous, dealer) == commitments[0]),
+		None => true,
+	}
+}
+
+/// Whether `share` is the evaluation for participant `index` of the committed polynomial.
+pub fn verify_share(commitments: &[G2Bytes], index: ParticipantIndex, share: &Fr) -> bool {
+	decode_commitments(commitments).is_some_and(|commitments| {
+		G2Projective::generator() * share == public_share(&commitments, index)
+	})
+}
+
+/// The encryption key of `secret`.
+pub fn encryption_key(secret: &Fr) -> G1Bytes {
+	g1_to_bytes(G1Projective::generator() * secret)
+}
+
+/// Whether `key` can be encrypted to.
+pub fn is_valid_encryption_key(key: &G1Bytes) -> bool {
+	G1Affine::deserialize_compressed(&key[..]).is_ok_and(|key| !key.is_zero())
+}
+
+fn share_mask(ephemeral: &G1Bytes, shared_key: &G1Bytes) -> [u8; 32] {
+	let mut hasher = Sha256::new();
+	hasher.update(SHARE_DOMAIN);
+	hasher.update(ephemeral);
+	hasher.update(shared_key);
+	hasher.finalize().into()
+}
+
+fn xor(mut data: [u8; 32], mask: [u8; 32]) -> [u8; 32] {
+	data.iter_mut().zip(mask).for_each(|(byte, mask)| *byte ^= mask);
+	data
+}
+
+/// Encrypt `share` to `recipient`, with an ephemeral key derived from `seed`.
+///
+/// The seed must be unique to the share: reusing it for different shares leaks them.
+pub fn encrypt_share(recipient: &G1Bytes, share: &Fr, seed: &[u8]) -> Option<EncryptedShare> {
+	let recipient = G1Affine::deserialize_compressed(&recipient[..]).ok()?;
+	if recipient.is_zero() {
+		return None;
+	}
+	let secret = scalar_from_seed(seed, b"ephemeral");
+	let ephemeral = encryption_key(&secret);
+	let mask = share_mask(&ephemeral, &g1_to_bytes(recipient * secret));
+	Some(EncryptedShare { ephemeral, ciphertext: xor(scalar_to_bytes(share), mask) })
+}
+
+/// The key `share` was encrypted with to the holder of the encryption `secret`.
+fn shared_key(secret: &Fr, share: &EncryptedShare) -> Option<G1Bytes> {
+	let ephemeral = G1Affine::deserialize_compressed(&share.ephemeral[..]).ok()?;
+	Some(g1_to_bytes(ephemeral * secret))
+}
+
+/// Decrypt `share` with the encryption `secret`.
+pub fn decrypt_share(secret: &Fr, share: &EncryptedShare) -> Option<Fr> {
+	decrypt_with_shared_key(share, &shared_key(secret, share)?)
+}
+
+/// Decrypt `share` with the key it was encrypted with.
+pub fn decrypt_with_shared_key(share: &EncryptedShare, shared_key: &G1Bytes) -> Option<Fr> {
+	scalar_from_bytes(&xor(share.ciphertext, share_mask(&share.ephemeral, shared_key)))
+}
+
+/// The challenge of a proof that `shared_key` is the key `ephemeral` agreed with the holder of
+/// `key`, given the prover's commitments to its nonce.
+fn proof_challenge(
+	key: &G1Bytes,
+	ephemeral: &G1Bytes,
+	shared_key: &G1Bytes,
+	commitments: [G1Projective; 2],
+) -> Fr {
+	let [to_generator, to_ephemeral] = commitments.map(g1_to_bytes);
+	let statement = [&key[..], ephemeral, shared_key, &to_generator, &to_ephemeral].concat();
+	scalar_from_seed(&statement, PROOF_DOMAIN)
+}
+
+/// Reveal the key `share` was encrypted with to the holder of the encryption `secret`, with a
+/// proof that it is, derived from `seed`.
+///
+/// The seed must be unique to the share, like the seed of [`encrypt_share`].
+pub fn reveal_shared_key(
+	secret: &Fr,
+	share: &EncryptedShare,
+	seed: &[u8],
+) -> Option<(G1Bytes, SharedKeyProof)> {
+	let ephemeral = G1Affine::deserialize_compressed(&share.ephemeral[..]).ok()?;
+	let shared_key = g1_to_bytes(ephemeral * secret);
+	let nonce = scalar_from_seed(seed, b"proof");
+	let commitments = [G1Projective::generator() * nonce, ephemeral * nonce];
+	let challenge =
+		proof_challenge(&encryption_key(secret), &share.ephemeral, &shared_key, commitments);
+	let proof = SharedKeyProof {
+		challenge: scalar_to_bytes(&challenge),
+		response: scalar_to_bytes(&(nonce + challenge * secret)),
+	};
+	Some((shared_key, proof))
+}
+
+/// Whether `proof` shows that `shared_key` is the key `ephemeral` agreed with the holder of the
+/// encryption key `key`.
+pub fn verify_shared_key(
+	key: &G1Bytes,
+	ephemeral: &G1Bytes,
+	shared_key: &G1Bytes,
+	proof: &SharedKeyProof,
+) -> bool {
+	let point = |bytes: &G1Bytes| G1Affine::deserialize_compressed(&bytes[..]).ok();
+	let (
+		Some(key_point),
+		Some(ephemeral_point),
+		Some(shared_point),
+		Some(challenge),
+		Some(response),
+	) = (
+		point(key),
+		point(ephemeral),
+		point(shared_key),
+		scalar_from_bytes(&proof.challenge),
+		scalar_from_bytes(&proof.response),
+	)
+	else {
+		return false;
+	};
+	let commitments = [
+		G1Projective::generator() * response - key_point * challenge,
+		ephemeral_point * response - shared_point * challenge,
+	];
+	proof_challenge(key, ephemeral, shared_key, commitments) == challenge
+}
+
+/// Whether a complaint holds: `shared_key` is proven to be the key `share` was encrypted with to
+/// the holder of the encryption key `key`, and `share` doesn't decrypt to the evaluation for
+/// participant `index` of the polynomial committed to by `commitments`.
+pub fn is_wrong_share(
+	commitments: &[G2Bytes],
+	index: ParticipantIndex,
+	key: &G1Bytes,
+	share: &EncryptedShare,
+	shared_key: &G1Bytes,
+	proof: &SharedKeyProof,
+) -> bool {
+	verify_shared_key(key, &share.ephemeral, shared_key, proof) &&
+		!decrypt_with_shared_key(share, shared_key)
+			.is_some_and(|share| verify_share(commitments, index, &share))
+}
+
+/// The Lagrange coefficients interpolating at zero from the participants `indices`, or `None` if
+/// an index repeats.
+pub fn lagrange_at_zero(indices: &[ParticipantIndex]) -> Option<Vec<Fr>> {
+	indices
+		.iter()
+		.map(|&i| {
+			let (numerator, denominator) = indices
+				.iter()
+				.filter(|&&j| j != i)
+				.fold((Fr::one(), Fr::one()), |(numerator, denominator), &j| {
+					(numerator * x(j), denominator * (x(j) - x(i)))
+				});
+			denominator.inverse().map(|inverse| numerator * inverse)
+		})
+		.collect::<Option<Vec<_>>>()
+		.filter(|_| {
+			let mut sorted = indices.to_vec();
+			sorted.sort_unstable();
+			sorted.windows(2).all(|pair| pair[0] != pair[1])
+		})
+}
+
+/// How the contributions of the dealers `indices` are weighted: summed for a new key,
+/// interpolated when resharing.
+fn weights(indices: &[ParticipantIndex], reshare: bool) -> Option<Vec<Fr>> {
+	if reshare {
+		lagrange_at_zero(indices)
+	} else {
+		Some(indices.iter().map(|_| Fr::one()).collect())
+	}
+}
+
+/// The commitments to the shared polynomial, from the commitments of the qualified dealers.
+pub fn aggregate_commitments(
+	dealings: &[(ParticipantIndex, &[G2Bytes])],
+	reshare: bool,
+) -> Option<Vec<G2Bytes>> {
+	let degree = dealings.first()?.1.len();
+	let decoded = dealings
+		.iter()
+		.map(|(_, commitments)| decode_commitments(commitments).filter(|c| c.len() == degree))
+		.collect::<Option<Vec<_>>>()?;
+	let indices: Vec<_> = dealings.iter().map(|(index, _)| *index).collect();
+	let weights = weights(&indices, reshare)?;
+	Some(
+		(0..degree)
+			.map(|k| {
+				g2_to_bytes(
+					decoded
+						.iter()
+						.zip(&weights)
+						.map(|(commitments, weight)| commitments[k] * weight)
+						.sum(),
+				)
+			})
+			.collect(),
+	)
+}
+
+/// A participant's share, from the evaluations it received from the qualified dealers.
+pub fn combine_shares(shares: &[(ParticipantIndex, Fr)], reshare: bool) -> Option<Fr> {
+	let indices: Vec<_> = shares.iter().map(|(index, _)| *index).collect();
+	let weights = weights(&indices, reshare)?;
+	Some(shares.iter().zip(weights).map(|((_, share), weight)| *share * weight).sum())
+}
+
+/// A member's signature of `round` with its `share`.
+pub fn partial_sign(share: &Fr, round: u64) -> Option<G1Bytes> {
+	Some(g1_to_bytes(hash_to_g1(&unchained_message(round))? * share))
+}
+
+/// Whether `signature` is member `index`'s signature of `round`.
+pub fn verify_partial(
+	commitments: &[G2Bytes],
+	index: ParticipantIndex,
+	round: u64,
+	signature: &G1Bytes,
+) -> bool {
+	let (Ok(signature), Some(message), Some(commitments)) = (
+		G1Affine::deserialize_compressed(&signature[..]),
+		hash_to_g1(&unchained_message(round)),
+		decode_commitments(commitments),
+	) else {
+		return false;
+	};
+	let public_share = public_share(&commitments, index).into_affine();
+	Bls12_381::multi_pairing([signature, -message], [G2Affine::generator(), public_share])
+		.0
+		.is_one()
+}
+
+/// The group's signature, from the partial signatures of at least `threshold` members.
+///
+/// The partial signatures must have been verified: a single invalid one spoils the result.
+pub fn aggregate_partials(partials: &[(ParticipantIndex, G1Bytes)]) -> Option<G1Bytes> {
+	let indices: Vec<_> = partials.iter().map(|(index, _)| *index).collect();
+	let weights = lagrange_at_zero(&indices)?;
+	partials
+		.iter()
+		.zip(weights)
+		.map(|((_, signature), weight)| {
+			G1Affine::deserialize_compressed(&signature[..])
+				.ok()
+				.map(|point| point * weight)
+		})
+		.sum::<Option<G1Projective>>()
+		.map(g1_to_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::bls::verify_unchained_g1;
+
+	struct Member {
+		index: ParticipantIndex,
+		secret: Fr,
+		share: Option<Fr>,
+	}
+
+	fn members(session: u8, count: u32) -> Vec<Member> {
+		(0..count)
+			.map(|index| Member {
+				index,
+				secret: scalar_from_seed(&[session, index as u8], b"encryption"),
+				share: None,
+			})
+			.collect()
+	}
+
+	fn deal(dealer: &Member, threshold: u32, recipients: &[Member]) -> Dealing {
+		let seed = [b'd', dealer.index as u8];
+		let polynomial = Polynomial::from_seed(&seed, threshold, dealer.share);
+		let shares = recipients
+			.iter()
+			.map(|recipient| {
+				let seed = [seed.as_slice(), &recipient.index.to_le_bytes()].concat();
+				let key = encryption_key(&recipient.secret);
+				encrypt_share(&key, &polynomial.evaluate(recipient.index), &seed)
+			})
+			.collect();
+		Dealing { commitments: polynomial.commitments(), shares }
+	}
+
+	/// Run a DKG from `dealers` to `participants`, returning the shared commitments.
+	fn run(
+		dealers: &[Member],
+		previous: Option<&[G2Bytes]>,
+		participants: &mut [Member],
+		threshold: u32,
+	) -> Vec<G2Bytes> {
+		let dealings: Vec<_> = dealers
+			.iter()
+			.map(|dealer| (dealer.index, deal(dealer, threshold, participants)))
+			.collect();
+		for (index, dealing) in &dealings {
+			let reshared = previous.map(|previous| (previous, *index));
+			assert!(verify_commitments(&dealing.commitments, threshold, reshared));
+		}
+		for participant in participants.iter_mut() {
+			let shares: Vec<_> = dealings
+				.iter()
+				.map(|(index, dealing)| {
+					let encrypted = dealing.shares[participant.index as usize].as_ref().unwrap();
+					let share = decrypt_share(&participant.secret, encrypted).unwrap();
+					assert!(verify_share(&dealing.commitments, participant.index, &share));
+					(*index, share)
+				})
+				.collect();
+			participant.share = combine_shares(&shares, previous.is_some());
+		}
+		let commitments: Vec<_> = dealings
+			.iter()
+			.map(|(index, dealing)| (*index, &dealing.commitments[..]))
+			.collect();
+		aggregate_commitments(&commitments, previous.is_some()).unwrap()
+	}
+
+	fn sign(signers: &[Member], commitments: &[G2Bytes], round: u64) -> G1Bytes {
+		let partials: Vec<_> = signers
+			.iter()
+			.map(|signer| {
+				let signature = partial_sign(signer.share.as_ref().unwrap(), round).unwrap();
+				assert!(verify_partial(commitments, signer.index, round, &signature));
+				(signer.index, signature)
+			})
+			.collect();
+		aggregate_partials(&partials).unwrap()
+	}
+
+	#[test]
+	fn threshold_signatures_verify_against_the_group_key() {
+		let mut committee = members(0, 4);
+		let threshold = threshold(4);
+		let commitments = run(&members(0, 4), None, &mut committee, threshold);
+		let key = commitments[0];
+
+		let signature = sign(&committee[1..], &commitments, 7);
+		assert!(verify_unchained_g1(&key, 7, &signature));
+		assert_eq!(sign(&committee[..3], &commitments, 7), signature);
+		assert!(!verify_unchained_g1(&key, 8, &signature));
+	}
+
+	#[test]
+	fn resharing_keeps_the_group_key() {
+		let mut committee = members(0, 4);
+		let commitments = run(&members(0, 4), None, &mut committee, threshold(4));
+		let key = commitments[0];
+
+		let mut next = members(1, 5);
+		let commitments = run(&committee[1..], Some(&commitments), &mut next, threshold(5));
+		assert_eq!(commitments[0], key);
+		assert!(verify_unchained_g1(&key, 9, &sign(&next[..4], &commitments, 9)));
+	}
+
+	#[test]
+	fn tampered_shares_and_partials_are_rejected() {
+		let dealer = &members(0, 1)[0];
+		let polynomial = Polynomial::from_seed(b"dealer", 3, None);
+		let commitments = polynomial.commitments();
+		assert!(verify_commitments(&commitments, 3, None));
+		assert!(!verify_commitments(&commitments, 4, None));
+		assert!(!verify_commitments(&commitments, 3, Some((&commitments, 0))));
+		assert!(verify_share(&commitments, 2, &polynomial.evaluate(2)));
+		assert!(!verify_share(&commitments, 2, &polynomial.evaluate(1)));
+
+		let key = encryption_key(&dealer.secret);
+		let mut encrypted = encrypt_share(&key, &polynomial.evaluate(2), b"seed").unwrap();
+		assert_eq!(decrypt_share(&dealer.secret, &encrypted), Some(polynomial.evaluate(2)));
+		encrypted.ciphertext[0] ^= 1;
+		assert_ne!(decrypt_share(&dealer.secret, &encrypted), Some(polynomial.evaluate(2)));
+
+		let signature = partial_sign(&polynomial.evaluate(1), 3).unwrap();
+		assert!(verify_partial(&commitments, 1, 3, &signature));
+		assert!(!verify_partial(&commitments, 2, 3, &signature));
+		assert!(lagrange_at_zero(&[1, 2, 1]).is_none());
+	}
+
+	#[test]
+	fn complaints_only_hold_against_wrong_shares() {
+		let members = members(0, 3);
+		let recipient = &members[2];
+		let key = encryption_key(&recipient.secret);
+		let polynomial = Polynomial::from_seed(b"dealer", 2, None);
+		let commitments = polynomial.commitments();
+		let right = encrypt_share(&key, &polynomial.evaluate(2), b"right").unwrap();
+		let wrong = encrypt_share(&key, &polynomial.evaluate(1), b"wrong").unwrap();
+
+		let (shared_key, proof) = reveal_shared_key(&recipient.secret, &wrong, b"proof").unwrap();
+		assert!(verify_shared_key(&key, &wrong.ephemeral, &shared_key, &proof));
+		assert_eq!(decrypt_with_shared_key(&wrong, &shared_key), Some(polynomial.evaluate(1)));
+		assert!(is_wrong_share(&commitments, 2, &key, &wrong, &shared_key, &proof));
+
+		// The right share can't be complained about.
+		let (shared_key, proof) = reveal_shared_key(&recipient.secret, &right, b"proof").unwrap();
+		assert!(!is_wrong_share(&commitments, 2, &key, &right, &shared_key, &proof));
+
+		// Nor can a made-up key, or another participant's proof, frame the dealer.
+		let (shared_key, proof) = reveal_shared_key(&recipient.secret, &wrong, b"proof").unwrap();
+		let other = encryption_key(&members[1].secret);
+		assert!(!is_wrong_share(&commitments, 2, &other, &wrong, &shared_key, &proof));
+		let made_up = encryption_key(&scalar_from_seed(b"made up", b""));
+		assert!(!is_wrong_share(&commitments, 2, &key, &wrong, &made_up, &proof));
+		let tampered = SharedKeyProof { response: proof.challenge, ..proof };
+		assert!(!is_wrong_share(&commitments, 2, &key, &wrong, &shared_key, &tampered));
+	}
+}
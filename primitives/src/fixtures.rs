@@ -0,0 +1,139 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Beacons and pulses signed with throwaway keys, for tests and benchmarks.
+//!
+//! The pulses use the formats of the networks their schemes come from, but they are signed with
+//! throwaway keys rather than recorded from the live beacons.
+//!
+//! The evmnet fixtures only show that [`crate::bn254`] verifies what it signs itself. Its
+//! `expand_message_xmd` padding and SvdW map have not been checked against rounds recorded from
+//! drand's evmnet yet: until two such rounds, with evmnet's chain hash, public key and `/info`,
+//! are added here and accepted by [`crate::bls::verify_pulse`], don't follow evmnet on a live
+//! chain.
+
+use crate::beacon::{BeaconInfo, Pulse, SignatureScheme};
+use hex_literal::hex;
+
+/// A beacon using the quicknet scheme.
+pub fn unchained_beacon() -> BeaconInfo {
+	BeaconInfo {
+		chain_hash: [1; 32],
+		public_key: hex!(
+			"999fb67ca4ef69afe3f3a52168ce34c32928bd79957757e211385704182814bcb58644f6c93e9a56"
+			"51fa362b32e4c49208b506ed8da63225b6b75fbc0be84063ad4e11325f185aede52f017cbc662ef5"
+			"b93b4c05278d595de014f5f4447053b9"
+		)
+		.to_vec()
+		.try_into()
+		.expect("96 bytes fit; qed"),
+		period: 3,
+		genesis_time: 1_692_803_367,
+		scheme: SignatureScheme::BlsUnchainedG1Rfc9380,
+	}
+}
+
+/// Round 1000 of [`unchained_beacon`].
+pub fn unchained_pulse() -> Pulse {
+	Pulse {
+		round: 1000,
+		randomness: hex!("f1c1ecfe3591fbc66b76a670705fffc8dd91327b1c4eaaa3b351ce413012449e"),
+		signature: hex!(
+			"80ac101025ac5a39e9e88a38aed82a696f142ced81631b46ad5421a8801de7cbef46800ec1e1d8f7"
+			"3a47ffe67759f53b"
+		)
+		.to_vec()
+		.try_into()
+		.expect("48 bytes fit; qed"),
+		previous_signature: Default::default(),
+	}
+}
+
+/// A beacon using the default League of Entropy scheme.
+pub fn chained_beacon() -> BeaconInfo {
+	BeaconInfo {
+		chain_hash: [2; 32],
+		public_key: hex!(
+			"87ec4cf646bf101bd884cfff19da187c50c9dfceb22271400cf92ec8cabeab6a62e9999174bc77ee"
+			"27743bd0c7d45ecf"
+		)
+		.to_vec()
+		.try_into()
+		.expect("48 bytes fit; qed"),
+		period: 30,
+		genesis_time: 1_595_431_050,
+		scheme: SignatureScheme::PedersenBlsChained,
+	}
+}
+
+/// Round 2 of [`chained_beacon`].
+pub fn chained_pulse() -> Pulse {
+	Pulse {
+		round: 2,
+		randomness: hex!("546f5dac4adefbcabbc6aac30a82c4ec786828dd51bc8567c584651508ee9df8"),
+		signature: hex!(
+			"99954fb8bb3d84f0d03d8beafe870b5d2851f5d13076135441d4d6f65824c213c64a282df13668ed"
+			"ffba21d11fc1a1fa0d09ff74b1b20da41e29ca2e15cfcd998acbbe3cecd6e029b53f6b028f2b4af7"
+			"fbeb54a8b99fb2680b70e907770ff9d9"
+		)
+		.to_vec()
+		.try_into()
+		.expect("96 bytes fit; qed"),
+		previous_signature: hex!(
+			"8da0d92238678043a7c72932da5978938f5527ad21c815d70eb141f0c1743eea1b5d144b01cf80e2"
+			"98bd543881444290163b50faf777c8f0829a9be186bb739eabf86a4f3582c940e53a0356404585b2"
+			"5ddbb337ac17dca79b3d71a6a2981341"
+		)
+		.to_vec()
+		.try_into()
+		.expect("96 bytes fit; qed"),
+	}
+}
+
+/// A beacon using the evmnet scheme.
+pub fn evmnet_beacon() -> BeaconInfo {
+	BeaconInfo {
+		chain_hash: [3; 32],
+		public_key: hex!(
+			"03d95b1d408495781f4a4232e9cd69494e409f9d04ed6c73c022a9343989d4150dbf0927743c6a13"
+			"154a464c4da81d5982459d4b2de480ab0a622a58584111e10f049031b1b09accbc4f6f3a594c7d6f"
+			"46aa58c21917410a37792a215bcd92a923ebbd86d47187880b98455bcffc588409393bb4671711a9"
+			"bdef3d11bbaadbb9"
+		)
+		.to_vec()
+		.try_into()
+		.expect("128 bytes fit; qed"),
+		period: 3,
+		genesis_time: 1_727_521_075,
+		scheme: SignatureScheme::BlsBn254UnchainedG1,
+	}
+}
+
+/// Round 1000 of [`evmnet_beacon`].
+pub fn evmnet_pulse() -> Pulse {
+	Pulse {
+		round: 1000,
+		randomness: hex!("a1b3a52fc3e13fd6e31b3e9697caa9701161882dbc833b133879b02c2d2d657d"),
+		signature: hex!(
+			"211345dbea88efc60785aa6aec595268b53ab46c3a6ce0b5c63777e12f93ae56273a278e44c1b9a0"
+			"d4e82fe2fd4df67ab9b14983ccb9e82832449c5f13568786"
+		)
+		.to_vec()
+		.try_into()
+		.expect("64 bytes fit; qed"),
+		previous_signature: Default::default(),
+	}
+}
@@ -0,0 +1,42 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Primitives shared between the Ideal Network runtime and node.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod api;
+pub mod beacon;
+pub mod bls;
+pub mod bn254;
+#[cfg(feature = "std")]
+pub mod dev;
+pub mod digest;
+pub mod dkg;
+#[cfg(any(test, feature = "runtime-benchmarks"))]
+pub mod fixtures;
+pub mod lottery;
+pub mod mixing;
+pub mod mmr;
+pub mod ordering;
+pub mod proof;
+pub mod pulse_cache;
+pub mod relay;
+pub mod shuffle;
+pub mod stats;
+pub mod time;
@@ -0,0 +1,121 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Per-account draws anchored to beacon randomness.
+//!
+//! An account evaluates an sr25519 VRF with its own key over a drand round, the randomness of
+//! that round and a context chosen by the application, e.g. `b"raffle-42"`. The output of that
+//! ticket is:
+//!
+//! - private until the account publishes the proof, since only the account's key computes it,
+//! - verifiable by anyone against the account's public key,
+//! - unbiasable by the account once its key is fixed: the key and the input determine the output,
+//!   and the randomness of the round is unknown before drand publishes it.
+//!
+//! An account that can pick its key after the round is published, e.g. by creating new accounts,
+//! can grind for a winning ticket. Applications must fix the eligible accounts before the round
+//! they draw on.
+
+use crate::beacon::{Randomness, RoundNumber};
+use codec::Encode;
+use sp_core::{
+	crypto::VrfPublic,
+	hashing::blake2_256,
+	sr25519::{
+		self,
+		vrf::{VrfSignData, VrfSignature, VrfTranscript},
+	},
+};
+use sp_runtime::Perbill;
+
+/// Label of the VRF transcript.
+pub const VRF_LABEL: &[u8] = b"ideal-network/lottery";
+
+/// The output of a ticket.
+pub type TicketOutput = [u8; 32];
+
+/// What an account evaluates its VRF over to draw on round `round` for `context`.
+pub fn ticket_sign_data(
+	round: RoundNumber,
+	randomness: &Randomness,
+	context: &[u8],
+) -> VrfSignData {
+	let round = round.to_le_bytes();
+	VrfTranscript::new(
+		VRF_LABEL,
+		&[(b"round", &round[..]), (b"randomness", &randomness[..]), (b"context", context)],
+	)
+	.into()
+}
+
+/// The output of `signature` if it is a ticket of `public` for round `round` and `context`.
+pub fn verify_ticket(
+	public: &sr25519::Public,
+	round: RoundNumber,
+	randomness: &Randomness,
+	context: &[u8],
+	signature: &VrfSignature,
+) -> Option<TicketOutput> {
+	public
+		.vrf_verify(&ticket_sign_data(round, randomness, context), signature)
+		.then(|| blake2_256(&signature.pre_output.encode()))
+}
+
+/// Whether a ticket with `output` is selected by a draw with probability `chance`.
+pub fn is_selected(output: &TicketOutput, chance: Perbill) -> bool {
+	let mut value = [0; 8];
+	value.copy_from_slice(&output[..8]);
+	chance.is_one() || u64::from_le_bytes(value) < chance.mul_floor(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{crypto::VrfSecret, Pair};
+
+	#[test]
+	fn tickets_verify_for_their_key_round_and_context() {
+		let (pair, other) =
+			(sr25519::Pair::from_seed(&[1; 32]), sr25519::Pair::from_seed(&[2; 32]));
+		let randomness = [7; 32];
+		let signature = pair.vrf_sign(&ticket_sign_data(3, &randomness, b"raffle"));
+
+		let output = verify_ticket(&pair.public(), 3, &randomness, b"raffle", &signature);
+		assert!(output.is_some());
+		// Deterministic for a key, a round and a context.
+		let again = pair.vrf_sign(&ticket_sign_data(3, &randomness, b"raffle"));
+		assert_eq!(verify_ticket(&pair.public(), 3, &randomness, b"raffle", &again), output);
+
+		assert_eq!(verify_ticket(&other.public(), 3, &randomness, b"raffle", &signature), None);
+		assert_eq!(verify_ticket(&pair.public(), 4, &randomness, b"raffle", &signature), None);
+		assert_eq!(verify_ticket(&pair.public(), 3, &[8; 32], b"raffle", &signature), None);
+		assert_eq!(verify_ticket(&pair.public(), 3, &randomness, b"other", &signature), None);
+	}
+
+	#[test]
+	fn selection_follows_the_chance() {
+		let (low, high) = ([0; 32], [0xff; 32]);
+		assert!(!is_selected(&low, Perbill::zero()));
+		assert!(is_selected(&low, Perbill::from_percent(1)));
+		assert!(!is_selected(&high, Perbill::from_percent(99)));
+		assert!(is_selected(&high, Perbill::one()));
+
+		let selected = (0..1000u32)
+			.filter(|n| is_selected(&blake2_256(&n.encode()), Perbill::from_percent(10)))
+			.count();
+		assert!((50..150).contains(&selected));
+	}
+}
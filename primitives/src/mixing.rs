@@ -0,0 +1,146 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Randomness mixed from a drand pulse and VRF outputs of the block authors.
+//!
+//! Every block author evaluates an sr25519 VRF over the latest pulse with its Aura session key
+//! and includes the output in its block. The mixed randomness of a round is
+//! `blake2_256(randomness || output_1 || … || output_n)` over the outputs of the blocks built on
+//! that round.
+//!
+//! # Threat model
+//!
+//! - A compromised drand group alone can't predict the mixed randomness: it doesn't know the
+//!   authors' keys, so it can't compute their outputs.
+//! - An author alone can't choose its output, which is fixed by its key and the pulse. The output
+//!   is mandatory while the round takes more, so it can only withhold its whole block, trading the
+//!   output for the next author's, like any slot-based randomness.
+//! - A drand group colluding with an author learns the mixed randomness a round early and, together
+//!   with withholding, biases it. So do authors controlling every slot of a round.
+//! - The outputs are verified against the author's session key on-chain, so a block can't carry
+//!   made-up contributions.
+//!
+//! Until the first author contributes to a round, there is no mixed randomness for it.
+
+use crate::beacon::{Randomness, RoundNumber};
+use alloc::vec::Vec;
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_core::{
+	crypto::VrfPublic,
+	hashing::blake2_256,
+	sr25519::{
+		self,
+		vrf::{VrfSignData, VrfSignature, VrfTranscript},
+	},
+};
+use sp_inherents::InherentIdentifier;
+use sp_runtime::RuntimeDebug;
+
+/// Identifier of the inherent data holding the authors' contributions.
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"idnvrfmx";
+
+/// Label of the VRF transcript.
+pub const VRF_LABEL: &[u8] = b"ideal-network/mixed-randomness";
+
+/// The output of an author's VRF.
+pub type VrfOutput = [u8; 32];
+
+/// A VRF evaluation by one of the node's session keys.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct VrfContribution {
+	/// The session key.
+	pub public: sr25519::Public,
+	/// The VRF signature over [`vrf_sign_data`].
+	pub signature: VrfSignature,
+}
+
+/// The inherent data: a contribution for every session key of the node, of which the runtime
+/// includes the author's.
+pub type InherentType = Vec<VrfContribution>;
+
+/// Mixed randomness of a round.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct MixedSeed<BlockNumber> {
+	/// The drand round that was mixed.
+	pub round: RoundNumber,
+	/// The mixed randomness.
+	pub randomness: Randomness,
+	/// The number of VRF outputs mixed in.
+	pub contributions: u32,
+	/// The block that mixed in the last output.
+	pub block_number: BlockNumber,
+}
+
+/// What authors evaluate the VRF over for round `round` with randomness `randomness`.
+pub fn vrf_sign_data(round: RoundNumber, randomness: &Randomness) -> VrfSignData {
+	let round = round.to_le_bytes();
+	VrfTranscript::new(VRF_LABEL, &[(b"round", &round[..]), (b"randomness", &randomness[..])])
+		.into()
+}
+
+/// The output of `signature` if it is a VRF evaluation by `public` over the round.
+pub fn verify_contribution(
+	public: &sr25519::Public,
+	round: RoundNumber,
+	randomness: &Randomness,
+	signature: &VrfSignature,
+) -> Option<VrfOutput> {
+	public
+		.vrf_verify(&vrf_sign_data(round, randomness), signature)
+		.then(|| blake2_256(&signature.pre_output.encode()))
+}
+
+/// The randomness `randomness` mixed with `outputs`.
+pub fn mix(randomness: &Randomness, outputs: &[VrfOutput]) -> Randomness {
+	let mut data = Vec::with_capacity(32 * (outputs.len() + 1));
+	data.extend_from_slice(randomness);
+	outputs.iter().for_each(|output| data.extend_from_slice(output));
+	blake2_256(&data)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_core::{crypto::VrfSecret, Pair};
+
+	#[test]
+	fn contributions_verify_for_their_key_and_round() {
+		let (pair, other) =
+			(sr25519::Pair::from_seed(&[1; 32]), sr25519::Pair::from_seed(&[2; 32]));
+		let randomness = [7; 32];
+		let signature = pair.vrf_sign(&vrf_sign_data(3, &randomness));
+
+		let output = verify_contribution(&pair.public(), 3, &randomness, &signature);
+		assert!(output.is_some());
+		// Deterministic for a key and a round.
+		let again = pair.vrf_sign(&vrf_sign_data(3, &randomness));
+		assert_eq!(verify_contribution(&pair.public(), 3, &randomness, &again), output);
+
+		assert_eq!(verify_contribution(&other.public(), 3, &randomness, &signature), None);
+		assert_eq!(verify_contribution(&pair.public(), 4, &randomness, &signature), None);
+		assert_eq!(verify_contribution(&pair.public(), 3, &[8; 32], &signature), None);
+	}
+
+	#[test]
+	fn every_output_changes_the_mix() {
+		let randomness = [7; 32];
+		assert_eq!(mix(&randomness, &[]), blake2_256(&randomness));
+		assert_ne!(mix(&randomness, &[[1; 32]]), mix(&randomness, &[]));
+		assert_ne!(mix(&randomness, &[[1; 32], [2; 32]]), mix(&randomness, &[[2; 32], [1; 32]]));
+		assert_ne!(mix(&[8; 32], &[[1; 32]]), mix(&randomness, &[[1; 32]]));
+	}
+}
@@ -0,0 +1,113 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Pulses committed to a Merkle Mountain Range.
+//!
+//! Every block appends one leaf to the runtime's MMR, holding the round and randomness of the
+//! pulses its parent stored, and commits the new root to its header under [`MMR_ENGINE_ID`].
+//! A leaf proof against the root of a recent header shows the randomness of an old round, long
+//! after the pulse is pruned from state. Proofs are checked with
+//! `pallet_mmr::verify_leaves_proof`, which needs no runtime state.
+
+use crate::beacon::{BeaconId, Randomness, RoundNumber};
+use alloc::vec::Vec;
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime::{traits::Header, ConsensusEngineId, DigestItem, RuntimeDebug};
+
+/// Engine id of the digest item holding the MMR root.
+pub const MMR_ENGINE_ID: ConsensusEngineId = *b"IDNM";
+
+/// The randomness of a round, as committed to the MMR.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct LeafPulse {
+	/// The beacon that signed the pulse.
+	pub beacon_id: BeaconId,
+	/// The round of the pulse.
+	pub round: RoundNumber,
+	/// The randomness of the pulse.
+	pub randomness: Randomness,
+}
+
+/// A leaf of the MMR: the pulses stored in the parent of the block that appended it.
+pub type PulseLeaf = Vec<LeafPulse>;
+
+/// The randomness `leaf` holds for round `round` of beacon `beacon_id`.
+pub fn leaf_randomness(
+	leaf: &PulseLeaf,
+	beacon_id: BeaconId,
+	round: RoundNumber,
+) -> Option<Randomness> {
+	leaf.iter()
+		.find(|pulse| pulse.beacon_id == beacon_id && pulse.round == round)
+		.map(|pulse| pulse.randomness)
+}
+
+/// The digest item committing to the MMR `root`.
+pub fn root_digest_item<Hash: Encode>(root: &Hash) -> DigestItem {
+	DigestItem::Consensus(MMR_ENGINE_ID, root.encode())
+}
+
+/// The MMR root committed to `header`.
+pub fn mmr_root<H: Header>(header: &H) -> Option<H::Hash> {
+	header
+		.digest()
+		.logs()
+		.iter()
+		.find_map(|item| item.consensus_try_to(&MMR_ENGINE_ID))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::digest::BeaconDigest;
+	use sp_core::H256;
+	use sp_runtime::{generic, traits::BlakeTwo256, Digest};
+
+	type TestHeader = generic::Header<u32, BlakeTwo256>;
+
+	fn header(logs: Vec<DigestItem>) -> TestHeader {
+		TestHeader::new(
+			1,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Digest { logs },
+		)
+	}
+
+	#[test]
+	fn root_is_read_back_from_the_header() {
+		let root = H256::repeat_byte(7);
+		let other = BeaconDigest { beacon_id: 0, pulse: crate::fixtures::unchained_pulse() };
+		assert_eq!(
+			mmr_root(&header(vec![other.to_digest_item(), root_digest_item(&root)])),
+			Some(root)
+		);
+		assert_eq!(mmr_root(&header(vec![other.to_digest_item()])), None);
+	}
+
+	#[test]
+	fn leaves_hold_rounds_of_several_beacons() {
+		let leaf = vec![
+			LeafPulse { beacon_id: 0, round: 10, randomness: [1; 32] },
+			LeafPulse { beacon_id: 1, round: 10, randomness: [2; 32] },
+		];
+		assert_eq!(leaf_randomness(&leaf, 1, 10), Some([2; 32]));
+		assert_eq!(leaf_randomness(&leaf, 1, 11), None);
+		assert_eq!(leaf_randomness(&leaf, 2, 10), None);
+	}
+}
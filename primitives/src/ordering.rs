@@ -0,0 +1,148 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Fair ordering of a block's transactions by the previous pulse.
+//!
+//! Collators order the ready transactions by fee priority, then by arrival, which lets them and
+//! whoever pays the most pick their place in the block. A block built fairly orders its
+//! non-inherent extrinsics by [`OrderingKey`] instead: first by bucket, then by a rank derived from
+//! the latest pulse at the parent block, so it can't be known before that block is built.
+//!
+//! Signed extrinsics are ranked by sender, keeping each sender's transactions in nonce order;
+//! unsigned ones by their own hash. Anyone can recompute the keys from the block and the parent
+//! state, and check that they ascend.
+//!
+//! The ordering doesn't prevent censorship, nor a user from grinding the rank of a fresh account
+//! after the pulse is known. Tips no longer order transactions within a bucket.
+
+use crate::beacon::Randomness;
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_core::hashing::blake2_256;
+use sp_runtime::RuntimeDebug;
+
+/// Domain separating the seed of a block's ordering.
+const ORDERING_DOMAIN: &[u8] = b"ideal-network/fair-ordering";
+
+/// Bucket of unsigned extrinsics, ordered first.
+pub const BUCKET_UNSIGNED: u8 = 0;
+
+/// Bucket of signed operational and mandatory extrinsics.
+pub const BUCKET_OPERATIONAL: u8 = 1;
+
+/// Bucket of signed normal extrinsics, ordered last.
+pub const BUCKET_NORMAL: u8 = 2;
+
+/// The position of an extrinsic in a fairly ordered block: keys ascend strictly.
+#[derive(
+	Encode,
+	Decode,
+	Clone,
+	Copy,
+	PartialEq,
+	Eq,
+	PartialOrd,
+	Ord,
+	RuntimeDebug,
+	TypeInfo,
+	MaxEncodedLen,
+)]
+pub struct OrderingKey {
+	/// The bucket of the extrinsic.
+	pub bucket: u8,
+	/// Its rank within the bucket.
+	pub rank: [u8; 32],
+	/// The nonce of a signed extrinsic, ordering the transactions of a sender.
+	pub nonce: u64,
+}
+
+/// The seed of the ordering of a block, from the latest pulse at its parent.
+pub fn block_seed(randomness: &Randomness) -> [u8; 32] {
+	blake2_256(&(ORDERING_DOMAIN, randomness).encode())
+}
+
+/// The rank of the signed extrinsics of the SCALE encoded `sender`.
+pub fn sender_rank(seed: &[u8; 32], sender: &[u8]) -> [u8; 32] {
+	blake2_256(&[&seed[..], sender].concat())
+}
+
+/// The rank of the SCALE encoded unsigned `extrinsic`.
+pub fn transaction_rank(seed: &[u8; 32], extrinsic: &[u8]) -> [u8; 32] {
+	blake2_256(&[&seed[..], extrinsic].concat())
+}
+
+/// The unsigned key of `extrinsic`.
+pub fn unsigned_key(seed: &[u8; 32], extrinsic: &[u8]) -> OrderingKey {
+	OrderingKey { bucket: BUCKET_UNSIGNED, rank: transaction_rank(seed, extrinsic), nonce: 0 }
+}
+
+/// The position of the first key that doesn't ascend strictly from the previous one.
+pub fn first_deviation(keys: impl IntoIterator<Item = OrderingKey>) -> Option<usize> {
+	let mut previous = None;
+	for (position, key) in keys.into_iter().enumerate() {
+		if previous.is_some_and(|previous| key <= previous) {
+			return Some(position);
+		}
+		previous = Some(key);
+	}
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn signed(seed: &[u8; 32], sender: u8, nonce: u64) -> OrderingKey {
+		OrderingKey { bucket: BUCKET_NORMAL, rank: sender_rank(seed, &[sender]), nonce }
+	}
+
+	#[test]
+	fn keys_follow_the_pulse() {
+		let seed = block_seed(&[1; 32]);
+		assert_ne!(seed, block_seed(&[2; 32]));
+		assert_ne!(sender_rank(&seed, &[1]), sender_rank(&block_seed(&[2; 32]), &[1]));
+
+		// Buckets come first, then senders, then nonces.
+		let mut keys = vec![signed(&seed, 1, 1), signed(&seed, 2, 0), signed(&seed, 1, 0)];
+		keys.push(unsigned_key(&seed, b"unsigned"));
+		keys.push(OrderingKey { bucket: BUCKET_OPERATIONAL, ..signed(&seed, 3, 0) });
+		keys.sort();
+		assert_eq!(keys[0].bucket, BUCKET_UNSIGNED);
+		assert_eq!(keys[1].bucket, BUCKET_OPERATIONAL);
+		let ones: Vec<u64> = keys
+			.iter()
+			.filter(|key| key.rank == signed(&seed, 1, 0).rank)
+			.map(|k| k.nonce)
+			.collect();
+		assert_eq!(ones, [0, 1]);
+	}
+
+	#[test]
+	fn deviations_are_found() {
+		let seed = block_seed(&[1; 32]);
+		let mut keys: Vec<_> = (0..8).map(|sender| signed(&seed, sender, 0)).collect();
+		keys.sort();
+		assert_eq!(first_deviation(keys.clone()), None);
+		assert_eq!(first_deviation(keys[3..].to_vec()), None);
+		assert_eq!(first_deviation([]), None);
+
+		keys.swap(4, 5);
+		assert_eq!(first_deviation(keys.clone()), Some(5));
+
+		// The same key twice doesn't ascend either.
+		assert_eq!(first_deviation([keys[0], keys[0]]), Some(1));
+	}
+}
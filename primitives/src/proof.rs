@@ -0,0 +1,176 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Storage proofs of stored pulses.
+//!
+//! A pulse stored by the Ideal Network can be checked on another chain from a Merkle proof of
+//! its storage entry against an IDN state root. Sibling parachains get that root from the IDN
+//! head data the relay chain provides, see [`verify_pulse_proof`]. Everything here is `no_std`.
+
+use crate::beacon::{BeaconId, Pulse, RoundNumber};
+use alloc::vec::Vec;
+use codec::{Decode, Encode};
+use sp_core::{
+	hashing::{twox_128, twox_64},
+	H256,
+};
+use sp_runtime::{generic, traits::BlakeTwo256, RuntimeDebug};
+use sp_trie::{HashDBT, LayoutV1, StorageProof};
+
+/// Name of the beacons pallet in the Ideal Network runtime, the prefix of its storage keys.
+pub const BEACONS_PALLET: &[u8] = b"Beacons";
+
+/// Header of an Ideal Network block.
+pub type IdnHeader = generic::Header<u32, BlakeTwo256>;
+
+/// Why a pulse proof was rejected.
+#[derive(Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum ProofError {
+	/// The head data is not an Ideal Network header.
+	InvalidHeadData,
+	/// The proof doesn't hold the state root.
+	RootMismatch,
+	/// The proof is incomplete or malformed.
+	InvalidProof,
+	/// The proof shows that no pulse is stored for the round.
+	NoPulse,
+	/// The stored value is not a pulse of the round.
+	InvalidPulse,
+}
+
+/// Storage key of `round` of beacon `beacon_id` in the pallet named `pallet`.
+///
+/// Pulses are kept in a double map hashed with `Twox64Concat`.
+pub fn pulse_key(pallet: &[u8], beacon_id: BeaconId, round: RoundNumber) -> Vec<u8> {
+	let twox_64_concat = |key: &[u8]| [&twox_64(key)[..], key].concat();
+	[
+		&twox_128(pallet)[..],
+		&twox_128(b"Pulses")[..],
+		&twox_64_concat(&beacon_id.encode()),
+		&twox_64_concat(&round.encode()),
+	]
+	.concat()
+}
+
+/// Verify that `proof` shows `round` of beacon `beacon_id` stored under `state_root`, and return
+/// the pulse.
+///
+/// The pulse signature is not checked: the proof shows the runtime stored the pulse, which it
+/// only does once the signature verified.
+pub fn verify_pulse_proof_at(
+	state_root: &H256,
+	beacon_id: BeaconId,
+	round: RoundNumber,
+	proof: Vec<Vec<u8>>,
+) -> Result<Pulse, ProofError> {
+	let db = StorageProof::new(proof).into_memory_db::<BlakeTwo256>();
+	if !db.contains(state_root, sp_trie::EMPTY_PREFIX) {
+		return Err(ProofError::RootMismatch);
+	}
+	let key = pulse_key(BEACONS_PALLET, beacon_id, round);
+	let value =
+		sp_trie::read_trie_value::<LayoutV1<BlakeTwo256>, _>(&db, state_root, &key, None, None)
+			.map_err(|_| ProofError::InvalidProof)?
+			.ok_or(ProofError::NoPulse)?;
+	let pulse = Pulse::decode(&mut &value[..]).map_err(|_| ProofError::InvalidPulse)?;
+	if pulse.round != round {
+		return Err(ProofError::InvalidPulse);
+	}
+	Ok(pulse)
+}
+
+/// Verify that `proof` shows `round` of beacon `beacon_id` stored in the Ideal Network block
+/// whose `head_data` the relay chain provided, and return the pulse.
+pub fn verify_pulse_proof(
+	head_data: &[u8],
+	beacon_id: BeaconId,
+	round: RoundNumber,
+	proof: Vec<Vec<u8>>,
+) -> Result<Pulse, ProofError> {
+	let header = IdnHeader::decode(&mut &head_data[..]).map_err(|_| ProofError::InvalidHeadData)?;
+	verify_pulse_proof_at(&header.state_root, beacon_id, round, proof)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::fixtures::unchained_pulse;
+	use sp_runtime::traits::Header as _;
+	use sp_trie::{MemoryDB, TrieDBMutBuilder, TrieMut};
+
+	/// A state holding `pulse` as round `round` of beacon 1, and a proof of every entry.
+	fn state(round: RoundNumber, pulse: &Pulse) -> (H256, Vec<Vec<u8>>) {
+		let mut db = MemoryDB::<BlakeTwo256>::default();
+		let mut root = H256::default();
+		{
+			let mut trie =
+				TrieDBMutBuilder::<LayoutV1<BlakeTwo256>>::new(&mut db, &mut root).build();
+			trie.insert(&pulse_key(BEACONS_PALLET, 1, round), &pulse.encode()).unwrap();
+			trie.insert(b"some other key", b"value").unwrap();
+		}
+		(root, db.drain().into_values().map(|(node, _)| node).collect())
+	}
+
+	#[test]
+	fn proof_of_a_stored_pulse_verifies() {
+		let pulse = unchained_pulse();
+		let (root, proof) = state(pulse.round, &pulse);
+		let header =
+			IdnHeader::new(7, Default::default(), root, Default::default(), Default::default());
+
+		assert_eq!(
+			verify_pulse_proof(&header.encode(), 1, pulse.round, proof.clone()),
+			Ok(pulse.clone())
+		);
+		assert_eq!(
+			verify_pulse_proof_at(&root, 2, pulse.round, proof.clone()),
+			Err(ProofError::NoPulse)
+		);
+		assert_eq!(
+			verify_pulse_proof_at(&H256::repeat_byte(1), 1, pulse.round, proof.clone()),
+			Err(ProofError::RootMismatch)
+		);
+		assert_eq!(
+			verify_pulse_proof(&[1, 2], 1, pulse.round, proof),
+			Err(ProofError::InvalidHeadData)
+		);
+	}
+
+	#[test]
+	fn pulse_must_match_the_round() {
+		let pulse = unchained_pulse();
+		let (root, proof) = state(pulse.round + 1, &pulse);
+		assert_eq!(
+			verify_pulse_proof_at(&root, 1, pulse.round + 1, proof),
+			Err(ProofError::InvalidPulse)
+		);
+	}
+
+	#[test]
+	fn incomplete_proofs_are_rejected() {
+		let pulse = unchained_pulse();
+		let (root, proof) = state(pulse.round, &pulse);
+		let root_only = StorageProof::new(proof)
+			.into_memory_db::<BlakeTwo256>()
+			.get(&root, sp_trie::EMPTY_PREFIX)
+			.into_iter()
+			.collect();
+		assert_eq!(
+			verify_pulse_proof_at(&root, 1, pulse.round, root_only),
+			Err(ProofError::InvalidProof)
+		);
+	}
+}
@@ -0,0 +1,76 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Access to the node's cache of already verified beacon pulses.
+//!
+//! The node keeps a cache of pulses whose signature it has checked, keyed by the beacon they were
+//! checked against, chain hash, public key and scheme, and by the round and signatures of the
+//! pulse. A transition that replaces the key of a beacon but keeps its chain hash thus starts from
+//! an empty cache. The runtime reaches it through the standard offchain local storage
+//! host functions, under the keys of [`storage_key`]: the node registers an offchain database
+//! extension on its runtime calls that serves those keys from the cache.
+//!
+//! The cache is node-local state: the runtime only consults it while validating transactions,
+//! never while executing a block. No custom host function is involved, so the runtime runs on
+//! relay chain validators as is.
+
+use crate::beacon::{BeaconInfo, Pulse};
+use alloc::vec::Vec;
+use codec::Encode;
+use sp_core::hashing::blake2_256;
+
+/// Prefix of the offchain local storage keys of verified pulses.
+pub const KEY_PREFIX: &[u8] = b"idn::verified-pulse::";
+
+/// The offchain local storage key of `pulse` verified against `beacon`.
+pub fn storage_key(beacon: &BeaconInfo, pulse: &Pulse) -> Vec<u8> {
+	let key = (
+		&beacon.chain_hash,
+		&beacon.public_key,
+		beacon.scheme,
+		pulse.round,
+		&pulse.signature,
+		// A chained signature is only valid together with the previous signature it covers.
+		&pulse.previous_signature,
+	);
+	[KEY_PREFIX, &key.using_encoded(blake2_256)[..]].concat()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::fixtures::{chained_beacon, chained_pulse, unchained_beacon, unchained_pulse};
+
+	#[test]
+	fn keys_cover_the_beacon_key_and_scheme() {
+		let (beacon, pulse) = (unchained_beacon(), unchained_pulse());
+		let key = storage_key(&beacon, &pulse);
+		assert!(key.starts_with(KEY_PREFIX));
+
+		// Same chain hash, another group key.
+		let rekeyed = BeaconInfo { public_key: chained_beacon().public_key, ..beacon.clone() };
+		assert_ne!(storage_key(&rekeyed, &pulse), key);
+		let rescheme = BeaconInfo { scheme: chained_beacon().scheme, ..beacon };
+		assert_ne!(storage_key(&rescheme, &pulse), key);
+
+		let chained = chained_pulse();
+		let resigned = Pulse { previous_signature: Default::default(), ..chained.clone() };
+		assert_ne!(
+			storage_key(&chained_beacon(), &resigned),
+			storage_key(&chained_beacon(), &chained)
+		);
+	}
+}
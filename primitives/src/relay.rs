@@ -0,0 +1,43 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Randomness of the relay chain, a fallback for when no beacon is available.
+//!
+//! The relay chain's BABE randomness is read from the relay state proof every parachain block
+//! carries. It is much weaker than a drand pulse: the relay block author's VRF output is known to
+//! them before the block is published and they can withhold the block to reroll it, and an
+//! epoch's randomness is known an epoch in advance and can be biased by the last authors of the
+//! epoch before. Only use it when a beacon is unavailable and the stakes are low.
+
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+
+/// BABE randomness of the relay chain.
+pub type RelayRandomness = [u8; 32];
+
+/// The randomness of the relay parent of a parachain block.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug, TypeInfo)]
+pub struct RelayRandomnessInfo {
+	/// Number of the relay parent.
+	pub relay_parent_number: u32,
+	/// The relay chain's current BABE epoch.
+	pub epoch_index: Option<u64>,
+	/// The VRF output of the relay parent's author, if it had one.
+	pub current_block: Option<RelayRandomness>,
+	/// The randomness of the epoch before the current one.
+	pub one_epoch_ago: Option<RelayRandomness>,
+}
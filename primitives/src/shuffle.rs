@@ -0,0 +1,77 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Seeded shuffles, e.g. of the collators of a session by a drand pulse.
+
+use crate::beacon::Randomness;
+use codec::Encode;
+use sp_core::hashing::blake2_256;
+
+/// Domain separating the seed of a session's collator order.
+const SESSION_DOMAIN: &[u8] = b"ideal-network/collator-shuffle";
+
+/// The seed of the collator order of session `session_index`, from the beacon `randomness`.
+pub fn session_seed(session_index: u32, randomness: &Randomness) -> [u8; 32] {
+	blake2_256(&(SESSION_DOMAIN, session_index, randomness).encode())
+}
+
+/// Shuffle `items` with a Fisher-Yates shuffle driven by `seed`.
+///
+/// The order only depends on `seed` and the number of items. Indices are drawn modulo the
+/// remaining length from 64-bit words, a bias that is negligible for collator sets.
+pub fn shuffle<T>(items: &mut [T], seed: &[u8; 32]) {
+	for i in (1..items.len()).rev() {
+		let word = blake2_256(&(seed, i as u64).encode());
+		let mut bytes = [0; 8];
+		bytes.copy_from_slice(&word[..8]);
+		let j = (u64::from_le_bytes(bytes) % (i as u64 + 1)) as usize;
+		items.swap(i, j);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn shuffles_are_seeded_permutations() {
+		let items: Vec<u32> = (0..16).collect();
+		let shuffled = |seed: &[u8; 32]| {
+			let mut shuffled = items.clone();
+			shuffle(&mut shuffled, seed);
+			shuffled
+		};
+
+		let once = shuffled(&session_seed(1, &[7; 32]));
+		assert_ne!(once, items);
+		let mut sorted = once.clone();
+		sorted.sort();
+		assert_eq!(sorted, items);
+
+		assert_eq!(shuffled(&session_seed(1, &[7; 32])), once);
+		assert_ne!(shuffled(&session_seed(2, &[7; 32])), once);
+		assert_ne!(shuffled(&session_seed(1, &[8; 32])), once);
+	}
+
+	#[test]
+	fn short_lists_are_left_alone() {
+		let mut empty: [u8; 0] = [];
+		shuffle(&mut empty, &[0; 32]);
+		let mut single = [1];
+		shuffle(&mut single, &[0; 32]);
+		assert_eq!(single, [1]);
+	}
+}
@@ -0,0 +1,226 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Lightweight statistical checks of a randomness stream.
+//!
+//! Samples are folded one at a time into a [`Window`], so the checks cost a constant amount of
+//! work per sample. The frequency (monobit) and runs tests are evaluated on complete windows of
+//! [`HealthThresholds::window`] samples, as squared z-scores; repeated values are caught as soon
+//! as they arrive. These can't prove randomness, they flag output that is clearly broken.
+
+use crate::beacon::Randomness;
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+
+/// Bits in a sample.
+const SAMPLE_BITS: u64 = 256;
+
+/// A statistical test of the randomness stream.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub enum StatTest {
+	/// As many ones as zeros.
+	#[codec(index = 0)]
+	Monobit,
+	/// As many runs of identical bits as expected from independent bits.
+	#[codec(index = 1)]
+	Runs,
+	/// No sample repeating the previous one.
+	#[codec(index = 2)]
+	Repeats,
+}
+
+/// When the tests fail.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct HealthThresholds {
+	/// Samples in a window.
+	pub window: u32,
+	/// Highest squared z-score of the monobit and runs tests, in hundredths.
+	pub max_score: u64,
+	/// Most repeated samples tolerated in a window.
+	pub max_repeats: u32,
+}
+
+impl Default for HealthThresholds {
+	/// Windows of 256 samples, failing beyond a z-score of 4 (`p < 10^-4`) or on any repeat.
+	fn default() -> Self {
+		Self { window: 256, max_score: 1_600, max_repeats: 0 }
+	}
+}
+
+/// Running counts over a window of samples.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct Window {
+	/// Samples folded in.
+	pub samples: u32,
+	/// Bits set among them.
+	pub ones: u64,
+	/// Runs of identical bits, reading the samples in order and their bits from the most
+	/// significant one.
+	pub runs: u64,
+	/// Samples equal to the sample before them.
+	pub repeats: u32,
+	/// The latest sample, also from the previous window.
+	pub last: Option<Randomness>,
+}
+
+impl Window {
+	/// Fold in `sample`.
+	pub fn push(&mut self, sample: &Randomness) {
+		let mut previous = match (self.samples, &self.last) {
+			(0, _) | (_, None) => None,
+			(_, Some(last)) => Some(last[31] & 1 == 1),
+		};
+		for byte in sample {
+			for shift in (0..8).rev() {
+				let bit = (byte >> shift) & 1 == 1;
+				if previous != Some(bit) {
+					self.runs += 1;
+				}
+				previous = Some(bit);
+			}
+		}
+		self.ones += sample.iter().map(|byte| u64::from(byte.count_ones())).sum::<u64>();
+		if self.last.as_ref() == Some(sample) {
+			self.repeats = self.repeats.saturating_add(1);
+		}
+		self.last = Some(*sample);
+		self.samples = self.samples.saturating_add(1);
+	}
+
+	/// Bits folded in.
+	pub fn bits(&self) -> u64 {
+		u64::from(self.samples) * SAMPLE_BITS
+	}
+
+	/// Squared z-score of the monobit test, in hundredths: `(2·ones − n)² / n` for `n` bits.
+	pub fn monobit_score(&self) -> u64 {
+		score(self.ones, self.bits())
+	}
+
+	/// Squared z-score of the runs test, in hundredths: `(2·runs − n)² / n` for `n` bits.
+	///
+	/// Independent fair bits make `n / 2` runs on average, with a variance of `n / 4`.
+	pub fn runs_score(&self) -> u64 {
+		score(self.runs, self.bits())
+	}
+
+	/// Whether the window holds all the samples it should.
+	pub fn is_complete(&self, thresholds: &HealthThresholds) -> bool {
+		self.samples >= thresholds.window
+	}
+
+	/// The first test the window fails. The monobit and runs tests are only evaluated once the
+	/// window is complete.
+	pub fn failure(&self, thresholds: &HealthThresholds) -> Option<StatTest> {
+		if self.repeats > thresholds.max_repeats {
+			Some(StatTest::Repeats)
+		} else if !self.is_complete(thresholds) {
+			None
+		} else if self.monobit_score() > thresholds.max_score {
+			Some(StatTest::Monobit)
+		} else if self.runs_score() > thresholds.max_score {
+			Some(StatTest::Runs)
+		} else {
+			None
+		}
+	}
+
+	/// An empty window following this one.
+	pub fn next(&self) -> Self {
+		Self { last: self.last, ..Default::default() }
+	}
+}
+
+/// `100 · (2·count − n)² / n`, saturating.
+fn score(count: u64, n: u64) -> u64 {
+	if n == 0 {
+		return 0;
+	}
+	let deviation = u128::from(count.saturating_mul(2).abs_diff(n));
+	let score = deviation * deviation * 100 / u128::from(n);
+	score.try_into().unwrap_or(u64::MAX)
+}
+
+/// The state of the checks of a beacon, as reported by the runtime.
+#[derive(Encode, Decode, Clone, Default, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct RandomnessHealth {
+	/// The window being filled.
+	pub current: Window,
+	/// The latest complete window.
+	pub previous: Option<Window>,
+	/// The test that failed, until governance clears the alarm.
+	pub alarm: Option<StatTest>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sha2::{Digest, Sha256};
+
+	fn samples(count: u32) -> impl Iterator<Item = Randomness> {
+		(0..count).map(|i| Sha256::digest(i.to_le_bytes()).into())
+	}
+
+	#[test]
+	fn hashes_pass() {
+		let thresholds = HealthThresholds::default();
+		let mut window = Window::default();
+		for sample in samples(thresholds.window) {
+			window.push(&sample);
+		}
+		assert!(window.is_complete(&thresholds));
+		assert_eq!(window.failure(&thresholds), None);
+		assert!(window.runs > 0 && window.ones > 0);
+	}
+
+	#[test]
+	fn biased_bits_fail_monobit() {
+		let thresholds = HealthThresholds::default();
+		let mut window = Window::default();
+		for mut sample in samples(thresholds.window) {
+			sample[0] |= 0xf0;
+			window.push(&sample);
+		}
+		assert_eq!(window.failure(&thresholds), Some(StatTest::Monobit));
+	}
+
+	#[test]
+	fn alternating_bits_fail_runs() {
+		let thresholds = HealthThresholds::default();
+		let mut window = Window::default();
+		for _ in 0..thresholds.window {
+			window.push(&[0b0101_0101; 32]);
+			window.push(&[0b1010_1010; 32]);
+		}
+		assert_eq!(window.monobit_score(), 0);
+		assert!(window.runs > window.bits() * 99 / 100);
+		assert_eq!(window.failure(&thresholds), Some(StatTest::Runs));
+	}
+
+	#[test]
+	fn repeats_fail_at_once_and_across_windows() {
+		let thresholds = HealthThresholds::default();
+		let mut window = Window::default();
+		let sample = samples(1).next().unwrap();
+		window.push(&sample);
+		assert_eq!(window.failure(&thresholds), None);
+
+		let mut next = window.next();
+		next.push(&sample);
+		assert_eq!(next.failure(&thresholds), Some(StatTest::Repeats));
+	}
+}
@@ -0,0 +1,63 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Wall-clock time according to a beacon.
+//!
+//! drand emits round `r` at `genesis_time + (r - 1) * period`, and nobody can sign it earlier, so
+//! a verified round is a lower bound of the current time that doesn't depend on the collators'
+//! clocks.
+//!
+//! It is only a lower bound. How much later the current time is depends on how quickly the round
+//! reached the chain and how quickly blocks were built since, and neither can be checked on chain
+//! without trusting a clock: a round can be submitted long after it was emitted, and the chain can
+//! halt. Consumers needing an upper bound must get it elsewhere.
+
+use crate::beacon::{BeaconId, RoundNumber};
+use codec::{Decode, Encode, MaxEncodedLen};
+use scale_info::TypeInfo;
+use sp_runtime::RuntimeDebug;
+
+/// The current time according to the latest verified round of a beacon.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug, TypeInfo, MaxEncodedLen)]
+pub struct BeaconTime {
+	/// The beacon.
+	pub beacon_id: BeaconId,
+	/// Its latest verified round.
+	pub round: RoundNumber,
+	/// Unix time, in milliseconds, at which the round was emitted: the current time is no
+	/// earlier.
+	pub time: u64,
+}
+
+impl BeaconTime {
+	/// Milliseconds by which `timestamp` is earlier than the current time can be.
+	pub fn lag(&self, timestamp: u64) -> u64 {
+		self.time.saturating_sub(timestamp)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn only_earlier_timestamps_lag() {
+		let time = BeaconTime { beacon_id: 0, round: 7, time: 10_000 };
+		assert_eq!(time.lag(9_000), 1_000);
+		assert_eq!(time.lag(10_000), 0);
+		assert_eq!(time.lag(1_000_000), 0);
+	}
+}
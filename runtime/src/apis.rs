@@ -19,6 +19,16 @@ use frame_support::{
 	genesis_builder_helper::{build_state, get_preset},
 	weights::Weight,
 };
+use ideal_nw_primitives::{
+	beacon::{BeaconId, BeaconInfo, Pulse, Randomness, RoundNumber},
+	dkg::{Committee, Dealing, DkgRound, G1Bytes, ParticipantIndex},
+	lottery::TicketOutput,
+	mixing::MixedSeed,
+	ordering::OrderingKey,
+	relay::RelayRandomnessInfo,
+	stats::RandomnessHealth,
+	time::BeaconTime,
+};
 use pallet_aura::Authorities;
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
@@ -33,11 +43,22 @@ use sp_version::RuntimeVersion;
 
 // Local module imports
 use super::{
-	AccountId, Balance, Block, ConsensusHook, Executive, InherentDataExt, Nonce, ParachainSystem,
-	Runtime, RuntimeCall, RuntimeGenesisConfig, SessionKeys, System, TransactionPayment,
+	AccountId, Balance, Beacons, Block, BlockNumber, ConsensusHook, Dkg, Executive, FairOrdering,
+	InherentDataExt, MixedRandomness, Mmr, Nonce, ParachainSystem, Runtime, RuntimeCall,
+	RuntimeGenesisConfig, SessionKeys, System, TimeOracle, TransactionPayment, VrfLottery,
 	SLOT_DURATION, VERSION,
 };
 
+/// The types of the runtime's MMR of pulses.
+mod mmr {
+	use super::Runtime;
+	pub use pallet_mmr::primitives::*;
+
+	pub type Leaf = <<Runtime as pallet_mmr::Config>::LeafData as LeafDataProvider>::LeafData;
+	pub type Hashing = <Runtime as pallet_mmr::Config>::Hashing;
+	pub type Hash = <Hashing as sp_runtime::traits::Hash>::Output;
+}
+
 impl_runtime_apis! {
 	impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime {
 		fn slot_duration() -> sp_consensus_aura::SlotDuration {
@@ -113,7 +134,7 @@ impl_runtime_apis! {
 			tx: <Block as BlockT>::Extrinsic,
 			block_hash: <Block as BlockT>::Hash,
 		) -> TransactionValidity {
-			Executive::validate_transaction(source, tx, block_hash)
+			crate::drand::with_pulse_cache(|| Executive::validate_transaction(source, tx, block_hash))
 		}
 	}
 
@@ -191,6 +212,143 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl ideal_nw_primitives::api::BeaconsApi<Block> for Runtime {
+		fn beacons() -> Vec<(BeaconId, BeaconInfo)> {
+			Beacons::beacons()
+		}
+
+		fn beacon(beacon: BeaconId) -> Option<BeaconInfo> {
+			Beacons::beacon(beacon)
+		}
+
+		fn beacon_at(beacon: BeaconId, round: RoundNumber) -> Option<BeaconInfo> {
+			Beacons::beacon_at(beacon, round)
+		}
+
+		fn latest_pulse(beacon: BeaconId) -> Option<Pulse> {
+			Beacons::latest_pulse(beacon)
+		}
+
+		fn pulse(beacon: BeaconId, round: RoundNumber) -> Option<Pulse> {
+			Beacons::pulse(beacon, round)
+		}
+
+		fn randomness_health(beacon: BeaconId) -> Option<RandomnessHealth> {
+			Beacons::randomness_health(beacon)
+		}
+
+		fn pulse_key(beacon: BeaconId, round: RoundNumber) -> Vec<u8> {
+			pallet_beacons::Pulses::<Runtime>::hashed_key_for(beacon, round)
+		}
+	}
+
+	impl ideal_nw_primitives::api::RelayRandomnessApi<Block> for Runtime {
+		fn relay_randomness() -> Option<RelayRandomnessInfo> {
+			super::relay_randomness::relay_randomness()
+		}
+	}
+
+	impl ideal_nw_primitives::api::MixedRandomnessApi<Block, BlockNumber> for Runtime {
+		fn vrf_input() -> Option<(RoundNumber, Randomness)> {
+			MixedRandomness::vrf_input()
+		}
+
+		fn mixed_randomness() -> Option<MixedSeed<BlockNumber>> {
+			MixedRandomness::mixed_randomness()
+		}
+	}
+
+	impl ideal_nw_primitives::api::DkgApi<Block, AuraId, BlockNumber> for Runtime {
+		fn pending_dkg() -> Option<DkgRound<AuraId, BlockNumber>> {
+			Dkg::pending_dkg()
+		}
+
+		fn encryption_keys() -> Vec<(ParticipantIndex, G1Bytes)> {
+			Dkg::encryption_keys()
+		}
+
+		fn dealings(session: u32) -> Vec<(ParticipantIndex, Dealing)> {
+			Dkg::dealings(session)
+		}
+
+		fn committee() -> Option<Committee<AuraId>> {
+			Dkg::committee()
+		}
+
+		fn next_committee() -> Option<Committee<AuraId>> {
+			Dkg::next_committee()
+		}
+
+		fn committee_beacon() -> Option<BeaconId> {
+			Dkg::committee_beacon()
+		}
+
+		fn partial_signatures(session: u32, round: RoundNumber) -> Vec<(ParticipantIndex, G1Bytes)> {
+			Dkg::partial_signatures(session, round)
+		}
+	}
+
+	impl ideal_nw_primitives::api::LotteryApi<Block, AccountId> for Runtime {
+		fn ticket(who: AccountId, round: RoundNumber, context: Vec<u8>) -> Option<TicketOutput> {
+			VrfLottery::ticket(&who, round, &context)
+		}
+	}
+
+	impl ideal_nw_primitives::api::BeaconTimeApi<Block> for Runtime {
+		fn beacon_time() -> Option<BeaconTime> {
+			TimeOracle::beacon_time()
+		}
+	}
+
+	impl ideal_nw_primitives::api::FairOrderingApi<Block> for Runtime {
+		fn ordering_keys(extrinsics: Vec<Vec<u8>>) -> Option<Vec<Option<OrderingKey>>> {
+			FairOrdering::ordering_keys(extrinsics)
+		}
+	}
+
+	impl mmr::MmrApi<Block, mmr::Hash, BlockNumber> for Runtime {
+		fn mmr_root() -> Result<mmr::Hash, mmr::Error> {
+			Ok(pallet_mmr::RootHash::<Runtime>::get())
+		}
+
+		fn mmr_leaf_count() -> Result<mmr::LeafIndex, mmr::Error> {
+			Ok(pallet_mmr::NumberOfLeaves::<Runtime>::get())
+		}
+
+		fn generate_proof(
+			block_numbers: Vec<BlockNumber>,
+			best_known_block_number: Option<BlockNumber>,
+		) -> Result<(Vec<mmr::EncodableOpaqueLeaf>, mmr::LeafProof<mmr::Hash>), mmr::Error> {
+			Mmr::generate_proof(block_numbers, best_known_block_number).map(|(leaves, proof)| {
+				let leaves = leaves.iter().map(mmr::EncodableOpaqueLeaf::from_leaf).collect();
+				(leaves, proof)
+			})
+		}
+
+		fn verify_proof(
+			leaves: Vec<mmr::EncodableOpaqueLeaf>,
+			proof: mmr::LeafProof<mmr::Hash>,
+		) -> Result<(), mmr::Error> {
+			let leaves = leaves
+				.into_iter()
+				.map(|leaf| leaf.into_opaque_leaf().try_decode().ok_or(mmr::Error::Verify))
+				.collect::<Result<Vec<mmr::Leaf>, mmr::Error>>()?;
+			Mmr::verify_leaves(leaves, proof)
+		}
+
+		fn verify_proof_stateless(
+			root: mmr::Hash,
+			leaves: Vec<mmr::EncodableOpaqueLeaf>,
+			proof: mmr::LeafProof<mmr::Hash>,
+		) -> Result<(), mmr::Error> {
+			let nodes = leaves
+				.into_iter()
+				.map(|leaf| mmr::DataOrHash::Data(leaf.into_opaque_leaf()))
+				.collect();
+			pallet_mmr::verify_leaves_proof::<mmr::Hashing, _>(root, nodes, proof)
+		}
+	}
+
 	#[cfg(feature = "try-runtime")]
 	impl frame_try_runtime::TryRuntime<Block> for Runtime {
 		fn on_runtime_upgrade(checks: frame_try_runtime::UpgradeCheckSelect) -> (Weight, Weight) {
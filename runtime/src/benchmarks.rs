@@ -24,4 +24,127 @@ frame_benchmarking::define_benchmarks!(
 	[pallet_collator_selection, CollatorSelection]
 	[cumulus_pallet_parachain_system, ParachainSystem]
 	[cumulus_pallet_xcmp_queue, XcmpQueue]
+	[pallet_optimistic_pulses, OptimisticPulses]
+	[pallet_beacons, Beacons]
+	[pallet_mmr, Mmr]
+	[pallet_mixed_randomness, MixedRandomness]
+	[pallet_dkg, Dkg]
+	[pallet_vrf_lottery, VrfLottery]
+	[pallet_beacon_time, TimeOracle]
+	[pallet_fair_ordering, FairOrdering]
 );
+
+use crate::{
+	configs::{LotteryBeacon, MixingBeacon, OrderingBeacon, TimeBeacon},
+	drand, AccountId, AuraId, Balance, ParachainSystem, Runtime, RuntimeCall, RuntimeOrigin,
+	Signature, UncheckedExtrinsic,
+};
+use codec::Encode;
+use frame_support::{traits::Get, BoundedVec};
+use ideal_nw_primitives::beacon::{Pulse, RoundNumber};
+use sp_core::{sr25519, Pair};
+use sp_runtime::DigestItem;
+
+/// Makes a collator with the given session key author the current block.
+fn set_author(author: sr25519::Public) {
+	pallet_aura::Authorities::<Runtime>::put(BoundedVec::truncate_from(alloc::vec![AuraId::from(
+		author
+	)]));
+	// Slot 0, whose author is the first authority.
+	let slot = sp_consensus_aura::Slot::from(0);
+	frame_system::Pallet::<Runtime>::deposit_log(DigestItem::PreRuntime(
+		sp_consensus_aura::AURA_ENGINE_ID,
+		slot.encode(),
+	));
+}
+
+/// Sets up the block author and the mixing beacon for the `pallet_mixed_randomness` benchmarks.
+pub struct MixedRandomnessBenchmarkHelper;
+
+impl pallet_mixed_randomness::BenchmarkHelper for MixedRandomnessBenchmarkHelper {
+	fn prepare(author: sr25519::Public, pulse: Pulse) {
+		set_author(author);
+		drand::set_live_pulse(MixingBeacon::get(), pulse);
+	}
+}
+
+/// Sets the Aura authorities for the `pallet_dkg` benchmarks.
+pub struct DkgBenchmarkHelper;
+
+impl pallet_dkg::BenchmarkHelper<AuraId> for DkgBenchmarkHelper {
+	fn set_authorities(authorities: alloc::vec::Vec<AuraId>) {
+		pallet_aura::Authorities::<Runtime>::put(BoundedVec::truncate_from(authorities));
+	}
+}
+
+/// Sets up the accounts and the lottery beacon for the `pallet_vrf_lottery` benchmarks.
+pub struct VrfLotteryBenchmarkHelper;
+
+impl pallet_vrf_lottery::BenchmarkHelper<AccountId> for VrfLotteryBenchmarkHelper {
+	fn account(seed: [u8; 32]) -> AccountId {
+		sr25519::Pair::from_seed(&seed).public().into()
+	}
+
+	fn store_pulse(pulse: Pulse) {
+		drand::set_live_pulse(LotteryBeacon::get(), pulse);
+	}
+}
+
+/// Sets up the time beacon and a sibling to report to for the `pallet_beacon_time` benchmarks.
+pub struct BeaconTimeBenchmarkHelper;
+
+impl pallet_beacon_time::BenchmarkHelper<RuntimeOrigin> for BeaconTimeBenchmarkHelper {
+	fn set_latest_round(round: RoundNumber) {
+		let signature = [7; 48];
+		drand::set_live_pulse(
+			TimeBeacon::get(),
+			Pulse {
+				round,
+				randomness: sp_core::hashing::sha2_256(&signature),
+				signature: signature.to_vec().try_into().expect("signature is 48 bytes; qed"),
+				previous_signature: Default::default(),
+			},
+		);
+	}
+
+	fn reporting_origin() -> RuntimeOrigin {
+		use xcm::latest::prelude::*;
+
+		let sibling = 2_000;
+		ParachainSystem::open_outbound_hrmp_channel_for_benchmarks_or_tests(sibling.into());
+		pallet_xcm::Origin::Xcm(Location::new(1, [Parachain(sibling)])).into()
+	}
+}
+
+/// Sets up the ordering beacon and builds balance transfers for the `pallet_fair_ordering`
+/// benchmarks.
+pub struct FairOrderingBenchmarkHelper;
+
+impl pallet_fair_ordering::BenchmarkHelper for FairOrderingBenchmarkHelper {
+	fn set_pulse(pulse: Pulse) {
+		drand::set_live_pulse(OrderingBeacon::get(), pulse);
+	}
+
+	fn signed_extrinsic(sender: u32) -> alloc::vec::Vec<u8> {
+		let who: AccountId = frame_benchmarking::account("sender", sender, 0);
+		let call = RuntimeCall::Balances(pallet_balances::Call::transfer_keep_alive {
+			dest: who.clone().into(),
+			value: Balance::MAX,
+		});
+		// Keys aren't computed from the signature, which needn't be valid.
+		let signature = Signature::from(sr25519::Signature::from_raw([0; 64]));
+		let extra: crate::SignedExtra = (
+			frame_system::CheckNonZeroSender::<Runtime>::new(),
+			frame_system::CheckSpecVersion::<Runtime>::new(),
+			frame_system::CheckTxVersion::<Runtime>::new(),
+			frame_system::CheckGenesis::<Runtime>::new(),
+			frame_system::CheckEra::<Runtime>::from(sp_runtime::generic::Era::Immortal),
+			frame_system::CheckNonce::<Runtime>::from(0),
+			frame_system::CheckWeight::<Runtime>::new(),
+			pallet_transaction_payment::ChargeTransactionPayment::<Runtime>::from(0),
+			cumulus_primitives_storage_weight_reclaim::StorageWeightReclaim::<Runtime>::new(),
+			frame_metadata_hash_extension::CheckMetadataHash::<Runtime>::new(false),
+		);
+		UncheckedExtrinsic::new_signed(call, who.into(), signature, extra).encode()
+	}
+}
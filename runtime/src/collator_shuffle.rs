@@ -0,0 +1,195 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Collator order shuffled by drand at every session rotation.
+//!
+//! Aura assigns slots round-robin over the authorities, in the order `pallet_session` queues the
+//! validators. `pallet_collator_selection` returns them in a predictable order, so the authors of
+//! every future slot are known well in advance. [`ShuffledSessions`] shuffles the validators of
+//! each new session with the latest pulse at the rotation that queues them, see
+//! [`ideal_nw_primitives::shuffle`]. The order of a session is then only known one session ahead,
+//! once it is queued.
+//!
+//! The order changes through `pallet_session` like any change of the collator set: Aura's
+//! authorities are replaced by its session handler when the session starts, and
+//! `cumulus_pallet_aura_ext` keeps checking the seal of that block against the authorities of its
+//! parent. Slots, the unincluded segment and `AuraUnincludedSegmentApi` are unaffected.
+//!
+//! The runtime shuffles with [`LatestPulse`](crate::drand::LatestPulse), which has no pulse while
+//! the beacon is stalled or its randomness alarm is raised: the sessions queued meanwhile keep the
+//! order `pallet_collator_selection` returns, rather than one a stale or suspect pulse picks.
+
+use alloc::vec::Vec;
+use core::marker::PhantomData;
+use frame_support::traits::Get;
+use ideal_nw_primitives::{beacon::Pulse, shuffle};
+use pallet_session::SessionManager;
+
+use super::{AccountId, Session};
+
+/// Shuffles the validators `Inner` returns for a new session with the latest pulse of `P`, or the
+/// current validators when `Inner` keeps them.
+///
+/// The order is left as is at genesis and while `P` has no pulse.
+pub struct ShuffledSessions<Inner, P>(PhantomData<(Inner, P)>);
+
+impl<Inner, P> SessionManager<AccountId> for ShuffledSessions<Inner, P>
+where
+	Inner: SessionManager<AccountId>,
+	P: Get<Option<Pulse>>,
+{
+	fn new_session(new_index: u32) -> Option<Vec<AccountId>> {
+		let validators = Inner::new_session(new_index);
+		let Some(pulse) = P::get() else { return validators };
+		// Reshuffle the current validators when `Inner` keeps them.
+		let mut validators = validators.unwrap_or_else(Session::validators);
+		shuffle::shuffle(&mut validators, &shuffle::session_seed(new_index, &pulse.randomness));
+		Some(validators)
+	}
+
+	fn new_session_genesis(new_index: u32) -> Option<Vec<AccountId>> {
+		Inner::new_session_genesis(new_index)
+	}
+
+	fn end_session(end_index: u32) {
+		Inner::end_session(end_index)
+	}
+
+	fn start_session(start_index: u32) {
+		Inner::start_session(start_index)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{configs::ShuffleBeacon, drand::LatestPulse, Runtime};
+	use frame_support::parameter_types;
+	use ideal_nw_primitives::{beacon::RoundNumber, stats::StatTest};
+	use sp_runtime::BuildStorage;
+
+	parameter_types! {
+		pub static Queued: Option<Vec<AccountId>> = None;
+	}
+
+	/// Queues the validators set in [`Queued`].
+	struct MockCollators;
+
+	impl SessionManager<AccountId> for MockCollators {
+		fn new_session(_: u32) -> Option<Vec<AccountId>> {
+			Queued::get()
+		}
+
+		fn new_session_genesis(_: u32) -> Option<Vec<AccountId>> {
+			Queued::get()
+		}
+
+		fn end_session(_: u32) {}
+
+		fn start_session(_: u32) {}
+	}
+
+	type Shuffled = ShuffledSessions<MockCollators, LatestPulse<ShuffleBeacon>>;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		frame_system::GenesisConfig::<Runtime>::default()
+			.build_storage()
+			.unwrap()
+			.into()
+	}
+
+	fn collators(count: u8) -> Vec<AccountId> {
+		(0..count).map(|i| AccountId::new([i; 32])).collect()
+	}
+
+	fn store_pulse(round: RoundNumber) -> Pulse {
+		let pulse = Pulse {
+			round,
+			randomness: [round as u8; 32],
+			signature: Default::default(),
+			previous_signature: Default::default(),
+		};
+		pallet_beacons::Pulses::<Runtime>::insert(ShuffleBeacon::get(), round, pulse.clone());
+		pallet_beacons::LatestRound::<Runtime>::insert(ShuffleBeacon::get(), round);
+		pulse
+	}
+
+	fn shuffled(mut validators: Vec<AccountId>, session: u32, pulse: &Pulse) -> Vec<AccountId> {
+		shuffle::shuffle(&mut validators, &shuffle::session_seed(session, &pulse.randomness));
+		validators
+	}
+
+	#[test]
+	fn queued_collators_are_shuffled_with_the_latest_pulse() {
+		new_test_ext().execute_with(|| {
+			store_pulse(1);
+			let pulse = store_pulse(2);
+			Queued::set(Some(collators(8)));
+
+			let order = Shuffled::new_session(3).unwrap();
+			assert_eq!(order, shuffled(collators(8), 3, &pulse));
+			let mut sorted = order.clone();
+			sorted.sort();
+			assert_eq!(sorted, collators(8));
+		});
+	}
+
+	#[test]
+	fn kept_collators_are_reshuffled() {
+		new_test_ext().execute_with(|| {
+			let pulse = store_pulse(1);
+			pallet_session::Validators::<Runtime>::put(collators(5));
+
+			assert_eq!(Shuffled::new_session(2), Some(shuffled(collators(5), 2, &pulse)));
+		});
+	}
+
+	#[test]
+	fn genesis_order_is_kept() {
+		new_test_ext().execute_with(|| {
+			store_pulse(1);
+			Queued::set(Some(collators(8)));
+
+			assert_eq!(Shuffled::new_session_genesis(0), Some(collators(8)));
+		});
+	}
+
+	#[test]
+	fn order_is_kept_without_a_live_pulse() {
+		new_test_ext().execute_with(|| {
+			// No pulse yet.
+			Queued::set(None);
+			assert_eq!(Shuffled::new_session(1), None);
+			Queued::set(Some(collators(8)));
+			assert_eq!(Shuffled::new_session(1), Some(collators(8)));
+
+			// A stalled beacon.
+			let pulse = store_pulse(1);
+			pallet_beacons::Stalled::<Runtime>::insert(ShuffleBeacon::get(), 1);
+			assert_eq!(Shuffled::new_session(2), Some(collators(8)));
+
+			// A beacon whose randomness alarm is raised.
+			pallet_beacons::Stalled::<Runtime>::remove(ShuffleBeacon::get());
+			pallet_beacons::Health::<Runtime>::mutate(ShuffleBeacon::get(), |health| {
+				health.alarm = Some(StatTest::Monobit)
+			});
+			assert_eq!(Shuffled::new_session(2), Some(collators(8)));
+
+			pallet_beacons::Health::<Runtime>::remove(ShuffleBeacon::get());
+			assert_eq!(Shuffled::new_session(2), Some(shuffled(collators(8), 2, &pulse)));
+		});
+	}
+}
@@ -17,15 +17,17 @@
 mod xcm_config;
 
 // Substrate and Polkadot dependencies
-use codec::Encode;
+use alloc::vec::Vec;
+use codec::{Decode, Encode};
 use cumulus_pallet_parachain_system::RelayNumberMonotonicallyIncreases;
 use cumulus_primitives_core::{AggregateMessageOrigin, ParaId};
 use frame_support::{
 	derive_impl,
-	dispatch::DispatchClass,
+	dispatch::{DispatchClass, GetDispatchInfo},
 	parameter_types,
 	traits::{
-		ConstBool, ConstU32, ConstU64, ConstU8, EitherOfDiverse, TransformOrigin, VariantCountOf,
+		ConstBool, ConstU32, ConstU64, ConstU8, EitherOfDiverse, Equals, Everything, Get,
+		TransformOrigin, VariantCountOf,
 	},
 	weights::{ConstantMultiplier, Weight},
 	PalletId,
@@ -34,15 +36,20 @@ use frame_system::{
 	limits::{BlockLength, BlockWeights},
 	EnsureRoot,
 };
+use ideal_nw_primitives::{
+	beacon::BeaconId,
+	ordering::{self, OrderingKey},
+};
 use pallet_xcm::{EnsureXcm, IsVoiceOfBody};
 use parachains_common::message_queue::{NarrowOriginToSibling, ParaIdToSibling};
 use polkadot_runtime_common::{
 	xcm_sender::NoPriceForMessageDelivery, BlockHashCount, SlowAdjustingFeeUpdate,
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
+use sp_core::sr25519;
 use sp_runtime::{
 	generic::{Era, SignedPayload},
-	traits::StaticLookup,
+	traits::{BlakeTwo256, Convert, StaticLookup},
 	Perbill, SaturatedConversion,
 };
 use sp_version::RuntimeVersion;
@@ -50,15 +57,20 @@ use xcm::latest::prelude::BodyId;
 
 // Local module imports
 use super::{
+	collator_shuffle::ShuffledSessions,
+	drand::{
+		BeaconClock, BeaconPulseVerifier, CachedVerifier, CommitteeBeacon, LatestPulse,
+		SchemeVerifier, StoredPulses,
+	},
 	weights::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight},
-	AccountId, Aura, Balance, Balances, Block, BlockNumber, CollatorSelection, ConsensusHook, Hash,
-	MessageQueue, Nonce, PalletInfo, ParachainSystem, Runtime, RuntimeCall, RuntimeEvent,
-	RuntimeFreezeReason, RuntimeHoldReason, RuntimeOrigin, RuntimeTask, Session, SessionKeys,
-	Signature, System, UncheckedExtrinsic, WeightToFee, XcmpQueue, AVERAGE_ON_INITIALIZE_RATIO,
-	EXISTENTIAL_DEPOSIT, HOURS, MAXIMUM_BLOCK_WEIGHT, MICROUNIT, NORMAL_DISPATCH_RATIO,
-	SLOT_DURATION, VERSION,
+	AccountId, Aura, Balance, Balances, Beacons, Block, BlockNumber, CollatorSelection,
+	ConsensusHook, FairOrdering, Hash, MessageQueue, Nonce, PalletInfo, ParachainSystem, Runtime,
+	RuntimeCall, RuntimeEvent, RuntimeFreezeReason, RuntimeHoldReason, RuntimeOrigin, RuntimeTask,
+	Session, SessionKeys, Signature, System, Timestamp, UncheckedExtrinsic, WeightToFee, XcmpQueue,
+	AVERAGE_ON_INITIALIZE_RATIO, EXISTENTIAL_DEPOSIT, HOURS, MAXIMUM_BLOCK_WEIGHT, MICROUNIT,
+	MILLISECS_PER_BLOCK, MINUTES, NORMAL_DISPATCH_RATIO, SLOT_DURATION, UNIT, VERSION,
 };
-use xcm_config::{RelayLocation, XcmOriginToTransactDispatchOrigin};
+use xcm_config::{RelayLocation, XcmOriginToTransactDispatchOrigin, XcmRouter};
 
 parameter_types! {
 	pub const Version: RuntimeVersion = VERSION;
@@ -120,12 +132,14 @@ impl frame_system::Config for Runtime {
 	/// The action to take on a Runtime Upgrade
 	type OnSetCode = cumulus_pallet_parachain_system::ParachainSetCode<Self>;
 	type MaxConsumers = frame_support::traits::ConstU32<16>;
+	/// Marks where the transactions start, for the fair ordering check.
+	type PostInherents = FairOrdering;
 }
 
 impl pallet_timestamp::Config for Runtime {
 	/// A timestamp: milliseconds since the unix epoch.
 	type Moment = u64;
-	type OnTimestampSet = Aura;
+	type OnTimestampSet = pallet_beacon_time::CheckDrift<Runtime, Aura>;
 	type MinimumPeriod = ConstU64<0>;
 	type WeightInfo = ();
 }
@@ -246,6 +260,8 @@ impl cumulus_pallet_xcmp_queue::Config for Runtime {
 parameter_types! {
 	pub const Period: u32 = 6 * HOURS;
 	pub const Offset: u32 = 0;
+	/// The beacon whose latest pulse shuffles the collators of a new session, quicknet at genesis.
+	pub const ShuffleBeacon: BeaconId = 0;
 }
 
 impl pallet_session::Config for Runtime {
@@ -255,7 +271,7 @@ impl pallet_session::Config for Runtime {
 	type ValidatorIdOf = pallet_collator_selection::IdentityCollator;
 	type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
 	type NextSessionRotation = pallet_session::PeriodicSessions<Period, Offset>;
-	type SessionManager = CollatorSelection;
+	type SessionManager = ShuffledSessions<CollatorSelection, LatestPulse<ShuffleBeacon>>;
 	// Essentially just Aura, but let's be pedantic.
 	type SessionHandler = <SessionKeys as sp_runtime::traits::OpaqueKeys>::KeyTypeIdProviders;
 	type Keys = SessionKeys;
@@ -301,18 +317,226 @@ impl pallet_collator_selection::Config for Runtime {
 
 parameter_types! {
 	pub const UnsignedPriority: u64 = 1 << 20;
-	pub const HttpFetchTimeout: u64 = 2_000;
-	pub const ApiEndpoint: &'static str = "https://drand.cloudflare.com";
 }
 
-impl pallet_drand::Config for Runtime {
+parameter_types! {
+	pub const SubmitterBond: Balance = 100 * UNIT;
+	pub const ChallengePeriod: BlockNumber = 10 * MINUTES;
+	pub const MaxPendingPulses: u32 = 256;
+	pub const MaxPendingPulsesPerSubmitter: u32 = 32;
+	/// Two quicknet rounds.
+	pub const MaxRoundsAhead: u64 = 2;
+	pub const MaxFinalizedPulses: u32 = 256;
+	pub const ChallengerReward: Perbill = Perbill::from_percent(50);
+	/// The beacon followed optimistically, which `pallet_beacons` doesn't verify. Registered by
+	/// governance next to quicknet.
+	pub const OptimisticBeacon: BeaconId = 1;
+}
+
+impl pallet_optimistic_pulses::Config for Runtime {
 	type RuntimeEvent = RuntimeEvent;
-	type WeightInfo = pallet_drand::weights::SubstrateWeight<Runtime>;
-	type AuthorityId = pallet_drand::crypto::TestAuthId;
-	type Verifier = pallet_drand::verifier::QuicknetVerifier;
+	type Currency = Balances;
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type Verifier = BeaconPulseVerifier<OptimisticBeacon>;
+	type SubmitterBond = SubmitterBond;
+	type ChallengePeriod = ChallengePeriod;
+	type MaxPending = MaxPendingPulses;
+	type MaxPendingPerSubmitter = MaxPendingPulsesPerSubmitter;
+	type MaxRoundsAhead = MaxRoundsAhead;
+	type MaxFinalized = MaxFinalizedPulses;
+	type ChallengerReward = ChallengerReward;
+	type WeightInfo = pallet_optimistic_pulses::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = crate::drand::OptimisticPulsesBenchmarkHelper;
+}
+
+parameter_types! {
+	pub const MaxBeacons: u32 = 8;
+	pub const MaxEndpoints: u32 = 8;
+	pub const StallThreshold: BlockNumber = 5 * MINUTES;
+}
+
+impl pallet_beacons::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type BeaconOrigin = EnsureRoot<AccountId>;
+	type Verifier = CachedVerifier<SchemeVerifier>;
+	type OptimisticBeacons = Equals<OptimisticBeacon>;
+	type MaxBeacons = MaxBeacons;
 	type UnsignedPriority = UnsignedPriority;
-	type HttpFetchTimeout = HttpFetchTimeout;
-	type ApiEndpoint = ApiEndpoint;
+	type MaxEndpoints = MaxEndpoints;
+	type StallThreshold = StallThreshold;
+	type WeightInfo = pallet_beacons::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = crate::drand::BeaconsBenchmarkHelper;
+}
+
+parameter_types! {
+	/// The beacon whose pulses block authors mix their VRF outputs into, quicknet at genesis.
+	pub const MixingBeacon: BeaconId = 0;
+	pub const MaxContributions: u32 = 16;
+}
+
+impl pallet_mixed_randomness::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AuthorityId = AuraId;
+	type FindAuthor = pallet_aura::FindAccountFromAuthorIndex<Runtime, Aura>;
+	type Pulse = LatestPulse<MixingBeacon>;
+	type MaxContributions = MaxContributions;
+	type WeightInfo = pallet_mixed_randomness::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = crate::benchmarks::MixedRandomnessBenchmarkHelper;
+}
+
+parameter_types! {
+	/// Seconds between two rounds of the collator beacon: one per block.
+	pub const CommitteeRoundPeriod: u32 = (MILLISECS_PER_BLOCK / 1_000) as u32;
+	pub const DkgPhaseLength: BlockNumber = 5 * MINUTES;
+	pub const MaxDkgParticipants: u32 = 16;
+	pub const MaxPendingRounds: u32 = 16;
+}
+
+/// The Aura authorities of the current session.
+pub struct AuraAuthorities;
+
+impl Get<Vec<AuraId>> for AuraAuthorities {
+	fn get() -> Vec<AuraId> {
+		pallet_aura::Authorities::<Runtime>::get().into_inner()
+	}
+}
+
+/// The index of the current session.
+pub struct CurrentSession;
+
+impl Get<u32> for CurrentSession {
+	fn get() -> u32 {
+		Session::current_index()
+	}
+}
+
+impl pallet_dkg::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AuthorityId = AuraId;
+	type Authorities = AuraAuthorities;
+	type CurrentSession = CurrentSession;
+	type Beacons = CommitteeBeacon;
+	type UnixTime = Timestamp;
+	type RoundPeriod = CommitteeRoundPeriod;
+	type PhaseLength = DkgPhaseLength;
+	type MaxParticipants = MaxDkgParticipants;
+	type MaxPendingRounds = MaxPendingRounds;
+	type UnsignedPriority = UnsignedPriority;
+	type WeightInfo = pallet_dkg::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = crate::benchmarks::DkgBenchmarkHelper;
+}
+
+parameter_types! {
+	/// The beacon lottery tickets draw on, quicknet at genesis.
+	pub const LotteryBeacon: BeaconId = 0;
+	/// Ten minutes of quicknet rounds.
+	pub const TicketLifetime: u64 = 200;
+	pub const MaxContextLen: u32 = 64;
+	pub const TicketDeposit: Balance = UNIT / 100;
+	pub const MaxPrunedTickets: u32 = 512;
+	/// The rounds of a ticket lifetime, and some for expired ones waiting to be pruned.
+	pub const MaxTicketRounds: u32 = 256;
+}
+
+/// The sr25519 key of an account, its raw bytes.
+pub struct Sr25519AccountKey;
+
+impl Convert<AccountId, sr25519::Public> for Sr25519AccountKey {
+	fn convert(who: AccountId) -> sr25519::Public {
+		sr25519::Public::from_raw(who.into())
+	}
+}
+
+impl pallet_vrf_lottery::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type AccountKey = Sr25519AccountKey;
+	type Pulses = StoredPulses<LotteryBeacon>;
+	type Currency = Balances;
+	type RuntimeHoldReason = RuntimeHoldReason;
+	type TicketDeposit = TicketDeposit;
+	type TicketLifetime = TicketLifetime;
+	type MaxContextLen = MaxContextLen;
+	type MaxPrunedTickets = MaxPrunedTickets;
+	type MaxRounds = MaxTicketRounds;
+	type WeightInfo = pallet_vrf_lottery::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = crate::benchmarks::VrfLotteryBenchmarkHelper;
+}
+
+parameter_types! {
+	/// The beacon the time oracle reads the time from, quicknet at genesis.
+	pub const TimeBeacon: BeaconId = 0;
+	pub const MaxTimestampDrift: u64 = 30_000;
+	pub const MaxTimeCallbackLen: u32 = 1_024;
+}
+
+impl pallet_beacon_time::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Source = BeaconClock<TimeBeacon>;
+	type MaxDrift = MaxTimestampDrift;
+	type XcmOrigin = EnsureXcm<Everything>;
+	type XcmSender = XcmRouter;
+	type MaxCallbackLen = MaxTimeCallbackLen;
+	type WeightInfo = pallet_beacon_time::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = crate::benchmarks::BeaconTimeBenchmarkHelper;
+}
+
+parameter_types! {
+	/// The beacon whose latest pulse orders the next block, quicknet at genesis.
+	pub const OrderingBeacon: BeaconId = 0;
+	pub const MaxCheckedTransactions: u32 = 1_024;
+}
+
+/// Ordering keys of the runtime's extrinsics.
+///
+/// Unsigned extrinsics come first, then signed ones by dispatch class. Signed extrinsics are
+/// ranked by the account they are looked up to, whatever address they use, and ordered by nonce.
+pub struct RuntimeExtrinsicKeys;
+
+impl pallet_fair_ordering::ExtrinsicKeys for RuntimeExtrinsicKeys {
+	fn ordering_key(extrinsic: &[u8], seed: &[u8; 32]) -> Option<OrderingKey> {
+		let xt = UncheckedExtrinsic::decode(&mut &extrinsic[..]).ok()?;
+		let Some((address, _, extra)) = xt.signature else {
+			return Some(ordering::unsigned_key(seed, extrinsic));
+		};
+		let sender = <Runtime as frame_system::Config>::Lookup::lookup(address).ok()?;
+		let bucket = match xt.function.get_dispatch_info().class {
+			DispatchClass::Normal => ordering::BUCKET_NORMAL,
+			DispatchClass::Operational | DispatchClass::Mandatory => ordering::BUCKET_OPERATIONAL,
+		};
+		let frame_system::CheckNonce(nonce) = extra.5;
+		Some(OrderingKey {
+			bucket,
+			rank: ordering::sender_rank(seed, &sender.encode()),
+			nonce: nonce.into(),
+		})
+	}
+}
+
+impl pallet_fair_ordering::Config for Runtime {
+	type RuntimeEvent = RuntimeEvent;
+	type Pulse = LatestPulse<OrderingBeacon>;
+	type Keys = RuntimeExtrinsicKeys;
+	type MaxChecked = MaxCheckedTransactions;
+	type WeightInfo = pallet_fair_ordering::weights::SubstrateWeight<Runtime>;
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = crate::benchmarks::FairOrderingBenchmarkHelper;
+}
+
+impl pallet_mmr::Config for Runtime {
+	const INDEXING_PREFIX: &'static [u8] = pallet_mmr::primitives::INDEXING_PREFIX;
+	type Hashing = BlakeTwo256;
+	type LeafData = Beacons;
+	type OnNewRoot = Beacons;
+	type BlockHashProvider = pallet_mmr::DefaultBlockHashProvider<Runtime>;
+	type WeightInfo = ();
+	#[cfg(feature = "runtime-benchmarks")]
+	type BenchmarkHelper = ();
 }
 
 impl<LocalCall> frame_system::offchain::CreateSignedTransaction<LocalCall> for Runtime
@@ -0,0 +1,225 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Runtime glue around the drand beacon.
+
+use core::{convert::Infallible, marker::PhantomData};
+use frame_support::traits::Get;
+use ideal_nw_primitives::{beacon, bls, pulse_cache};
+use pallet_beacon_time::{TimeSource, VerifiedRound};
+use pallet_beacons::VerifyBeaconPulse;
+use pallet_optimistic_pulses::VerifyPulse;
+use sp_core::offchain::StorageKind;
+use sp_io::offchain;
+use sp_runtime::DispatchError;
+
+use super::{Beacons, Runtime};
+
+environmental::environmental!(pulse_cache_scope: ());
+
+/// Run `f` with the node's verified-pulse cache available to [`CachedVerifier`].
+///
+/// Only the transaction pool entry point runs in this scope, see `apis.rs`. Blocks are executed
+/// outside of it, so their validity never depends on node-local state.
+pub fn with_pulse_cache<R>(f: impl FnOnce() -> R) -> R {
+	pulse_cache_scope::using(&mut (), f)
+}
+
+/// A verifier that consults the node's verified-pulse cache before running `V`.
+///
+/// While a transaction is validated, a pulse the node has already verified is accepted without
+/// repeating the pairing check, and newly verified pulses are added to the cache. Everywhere else,
+/// block execution included, `V` is always run and the cache is neither read nor written.
+pub struct CachedVerifier<V>(PhantomData<V>);
+
+impl<V: VerifyBeaconPulse> VerifyBeaconPulse for CachedVerifier<V> {
+	fn verify(beacon: &beacon::BeaconInfo, pulse: &beacon::Pulse) -> bool {
+		cached(beacon, pulse, || Ok::<_, Infallible>(V::verify(beacon, pulse)))
+			.unwrap_or_else(|never| match never {})
+	}
+}
+
+/// Run `verify`, unless the cache already knows `pulse` is valid for `beacon`.
+fn cached<E>(
+	beacon: &beacon::BeaconInfo,
+	pulse: &beacon::Pulse,
+	verify: impl FnOnce() -> Result<bool, E>,
+) -> Result<bool, E> {
+	if pulse_cache_scope::with(|_| ()).is_none() {
+		return verify();
+	}
+	let key = pulse_cache::storage_key(beacon, pulse);
+	if offchain::local_storage_get(StorageKind::PERSISTENT, &key).is_some() {
+		return Ok(true);
+	}
+
+	let verified = verify()?;
+	if verified {
+		offchain::local_storage_set(StorageKind::PERSISTENT, &key, &[]);
+	}
+	Ok(verified)
+}
+
+/// Verifies pulses against the beacon `B` of `pallet_beacons` as it signs their round.
+pub struct BeaconPulseVerifier<B>(PhantomData<B>);
+
+impl<B: Get<beacon::BeaconId>> VerifyPulse for BeaconPulseVerifier<B> {
+	fn verify(pulse: &beacon::Pulse) -> Result<bool, DispatchError> {
+		let beacon = Beacons::beacon_at(B::get(), pulse.round)
+			.ok_or(DispatchError::Other("beacon is not registered"))?;
+		Ok(SchemeVerifier::verify(&beacon, pulse))
+	}
+
+	fn current_round() -> Result<beacon::RoundNumber, DispatchError> {
+		let beacon =
+			Beacons::beacon(B::get()).ok_or(DispatchError::Other("beacon is not registered"))?;
+		Ok(beacon.round_at(pallet_timestamp::Now::<Runtime>::get() / 1_000))
+	}
+}
+
+/// Verifies pulses of the beacons followed by `pallet_beacons`, according to their scheme.
+pub struct SchemeVerifier;
+
+impl VerifyBeaconPulse for SchemeVerifier {
+	fn verify(beacon: &beacon::BeaconInfo, pulse: &beacon::Pulse) -> bool {
+		bls::verify_pulse(beacon, pulse)
+	}
+}
+
+/// Registers the beacon of the collator committee of `pallet_dkg` with `pallet_beacons`.
+pub struct CommitteeBeacon;
+
+impl pallet_dkg::RegisterBeacon for CommitteeBeacon {
+	fn register_beacon(info: beacon::BeaconInfo) -> Result<beacon::BeaconId, DispatchError> {
+		Beacons::do_register_beacon(info).map_err(Into::into)
+	}
+}
+
+/// The stored pulses of the beacon `B` of `pallet_beacons`.
+pub struct StoredPulses<B>(PhantomData<B>);
+
+impl<B: Get<beacon::BeaconId>> pallet_vrf_lottery::PulseProvider for StoredPulses<B> {
+	fn latest_pulse() -> Option<beacon::Pulse> {
+		Beacons::latest_pulse(B::get())
+	}
+
+	fn pulse(round: beacon::RoundNumber) -> Option<beacon::Pulse> {
+		Beacons::pulse(B::get(), round)
+	}
+}
+
+/// The latest verified round of the beacon `B` of `pallet_beacons`, as a clock.
+pub struct BeaconClock<B>(PhantomData<B>);
+
+impl<B: Get<beacon::BeaconId>> TimeSource for BeaconClock<B> {
+	fn latest_round() -> Option<VerifiedRound> {
+		let beacon_id = B::get();
+		if Beacons::is_stalled(beacon_id) {
+			return None;
+		}
+		let round = pallet_beacons::LatestRound::<Runtime>::get(beacon_id)?;
+		Some((beacon_id, Beacons::beacon(beacon_id)?, round))
+	}
+}
+
+/// The latest pulse of the beacon `B` of `pallet_beacons`, see
+/// [`pallet_beacons::Pallet::live_pulse`].
+pub struct LatestPulse<B>(PhantomData<B>);
+
+impl<B: Get<beacon::BeaconId>> Get<Option<beacon::Pulse>> for LatestPulse<B> {
+	fn get() -> Option<beacon::Pulse> {
+		Beacons::live_pulse(B::get())
+	}
+}
+
+/// Register quicknet under distinct chain hashes until `beacon_id` exists, returning it.
+#[cfg(feature = "runtime-benchmarks")]
+pub(crate) fn register_up_to(beacon_id: beacon::BeaconId) -> beacon::BeaconInfo {
+	let mut chain_hash = 0;
+	while Beacons::beacon(beacon_id).is_none() {
+		chain_hash += 1;
+		let info =
+			beacon::BeaconInfo { chain_hash: [chain_hash; 32], ..beacon::BeaconInfo::quicknet() };
+		Beacons::do_register_beacon(info).expect("fewer beacons than `MaxBeacons`; qed");
+	}
+	Beacons::beacon(beacon_id).expect("registered above; qed")
+}
+
+/// Make `pulse` the live pulse of `beacon_id`, as if the beacon just stored it.
+#[cfg(feature = "runtime-benchmarks")]
+pub(crate) fn set_live_pulse(beacon_id: beacon::BeaconId, pulse: beacon::Pulse) {
+	register_up_to(beacon_id);
+	let round = pulse.round;
+	pallet_beacons::Pulses::<Runtime>::insert(beacon_id, round, pulse);
+	pallet_beacons::LatestRound::<Runtime>::insert(beacon_id, round);
+	pallet_beacons::LastPulseBlock::<Runtime>::insert(
+		beacon_id,
+		frame_system::Pallet::<Runtime>::block_number(),
+	);
+}
+
+/// Sets up quicknet for the `pallet_optimistic_pulses` benchmarks.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct OptimisticPulsesBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_optimistic_pulses::BenchmarkHelper for OptimisticPulsesBenchmarkHelper {
+	fn prepare_verifier() {
+		use crate::configs::{MaxPendingPulses, OptimisticBeacon};
+
+		let beacon = register_up_to(OptimisticBeacon::get());
+		let round = u64::from(MaxPendingPulses::get());
+		pallet_timestamp::Pallet::<Runtime>::set_timestamp(beacon.round_time(round) * 1_000);
+	}
+
+	fn invalid_pulse(round: beacon::RoundNumber) -> beacon::Pulse {
+		// The BLS12-381 G1 generator: a well formed point, but not a signature for any round.
+		let signature = hex_literal::hex!(
+			"97f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb"
+		);
+		beacon::Pulse {
+			round,
+			randomness: sp_core::hashing::sha2_256(&signature),
+			signature: signature.to_vec().try_into().expect("signature is 48 bytes; qed"),
+			previous_signature: Default::default(),
+		}
+	}
+}
+
+/// Sets up beacons signed with throwaway keys for the `pallet_beacons` benchmarks.
+#[cfg(feature = "runtime-benchmarks")]
+pub struct BeaconsBenchmarkHelper;
+
+#[cfg(feature = "runtime-benchmarks")]
+impl pallet_beacons::BenchmarkHelper for BeaconsBenchmarkHelper {
+	fn beacon(scheme: beacon::SignatureScheme) -> beacon::BeaconInfo {
+		use ideal_nw_primitives::fixtures::*;
+		match scheme {
+			beacon::SignatureScheme::BlsUnchainedG1Rfc9380 => unchained_beacon(),
+			beacon::SignatureScheme::PedersenBlsChained => chained_beacon(),
+			beacon::SignatureScheme::BlsBn254UnchainedG1 => evmnet_beacon(),
+		}
+	}
+
+	fn pulse(scheme: beacon::SignatureScheme) -> beacon::Pulse {
+		use ideal_nw_primitives::fixtures::*;
+		match scheme {
+			beacon::SignatureScheme::BlsUnchainedG1Rfc9380 => unchained_pulse(),
+			beacon::SignatureScheme::PedersenBlsChained => chained_pulse(),
+			beacon::SignatureScheme::BlsBn254UnchainedG1 => evmnet_pulse(),
+		}
+	}
+}
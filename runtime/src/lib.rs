@@ -18,6 +18,8 @@
 // `construct_runtime!` does a lot of recursion and requires us to increase the limit to 256.
 #![recursion_limit = "256"]
 
+extern crate alloc;
+
 // Make the WASM binary available.
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
@@ -25,7 +27,11 @@ include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 pub mod apis;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarks;
+pub mod collator_shuffle;
 mod configs;
+pub mod drand;
+mod migrations;
+pub mod relay_randomness;
 mod weights;
 
 use smallvec::smallvec;
@@ -106,6 +112,9 @@ pub type SignedExtra = (
 pub type UncheckedExtrinsic =
 	generic::UncheckedExtrinsic<Address, RuntimeCall, Signature, SignedExtra>;
 
+/// Migrations to apply on runtime upgrade.
+pub type Migrations = (migrations::DrandToBeacons, migrations::FollowQuicknet);
+
 /// Executive: handles dispatch to the various modules.
 pub type Executive = frame_executive::Executive<
 	Runtime,
@@ -113,6 +122,7 @@ pub type Executive = frame_executive::Executive<
 	frame_system::ChainContext<Runtime>,
 	Runtime,
 	AllPalletsWithSystem,
+	Migrations,
 >;
 
 /// Handles converting a weight scalar to a fee value, based on the scale and granularity of the
@@ -175,10 +185,10 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("ideal-nw-runtime"),
 	impl_name: create_runtime_str!("ideal-nw-runtime"),
 	authoring_version: 1,
-	spec_version: 5,
+	spec_version: 6,
 	impl_version: 0,
 	apis: apis::RUNTIME_API_VERSIONS,
-	transaction_version: 1,
+	transaction_version: 2,
 	state_version: 1,
 };
 
@@ -302,9 +312,23 @@ mod runtime {
 	#[runtime::pallet_index(33)]
 	pub type MessageQueue = pallet_message_queue;
 
-	// Drand
-	#[runtime::pallet_index(40)]
-	pub type Drand = pallet_drand;
+	// Drand. Index 40 belonged to `pallet_drand`, replaced by `pallet_beacons`.
+	#[runtime::pallet_index(41)]
+	pub type OptimisticPulses = pallet_optimistic_pulses;
+	#[runtime::pallet_index(42)]
+	pub type Beacons = pallet_beacons;
+	#[runtime::pallet_index(43)]
+	pub type Mmr = pallet_mmr;
+	#[runtime::pallet_index(44)]
+	pub type MixedRandomness = pallet_mixed_randomness;
+	#[runtime::pallet_index(45)]
+	pub type Dkg = pallet_dkg;
+	#[runtime::pallet_index(46)]
+	pub type VrfLottery = pallet_vrf_lottery;
+	#[runtime::pallet_index(47)]
+	pub type TimeOracle = pallet_beacon_time;
+	#[runtime::pallet_index(48)]
+	pub type FairOrdering = pallet_fair_ordering;
 }
 
 cumulus_pallet_parachain_system::register_validate_block! {
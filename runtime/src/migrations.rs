@@ -0,0 +1,378 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Storage migrations run on runtime upgrades.
+
+use codec::{Decode, Encode};
+use frame_support::{
+	storage_alias,
+	traits::{ConstU32, Get, OnRuntimeUpgrade},
+	weights::Weight,
+	BoundedVec,
+};
+use ideal_nw_primitives::beacon::{BeaconId, BeaconInfo, Pulse, SignatureScheme};
+#[cfg(feature = "try-runtime")]
+use ideal_nw_primitives::beacon::{ChainHash, RoundNumber};
+use pallet_beacons::Endpoint;
+
+use super::{Beacons, Runtime, System};
+
+const LOG_TARGET: &str = "runtime::migrations";
+
+/// The storage `pallet_drand` left behind, under its former runtime name `Drand`.
+///
+/// Only what [`DrandToBeacons`] reads is declared, in `pallet_drand`'s own encoding.
+mod drand {
+	use super::*;
+	use frame_support::Blake2_128Concat;
+
+	pub type BoundedHash = BoundedVec<u8, ConstU32<32>>;
+
+	#[derive(Encode, Decode)]
+	pub struct Metadata {
+		pub beacon_id: BoundedHash,
+	}
+
+	#[derive(Encode, Decode)]
+	pub struct BeaconConfiguration {
+		pub public_key: BoundedVec<u8, ConstU32<96>>,
+		pub period: u32,
+		pub genesis_time: u32,
+		pub hash: BoundedHash,
+		pub group_hash: BoundedHash,
+		pub scheme_id: BoundedHash,
+		pub metadata: Metadata,
+	}
+
+	#[derive(Encode, Decode)]
+	pub struct Pulse {
+		pub round: u64,
+		pub randomness: BoundedHash,
+		pub signature: BoundedVec<u8, ConstU32<144>>,
+	}
+
+	#[storage_alias]
+	pub type BeaconConfig = StorageValue<Drand, BeaconConfiguration>;
+
+	#[storage_alias]
+	pub type NextUnsignedAt = StorageValue<Drand, u32>;
+
+	#[storage_alias]
+	pub type LastStoredRound = StorageValue<Drand, u64>;
+
+	#[storage_alias]
+	pub type Pulses = StorageMap<Drand, Blake2_128Concat, u64, Pulse>;
+
+	/// The `pallet_beacons` beacon the rounds in [`Pulses`] belong to, written by the migration.
+	#[storage_alias]
+	pub type MovedTo = StorageValue<Drand, BeaconId>;
+
+	/// The block the migration ran in, after which no round was added to [`Pulses`].
+	#[storage_alias]
+	pub type MovedAt = StorageValue<Drand, crate::BlockNumber>;
+}
+
+/// Hands the beacon followed by the removed `pallet_drand` over to `pallet_beacons`.
+///
+/// The beacon is registered with `pallet_beacons` unless it already follows it, and its latest
+/// pulse is stored there, so consumers keep their randomness across the upgrade. Unless
+/// governance set endpoints already, the offchain worker keeps fetching from the endpoint
+/// `pallet_drand` had compiled in, now through [`pallet_beacons::Endpoints`]. The configuration
+/// and bookkeeping values of `pallet_drand` are then removed.
+///
+/// Its `Pulses` map is left in place: it holds every round the chain ever stored, too many to
+/// move or clear in one block, and tools reading the chain's history still find them there. The
+/// id of the beacon they belong to is kept next to them, under `Drand::MovedTo`, and the block
+/// the migration ran in under `Drand::MovedAt`.
+pub struct DrandToBeacons;
+
+impl OnRuntimeUpgrade for DrandToBeacons {
+	fn on_runtime_upgrade() -> Weight {
+		let db = <Runtime as frame_system::Config>::DbWeight::get();
+		let Some(config) = drand::BeaconConfig::take() else {
+			log::info!(target: LOG_TARGET, "no pallet_drand beacon to migrate");
+			return db.reads(1);
+		};
+		let latest_round = drand::LastStoredRound::take();
+		drand::NextUnsignedAt::kill();
+		pallet_beacons::Endpoints::<Runtime>::mutate(|endpoints| {
+			if endpoints.is_empty() {
+				// Fits, `MaxEndpoints` is above zero.
+				let _ = endpoints.try_push(drand_endpoint());
+			}
+		});
+		let Some(info) = beacon_info(&config) else {
+			log::warn!(target: LOG_TARGET, "dropping malformed pallet_drand beacon");
+			return db.reads_writes(3, 4);
+		};
+
+		let chain_hash = info.chain_hash;
+		let beacon_id = match pallet_beacons::BeaconIds::<Runtime>::get(chain_hash) {
+			Some(beacon_id) => beacon_id,
+			None => match Beacons::do_register_beacon(info) {
+				Ok(beacon_id) => beacon_id,
+				Err(err) => {
+					log::warn!(target: LOG_TARGET, "could not register the drand beacon: {err:?}");
+					return db.reads_writes(5, 4);
+				},
+			},
+		};
+
+		drand::MovedTo::put(beacon_id);
+		drand::MovedAt::put(System::block_number());
+
+		let pulse = latest_round.and_then(|round| drand::Pulses::get(round)).and_then(|pulse| {
+			Some(Pulse {
+				round: pulse.round,
+				randomness: pulse.randomness.into_inner().try_into().ok()?,
+				signature: pulse.signature.into_inner().try_into().ok()?,
+				previous_signature: Default::default(),
+			})
+		});
+		let seeded = match pulse {
+			Some(pulse)
+				if pallet_beacons::LatestRound::<Runtime>::get(beacon_id)
+					.map_or(true, |latest| pulse.round > latest) =>
+			{
+				let round = pulse.round;
+				pallet_beacons::Pulses::<Runtime>::insert(beacon_id, round, pulse);
+				pallet_beacons::LatestRound::<Runtime>::insert(beacon_id, round);
+				pallet_beacons::LastPulseBlock::<Runtime>::insert(
+					beacon_id,
+					System::block_number(),
+				);
+				Some(round)
+			},
+			_ => None,
+		};
+
+		log::info!(
+			target: LOG_TARGET,
+			"moved the pallet_drand beacon to beacon {beacon_id}, latest round {seeded:?}"
+		);
+		db.reads_writes(10, 13)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn pre_upgrade() -> Result<alloc::vec::Vec<u8>, sp_runtime::TryRuntimeError> {
+		let chain_hash = drand::BeaconConfig::get().and_then(|config| beacon_info(&config));
+		let latest =
+			drand::LastStoredRound::get().filter(|round| drand::Pulses::contains_key(round));
+		Ok((chain_hash.map(|info| info.chain_hash), latest).encode())
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(state: alloc::vec::Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+		use frame_support::ensure;
+
+		let (chain_hash, latest): (Option<ChainHash>, Option<RoundNumber>) =
+			Decode::decode(&mut &state[..]).map_err(|_| "undecodable pre-upgrade state")?;
+		ensure!(!drand::BeaconConfig::exists(), "the pallet_drand beacon is left behind");
+		ensure!(!drand::LastStoredRound::exists(), "the pallet_drand round is left behind");
+		let Some(chain_hash) = chain_hash else { return Ok(()) };
+
+		let beacon_id = pallet_beacons::BeaconIds::<Runtime>::get(chain_hash)
+			.ok_or("the pallet_drand beacon is not registered")?;
+		ensure!(drand::MovedTo::get() == Some(beacon_id), "the moved rounds are not attributed");
+		ensure!(drand::MovedAt::exists(), "the migration block is not recorded");
+		ensure!(
+			!pallet_beacons::Endpoints::<Runtime>::get().is_empty(),
+			"no endpoint to fetch pulses from"
+		);
+		if let Some(latest) = latest {
+			ensure!(
+				pallet_beacons::LatestRound::<Runtime>::get(beacon_id) >= Some(latest),
+				"the latest pallet_drand pulse was not carried over"
+			);
+		}
+		Ok(())
+	}
+}
+
+/// Makes sure beacon 0, which the randomness consumers of the runtime read, is registered.
+///
+/// Runs after [`DrandToBeacons`]. If the chain followed no beacon at all, it starts following
+/// drand's quicknet as beacon 0, fetched from the endpoint `pallet_drand` used.
+pub struct FollowQuicknet;
+
+impl OnRuntimeUpgrade for FollowQuicknet {
+	fn on_runtime_upgrade() -> Weight {
+		let db = <Runtime as frame_system::Config>::DbWeight::get();
+		if pallet_beacons::NextBeaconId::<Runtime>::get() > 0 {
+			return db.reads(1);
+		}
+		match Beacons::do_register_beacon(BeaconInfo::quicknet()) {
+			Ok(beacon_id) =>
+				log::info!(target: LOG_TARGET, "following quicknet as beacon {beacon_id}"),
+			Err(err) => log::warn!(target: LOG_TARGET, "could not register quicknet: {err:?}"),
+		}
+		pallet_beacons::Endpoints::<Runtime>::mutate(|endpoints| {
+			if endpoints.is_empty() {
+				// Fits, `MaxEndpoints` is above zero.
+				let _ = endpoints.try_push(drand_endpoint());
+			}
+		});
+		db.reads_writes(5, 6)
+	}
+
+	#[cfg(feature = "try-runtime")]
+	fn post_upgrade(_state: alloc::vec::Vec<u8>) -> Result<(), sp_runtime::TryRuntimeError> {
+		frame_support::ensure!(Beacons::beacon(0).is_some(), "beacon 0 is not registered");
+		frame_support::ensure!(
+			!pallet_beacons::Endpoints::<Runtime>::get().is_empty(),
+			"no endpoint to fetch pulses from"
+		);
+		Ok(())
+	}
+}
+
+/// The endpoint `pallet_drand` fetched from, with its timeout.
+fn drand_endpoint() -> Endpoint {
+	Endpoint {
+		url: b"https://drand.cloudflare.com"
+			.to_vec()
+			.try_into()
+			.expect("short URL fits; qed"),
+		timeout_ms: 2_000,
+		backoff_ms: 6_000,
+	}
+}
+
+/// The `pallet_beacons` form of a `pallet_drand` beacon configuration.
+fn beacon_info(config: &drand::BeaconConfiguration) -> Option<BeaconInfo> {
+	let scheme = core::str::from_utf8(&config.scheme_id).ok()?;
+	Some(BeaconInfo {
+		chain_hash: config.hash.to_vec().try_into().ok()?,
+		public_key: config.public_key.to_vec().try_into().ok()?,
+		period: config.period,
+		genesis_time: config.genesis_time.into(),
+		scheme: SignatureScheme::from_scheme_id(scheme)?,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use frame_support::storage::unhashed;
+	use sp_core::hashing::{blake2_128, twox_128};
+	use sp_runtime::BuildStorage;
+
+	fn new_test_ext() -> sp_io::TestExternalities {
+		let mut ext: sp_io::TestExternalities = frame_system::GenesisConfig::<Runtime>::default()
+			.build_storage()
+			.unwrap()
+			.into();
+		ext.execute_with(|| System::set_block_number(7));
+		ext
+	}
+
+	/// The key of the `pallet_drand` storage item `item`.
+	fn drand_key(item: &str) -> Vec<u8> {
+		[twox_128(b"Drand"), twox_128(item.as_bytes())].concat()
+	}
+
+	/// A `BoundedVec<u8, _>` as SCALE encodes it: a compact length, then the bytes.
+	fn bytes(bytes: &[u8]) -> Vec<u8> {
+		[codec::Compact(bytes.len() as u32).encode(), bytes.to_vec()].concat()
+	}
+
+	/// Write quicknet and its round `round` the way `pallet_drand` stored them.
+	fn put_drand_beacon(round: u64, signature: &[u8]) {
+		let quicknet = BeaconInfo::quicknet();
+		// `BeaconConfiguration { public_key, period: u32, genesis_time: u32, hash, group_hash,
+		// scheme_id, metadata: Metadata { beacon_id } }`.
+		let config = [
+			bytes(&quicknet.public_key),
+			3u32.to_le_bytes().to_vec(),
+			1_692_803_367u32.to_le_bytes().to_vec(),
+			bytes(&quicknet.chain_hash),
+			bytes(&[0xaa; 32]),
+			bytes(b"bls-unchained-g1-rfc9380"),
+			bytes(&quicknet.chain_hash),
+		]
+		.concat();
+		unhashed::put_raw(&drand_key("BeaconConfig"), &config);
+		unhashed::put_raw(&drand_key("NextUnsignedAt"), &5u32.to_le_bytes());
+		unhashed::put_raw(&drand_key("LastStoredRound"), &round.to_le_bytes());
+		// `Pulse { round: u64, randomness, signature }` under `Blake2_128Concat` round keys.
+		let pulse = [
+			round.to_le_bytes().to_vec(),
+			bytes(&sp_io::hashing::sha2_256(signature)),
+			bytes(signature),
+		]
+		.concat();
+		let key = [
+			drand_key("Pulses"),
+			blake2_128(&round.to_le_bytes()).to_vec(),
+			round.to_le_bytes().to_vec(),
+		]
+		.concat();
+		unhashed::put_raw(&key, &pulse);
+	}
+
+	#[test]
+	fn drand_beacon_moves_to_beacon_0() {
+		new_test_ext().execute_with(|| {
+			let signature = [0x8d; 48];
+			put_drand_beacon(1_000, &signature);
+
+			DrandToBeacons::on_runtime_upgrade();
+
+			assert_eq!(Beacons::beacon(0), Some(BeaconInfo::quicknet()));
+			assert_eq!(
+				Beacons::latest_pulse(0),
+				Some(Pulse {
+					round: 1_000,
+					randomness: sp_io::hashing::sha2_256(&signature),
+					signature: signature.to_vec().try_into().unwrap(),
+					previous_signature: Default::default(),
+				})
+			);
+			assert_eq!(pallet_beacons::LastPulseBlock::<Runtime>::get(0), Some(7));
+			assert_eq!(
+				pallet_beacons::Endpoints::<Runtime>::get().into_inner(),
+				vec![drand_endpoint()]
+			);
+
+			assert!(!drand::BeaconConfig::exists());
+			assert!(!drand::NextUnsignedAt::exists());
+			assert!(!drand::LastStoredRound::exists());
+			assert_eq!(drand::MovedTo::get(), Some(0));
+			assert_eq!(drand::MovedAt::get(), Some(7));
+			assert_eq!(drand::Pulses::get(1_000).map(|pulse| pulse.round), Some(1_000));
+
+			// Beacon 0 is followed now, quicknet is not registered twice.
+			FollowQuicknet::on_runtime_upgrade();
+			assert_eq!(pallet_beacons::NextBeaconId::<Runtime>::get(), 1);
+		});
+	}
+
+	#[test]
+	fn quicknet_is_followed_without_a_drand_beacon() {
+		new_test_ext().execute_with(|| {
+			DrandToBeacons::on_runtime_upgrade();
+			assert_eq!(drand::MovedTo::get(), None);
+			assert_eq!(drand::MovedAt::get(), None);
+
+			FollowQuicknet::on_runtime_upgrade();
+			assert_eq!(Beacons::beacon(0), Some(BeaconInfo::quicknet()));
+			assert_eq!(
+				pallet_beacons::Endpoints::<Runtime>::get().into_inner(),
+				vec![drand_endpoint()]
+			);
+		});
+	}
+}
@@ -0,0 +1,98 @@
+/*
+ * Copyright 2024 by Ideal Labs, LLC
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *     http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! The relay chain's BABE randomness, read from the relay state proof.
+//!
+//! **This is a weak source of randomness.** Relay block authors know their VRF output before
+//! publishing and can withhold a block to reroll it, and epoch randomness is known an epoch in
+//! advance. It is only meant for consumers that need *some* randomness while no beacon is
+//! available, see [`ideal_nw_primitives::relay`]. Prefer [`pallet_beacons::BeaconRandomness`].
+//!
+//! The collator includes the randomness keys in the relay state proof of every block. The proof
+//! is only in state once the parachain inherent is applied, so in `on_initialize` there is no
+//! randomness yet.
+
+use cumulus_pallet_parachain_system::{
+	relay_state_snapshot::RelayChainStateProof, RelayStateProof,
+};
+use cumulus_primitives_core::relay_chain::well_known_keys;
+use frame_support::traits::Randomness;
+use ideal_nw_primitives::relay::{RelayRandomness, RelayRandomnessInfo};
+use sp_runtime::traits::{BlakeTwo256, Hash as HashT};
+
+use super::{BlockNumber, Hash, ParachainInfo, ParachainSystem, Runtime, System};
+
+/// The relay state proof of the current block.
+fn relay_state_proof() -> Option<(u32, RelayChainStateProof)> {
+	let validation_data = ParachainSystem::validation_data()?;
+	let proof = RelayStateProof::<Runtime>::get()?;
+	let proof = RelayChainStateProof::new(
+		ParachainInfo::parachain_id(),
+		validation_data.relay_parent_storage_root,
+		proof,
+	)
+	.ok()?;
+	Some((validation_data.relay_parent_number, proof))
+}
+
+/// The randomness of the relay parent of the current block.
+pub fn relay_randomness() -> Option<RelayRandomnessInfo> {
+	let (relay_parent_number, proof) = relay_state_proof()?;
+	Some(RelayRandomnessInfo {
+		relay_parent_number,
+		epoch_index: proof.read_optional_entry(well_known_keys::EPOCH_INDEX).ok().flatten(),
+		current_block: proof
+			.read_optional_entry::<Option<RelayRandomness>>(
+				well_known_keys::CURRENT_BLOCK_RANDOMNESS,
+			)
+			.ok()
+			.flatten()
+			.flatten(),
+		one_epoch_ago: proof
+			.read_optional_entry(well_known_keys::ONE_EPOCH_AGO_RANDOMNESS)
+			.ok()
+			.flatten(),
+	})
+}
+
+/// Randomness for `subject` from `randomness`, the default hash without it.
+fn derive(subject: &[u8], randomness: Option<RelayRandomness>) -> (Hash, BlockNumber) {
+	let seed = randomness.map(|randomness| BlakeTwo256::hash_of(&(subject, randomness)));
+	(seed.unwrap_or_default(), System::block_number())
+}
+
+/// Randomness from the VRF output of the relay parent's author.
+///
+/// Weak: the author can withhold the relay block to reroll it. See the module documentation.
+pub struct RelayBlockRandomness;
+
+impl Randomness<Hash, BlockNumber> for RelayBlockRandomness {
+	fn random(subject: &[u8]) -> (Hash, BlockNumber) {
+		derive(subject, relay_randomness().and_then(|info| info.current_block))
+	}
+}
+
+/// Randomness of the relay chain's previous BABE epoch.
+///
+/// Weak: it is known for a whole epoch, and the returned block number is the current block
+/// rather than when it became known. See the module documentation.
+pub struct RelayEpochRandomness;
+
+impl Randomness<Hash, BlockNumber> for RelayEpochRandomness {
+	fn random(subject: &[u8]) -> (Hash, BlockNumber) {
+		derive(subject, relay_randomness().and_then(|info| info.one_epoch_ago))
+	}
+}